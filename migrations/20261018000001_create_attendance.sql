@@ -0,0 +1,60 @@
+-- Attendance module: per-class daily registers and per-student marks.
+-- All scoping via org_id; student rows use the composite (student_id, org_id)
+-- FK introduced in 20260503000003 so a mark can never cross tenants.
+
+-- ── attendance_registers: one per class per school day ───────────────
+
+CREATE TABLE IF NOT EXISTS attendance_registers (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    register_date       DATE NOT NULL,
+    grade_level         TEXT NOT NULL,
+    section             TEXT,
+
+    taken_by_user_id    UUID REFERENCES users(id) ON DELETE SET NULL,
+
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Section is nullable (single-arm classes), so uniqueness goes through COALESCE.
+CREATE UNIQUE INDEX idx_attendance_registers_class_day
+    ON attendance_registers(org_id, register_date, grade_level, COALESCE(section, ''));
+CREATE INDEX idx_attendance_registers_org_date ON attendance_registers(org_id, register_date DESC);
+
+CREATE TRIGGER update_attendance_registers_updated_at
+    BEFORE UPDATE ON attendance_registers FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- ── attendance_records: one mark per student per register ────────────
+
+CREATE TABLE IF NOT EXISTS attendance_records (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    register_id         UUID NOT NULL REFERENCES attendance_registers(id) ON DELETE CASCADE,
+    student_id          UUID NOT NULL,
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    -- Denormalized from the register so per-student history scans don't join.
+    attendance_date     DATE NOT NULL,
+    status              TEXT NOT NULL,
+    absence_reason      TEXT,
+    note                TEXT,
+    marked_by_user_id   UUID REFERENCES users(id) ON DELETE SET NULL,
+
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT attendance_records_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT attendance_records_register_student_unique UNIQUE (register_id, student_id),
+    CONSTRAINT attendance_records_status_chk CHECK (status IN ('present', 'absent', 'late', 'excused')),
+    CONSTRAINT attendance_records_reason_chk CHECK (absence_reason IS NULL OR status IN ('absent', 'excused'))
+);
+
+CREATE INDEX idx_attendance_records_student_date ON attendance_records(student_id, attendance_date DESC);
+CREATE INDEX idx_attendance_records_org_date ON attendance_records(org_id, attendance_date DESC);
+
+CREATE TRIGGER update_attendance_records_updated_at
+    BEFORE UPDATE ON attendance_records FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
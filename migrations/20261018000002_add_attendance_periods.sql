@@ -0,0 +1,31 @@
+-- Period-level attendance. A register is now either a daily register
+-- (period columns NULL) or a register for one lesson period of the bell
+-- schedule. school_schedule_periods is rebuilt on every setup save, so the
+-- period is snapshotted by label/position rather than referenced by FK.
+
+ALTER TABLE attendance_registers
+    ADD COLUMN IF NOT EXISTS period_label    TEXT,
+    ADD COLUMN IF NOT EXISTS period_position SMALLINT;
+
+ALTER TABLE attendance_registers
+    ADD CONSTRAINT attendance_registers_period_chk
+        CHECK ((period_label IS NULL) = (period_position IS NULL));
+
+DROP INDEX IF EXISTS idx_attendance_registers_class_day;
+CREATE UNIQUE INDEX idx_attendance_registers_class_day
+    ON attendance_registers(org_id, register_date, grade_level, COALESCE(section, ''), COALESCE(period_label, ''));
+
+-- period_label is denormalized onto records so daily stats can filter
+-- without joining registers. is_derived marks daily rows rolled up from
+-- period marks (as opposed to a register a teacher took directly).
+ALTER TABLE attendance_records
+    ADD COLUMN IF NOT EXISTS period_label TEXT,
+    ADD COLUMN IF NOT EXISTS is_derived   BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE attendance_records
+    ADD CONSTRAINT attendance_records_derived_chk
+        CHECK (NOT is_derived OR period_label IS NULL);
+
+CREATE INDEX idx_attendance_records_student_day
+    ON attendance_records(student_id, attendance_date)
+    WHERE period_label IS NOT NULL;
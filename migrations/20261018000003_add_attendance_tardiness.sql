@@ -0,0 +1,36 @@
+-- Late grace period and tardy-to-absence conversion.
+--
+-- arrival_time lets the server classify present vs late against the class
+-- start time plus school_configs.late_grace_period.
+--
+-- attendance_summaries caches each student's daily-mark totals with the
+-- school's tardies_to_absence conversion applied. It is refreshed whenever
+-- marks are written and rebuilt for the whole school when the policies
+-- section of school setup is saved.
+
+ALTER TABLE attendance_records
+    ADD COLUMN IF NOT EXISTS arrival_time TIME;
+
+CREATE TABLE IF NOT EXISTS attendance_summaries (
+    student_id          UUID PRIMARY KEY,
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    present             INTEGER NOT NULL DEFAULT 0,
+    late                INTEGER NOT NULL DEFAULT 0,
+    absent              INTEGER NOT NULL DEFAULT 0,
+    excused             INTEGER NOT NULL DEFAULT 0,
+    -- Lates counted as absences: floor(late / tardies_to_absence).
+    converted_lates     INTEGER NOT NULL DEFAULT 0,
+    attendance_rate     DOUBLE PRECISION,
+
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT attendance_summaries_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_attendance_summaries_org_id ON attendance_summaries(org_id);
+
+CREATE TRIGGER update_attendance_summaries_updated_at
+    BEFORE UPDATE ON attendance_summaries FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,65 @@
+-- Attendance alerting: flags raised by the scheduled evaluator, and a
+-- notifications outbox for messages to guardians.
+
+-- ── attendance_flags: one open flag per student per kind ─────────────
+
+CREATE TABLE IF NOT EXISTS attendance_flags (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id          UUID NOT NULL,
+
+    kind                TEXT NOT NULL,
+    -- Consecutive absent days, or the attendance rate, at the latest evaluation.
+    value               DOUBLE PRECISION NOT NULL,
+    -- The school's setting the value was compared against.
+    threshold           DOUBLE PRECISION NOT NULL,
+
+    flagged_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    last_evaluated_at   TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    resolved_at         TIMESTAMPTZ,
+
+    CONSTRAINT attendance_flags_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT attendance_flags_kind_chk CHECK (kind IN ('consecutive_absence', 'below_threshold'))
+);
+
+CREATE UNIQUE INDEX idx_attendance_flags_open
+    ON attendance_flags(student_id, kind) WHERE resolved_at IS NULL;
+CREATE INDEX idx_attendance_flags_org_flagged ON attendance_flags(org_id, flagged_at DESC);
+
+-- ── notifications: outbox, one row per recipient per channel ─────────
+
+CREATE TABLE IF NOT EXISTS notifications (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id          UUID,
+    guardian_id         UUID REFERENCES student_guardians(id) ON DELETE SET NULL,
+
+    kind                TEXT NOT NULL,
+    channel             TEXT NOT NULL,
+    -- Email address or phone number, snapshotted when queued.
+    recipient           TEXT NOT NULL,
+    subject             TEXT,
+    body                TEXT NOT NULL,
+    -- Row that caused the notification (e.g. an attendance flag).
+    source_id           UUID,
+
+    status              TEXT NOT NULL DEFAULT 'pending',
+    sent_at             TIMESTAMPTZ,
+
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT notifications_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT notifications_channel_chk CHECK (channel IN ('email', 'sms')),
+    CONSTRAINT notifications_status_chk CHECK (status IN ('pending', 'sent', 'failed'))
+);
+
+CREATE INDEX idx_notifications_pending ON notifications(created_at) WHERE status = 'pending';
+CREATE INDEX idx_notifications_student ON notifications(student_id, created_at DESC);
+CREATE INDEX idx_notifications_org_created ON notifications(org_id, created_at DESC);
+
+CREATE TRIGGER update_notifications_updated_at
+    BEFORE UPDATE ON notifications FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
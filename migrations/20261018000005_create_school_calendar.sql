@@ -0,0 +1,66 @@
+-- School calendar: typed term dates, the school week, and dated events
+-- (mid-term breaks, public holidays, closures) that take days out of term.
+
+-- Term dates were free TEXT. YYYY-MM-DD values, and ISO datetimes from date
+-- pickers (taken by their date part), become DATE. Anything else, including
+-- impossible dates like 2024-02-30, becomes NULL, with the original kept in
+-- legacy_start_date / legacy_end_date so it can be re-entered by hand.
+CREATE FUNCTION school_terms_try_date(v TEXT) RETURNS DATE
+LANGUAGE plpgsql IMMUTABLE AS $$
+BEGIN
+    IF v IS NULL OR TRIM(v) !~ '^\d{4}-\d{2}-\d{2}' THEN
+        RETURN NULL;
+    END IF;
+    RETURN substring(TRIM(v), 1, 10)::date;
+EXCEPTION WHEN others THEN
+    RETURN NULL;
+END;
+$$;
+
+ALTER TABLE school_terms
+    ADD COLUMN IF NOT EXISTS legacy_start_date TEXT,
+    ADD COLUMN IF NOT EXISTS legacy_end_date TEXT;
+
+UPDATE school_terms SET
+    legacy_start_date = CASE
+        WHEN TRIM(start_date) <> '' AND school_terms_try_date(start_date) IS NULL THEN start_date
+    END,
+    legacy_end_date = CASE
+        WHEN TRIM(end_date) <> '' AND school_terms_try_date(end_date) IS NULL THEN end_date
+    END;
+
+ALTER TABLE school_terms
+    ALTER COLUMN start_date TYPE DATE USING school_terms_try_date(start_date),
+    ALTER COLUMN end_date TYPE DATE USING school_terms_try_date(end_date);
+
+DROP FUNCTION school_terms_try_date(TEXT);
+
+-- Weekdays the school is open, e.g. ["monday", …, "friday"]. Empty means Monday–Friday.
+ALTER TABLE school_configs
+    ADD COLUMN IF NOT EXISTS school_days JSONB NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS school_calendar_events (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    kind                TEXT NOT NULL,
+    name                TEXT NOT NULL,
+    start_date          DATE NOT NULL,
+    end_date            DATE NOT NULL,
+    notes               TEXT,
+
+    created_by_user_id  UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT school_calendar_events_kind_chk
+        CHECK (kind IN ('mid_term_break', 'public_holiday', 'closure')),
+    CONSTRAINT school_calendar_events_dates_chk CHECK (end_date >= start_date)
+);
+
+CREATE INDEX idx_school_calendar_events_org_dates
+    ON school_calendar_events(org_id, start_date, end_date);
+
+CREATE TRIGGER update_school_calendar_events_updated_at
+    BEFORE UPDATE ON school_calendar_events FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
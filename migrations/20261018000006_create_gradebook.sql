@@ -0,0 +1,80 @@
+-- Gradebook: component scores per student, subject and term.
+--
+-- Subjects and terms are stored by name rather than by id because the
+-- school setup child tables are rebuilt (new ids) on every save.
+--
+-- gradebook_sheets is one class's sheet for a subject and term; it carries
+-- the max score per component and the publish lock.
+-- gradebook_scores holds one row per student per subject per term, with a
+-- column per assessment component from the grading section.
+
+CREATE TABLE IF NOT EXISTS gradebook_sheets (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    academic_year           TEXT NOT NULL,
+    term                    TEXT NOT NULL,
+    subject                 TEXT NOT NULL,
+    grade_level             TEXT NOT NULL,
+    section                 TEXT,
+
+    -- Component → max score, e.g. {"ca": 40, "exam": 100}. Missing
+    -- components default to their weight.
+    max_scores              JSONB NOT NULL DEFAULT '{}',
+
+    status                  TEXT NOT NULL DEFAULT 'draft',
+    published_at            TIMESTAMPTZ,
+    published_by_user_id    UUID REFERENCES users(id) ON DELETE SET NULL,
+
+    created_by_user_id      UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT gradebook_sheets_status_chk CHECK (status IN ('draft', 'published'))
+);
+
+CREATE UNIQUE INDEX idx_gradebook_sheets_class
+    ON gradebook_sheets(org_id, academic_year, term, subject, grade_level, COALESCE(section, ''));
+
+CREATE TRIGGER update_gradebook_sheets_updated_at
+    BEFORE UPDATE ON gradebook_sheets FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS gradebook_scores (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    sheet_id                UUID NOT NULL REFERENCES gradebook_sheets(id) ON DELETE CASCADE,
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+
+    -- Denormalised from the sheet so a student has one row per subject
+    -- per term even if they change class mid-term.
+    academic_year           TEXT NOT NULL,
+    term                    TEXT NOT NULL,
+    subject                 TEXT NOT NULL,
+
+    ca                      DOUBLE PRECISION,
+    exam                    DOUBLE PRECISION,
+    assignment              DOUBLE PRECISION,
+    test                    DOUBLE PRECISION,
+    project                 DOUBLE PRECISION,
+
+    entered_by_user_id      UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT gradebook_scores_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT gradebook_scores_non_negative_chk CHECK (
+        COALESCE(ca, 0) >= 0 AND COALESCE(exam, 0) >= 0 AND COALESCE(assignment, 0) >= 0
+        AND COALESCE(test, 0) >= 0 AND COALESCE(project, 0) >= 0
+    )
+);
+
+CREATE UNIQUE INDEX idx_gradebook_scores_student_subject_term
+    ON gradebook_scores(student_id, academic_year, term, subject);
+CREATE INDEX idx_gradebook_scores_sheet ON gradebook_scores(sheet_id);
+CREATE INDEX idx_gradebook_scores_org_term ON gradebook_scores(org_id, academic_year, term);
+
+CREATE TRIGGER update_gradebook_scores_updated_at
+    BEFORE UPDATE ON gradebook_scores FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
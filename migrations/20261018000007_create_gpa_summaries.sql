@@ -0,0 +1,27 @@
+-- Cached cumulative GPA per student.
+--
+-- Rebuilt from gradebook scores and the grading scale's gpa_points whenever
+-- scores are saved or the grading section changes, so the student list can
+-- sort and filter by GPA without grading every score on each request.
+-- Empty for schools with gpa_enabled off.
+
+CREATE TABLE IF NOT EXISTS gpa_summaries (
+    student_id          UUID PRIMARY KEY,
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    -- Mean gpa_points over every graded subject result, all terms.
+    cumulative_gpa      DOUBLE PRECISION NOT NULL,
+    terms               INTEGER NOT NULL DEFAULT 0,
+    subjects            INTEGER NOT NULL DEFAULT 0,
+
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT gpa_summaries_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_gpa_summaries_org_gpa ON gpa_summaries(org_id, cumulative_gpa);
+
+CREATE TRIGGER update_gpa_summaries_updated_at
+    BEFORE UPDATE ON gpa_summaries FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
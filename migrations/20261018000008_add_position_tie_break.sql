@@ -0,0 +1,7 @@
+-- How class and subject positions handle equal scores on report cards:
+--   shared     — tied students share a position and the next is skipped (1, 2, 2, 4)
+--   dense      — tied students share a position and the next isn't skipped (1, 2, 2, 3)
+--   exam_score — the higher exam score goes first; remaining ties are shared
+-- NULL behaves as 'shared'.
+ALTER TABLE school_configs
+    ADD COLUMN IF NOT EXISTS position_tie_break TEXT;
@@ -0,0 +1,40 @@
+-- Psychomotor and affective trait ratings per student per term.
+--
+-- Traits are the names in school_configs.psychomotor_traits and
+-- affective_traits; ratings are values from trait_rating_scale. Both are
+-- stored as text so renaming a trait or changing the scale leaves past
+-- ratings readable.
+
+-- Rating scale for traits, e.g. [{"rating": "5", "descriptor": "Excellent"}, …],
+-- highest first. Empty means the default 5-point scale.
+ALTER TABLE school_configs
+    ADD COLUMN IF NOT EXISTS trait_rating_scale JSONB NOT NULL DEFAULT '[]';
+
+CREATE TABLE IF NOT EXISTS student_trait_ratings (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+
+    academic_year           TEXT NOT NULL,
+    term                    TEXT NOT NULL,
+    category                TEXT NOT NULL,
+    trait                   TEXT NOT NULL,
+    rating                  TEXT NOT NULL,
+
+    rated_by_user_id        UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT student_trait_ratings_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT student_trait_ratings_category_chk CHECK (category IN ('psychomotor', 'affective'))
+);
+
+CREATE UNIQUE INDEX idx_student_trait_ratings_student_term_trait
+    ON student_trait_ratings(student_id, academic_year, term, category, trait);
+CREATE INDEX idx_student_trait_ratings_org_term
+    ON student_trait_ratings(org_id, academic_year, term);
+
+CREATE TRIGGER update_student_trait_ratings_updated_at
+    BEFORE UPDATE ON student_trait_ratings FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,65 @@
+-- Report card comments and per-school comment banks.
+--
+-- report_card_comments holds one comment per student per term for each
+-- kind: a subject teacher's comment per subject, the class teacher's
+-- comment and the principal's remark. subject is '' for the last two so
+-- the unique index can cover every kind.
+--
+-- comment_bank holds reusable comments. kind and grade narrow where an
+-- entry is suggested; NULL means any. grade is a grade from
+-- school_grading_scales, stored by name because that table is rebuilt on
+-- every setup save.
+
+CREATE TABLE IF NOT EXISTS report_card_comments (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+
+    academic_year           TEXT NOT NULL,
+    term                    TEXT NOT NULL,
+    kind                    TEXT NOT NULL,
+    subject                 TEXT NOT NULL DEFAULT '',
+    comment                 TEXT NOT NULL,
+
+    author_user_id          UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT report_card_comments_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT report_card_comments_kind_chk
+        CHECK (kind IN ('subject_teacher', 'class_teacher', 'principal')),
+    CONSTRAINT report_card_comments_subject_chk
+        CHECK ((kind = 'subject_teacher') = (subject <> ''))
+);
+
+CREATE UNIQUE INDEX idx_report_card_comments_student_term
+    ON report_card_comments(student_id, academic_year, term, kind, subject);
+CREATE INDEX idx_report_card_comments_org_term
+    ON report_card_comments(org_id, academic_year, term);
+
+CREATE TRIGGER update_report_card_comments_updated_at
+    BEFORE UPDATE ON report_card_comments FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS comment_bank (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+
+    kind                    TEXT,
+    grade                   TEXT,
+    comment                 TEXT NOT NULL,
+
+    created_by_user_id      UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT comment_bank_kind_chk
+        CHECK (kind IS NULL OR kind IN ('subject_teacher', 'class_teacher', 'principal'))
+);
+
+CREATE INDEX idx_comment_bank_org ON comment_bank(org_id, kind, grade);
+
+CREATE TRIGGER update_comment_bank_updated_at
+    BEFORE UPDATE ON comment_bank FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,74 @@
+-- Result publication workflow: score sheets move draft → submitted →
+-- approved → published, every move is logged, and publishing freezes the
+-- graded results.
+--
+-- gradebook_scores.frozen_result holds the total, grade and pass mark
+-- outcome computed when the sheet was published, so later changes to
+-- weights, the grading scale or the passmark don't alter published
+-- results. It is cleared when the sheet leaves published. Sheets published
+-- before this migration have no snapshot until they are next published.
+
+ALTER TABLE gradebook_sheets DROP CONSTRAINT gradebook_sheets_status_chk;
+ALTER TABLE gradebook_sheets
+    ADD CONSTRAINT gradebook_sheets_status_chk
+        CHECK (status IN ('draft', 'submitted', 'approved', 'published')),
+    ADD COLUMN IF NOT EXISTS submitted_at          TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS submitted_by_user_id  UUID REFERENCES users(id) ON DELETE SET NULL,
+    ADD COLUMN IF NOT EXISTS approved_at           TIMESTAMPTZ,
+    ADD COLUMN IF NOT EXISTS approved_by_user_id   UUID REFERENCES users(id) ON DELETE SET NULL;
+
+ALTER TABLE gradebook_scores ADD COLUMN IF NOT EXISTS frozen_result JSONB;
+
+-- ── result_status_history: audit log ─────────────────────────────────
+--
+-- The class, subject and term are copied from the sheet so the log
+-- survives the sheet being deleted.
+
+CREATE TABLE IF NOT EXISTS result_status_history (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    sheet_id            UUID REFERENCES gradebook_sheets(id) ON DELETE SET NULL,
+
+    academic_year       TEXT NOT NULL,
+    term                TEXT NOT NULL,
+    subject             TEXT NOT NULL,
+    grade_level         TEXT NOT NULL,
+    section             TEXT,
+
+    from_status         TEXT NOT NULL,
+    to_status           TEXT NOT NULL,
+    reason              TEXT,
+    changed_by_user_id  UUID REFERENCES users(id) ON DELETE SET NULL,
+    changed_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT result_status_history_from_chk
+        CHECK (from_status IN ('draft', 'submitted', 'approved', 'published')),
+    CONSTRAINT result_status_history_to_chk
+        CHECK (to_status IN ('draft', 'submitted', 'approved', 'published'))
+);
+
+CREATE INDEX idx_result_status_history_class
+    ON result_status_history(org_id, academic_year, term, grade_level, changed_at DESC);
+CREATE INDEX idx_result_status_history_sheet ON result_status_history(sheet_id, changed_at DESC);
+
+-- ── result_notices: guardians told a subject's results are out ───────
+--
+-- One row per student per subject and term. Each publish notifies guardians
+-- of the subjects newly out; republishing a subject doesn't notify again.
+-- A notification's source_id is the row for its first subject.
+
+CREATE TABLE IF NOT EXISTS result_notices (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id          UUID NOT NULL,
+    academic_year       TEXT NOT NULL,
+    term                TEXT NOT NULL,
+    subject             TEXT NOT NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT result_notices_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_result_notices_student_subject
+    ON result_notices(student_id, academic_year, term, subject);
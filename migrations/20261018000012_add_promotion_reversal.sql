@@ -0,0 +1,28 @@
+-- Promotion batches can be reverted. The reversal writes one 'revert'
+-- class history row per student carrying the reverted batch's
+-- promotion_batch_id, so a batch with revert rows has been undone.
+--
+-- student_status_history.promotion_batch_id ties the status rows a batch
+-- writes for graduates to that batch, so the status before graduation can
+-- be restored. Rows written before this migration are matched to their
+-- batch by student and transaction time.
+
+ALTER TABLE student_class_history DROP CONSTRAINT student_class_history_kind_chk;
+ALTER TABLE student_class_history
+    ADD CONSTRAINT student_class_history_kind_chk
+        CHECK (change_kind IN ('promote', 'retain', 'graduate', 'manual', 'revert'));
+
+ALTER TABLE student_status_history ADD COLUMN IF NOT EXISTS promotion_batch_id UUID;
+
+UPDATE student_status_history s
+SET promotion_batch_id = c.promotion_batch_id
+FROM student_class_history c
+WHERE c.change_kind = 'graduate'
+  AND c.promotion_batch_id IS NOT NULL
+  AND c.student_id = s.student_id
+  AND c.changed_at = s.changed_at
+  AND s.to_status = 'graduated'
+  AND s.promotion_batch_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_student_status_history_batch
+    ON student_status_history(promotion_batch_id) WHERE promotion_batch_id IS NOT NULL;
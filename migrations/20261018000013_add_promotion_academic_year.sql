@@ -0,0 +1,5 @@
+-- The academic year a promotion batch closed, from the request or the
+-- school's current academic year at the time. Written on every row of the
+-- batch and of its reversal; NULL for manual class changes and for batches
+-- run before this migration.
+ALTER TABLE student_class_history ADD COLUMN IF NOT EXISTS academic_year TEXT;
@@ -0,0 +1,54 @@
+-- Academic year rollover: closing a year archives its configuration and
+-- keeps the class rolls it ended with.
+
+-- ── academic_year_archives ───────────────────────────────────────────
+--
+-- The school's configuration as it stood when the year was closed: the
+-- school_configs row and the terms, grade levels, grading scale, subjects
+-- and fee rows, as JSON. One per closed academic year.
+
+CREATE TABLE IF NOT EXISTS academic_year_archives (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    academic_year       TEXT NOT NULL,
+    next_academic_year  TEXT NOT NULL,
+    configuration       JSONB NOT NULL,
+    promotion_batch_id  UUID NOT NULL,
+    archived_by_user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    archived_at         TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE UNIQUE INDEX idx_academic_year_archives_year
+    ON academic_year_archives(org_id, academic_year);
+
+-- ── academic_year_class_rolls ────────────────────────────────────────
+--
+-- Every active student's class at the close of an academic year. Names and
+-- admission numbers are copied so the roll reads as it was.
+
+CREATE TABLE IF NOT EXISTS academic_year_class_rolls (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id              UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    academic_year       TEXT NOT NULL,
+    student_id          UUID NOT NULL,
+    admission_number    TEXT NOT NULL,
+    first_name          TEXT NOT NULL,
+    last_name           TEXT NOT NULL,
+    grade_level         TEXT NOT NULL,
+    section             TEXT,
+    stream              TEXT,
+    captured_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT academic_year_class_rolls_student_org_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE
+);
+
+CREATE UNIQUE INDEX idx_academic_year_class_rolls_student
+    ON academic_year_class_rolls(org_id, academic_year, student_id);
+CREATE INDEX idx_academic_year_class_rolls_class
+    ON academic_year_class_rolls(org_id, academic_year, grade_level, section);
+
+-- Year printed in new admission numbers. A rollover sets it to the start
+-- year of the new academic year and restarts the sequence; NULL uses the
+-- calendar year in the school's timezone.
+ALTER TABLE school_configs ADD COLUMN IF NOT EXISTS admission_number_year SMALLINT;
@@ -0,0 +1,63 @@
+-- Fee invoices generated from school_fee_categories. Each invoice bills one
+-- student for one term of an academic year; its lines copy the category
+-- name, type, frequency and amount at the time, since categories are
+-- rewritten whenever the fees section of school setup is saved.
+
+CREATE TABLE IF NOT EXISTS fee_invoices (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+    invoice_number          TEXT NOT NULL,
+    academic_year           TEXT NOT NULL,
+    term                    TEXT NOT NULL,
+    -- The student's class and boarding status the invoice was priced for.
+    grade_level             TEXT NOT NULL,
+    section                 TEXT,
+    boarding_status         TEXT,
+    currency                TEXT,
+    total_amount            NUMERIC(14, 2) NOT NULL DEFAULT 0,
+    issue_date              DATE NOT NULL DEFAULT CURRENT_DATE,
+    due_date                DATE NOT NULL,
+    status                  TEXT NOT NULL DEFAULT 'issued',
+    void_reason             TEXT,
+    voided_at               TIMESTAMPTZ,
+    voided_by_user_id       UUID REFERENCES users(id) ON DELETE SET NULL,
+    -- Set on the invoice a regeneration issued in place of a voided one.
+    replaces_invoice_id     UUID REFERENCES fee_invoices(id) ON DELETE SET NULL,
+    created_by_user_id      UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fee_invoices_student_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT fee_invoices_status_chk CHECK (status IN ('issued', 'void')),
+    CONSTRAINT fee_invoices_void_chk CHECK ((status = 'void') = (voided_at IS NOT NULL))
+);
+
+CREATE UNIQUE INDEX idx_fee_invoices_number ON fee_invoices(org_id, invoice_number);
+-- One live invoice per student per term.
+CREATE UNIQUE INDEX idx_fee_invoices_student_term
+    ON fee_invoices(org_id, student_id, academic_year, term) WHERE status = 'issued';
+CREATE INDEX idx_fee_invoices_org_term ON fee_invoices(org_id, academic_year, term);
+
+CREATE TRIGGER update_fee_invoices_updated_at
+    BEFORE UPDATE ON fee_invoices FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS fee_invoice_lines (
+    id                  UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    invoice_id          UUID NOT NULL REFERENCES fee_invoices(id) ON DELETE CASCADE,
+    category            TEXT NOT NULL,
+    fee_type            TEXT,
+    frequency           TEXT,
+    -- Billing periods covered, e.g. months in the term for a monthly fee.
+    quantity            NUMERIC(6, 2) NOT NULL DEFAULT 1,
+    unit_amount         NUMERIC(14, 2) NOT NULL,
+    amount              NUMERIC(14, 2) NOT NULL,
+    position            SMALLINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX idx_fee_invoice_lines_invoice ON fee_invoice_lines(invoice_id);
+
+-- Next invoice number per school, as for admission numbers.
+ALTER TABLE school_configs ADD COLUMN IF NOT EXISTS invoice_next_seq INTEGER NOT NULL DEFAULT 1;
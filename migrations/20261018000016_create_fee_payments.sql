@@ -0,0 +1,45 @@
+-- Payments received from students, and how each is applied to their fee
+-- invoices. Whatever part of a payment isn't allocated is the student's
+-- credit, applied to the next invoice they're issued.
+
+CREATE TABLE IF NOT EXISTS fee_payments (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+    receipt_number          TEXT NOT NULL,
+    amount                  NUMERIC(14, 2) NOT NULL,
+    currency                TEXT,
+    method                  TEXT NOT NULL,
+    -- Bank or card reference, teller number, etc.
+    reference               TEXT,
+    paid_on                 DATE NOT NULL DEFAULT CURRENT_DATE,
+    notes                   TEXT,
+    recorded_by_user_id     UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fee_payments_student_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT fee_payments_amount_chk CHECK (amount > 0),
+    CONSTRAINT fee_payments_method_chk CHECK (method IN ('cash', 'bank_transfer', 'card'))
+);
+
+CREATE UNIQUE INDEX idx_fee_payments_receipt ON fee_payments(org_id, receipt_number);
+CREATE INDEX idx_fee_payments_student ON fee_payments(org_id, student_id, paid_on DESC);
+CREATE INDEX idx_fee_payments_org_date ON fee_payments(org_id, paid_on);
+
+CREATE TABLE IF NOT EXISTS fee_payment_allocations (
+    id              UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    payment_id      UUID NOT NULL REFERENCES fee_payments(id) ON DELETE CASCADE,
+    invoice_id      UUID NOT NULL REFERENCES fee_invoices(id) ON DELETE CASCADE,
+    amount          NUMERIC(14, 2) NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT fee_payment_allocations_amount_chk CHECK (amount > 0)
+);
+
+CREATE UNIQUE INDEX idx_fee_payment_allocations_pair
+    ON fee_payment_allocations(payment_id, invoice_id);
+CREATE INDEX idx_fee_payment_allocations_invoice ON fee_payment_allocations(invoice_id);
+
+-- Next receipt number per school, as for invoice numbers.
+ALTER TABLE school_configs ADD COLUMN IF NOT EXISTS receipt_next_seq INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,59 @@
+-- Discounts and scholarships on fee invoices. A discount type from school
+-- setup (school_fee_discounts) reaches a student by assignment, or
+-- automatically by sibling position; a scholarship is assigned with its own
+-- percentage. Invoice lines record every discount that reduced them.
+
+-- Set on a discount type given automatically to the child in this position
+-- among siblings (eldest = 1). The highest position also covers the
+-- children after it.
+ALTER TABLE school_fee_discounts ADD COLUMN IF NOT EXISTS sibling_position SMALLINT;
+
+CREATE TABLE IF NOT EXISTS student_fee_discounts (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    org_id                  UUID NOT NULL REFERENCES organizations(id) ON DELETE CASCADE,
+    student_id              UUID NOT NULL,
+    kind                    TEXT NOT NULL DEFAULT 'discount',
+    -- A discount type's name, or the scholarship's own.
+    name                    TEXT NOT NULL,
+    -- NULL: the discount type's percentage and applies_to at invoicing time.
+    percentage              NUMERIC(5, 2),
+    applies_to              TEXT,
+    -- NULL: every academic year.
+    academic_year           TEXT,
+    reason                  TEXT,
+    assigned_by_user_id     UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT student_fee_discounts_student_fk
+        FOREIGN KEY (student_id, org_id) REFERENCES students(id, org_id) ON DELETE CASCADE,
+    CONSTRAINT student_fee_discounts_kind_chk CHECK (kind IN ('discount', 'scholarship')),
+    CONSTRAINT student_fee_discounts_percentage_chk
+        CHECK (percentage IS NULL OR (percentage > 0 AND percentage <= 100))
+);
+
+CREATE UNIQUE INDEX idx_student_fee_discounts_unique
+    ON student_fee_discounts(org_id, student_id, LOWER(name), COALESCE(academic_year, ''));
+
+ALTER TABLE fee_invoice_lines
+    ADD COLUMN IF NOT EXISTS discount_amount NUMERIC(14, 2) NOT NULL DEFAULT 0;
+ALTER TABLE fee_invoices
+    ADD COLUMN IF NOT EXISTS discount_amount NUMERIC(14, 2) NOT NULL DEFAULT 0;
+
+CREATE TABLE IF NOT EXISTS fee_invoice_line_discounts (
+    id                      UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    line_id                 UUID NOT NULL REFERENCES fee_invoice_lines(id) ON DELETE CASCADE,
+    name                    TEXT NOT NULL,
+    kind                    TEXT NOT NULL,
+    -- assigned | sibling
+    source                  TEXT NOT NULL,
+    percentage              NUMERIC(5, 2) NOT NULL,
+    amount                  NUMERIC(14, 2) NOT NULL,
+    -- Why it applied, e.g. the sibling position.
+    detail                  TEXT,
+    student_discount_id     UUID REFERENCES student_fee_discounts(id) ON DELETE SET NULL,
+    position                SMALLINT NOT NULL DEFAULT 0,
+
+    CONSTRAINT fee_invoice_line_discounts_source_chk CHECK (source IN ('assigned', 'sibling'))
+);
+
+CREATE INDEX idx_fee_invoice_line_discounts_line ON fee_invoice_line_discounts(line_id);
@@ -8,6 +8,8 @@ pub struct AppConfig {
     pub workos: WorkOsConfig,
     pub auth: AuthConfig,
     pub cors: CorsConfig,
+    pub jobs: JobsConfig,
+    pub report_cards: ReportCardsConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -87,6 +89,18 @@ pub struct CorsConfig {
     pub base_domain: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct JobsConfig {
+    /// Seconds between attendance alert evaluations. 0 disables the job.
+    pub attendance_alerts_interval_secs: u64,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ReportCardsConfig {
+    /// DEV/TEST ONLY: let `logo_url` point at loopback or private addresses.
+    pub allow_private_logo_hosts: bool,
+}
+
 /// Accepts either a JSON array of strings or a comma-separated string.
 fn deserialize_string_or_vec<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
@@ -0,0 +1,179 @@
+use axum::extract::{Path, Query, State};
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::attendance::{
+    AttendanceAlertRunResponse, AttendanceFlagQuery, AttendanceFlagResponse, ClassPeriodsQuery, ClassPeriodsResponse, MarkRegisterRequest, RegisterQuery, RegisterResponse,
+    StudentAttendanceQuery, StudentAttendanceResponse,
+};
+use crate::models::auth::{CurrentUser, ErrorResponse};
+use crate::state::AppState;
+
+use super::students::{resolve_admin_and_org, resolve_user_and_org};
+
+/// Take or update a class register for one day, or for one lesson period when
+/// `period_id` is set. Any staff member of the school may mark attendance;
+/// marks are upserted per student.
+#[utoipa::path(
+    put,
+    path = "/api/v1/attendance/registers",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = MarkRegisterRequest,
+    responses(
+        (status = 200, description = "Register after applying the marks", body = RegisterResponse),
+        (status = 400, description = "Invalid status / reason / date / period, student not in this class, or mode not enabled for the class", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student or schedule period not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn mark_register(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<MarkRegisterRequest>,
+) -> Result<Json<RegisterResponse>, AppError> {
+    let (user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state
+        .attendance_service
+        .mark_register(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get the register for a class and date: the roster with any marks taken so far.
+#[utoipa::path(
+    get,
+    path = "/api/v1/attendance/registers",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("date" = chrono::NaiveDate, Query, description = "Register date (YYYY-MM-DD)"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+        ("period_id" = Option<uuid::Uuid>, Query, description = "Lesson period; omit for the daily register"),
+    ),
+    responses(
+        (status = 200, description = "Class register", body = RegisterResponse),
+        (status = 400, description = "Unknown grade_level, or period not on this class's schedule", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Schedule period not found", body = ErrorResponse),
+    )
+)]
+pub async fn get_register(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<RegisterQuery>,
+) -> Result<Json<RegisterResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.attendance_service.get_register(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Tracking mode and attendable lesson periods for a grade level.
+#[utoipa::path(
+    get,
+    path = "/api/v1/attendance/periods",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+    ),
+    responses(
+        (status = 200, description = "Tracking mode and non-break periods", body = ClassPeriodsResponse),
+        (status = 400, description = "Unknown grade_level", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn class_periods(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ClassPeriodsQuery>,
+) -> Result<Json<ClassPeriodsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.attendance_service.class_periods(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Attendance history for one student, with counts and rate over the window.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/attendance",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("from" = Option<chrono::NaiveDate>, Query, description = "Inclusive start date"),
+        ("to" = Option<chrono::NaiveDate>, Query, description = "Inclusive end date"),
+    ),
+    responses(
+        (status = 200, description = "Student attendance history", body = StudentAttendanceResponse),
+        (status = 400, description = "'from' after 'to'", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn student_attendance(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentAttendanceQuery>,
+) -> Result<Json<StudentAttendanceResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state
+        .attendance_service
+        .student_history(org_id, id, q)
+        .await?;
+    Ok(Json(response))
+}
+
+/// Students flagged for consecutive absences or a rate below the school's
+/// attendance threshold.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/attendance-flags",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("status" = Option<String>, Query, description = "open (default) | resolved | all"),
+        ("kind" = Option<String>, Query, description = "consecutive_absence | below_threshold"),
+        ("grade_level" = Option<String>, Query, description = "Exact grade level"),
+    ),
+    responses(
+        (status = 200, description = "Attendance flags, newest first", body = Vec<AttendanceFlagResponse>),
+        (status = 400, description = "Invalid status or kind", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_flags(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<AttendanceFlagQuery>,
+) -> Result<Json<Vec<AttendanceFlagResponse>>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let flags = state.attendance_service.list_flags(org_id, q).await?;
+    Ok(Json(flags))
+}
+
+/// Run the attendance alert evaluator for the admin's school now, instead of
+/// waiting for the scheduled job.
+#[utoipa::path(
+    post,
+    path = "/api/v1/attendance/alerts/run",
+    tag = "Attendance",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    responses(
+        (status = 200, description = "Flags opened/resolved and notifications queued", body = AttendanceAlertRunResponse),
+        (status = 400, description = "attendance_alerts is turned off", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Not an admin", body = ErrorResponse),
+    )
+)]
+pub async fn run_alerts(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+) -> Result<Json<AttendanceAlertRunResponse>, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let run = state.attendance_service.evaluate_alerts(org_id).await?;
+    Ok(Json(run))
+}
@@ -0,0 +1,163 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::auth::{CurrentUser, ErrorResponse};
+use crate::models::calendar::{
+    CalendarDayQuery, CalendarDayResponse, CalendarEventQuery, CalendarEventResponse,
+    CreateCalendarEventRequest, CurrentTermResponse, UpdateCalendarEventRequest,
+};
+use crate::state::AppState;
+
+use super::students::{resolve_admin_and_org, resolve_user_and_org};
+
+/// List mid-term breaks, public holidays and closures overlapping a window.
+#[utoipa::path(
+    get,
+    path = "/api/v1/calendar/events",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("from" = Option<chrono::NaiveDate>, Query, description = "Events ending on or after this date"),
+        ("to" = Option<chrono::NaiveDate>, Query, description = "Events starting on or before this date"),
+        ("kind" = Option<String>, Query, description = "mid_term_break | public_holiday | closure"),
+    ),
+    responses(
+        (status = 200, description = "Events in date order", body = Vec<CalendarEventResponse>),
+        (status = 400, description = "Invalid kind, or 'from' after 'to'", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_events(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<CalendarEventQuery>,
+) -> Result<Json<Vec<CalendarEventResponse>>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let events = state.calendar_service.list_events(org_id, q).await?;
+    Ok(Json(events))
+}
+
+/// Add a mid-term break, public holiday or closure.
+#[utoipa::path(
+    post,
+    path = "/api/v1/calendar/events",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = CreateCalendarEventRequest,
+    responses(
+        (status = 201, description = "Event created", body = CalendarEventResponse),
+        (status = 400, description = "Invalid kind, empty name, or end before start", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+    )
+)]
+pub async fn create_event(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateCalendarEventRequest>,
+) -> Result<(StatusCode, Json<CalendarEventResponse>), AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let event = state
+        .calendar_service
+        .create_event(org_id, req, Some(user_id))
+        .await?;
+    Ok((StatusCode::CREATED, Json(event)))
+}
+
+/// Partially update a calendar event.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/calendar/events/{id}",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Event id")),
+    request_body = UpdateCalendarEventRequest,
+    responses(
+        (status = 200, description = "Updated event", body = CalendarEventResponse),
+        (status = 400, description = "Invalid kind, empty name, or end before start", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Event not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn update_event(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateCalendarEventRequest>,
+) -> Result<Json<CalendarEventResponse>, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let event = state.calendar_service.update_event(org_id, id, req).await?;
+    Ok(Json(event))
+}
+
+/// Delete a calendar event.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/calendar/events/{id}",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Event id")),
+    responses(
+        (status = 204, description = "Event deleted"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Event not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn delete_event(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    state.calendar_service.delete_event(org_id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Whether a date is an instructional day, and why not if it isn't.
+#[utoipa::path(
+    get,
+    path = "/api/v1/calendar/day",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("date" = Option<chrono::NaiveDate>, Query, description = "Defaults to today in the school's timezone"),
+    ),
+    responses(
+        (status = 200, description = "Instructional status of the date", body = CalendarDayResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn day(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<CalendarDayQuery>,
+) -> Result<Json<CalendarDayResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.calendar_service.day(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// The term containing today in the school's timezone, and the next one.
+#[utoipa::path(
+    get,
+    path = "/api/v1/calendar/current-term",
+    tag = "Calendar",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    responses(
+        (status = 200, description = "Current and next term", body = CurrentTermResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn current_term(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+) -> Result<Json<CurrentTermResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.calendar_service.current_term(org_id).await?;
+    Ok(Json(response))
+}
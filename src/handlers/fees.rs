@@ -0,0 +1,355 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::auth::{CurrentUser, ErrorResponse};
+use crate::models::fees::{
+    AssignDiscountRequest, GenerateInvoicesRequest, GenerateInvoicesResponse, Invoice,
+    InvoiceListQuery, InvoiceListResponse, Payment, PaymentListQuery, PaymentListResponse,
+    RecordPaymentRequest, RegenerateInvoiceRequest, StudentDiscount, StudentDiscountsResponse,
+    StudentFeeStatement, VoidInvoiceRequest,
+};
+use crate::state::AppState;
+
+use super::students::{resolve_admin_and_org, resolve_user_and_org};
+
+/// Issue term invoices to active students from the school's fee categories,
+/// by each student's grade level and boarding status.
+#[utoipa::path(
+    post,
+    path = "/api/v1/fees/invoices/generate",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = GenerateInvoicesRequest,
+    responses(
+        (status = 200, description = "Invoices issued and students skipped", body = GenerateInvoicesResponse),
+        (status = 400, description = "Unknown term, grade level or category, no fee categories, or a category or term that can't be priced", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+    )
+)]
+pub async fn generate_invoices(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<GenerateInvoicesRequest>,
+) -> Result<Json<GenerateInvoicesResponse>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .fees_service
+        .generate_invoices(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Invoices, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/fees/invoices",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Exact academic year"),
+        ("term" = Option<String>, Query, description = "Term name"),
+        ("student_id" = Option<uuid::Uuid>, Query, description = "One student's invoices"),
+        ("grade_level" = Option<String>, Query, description = "Grade level the invoice was priced for"),
+        ("status" = Option<String>, Query, description = "issued | void"),
+        ("page" = Option<i64>, Query, description = "1-based page (default 1)"),
+        ("page_size" = Option<i64>, Query, description = "Default 25, max 100"),
+    ),
+    responses(
+        (status = 200, description = "Invoices", body = InvoiceListResponse),
+        (status = 400, description = "Invalid status", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_invoices(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<InvoiceListQuery>,
+) -> Result<Json<InvoiceListResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.list_invoices(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// One invoice with its lines.
+#[utoipa::path(
+    get,
+    path = "/api/v1/fees/invoices/{id}",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Invoice id")),
+    responses(
+        (status = 200, description = "Invoice", body = Invoice),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No invoice with that id in this school", body = ErrorResponse),
+    )
+)]
+pub async fn get_invoice(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Invoice>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.invoice(org_id, id).await?;
+    Ok(Json(response))
+}
+
+/// Void an invoice. It stays on record but no longer counts as billed.
+#[utoipa::path(
+    post,
+    path = "/api/v1/fees/invoices/{id}/void",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Invoice id")),
+    request_body = VoidInvoiceRequest,
+    responses(
+        (status = 200, description = "Voided invoice", body = Invoice),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "No invoice with that id in this school", body = ErrorResponse),
+        (status = 409, description = "Invoice is already void", body = ErrorResponse),
+    )
+)]
+pub async fn void_invoice(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<VoidInvoiceRequest>,
+) -> Result<Json<Invoice>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .fees_service
+        .void_invoice(org_id, id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Void an invoice and issue a replacement priced from the current fee
+/// categories and the student's current class and boarding status.
+#[utoipa::path(
+    post,
+    path = "/api/v1/fees/invoices/{id}/regenerate",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Invoice id")),
+    request_body = RegenerateInvoiceRequest,
+    responses(
+        (status = 200, description = "The replacement invoice", body = Invoice),
+        (status = 400, description = "Term no longer configured, or no fees apply any more", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "No invoice with that id in this school", body = ErrorResponse),
+        (status = 409, description = "Invoice is void", body = ErrorResponse),
+    )
+)]
+pub async fn regenerate_invoice(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<RegenerateInvoiceRequest>,
+) -> Result<Json<Invoice>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .fees_service
+        .regenerate_invoice(org_id, id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Record a payment from a student. It settles `invoice_id` first, then
+/// their other open invoices, oldest due first; any excess is held as
+/// credit for their next invoice.
+#[utoipa::path(
+    post,
+    path = "/api/v1/students/{id}/fees/payments",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Student id")),
+    request_body = RecordPaymentRequest,
+    responses(
+        (status = 201, description = "Payment recorded, with its allocations", body = Payment),
+        (status = 400, description = "Invalid amount, method or date, or an invoice that isn't this student's or is void", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Student not found", body = ErrorResponse),
+    )
+)]
+pub async fn record_payment(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<RecordPaymentRequest>,
+) -> Result<(StatusCode, Json<Payment>), AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .fees_service
+        .record_payment(org_id, id, req, Some(user_id))
+        .await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// A student's fee account: invoices, payments, balance and credit.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/fees",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Student id")),
+    responses(
+        (status = 200, description = "Fee statement", body = StudentFeeStatement),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found", body = ErrorResponse),
+    )
+)]
+pub async fn student_fees(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StudentFeeStatement>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.student_statement(org_id, id).await?;
+    Ok(Json(response))
+}
+
+/// Payments received, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/fees/payments",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("student_id" = Option<uuid::Uuid>, Query, description = "One student's payments"),
+        ("method" = Option<String>, Query, description = "cash | bank_transfer | card"),
+        ("from" = Option<chrono::NaiveDate>, Query, description = "Paid on or after"),
+        ("to" = Option<chrono::NaiveDate>, Query, description = "Paid on or before"),
+        ("page" = Option<i64>, Query, description = "1-based page (default 1)"),
+        ("page_size" = Option<i64>, Query, description = "Default 25, max 100"),
+    ),
+    responses(
+        (status = 200, description = "Payments", body = PaymentListResponse),
+        (status = 400, description = "Invalid method, or from after to", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_payments(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<PaymentListQuery>,
+) -> Result<Json<PaymentListResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.list_payments(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// One payment with the invoices it was applied to.
+#[utoipa::path(
+    get,
+    path = "/api/v1/fees/payments/{id}",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Payment id")),
+    responses(
+        (status = 200, description = "Payment", body = Payment),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No payment with that id in this school", body = ErrorResponse),
+    )
+)]
+pub async fn get_payment(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Payment>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.payment(org_id, id).await?;
+    Ok(Json(response))
+}
+
+/// A student's assigned discounts and scholarships, and the sibling
+/// discount they get automatically.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/fees/discounts",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Student id")),
+    responses(
+        (status = 200, description = "Discounts", body = StudentDiscountsResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found", body = ErrorResponse),
+    )
+)]
+pub async fn student_discounts(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StudentDiscountsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.fees_service.student_discounts(org_id, id).await?;
+    Ok(Json(response))
+}
+
+/// Assign a discount type from school setup, or a scholarship at its own
+/// percentage, to a student. Applies to invoices generated from now on.
+#[utoipa::path(
+    post,
+    path = "/api/v1/students/{id}/fees/discounts",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Student id")),
+    request_body = AssignDiscountRequest,
+    responses(
+        (status = 201, description = "Discount assigned", body = StudentDiscount),
+        (status = 400, description = "Unknown discount type, kind or applies_to, or an invalid percentage", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Student not found", body = ErrorResponse),
+        (status = 409, description = "Already assigned for that academic year", body = ErrorResponse),
+    )
+)]
+pub async fn assign_discount(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<AssignDiscountRequest>,
+) -> Result<(StatusCode, Json<StudentDiscount>), AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .fees_service
+        .assign_discount(org_id, id, req, Some(user_id))
+        .await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// Take a discount or scholarship off a student. Issued invoices keep it
+/// until regenerated.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/students/{id}/fees/discounts/{discount_id}",
+    tag = "Fees",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("discount_id" = uuid::Uuid, Path, description = "Assigned discount id"),
+    ),
+    responses(
+        (status = 204, description = "Discount removed"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Student or discount not found", body = ErrorResponse),
+    )
+)]
+pub async fn remove_discount(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path((id, discount_id)): Path<(Uuid, Uuid)>,
+) -> Result<StatusCode, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    state
+        .fees_service
+        .remove_discount(org_id, id, discount_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
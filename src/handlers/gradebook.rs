@@ -0,0 +1,453 @@
+use std::collections::HashMap;
+
+use axum::body::Body;
+use axum::extract::{Multipart, Path, Query, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::Response;
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::auth::{CurrentUser, ErrorResponse};
+use crate::models::gradebook::{
+    BroadsheetQuery, ClassResultStatusResponse, ClassResultsQuery, ClassResultsResponse, ClassStatisticsQuery,
+    ClassStatisticsResponse, ResultStatusQuery, SaveScoreSheetRequest, ScoreImportDefaults,
+    ScoreImportResponse, ScoreSheetQuery, ScoreSheetResponse, SetResultStatusRequest,
+    SheetStatusRequest, StudentResultsResponse, StudentScoresQuery, StudentScoresResponse,
+    StudentStatisticsQuery, StudentStatisticsResponse,
+};
+use crate::services::gradebook::publication::SheetStatus;
+use crate::state::AppState;
+
+use super::students::{resolve_admin_and_org, resolve_user_and_org};
+
+/// Enter or update a class's scores for one subject and term. Any staff
+/// member may enter scores; they are upserted per student and component.
+#[utoipa::path(
+    put,
+    path = "/api/v1/gradebook/sheets",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = SaveScoreSheetRequest,
+    responses(
+        (status = 200, description = "Score sheet after applying the entries", body = ScoreSheetResponse),
+        (status = 400, description = "Unknown term / subject / grade_level / component, score outside 0..max, or student not in this class", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+        (status = 409, description = "Sheet is past draft and locked", body = ErrorResponse),
+    )
+)]
+pub async fn save_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveScoreSheetRequest>,
+) -> Result<Json<ScoreSheetResponse>, AppError> {
+    let (user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state
+        .gradebook_service
+        .save_sheet(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get the score sheet for a class, subject and term: the roster with any
+/// scores entered so far.
+#[utoipa::path(
+    get,
+    path = "/api/v1/gradebook/sheets",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("subject" = String, Query, description = "Subject name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+    ),
+    responses(
+        (status = 200, description = "Score sheet", body = ScoreSheetResponse),
+        (status = 400, description = "Unknown term, subject or grade_level", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn get_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ScoreSheetQuery>,
+) -> Result<Json<ScoreSheetResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.get_sheet(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Move one score sheet a step through the workflow, or back to draft.
+/// Admin only; the change is recorded in the status history.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/gradebook/sheets/{id}/status",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Score sheet id")),
+    request_body = SheetStatusRequest,
+    responses(
+        (status = 200, description = "Score sheet with its new status", body = ScoreSheetResponse),
+        (status = 400, description = "Invalid status, a skipped step, or weights, passmark or grading scale not usable when publishing", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Score sheet not found", body = ErrorResponse),
+    )
+)]
+pub async fn set_sheet_status(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<SheetStatusRequest>,
+) -> Result<Json<ScoreSheetResponse>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .gradebook_service
+        .set_sheet_status(org_id, id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Import scores from a CSV file. Multipart form fields:
+/// - `file` (required): CSV bytes (up to 5000 rows)
+/// - `mapping` (required): JSON object mapping CSV header → `admission_number`, `academic_year`, `term`, `subject` or a component key
+/// - `academic_year`, `term`, `subject` (optional): apply to every row when the CSV has no such column
+/// - `skip_invalid` (optional): "true" to import valid rows even with errors; otherwise 422 on any error.
+///
+/// Any staff member may import scores. Returns 422 on validation errors when
+/// `skip_invalid != true` (no scores saved).
+#[utoipa::path(
+    post,
+    path = "/api/v1/gradebook/bulk-import",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body(content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Imported (with optional row errors)", body = ScoreImportResponse),
+        (status = 400, description = "Missing file or mapping / invalid mapping target / no score components enabled", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 422, description = "Validation errors and skip_invalid=false", body = ScoreImportResponse),
+    )
+)]
+pub async fn bulk_import(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> Result<(StatusCode, Json<ScoreImportResponse>), AppError> {
+    let (user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+
+    let mut file_bytes: Option<Vec<u8>> = None;
+    let mut mapping: Option<HashMap<String, String>> = None;
+    let mut defaults = ScoreImportDefaults::default();
+    let mut skip_invalid = false;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| AppError::BadRequest(format!("multipart error: {e}")))?
+    {
+        let name = field.name().unwrap_or("").to_string();
+        match name.as_str() {
+            "file" => {
+                let bytes = field
+                    .bytes()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("failed to read file: {e}")))?;
+                file_bytes = Some(bytes.to_vec());
+            }
+            "mapping" => {
+                let s = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("failed to read mapping: {e}")))?;
+                mapping = Some(
+                    serde_json::from_str(&s)
+                        .map_err(|e| AppError::BadRequest(format!("invalid mapping JSON: {e}")))?,
+                );
+            }
+            "skip_invalid" => {
+                let s = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("read skip_invalid: {e}")))?;
+                skip_invalid = matches!(s.as_str(), "true" | "1");
+            }
+            "academic_year" | "term" | "subject" => {
+                let s = field
+                    .text()
+                    .await
+                    .map_err(|e| AppError::BadRequest(format!("read {name}: {e}")))?;
+                let value = Some(s.trim().to_string()).filter(|v| !v.is_empty());
+                match name.as_str() {
+                    "academic_year" => defaults.academic_year = value,
+                    "term" => defaults.term = value,
+                    _ => defaults.subject = value,
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let file_bytes = file_bytes.ok_or_else(|| AppError::BadRequest("missing 'file' field".into()))?;
+    let mapping = mapping.ok_or_else(|| AppError::BadRequest("missing 'mapping' field".into()))?;
+
+    let (response, ok) = state
+        .gradebook_service
+        .bulk_import(org_id, &file_bytes, mapping, defaults, skip_invalid, Some(user_id))
+        .await?;
+
+    let status = if ok { StatusCode::OK } else { StatusCode::UNPROCESSABLE_ENTITY };
+    Ok((status, Json(response)))
+}
+
+/// Where a class's score sheets for a term are in the publication workflow,
+/// with the status history.
+#[utoipa::path(
+    get,
+    path = "/api/v1/gradebook/results/status",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm; omit for the whole grade level"),
+    ),
+    responses(
+        (status = 200, description = "Class result status", body = ClassResultStatusResponse),
+        (status = 400, description = "Unknown term or grade_level", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn result_status(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ResultStatusQuery>,
+) -> Result<Json<ClassResultStatusResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.result_status(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Move a class's results for a term one step through draft → submitted →
+/// approved → published, or back to draft. Any staff member may submit;
+/// approving, publishing and returning to draft need an admin. Publishing
+/// freezes the results and, with `exam_result_notify` on, notifies guardians.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/gradebook/results/status",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = SetResultStatusRequest,
+    responses(
+        (status = 200, description = "Class result status after the change", body = ClassResultStatusResponse),
+        (status = 400, description = "Invalid status, a sheet not ready for it, unknown term / subject / grade_level, or weights, passmark or grading scale not usable when publishing", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — only submitting is open to non-admins", body = ErrorResponse),
+        (status = 404, description = "No score sheets for this class and term", body = ErrorResponse),
+    )
+)]
+pub async fn set_result_status(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<SetResultStatusRequest>,
+) -> Result<Json<ClassResultStatusResponse>, AppError> {
+    let (user_id, org_id) = if SheetStatus::parse(&req.status)? == SheetStatus::Submitted {
+        resolve_user_and_org(&state, &current_user).await?
+    } else {
+        resolve_admin_and_org(&state, &current_user).await?
+    };
+    let response = state
+        .gradebook_service
+        .set_result_status(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Scores for one student across subjects and terms.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/scores",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Only this academic year"),
+        ("term" = Option<String>, Query, description = "Only this term"),
+    ),
+    responses(
+        (status = 200, description = "Student scores by subject and term", body = StudentScoresResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn student_scores(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentScoresQuery>,
+) -> Result<Json<StudentScoresResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.student_scores(org_id, id, q).await?;
+    Ok(Json(response))
+}
+
+/// Weighted term totals, grades and pass/fail for every student in a class.
+#[utoipa::path(
+    get,
+    path = "/api/v1/gradebook/results",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+        ("subject" = Option<String>, Query, description = "Only this subject"),
+    ),
+    responses(
+        (status = 200, description = "Class results", body = ClassResultsResponse),
+        (status = 400, description = "Unknown term / subject / grade_level, or weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn class_results(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ClassResultsQuery>,
+) -> Result<Json<ClassResultsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.class_results(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Weighted totals and grades for one student, per term.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/results",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Only this academic year"),
+        ("term" = Option<String>, Query, description = "Only this term"),
+    ),
+    responses(
+        (status = 200, description = "Student results by term", body = StudentResultsResponse),
+        (status = 400, description = "Weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn student_results(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentScoresQuery>,
+) -> Result<Json<StudentResultsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.student_results(org_id, id, q).await?;
+    Ok(Json(response))
+}
+
+/// Class and subject positions, class averages and highest/lowest totals.
+#[utoipa::path(
+    get,
+    path = "/api/v1/gradebook/statistics",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+    ),
+    responses(
+        (status = 200, description = "Class statistics", body = ClassStatisticsResponse),
+        (status = 400, description = "Unknown term / grade_level, or weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn class_statistics(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ClassStatisticsQuery>,
+) -> Result<Json<ClassStatisticsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.class_statistics(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// One student's class and subject positions for a term, with the class figures.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/statistics",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+    ),
+    responses(
+        (status = 200, description = "Student statistics", body = StudentStatisticsResponse),
+        (status = 400, description = "Unknown term, or weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found, or no results for that term", body = ErrorResponse),
+    )
+)]
+pub async fn student_statistics(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentStatisticsQuery>,
+) -> Result<Json<StudentStatisticsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.gradebook_service.student_statistics(org_id, id, q).await?;
+    Ok(Json(response))
+}
+
+/// Class broadsheet for a term as CSV (default) or XLSX: one row per student
+/// with each subject's component scores, total and grade, then the overall
+/// total, average and position.
+#[utoipa::path(
+    get,
+    path = "/api/v1/gradebook/broadsheet",
+    tag = "Gradebook",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+        ("format" = Option<String>, Query, description = "csv (default) or xlsx"),
+    ),
+    responses(
+        (status = 200, description = "CSV (text/csv) or XLSX file", content_type = "text/csv"),
+        (status = 400, description = "Unknown term, grade_level or format, or grading section not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No results for this class and term", body = ErrorResponse),
+    )
+)]
+pub async fn broadsheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<BroadsheetQuery>,
+) -> Result<Response, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let file = state.gradebook_service.broadsheet(org_id, q).await?;
+    let disposition =
+        HeaderValue::from_str(&format!("attachment; filename=\"{}\"", file.filename))
+            .map_err(|e| AppError::Internal(format!("invalid disposition header: {e}")))?;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, file.content_type)
+        .header(header::CONTENT_DISPOSITION, disposition)
+        // Student records; tell browsers and intermediaries not to cache.
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(file.bytes))
+        .map_err(|e| AppError::Internal(format!("response build: {e}")))
+}
@@ -1,4 +1,9 @@
+pub mod attendance;
 pub mod auth;
+pub mod calendar;
+pub mod fees;
+pub mod gradebook;
 pub mod health;
+pub mod report_cards;
 pub mod school_setup;
 pub mod students;
@@ -0,0 +1,437 @@
+use axum::body::Body;
+use axum::extract::{Path, Query, State};
+use axum::http::{header, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::auth::{CurrentUser, ErrorResponse};
+use crate::models::gradebook::StudentScoresQuery;
+use crate::models::report_cards::{
+    ClassReportCardsQuery, CommentBankQuery, CommentBankResponse, CommentSheetQuery,
+    CommentSheetResponse, CreateCommentBankRequest, ReportCard, ReportCardQuery,
+    SaveCommentSheetRequest, SaveTraitSheetRequest, StudentCommentsResponse,
+    StudentTraitRatingsResponse, TraitSheetQuery, TraitSheetResponse, Transcript, TranscriptQuery,
+    UpdateCommentBankRequest,
+};
+use crate::services::report_cards::comments::CommentKind;
+use crate::services::report_cards::{ReportFile, ReportFormat};
+use crate::state::AppState;
+
+use super::students::{resolve_admin_and_org, resolve_user_and_org};
+
+/// One student's term report card as PDF (default), HTML or JSON.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/report-card",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("format" = Option<String>, Query, description = "pdf (default), html or json"),
+    ),
+    responses(
+        (status = 200, description = "Report card (application/pdf, text/html or JSON)", body = ReportCard),
+        (status = 400, description = "Unknown term or format, or weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found, or no results for that term", body = ErrorResponse),
+    )
+)]
+pub async fn student_report_card(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<ReportCardQuery>,
+) -> Result<Response, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let format = ReportFormat::parse(q.format.as_deref())?;
+    let card = state
+        .report_card_service
+        .student_report_card(org_id, id, &q)
+        .await?;
+    if format == ReportFormat::Json {
+        return Ok(Json(card).into_response());
+    }
+    let file = state.report_card_service.render(&card, format).await?;
+    file_response(file, format == ReportFormat::Html)
+}
+
+/// A student's academic transcript over every term and year they have
+/// results in, as PDF (default) or JSON. Available whatever the student's
+/// status, including graduated and transferred students.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/transcript",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("format" = Option<String>, Query, description = "pdf (default) or json"),
+    ),
+    responses(
+        (status = 200, description = "Transcript (application/pdf or JSON)", body = Transcript),
+        (status = 400, description = "Unknown format, or weights, passmark or grading scale not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found", body = ErrorResponse),
+    )
+)]
+pub async fn student_transcript(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<TranscriptQuery>,
+) -> Result<Response, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let format = ReportFormat::parse(q.format.as_deref())?;
+    if format == ReportFormat::Html {
+        return Err(AppError::BadRequest(
+            "Transcripts are available as pdf or json".into(),
+        ));
+    }
+    let transcript = state.report_card_service.transcript(org_id, id).await?;
+    if format == ReportFormat::Json {
+        return Ok(Json(transcript).into_response());
+    }
+    let file = state
+        .report_card_service
+        .render_transcript(&transcript, format)
+        .await?;
+    file_response(file, false)
+}
+
+/// Report cards for a whole class as a ZIP of PDF (default) or HTML files.
+#[utoipa::path(
+    get,
+    path = "/api/v1/report-cards",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+        ("format" = Option<String>, Query, description = "pdf (default) or html"),
+    ),
+    responses(
+        (status = 200, description = "ZIP archive (application/zip)", content_type = "application/zip"),
+        (status = 400, description = "Unknown term, grade_level or format, or grading section not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No results for this class and term", body = ErrorResponse),
+    )
+)]
+pub async fn class_report_cards(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ClassReportCardsQuery>,
+) -> Result<Response, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let file = state.report_card_service.class_report_cards(org_id, q).await?;
+    file_response(file, false)
+}
+
+/// Enter or update psychomotor and affective trait ratings for a class in
+/// one term. Any staff member may rate; ratings are upserted per trait.
+#[utoipa::path(
+    put,
+    path = "/api/v1/report-cards/traits",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = SaveTraitSheetRequest,
+    responses(
+        (status = 200, description = "Trait sheet after applying the entries", body = TraitSheetResponse),
+        (status = 400, description = "Unknown term / grade_level / trait, rating not on the scale, or student not in this class", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn save_trait_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveTraitSheetRequest>,
+) -> Result<Json<TraitSheetResponse>, AppError> {
+    let (user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state
+        .report_card_service
+        .save_trait_sheet(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get a class's trait ratings for a term: the roster, the configured
+/// traits and rating scale, and any ratings entered so far.
+#[utoipa::path(
+    get,
+    path = "/api/v1/report-cards/traits",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+    ),
+    responses(
+        (status = 200, description = "Trait sheet", body = TraitSheetResponse),
+        (status = 400, description = "Unknown term or grade_level", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn get_trait_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<TraitSheetQuery>,
+) -> Result<Json<TraitSheetResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.report_card_service.get_trait_sheet(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Trait ratings for one student by term.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/traits",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Only this academic year"),
+        ("term" = Option<String>, Query, description = "Only this term"),
+    ),
+    responses(
+        (status = 200, description = "Student trait ratings by term", body = StudentTraitRatingsResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn student_traits(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentScoresQuery>,
+) -> Result<Json<StudentTraitRatingsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state
+        .report_card_service
+        .student_trait_ratings(org_id, id, q)
+        .await?;
+    Ok(Json(response))
+}
+
+/// Enter or update one kind of comment for a class in one term. Subject and
+/// class teacher comments are open to staff; the principal's remark needs
+/// an admin.
+#[utoipa::path(
+    put,
+    path = "/api/v1/report-cards/comments",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = SaveCommentSheetRequest,
+    responses(
+        (status = 200, description = "Comment sheet after applying the entries", body = CommentSheetResponse),
+        (status = 400, description = "Unknown kind / term / grade_level / subject, kind turned off, comment over the character limit, or student not in this class", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Principal remarks require admin", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn save_comment_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<SaveCommentSheetRequest>,
+) -> Result<Json<CommentSheetResponse>, AppError> {
+    let (user_id, org_id) = if CommentKind::parse(&req.kind)? == CommentKind::Principal {
+        resolve_admin_and_org(&state, &current_user).await?
+    } else {
+        resolve_user_and_org(&state, &current_user).await?
+    };
+    let response = state
+        .report_card_service
+        .save_comment_sheet(org_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Get a class's comments of one kind for a term, with each student's grade
+/// and suggestions from the comment bank.
+#[utoipa::path(
+    get,
+    path = "/api/v1/report-cards/comments",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+        ("term" = String, Query, description = "Term name from school setup"),
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Section / arm, if the class has one"),
+        ("kind" = String, Query, description = "subject_teacher | class_teacher | principal"),
+        ("subject" = Option<String>, Query, description = "Required for subject_teacher"),
+    ),
+    responses(
+        (status = 200, description = "Comment sheet", body = CommentSheetResponse),
+        (status = 400, description = "Unknown kind, term, grade_level or subject", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn get_comment_sheet(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<CommentSheetQuery>,
+) -> Result<Json<CommentSheetResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.report_card_service.get_comment_sheet(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Report card comments for one student.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/{id}/comments",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("id" = uuid::Uuid, Path, description = "Student id"),
+        ("academic_year" = Option<String>, Query, description = "Only this academic year"),
+        ("term" = Option<String>, Query, description = "Only this term"),
+    ),
+    responses(
+        (status = 200, description = "Student comments by term", body = StudentCommentsResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "Student not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn student_comments(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Query(q): Query<StudentScoresQuery>,
+) -> Result<Json<StudentCommentsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.report_card_service.student_comments(org_id, id, q).await?;
+    Ok(Json(response))
+}
+
+/// List the school's comment bank.
+#[utoipa::path(
+    get,
+    path = "/api/v1/report-cards/comment-bank",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("kind" = Option<String>, Query, description = "Entries for this kind, including those for any kind"),
+        ("grade" = Option<String>, Query, description = "Entries for this grade, including those for any grade"),
+    ),
+    responses(
+        (status = 200, description = "Comment bank entries, grade-specific first", body = Vec<CommentBankResponse>),
+        (status = 400, description = "Invalid kind", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_comment_bank(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<CommentBankQuery>,
+) -> Result<Json<Vec<CommentBankResponse>>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let entries = state.report_card_service.list_comment_bank(org_id, q).await?;
+    Ok(Json(entries))
+}
+
+/// Add a comment to the bank, optionally for one kind and grade.
+#[utoipa::path(
+    post,
+    path = "/api/v1/report-cards/comment-bank",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = CreateCommentBankRequest,
+    responses(
+        (status = 201, description = "Entry created", body = CommentBankResponse),
+        (status = 400, description = "Invalid kind, unknown grade, or empty / over-limit comment", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+    )
+)]
+pub async fn create_comment_bank_entry(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<CreateCommentBankRequest>,
+) -> Result<(StatusCode, Json<CommentBankResponse>), AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let entry = state
+        .report_card_service
+        .create_comment_bank_entry(org_id, req, Some(user_id))
+        .await?;
+    Ok((StatusCode::CREATED, Json(entry)))
+}
+
+/// Partially update a comment bank entry.
+#[utoipa::path(
+    patch,
+    path = "/api/v1/report-cards/comment-bank/{id}",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Comment bank entry id")),
+    request_body = UpdateCommentBankRequest,
+    responses(
+        (status = 200, description = "Updated entry", body = CommentBankResponse),
+        (status = 400, description = "Invalid kind, unknown grade, or empty / over-limit comment", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Entry not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn update_comment_bank_entry(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(req): Json<UpdateCommentBankRequest>,
+) -> Result<Json<CommentBankResponse>, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let entry = state
+        .report_card_service
+        .update_comment_bank_entry(org_id, id, req)
+        .await?;
+    Ok(Json(entry))
+}
+
+/// Delete a comment bank entry.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/report-cards/comment-bank/{id}",
+    tag = "Report Cards",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("id" = uuid::Uuid, Path, description = "Comment bank entry id")),
+    responses(
+        (status = 204, description = "Entry deleted"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "Entry not found in this school", body = ErrorResponse),
+    )
+)]
+pub async fn delete_comment_bank_entry(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    state.report_card_service.delete_comment_bank_entry(org_id, id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Download response; HTML can be shown inline so it opens in the browser.
+fn file_response(file: ReportFile, inline: bool) -> Result<Response, AppError> {
+    let kind = if inline { "inline" } else { "attachment" };
+    let disposition =
+        HeaderValue::from_str(&format!("{kind}; filename=\"{}\"", file.filename))
+            .map_err(|e| AppError::Internal(format!("invalid disposition header: {e}")))?;
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, file.content_type)
+        .header(header::CONTENT_DISPOSITION, disposition)
+        // Student records; tell browsers and intermediaries not to cache.
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(file.bytes))
+        .map_err(|e| AppError::Internal(format!("response build: {e}")))
+}
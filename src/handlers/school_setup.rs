@@ -189,12 +189,16 @@ fn assemble_json(data: &SchoolSetupData) -> serde_json::Value {
     let mut sec = serde_json::Map::new();
     insert_str(&mut sec, "calendar_type", &c.calendar_type);
     insert_str(&mut sec, "current_academic_year", &c.current_academic_year);
+    insert_json(&mut sec, "school_days", &c.school_days);
     if !data.terms.is_empty() {
         let terms: Vec<serde_json::Value> = data.terms.iter().map(|t| {
             let mut m = serde_json::Map::new();
             m.insert("name".into(), json!(t.name));
             if let Some(ref d) = t.start_date { m.insert("start_date".into(), json!(d)); }
             if let Some(ref d) = t.end_date { m.insert("end_date".into(), json!(d)); }
+            // Read-only: what the calendar migration couldn't convert.
+            if let Some(ref d) = t.legacy_start_date { m.insert("legacy_start_date".into(), json!(d)); }
+            if let Some(ref d) = t.legacy_end_date { m.insert("legacy_end_date".into(), json!(d)); }
             json!(m)
         }).collect();
         sec.insert("terms".into(), json!(terms));
@@ -291,7 +295,11 @@ fn assemble_json(data: &SchoolSetupData) -> serde_json::Value {
     insert_str(&mut sec, "late_fee_grace_days", &c.late_fee_grace_days);
     if !data.fee_discounts.is_empty() {
         let discs: Vec<serde_json::Value> = data.fee_discounts.iter().map(|d| {
-            json!({"name": d.name, "percentage": d.percentage, "applies_to": d.applies_to})
+            let mut disc = json!({"name": d.name, "percentage": d.percentage, "applies_to": d.applies_to});
+            if let Some(p) = d.sibling_position {
+                disc["sibling_position"] = json!(p);
+            }
+            disc
         }).collect();
         sec.insert("discount_types".into(), json!(discs));
     }
@@ -305,6 +313,7 @@ fn assemble_json(data: &SchoolSetupData) -> serde_json::Value {
     insert_bool(&mut sec, "show_highest_lowest", &c.show_highest_lowest);
     insert_bool(&mut sec, "show_grading_legend", &c.show_grading_legend);
     insert_bool(&mut sec, "show_position", &c.show_position);
+    insert_str(&mut sec, "position_tie_break", &c.position_tie_break);
     insert_bool(&mut sec, "show_gpa", &c.show_gpa);
     insert_bool(&mut sec, "show_effort_grades", &c.show_effort_grades);
     insert_bool(&mut sec, "show_behavior_rating", &c.show_behavior_rating);
@@ -312,6 +321,7 @@ fn assemble_json(data: &SchoolSetupData) -> serde_json::Value {
     insert_json(&mut sec, "psychomotor_traits", &c.psychomotor_traits);
     insert_bool(&mut sec, "show_affective", &c.show_affective);
     insert_json(&mut sec, "affective_traits", &c.affective_traits);
+    insert_json(&mut sec, "trait_rating_scale", &c.trait_rating_scale);
     insert_bool(&mut sec, "show_teacher_comments", &c.show_teacher_comments);
     insert_bool(&mut sec, "show_class_teacher_comment", &c.show_class_teacher_comment);
     insert_bool(&mut sec, "show_principal_signature", &c.show_principal_signature);
@@ -10,14 +10,18 @@ use uuid::Uuid;
 use crate::errors::AppError;
 use crate::models::auth::{CurrentUser, ErrorResponse};
 use crate::models::students::{
-    BulkImportResponse, ChangeClassRequest, ChangeStatusRequest, CreateStudentRequest,
-    PromoteRequest, PromoteSummary, StatusChangeResponse, StudentDetailQuery, StudentListQuery,
-    StudentListResponse, StudentResponse, UpdateStudentRequest,
+    AcademicYearArchive, BulkImportResponse, ChangeClassRequest, ChangeStatusRequest,
+    ClassRollQuery, ClassRollResponse, CreateStudentRequest, PromoteRequest, PromoteSummary,
+    PromotionBatchDetail, PromotionBatchListQuery, PromotionBatchListResponse,
+    PromotionSuggestionsQuery, PromotionSuggestionsResponse, RevertPromotionRequest,
+    RevertPromotionSummary, RolloverRequest, RolloverSummary, StatusChangeResponse,
+    StudentDetailQuery, StudentListQuery, StudentListResponse, StudentResponse,
+    UpdateStudentRequest,
 };
 use crate::state::AppState;
 
 /// Resolve the requesting user's local id and org_id.
-async fn resolve_user_and_org(
+pub(super) async fn resolve_user_and_org(
     state: &AppState,
     current_user: &CurrentUser,
 ) -> Result<(Uuid, Uuid), AppError> {
@@ -36,7 +40,7 @@ async fn resolve_user_and_org(
 /// All write endpoints in this module gate on this. Non-admin staff (teachers,
 /// office) can still call read endpoints; tightening this further requires
 /// proper school-staff roles, which don't exist yet.
-async fn resolve_admin_and_org(
+pub(super) async fn resolve_admin_and_org(
     state: &AppState,
     current_user: &CurrentUser,
 ) -> Result<(Uuid, Uuid), AppError> {
@@ -71,12 +75,14 @@ async fn resolve_admin_and_org(
         ("boarding_status" = Option<String>, Query, description = "day | boarding | weekly_boarding"),
         ("page" = Option<i64>, Query, description = "1-indexed page (default 1)"),
         ("page_size" = Option<i64>, Query, description = "Default 25, max 100"),
-        ("sort" = Option<String>, Query, description = "last_name | first_name | admission_number | enrollment_date | created_at"),
+        ("min_gpa" = Option<f64>, Query, description = "Cumulative GPA at least this"),
+        ("max_gpa" = Option<f64>, Query, description = "Cumulative GPA at most this"),
+        ("sort" = Option<String>, Query, description = "last_name | first_name | admission_number | enrollment_date | created_at | gpa"),
         ("order" = Option<String>, Query, description = "asc | desc"),
     ),
     responses(
         (status = 200, description = "Page of students with summary", body = StudentListResponse),
-        (status = 400, description = "User has no organization", body = ErrorResponse),
+        (status = 400, description = "User has no organization, or min_gpa above max_gpa", body = ErrorResponse),
         (status = 401, description = "Not authenticated", body = ErrorResponse),
     )
 )]
@@ -259,6 +265,10 @@ pub async fn change_class(
 
 /// Bulk promote / retain / graduate students. All decisions in one transaction;
 /// each writes a `student_class_history` row sharing a `promotion_batch_id`.
+/// With `dry_run` nothing is saved and the summary lists each student's
+/// class and status before and after.
+/// With `skip_invalid` failing decisions are reported in `errors` and the
+/// rest are applied.
 #[utoipa::path(
     post,
     path = "/api/v1/students/promote",
@@ -270,7 +280,7 @@ pub async fn change_class(
         (status = 400, description = "Invalid action / missing to_grade for promote / duplicate student_id", body = ErrorResponse),
         (status = 401, description = "Not authenticated", body = ErrorResponse),
         (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
-        (status = 404, description = "One or more student_ids not found", body = ErrorResponse),
+        (status = 404, description = "One or more student_ids not found, without skip_invalid", body = ErrorResponse),
     )
 )]
 pub async fn promote(
@@ -283,6 +293,226 @@ pub async fn promote(
     Ok(Json(response))
 }
 
+/// Past promotion batches, most recent first, with counts by decision.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/promotions",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = Option<String>, Query, description = "Only batches that closed this academic year"),
+        ("page" = Option<i64>, Query, description = "1-based page (default 1)"),
+        ("page_size" = Option<i64>, Query, description = "Default 25, max 100"),
+    ),
+    responses(
+        (status = 200, description = "Promotion batches", body = PromotionBatchListResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn list_promotion_batches(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<PromotionBatchListQuery>,
+) -> Result<Json<PromotionBatchListResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.students_service.list_promotion_batches(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// One promotion batch with each student's decision.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/promotions/{batch_id}",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("batch_id" = uuid::Uuid, Path, description = "promotion_batch_id returned by POST /students/promote")),
+    responses(
+        (status = 200, description = "Batch and decisions", body = PromotionBatchDetail),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No promotion batch with that id in this school", body = ErrorResponse),
+    )
+)]
+pub async fn get_promotion_batch(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(batch_id): Path<Uuid>,
+) -> Result<Json<PromotionBatchDetail>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.students_service.promotion_batch(org_id, batch_id).await?;
+    Ok(Json(response))
+}
+
+/// A promotion batch's decisions as CSV.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/promotions/{batch_id}/export",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("batch_id" = uuid::Uuid, Path, description = "promotion_batch_id returned by POST /students/promote")),
+    responses(
+        (status = 200, description = "CSV file", content_type = "text/csv"),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 404, description = "No promotion batch with that id in this school", body = ErrorResponse),
+    )
+)]
+pub async fn export_promotion_batch(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(batch_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let bytes = state.students_service.promotion_batch_csv(org_id, batch_id).await?;
+    let filename = format!("promotion_batch_{batch_id}.csv");
+    let disposition =
+        HeaderValue::from_str(&format!("attachment; filename=\"{filename}\""))
+            .map_err(|e| AppError::Internal(format!("invalid disposition header: {e}")))?;
+
+    let response = Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "text/csv; charset=utf-8")
+        .header(header::CONTENT_DISPOSITION, disposition)
+        // Sensitive PII; tell browsers and intermediaries not to cache.
+        .header(header::CACHE_CONTROL, "no-store")
+        .body(Body::from(bytes))
+        .map_err(|e| AppError::Internal(format!("response build: {e}")))?;
+    Ok(response)
+}
+
+/// Undo a promotion batch, restoring every student's class and status from
+/// before it. Refused if any of them has changed since.
+#[utoipa::path(
+    post,
+    path = "/api/v1/students/promotions/{batch_id}/revert",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(("batch_id" = uuid::Uuid, Path, description = "promotion_batch_id returned by POST /students/promote")),
+    request_body = RevertPromotionRequest,
+    responses(
+        (status = 200, description = "Batch reverted", body = RevertPromotionSummary),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 404, description = "No promotion batch with that id in this school", body = ErrorResponse),
+        (status = 409, description = "Batch already reverted, or students changed since", body = ErrorResponse),
+    )
+)]
+pub async fn revert_promotion(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Path(batch_id): Path<Uuid>,
+    Json(req): Json<RevertPromotionRequest>,
+) -> Result<Json<RevertPromotionSummary>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state
+        .students_service
+        .revert_promotion(org_id, batch_id, req, Some(user_id))
+        .await?;
+    Ok(Json(response))
+}
+
+/// Close the current academic year and open the next: every active student
+/// moves up a grade level (or graduates from the last), the year's class
+/// rolls and school configuration are archived, `current_academic_year` and
+/// the terms advance, and the admission number sequence restarts.
+#[utoipa::path(
+    post,
+    path = "/api/v1/students/rollover",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    request_body = RolloverRequest,
+    responses(
+        (status = 200, description = "Rollover summary", body = RolloverSummary),
+        (status = 400, description = "No current academic year or grade levels, invalid decision or term, or students in unconfigured grade levels", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+        (status = 409, description = "The current academic year has already been rolled over", body = ErrorResponse),
+    )
+)]
+pub async fn rollover(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Json(req): Json<RolloverRequest>,
+) -> Result<Json<RolloverSummary>, AppError> {
+    let (user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state.students_service.rollover(org_id, req, Some(user_id)).await?;
+    Ok(Json(response))
+}
+
+/// School configuration archived by each rollover, most recent first.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/rollover/archives",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    responses(
+        (status = 200, description = "Archived academic years", body = [AcademicYearArchive]),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn academic_year_archives(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+) -> Result<Json<Vec<AcademicYearArchive>>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.students_service.academic_year_archives(org_id).await?;
+    Ok(Json(response))
+}
+
+/// Class rolls of a closed academic year, as they stood at its rollover.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/class-rolls",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("academic_year" = String, Query, description = "A rolled-over academic year"),
+        ("grade_level" = Option<String>, Query, description = "Only this grade level"),
+        ("section" = Option<String>, Query, description = "Only this section"),
+    ),
+    responses(
+        (status = 200, description = "Class roll entries", body = ClassRollResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+    )
+)]
+pub async fn class_rolls(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<ClassRollQuery>,
+) -> Result<Json<ClassRollResponse>, AppError> {
+    let (_user_id, org_id) = resolve_user_and_org(&state, &current_user).await?;
+    let response = state.students_service.class_rolls(org_id, q).await?;
+    Ok(Json(response))
+}
+
+/// Proposed promote / retain / graduate decisions for a grade level, from
+/// `promotion_rules` and the year's results and attendance. Nothing is
+/// changed; edit the decisions and submit them to `POST /students/promote`.
+#[utoipa::path(
+    get,
+    path = "/api/v1/students/promotion-suggestions",
+    tag = "Students",
+    security(("session_cookie" = []), ("bearer_token" = [])),
+    params(
+        ("grade_level" = String, Query, description = "Grade level configured in school setup"),
+        ("section" = Option<String>, Query, description = "Only this section"),
+        ("academic_year" = Option<String>, Query, description = "Defaults to the current academic year"),
+    ),
+    responses(
+        (status = 200, description = "Suggested decisions", body = PromotionSuggestionsResponse),
+        (status = 400, description = "Unknown grade_level, no academic year, or grading section not usable", body = ErrorResponse),
+        (status = 401, description = "Not authenticated", body = ErrorResponse),
+        (status = 403, description = "Forbidden — requires admin", body = ErrorResponse),
+    )
+)]
+pub async fn promotion_suggestions(
+    Extension(current_user): Extension<CurrentUser>,
+    State(state): State<AppState>,
+    Query(q): Query<PromotionSuggestionsQuery>,
+) -> Result<Json<PromotionSuggestionsResponse>, AppError> {
+    let (_user_id, org_id) = resolve_admin_and_org(&state, &current_user).await?;
+    let response = state.students_service.suggest_promotions(org_id, q).await?;
+    Ok(Json(response))
+}
+
 /// Bulk import students from a CSV file. Multipart form fields:
 /// - `file` (required): CSV bytes (up to 5000 rows)
 /// - `mapping` (required): JSON object mapping CSV header → field key. Guardian fields use `guardian1_first_name` etc.
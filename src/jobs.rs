@@ -0,0 +1,35 @@
+//! Background jobs spawned alongside the HTTP server.
+
+use std::time::Duration;
+
+use tokio::time::MissedTickBehavior;
+
+use crate::state::AppState;
+
+/// Start the periodic attendance alert evaluator, unless
+/// `jobs.attendance_alerts_interval_secs` is 0. The first run happens at
+/// startup.
+pub fn spawn_attendance_alerts(state: &AppState) {
+    let secs = state.config.jobs.attendance_alerts_interval_secs;
+    if secs == 0 {
+        tracing::info!("Attendance alert job disabled");
+        return;
+    }
+    let service = state.attendance_service.clone();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(secs));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match service.evaluate_alerts_for_all().await {
+                Ok(run) => tracing::info!(
+                    flagged = run.flagged,
+                    resolved = run.resolved,
+                    notifications = run.notifications_queued,
+                    "Attendance alerts evaluated"
+                ),
+                Err(e) => tracing::error!(error = %e, "Attendance alert job failed"),
+            }
+        }
+    });
+}
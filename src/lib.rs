@@ -2,6 +2,7 @@ pub mod config;
 pub mod db;
 pub mod errors;
 pub mod handlers;
+pub mod jobs;
 pub mod middleware;
 pub mod models;
 pub mod routes;
@@ -57,8 +58,65 @@ use crate::state::AppState;
         handlers::students::change_status,
         handlers::students::change_class,
         handlers::students::promote,
+        handlers::students::promotion_suggestions,
+        handlers::students::list_promotion_batches,
+        handlers::students::get_promotion_batch,
+        handlers::students::export_promotion_batch,
+        handlers::students::revert_promotion,
+        handlers::students::rollover,
+        handlers::students::academic_year_archives,
+        handlers::students::class_rolls,
         handlers::students::bulk_import,
         handlers::students::export,
+        handlers::attendance::mark_register,
+        handlers::attendance::get_register,
+        handlers::attendance::class_periods,
+        handlers::attendance::student_attendance,
+        handlers::attendance::list_flags,
+        handlers::attendance::run_alerts,
+        handlers::calendar::list_events,
+        handlers::calendar::create_event,
+        handlers::calendar::update_event,
+        handlers::calendar::delete_event,
+        handlers::calendar::day,
+        handlers::calendar::current_term,
+        handlers::gradebook::save_sheet,
+        handlers::gradebook::get_sheet,
+        handlers::gradebook::set_sheet_status,
+        handlers::gradebook::bulk_import,
+        handlers::gradebook::result_status,
+        handlers::gradebook::set_result_status,
+        handlers::gradebook::student_scores,
+        handlers::gradebook::class_results,
+        handlers::gradebook::student_results,
+        handlers::gradebook::class_statistics,
+        handlers::gradebook::student_statistics,
+        handlers::gradebook::broadsheet,
+        handlers::report_cards::student_report_card,
+        handlers::report_cards::class_report_cards,
+        handlers::report_cards::student_transcript,
+        handlers::report_cards::save_trait_sheet,
+        handlers::report_cards::get_trait_sheet,
+        handlers::report_cards::student_traits,
+        handlers::report_cards::save_comment_sheet,
+        handlers::report_cards::get_comment_sheet,
+        handlers::report_cards::student_comments,
+        handlers::report_cards::list_comment_bank,
+        handlers::report_cards::create_comment_bank_entry,
+        handlers::report_cards::update_comment_bank_entry,
+        handlers::report_cards::delete_comment_bank_entry,
+        handlers::fees::generate_invoices,
+        handlers::fees::list_invoices,
+        handlers::fees::get_invoice,
+        handlers::fees::void_invoice,
+        handlers::fees::regenerate_invoice,
+        handlers::fees::record_payment,
+        handlers::fees::student_fees,
+        handlers::fees::list_payments,
+        handlers::fees::get_payment,
+        handlers::fees::student_discounts,
+        handlers::fees::assign_discount,
+        handlers::fees::remove_discount,
     ),
     components(schemas(
         models::user::UserResponse,
@@ -91,6 +149,22 @@ use crate::state::AppState;
         models::students::PromoteRequest,
         models::students::PromoteDecision,
         models::students::PromoteSummary,
+        models::students::PromoteChange,
+        models::students::PromoteError,
+        models::students::PromotionBatch,
+        models::students::PromotionBatchListResponse,
+        models::students::PromotionBatchDecision,
+        models::students::PromotionBatchDetail,
+        models::students::RevertPromotionRequest,
+        models::students::RevertPromotionSummary,
+        models::students::RolloverRequest,
+        models::students::RolloverTerm,
+        models::students::RolloverSummary,
+        models::students::AcademicYearArchive,
+        models::students::ClassRollEntry,
+        models::students::ClassRollResponse,
+        models::students::SuggestedDecision,
+        models::students::PromotionSuggestionsResponse,
         models::students::GuardianInput,
         models::students::GuardianResponse,
         models::students::StudentResponse,
@@ -102,6 +176,98 @@ use crate::state::AppState;
         models::students::BulkImportResponse,
         models::students::ImportRowError,
         models::students::ImportedStudent,
+        models::attendance::AttendanceMarkInput,
+        models::attendance::MarkRegisterRequest,
+        models::attendance::RegisterEntry,
+        models::attendance::RegisterResponse,
+        models::attendance::AttendanceCounts,
+        models::attendance::AttendanceEntry,
+        models::attendance::StudentAttendanceResponse,
+        models::attendance::LessonPeriod,
+        models::attendance::ClassPeriodsResponse,
+        models::attendance::AttendanceFlagResponse,
+        models::attendance::AttendanceAlertRunResponse,
+        models::calendar::CreateCalendarEventRequest,
+        models::calendar::UpdateCalendarEventRequest,
+        models::calendar::CalendarEventResponse,
+        models::calendar::TermSummary,
+        models::calendar::CalendarDayResponse,
+        models::calendar::InstructionalDayCount,
+        models::calendar::CurrentTermResponse,
+        models::gradebook::ScoreEntryInput,
+        models::gradebook::SaveScoreSheetRequest,
+        models::gradebook::SheetStatusRequest,
+        models::gradebook::ImportedSheet,
+        models::gradebook::ScoreImportResponse,
+        models::gradebook::SetResultStatusRequest,
+        models::gradebook::ResultSheetStatus,
+        models::gradebook::ResultStatusChange,
+        models::gradebook::ClassResultStatusResponse,
+        models::gradebook::ScoreComponent,
+        models::gradebook::ScoreSheetEntry,
+        models::gradebook::ScoreSheetResponse,
+        models::gradebook::StudentSubjectScores,
+        models::gradebook::StudentScoresResponse,
+        models::gradebook::SubjectResult,
+        models::gradebook::StudentTermResult,
+        models::gradebook::ClassResultsResponse,
+        models::gradebook::TermResults,
+        models::gradebook::StudentResultsResponse,
+        models::gradebook::SubjectStatistics,
+        models::gradebook::SubjectPosition,
+        models::gradebook::StudentPosition,
+        models::gradebook::ClassStatisticsResponse,
+        models::gradebook::StudentSubjectStatistics,
+        models::gradebook::StudentStatisticsResponse,
+        models::report_cards::ReportCardSchool,
+        models::report_cards::ReportCardStudent,
+        models::report_cards::ReportCardSubject,
+        models::report_cards::ReportCardSummary,
+        models::report_cards::ReportCardLegendBand,
+        models::report_cards::ReportCardAttendance,
+        models::report_cards::ReportCardNextTerm,
+        models::report_cards::ReportCardTrait,
+        models::report_cards::ReportCard,
+        models::report_cards::TranscriptStudent,
+        models::report_cards::TranscriptSubject,
+        models::report_cards::TranscriptTerm,
+        models::report_cards::Transcript,
+        models::report_cards::TraitRatingInput,
+        models::report_cards::SaveTraitSheetRequest,
+        models::report_cards::TraitRatingLevel,
+        models::report_cards::TraitSheetEntry,
+        models::report_cards::TraitSheetResponse,
+        models::report_cards::StudentTraitTerm,
+        models::report_cards::StudentTraitRatingsResponse,
+        models::report_cards::CommentInput,
+        models::report_cards::SaveCommentSheetRequest,
+        models::report_cards::CommentSheetEntry,
+        models::report_cards::CommentSheetResponse,
+        models::report_cards::StudentComment,
+        models::report_cards::StudentCommentsResponse,
+        models::report_cards::CreateCommentBankRequest,
+        models::report_cards::UpdateCommentBankRequest,
+        models::report_cards::CommentBankResponse,
+        models::fees::GenerateInvoicesRequest,
+        models::fees::GenerateInvoicesResponse,
+        models::fees::SkippedStudent,
+        models::fees::VoidInvoiceRequest,
+        models::fees::RegenerateInvoiceRequest,
+        models::fees::Invoice,
+        models::fees::InvoiceLine,
+        models::fees::InvoiceSummary,
+        models::fees::InvoiceListResponse,
+        models::fees::RecordPaymentRequest,
+        models::fees::Payment,
+        models::fees::PaymentAllocation,
+        models::fees::PaymentListResponse,
+        models::fees::RecentPayment,
+        models::fees::StudentFeeStatement,
+        models::fees::LineDiscount,
+        models::fees::AssignDiscountRequest,
+        models::fees::StudentDiscount,
+        models::fees::SiblingDiscount,
+        models::fees::StudentDiscountsResponse,
     )),
     modifiers(&SecurityAddon),
     tags(
@@ -109,6 +275,11 @@ use crate::state::AppState;
         (name = "Auth", description = "Authentication endpoints"),
         (name = "Schools", description = "School setup and branding endpoints"),
         (name = "Students", description = "Student records, guardians, status/class changes, promotion, CSV import/export"),
+        (name = "Attendance", description = "Daily and per-period class registers, per-student attendance history, absence alerts"),
+        (name = "Calendar", description = "Terms, school days, mid-term breaks, holidays and closures"),
+        (name = "Gradebook", description = "Score sheets per class, subject and term; weighted results and grades; result approval and publication; broadsheets"),
+        (name = "Report Cards", description = "Term report cards as PDF, HTML or JSON, class bundles as ZIP, trait ratings, comments and the comment bank"),
+        (name = "Fees", description = "Term invoices generated from the school's fee categories, the discounts and scholarships that reduce them, and the payments that settle them"),
     )
 )]
 struct ApiDoc;
@@ -144,8 +315,9 @@ impl Modify for SecurityAddon {
 pub fn build_router(state: AppState) -> Router {
     let cors = build_cors_layer(&state);
 
-    // Body limits are scoped per sub-router (see routes/{auth,schools,students}.rs):
-    // 1MB on most endpoints, 10MB on /api/v1/students/bulk-import only.
+    // Body limits are scoped per sub-router (see routes/{auth,schools,students,attendance,
+    // calendar,gradebook,report_cards,fees}.rs): 1MB on most endpoints, 10MB on the
+    // CSV uploads at /api/v1/students/bulk-import and /api/v1/gradebook/bulk-import.
     // tower-http's RequestBodyLimitLayer composes most-restrictive-wins, so a
     // global limit would cap the upload route too — hence the per-router setup.
     let api_routes = routes::build(state.clone())
@@ -35,6 +35,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Build app
     let state = schoolnify_api::state::AppState::new(config.clone(), db_pool);
+    schoolnify_api::jobs::spawn_attendance_alerts(&state);
     let app = schoolnify_api::build_router(state);
 
     // Start server
@@ -0,0 +1,286 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// ── DB Row Models ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AttendanceRegisterRow {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub register_date: NaiveDate,
+    pub grade_level: String,
+    pub section: Option<String>,
+    /// Null for a daily register; the lesson period's label otherwise.
+    pub period_label: Option<String>,
+    pub period_position: Option<i16>,
+    pub taken_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AttendanceRecordRow {
+    pub id: Uuid,
+    pub register_id: Uuid,
+    pub student_id: Uuid,
+    pub org_id: Uuid,
+    pub attendance_date: NaiveDate,
+    pub status: String,
+    pub absence_reason: Option<String>,
+    pub note: Option<String>,
+    pub marked_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub period_label: Option<String>,
+    /// Daily mark rolled up from period marks rather than taken directly.
+    pub is_derived: bool,
+    pub arrival_time: Option<NaiveTime>,
+}
+
+// ── Request DTOs ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AttendanceMarkInput {
+    pub student_id: Uuid,
+    /// present | absent | late | excused. May be omitted when `arrival_time`
+    /// is given; present vs late is then decided by the late grace period.
+    #[serde(default)]
+    pub status: Option<String>,
+    /// Arrival time as HH:MM (24h). Classified against the class start time
+    /// plus the school's `late_grace_period`.
+    #[serde(default)]
+    pub arrival_time: Option<String>,
+    /// Must be one of the school's `absence_categories`. Only for absent/excused.
+    #[serde(default)]
+    pub absence_reason: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MarkRegisterRequest {
+    pub date: NaiveDate,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// A lesson period from the class's bell schedule. Omit for a daily register.
+    #[serde(default)]
+    pub period_id: Option<Uuid>,
+    pub records: Vec<AttendanceMarkInput>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RegisterQuery {
+    pub date: NaiveDate,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    #[serde(default)]
+    pub period_id: Option<Uuid>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassPeriodsQuery {
+    pub grade_level: String,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct StudentAttendanceQuery {
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    #[serde(default)]
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct AttendanceFlagQuery {
+    /// open (default) | resolved | all
+    #[serde(default)]
+    pub status: Option<String>,
+    /// consecutive_absence | below_threshold
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub grade_level: Option<String>,
+}
+
+// ── Response DTOs ───────────────────────────────────────────────────────
+
+/// One line of a class register. `status` is null for students not yet marked.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegisterEntry {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absence_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// HH:MM, when the mark was taken from an arrival time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_time: Option<String>,
+    /// True when this daily mark was rolled up from the student's period marks.
+    pub is_derived: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RegisterResponse {
+    /// Null when no register has been taken for this class and date yet.
+    pub id: Option<Uuid>,
+    pub date: NaiveDate,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Present for period registers only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub taken_by: Option<Uuid>,
+    pub entries: Vec<RegisterEntry>,
+    pub counts: AttendanceCounts,
+}
+
+#[derive(Debug, Default, Clone, Serialize, ToSchema)]
+pub struct AttendanceCounts {
+    pub present: i64,
+    pub absent: i64,
+    pub late: i64,
+    pub excused: i64,
+    /// Lates counted as absences under the school's `tardies_to_absence` policy.
+    pub converted_lates: i64,
+}
+
+impl AttendanceCounts {
+    pub fn add(&mut self, status: &str, n: i64) {
+        match status {
+            "present" => self.present += n,
+            "absent" => self.absent += n,
+            "late" => self.late += n,
+            "excused" => self.excused += n,
+            _ => {}
+        }
+    }
+
+    /// Count every `tardies_to_absence` lates as one absence.
+    pub fn apply_tardy_conversion(&mut self, tardies_to_absence: Option<i64>) {
+        self.converted_lates = match tardies_to_absence {
+            Some(n) if n > 0 => self.late / n,
+            _ => 0,
+        };
+    }
+
+    /// Attended (present + late, less converted lates) as a percentage of
+    /// counted days, rounded to one decimal. Excused days are left out of the
+    /// denominator. None when there is nothing to count.
+    pub fn rate(&self) -> Option<f64> {
+        let counted = self.present + self.late + self.absent;
+        let attended = self.present + self.late - self.converted_lates;
+        if counted == 0 {
+            return None;
+        }
+        Some(round1(attended as f64 * 100.0 / counted as f64))
+    }
+}
+
+fn round1(v: f64) -> f64 {
+    (v * 10.0).round() / 10.0
+}
+
+/// Compact attendance entry used by `?include=recent_attendance` and the
+/// per-student history endpoint.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AttendanceEntry {
+    pub date: NaiveDate,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub absence_reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arrival_time: Option<String>,
+}
+
+impl From<AttendanceRecordRow> for AttendanceEntry {
+    fn from(r: AttendanceRecordRow) -> Self {
+        Self {
+            date: r.attendance_date,
+            status: r.status,
+            absence_reason: r.absence_reason,
+            note: r.note,
+            arrival_time: r.arrival_time.map(|t| t.format("%H:%M").to_string()),
+        }
+    }
+}
+
+/// A lesson period attendance can be taken against.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LessonPeriod {
+    pub id: Uuid,
+    pub label: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassPeriodsResponse {
+    pub grade_level: String,
+    /// Group the grade level belongs to (e.g. "Secondary"), if configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// daily | per_period | both
+    pub tracking_mode: String,
+    /// Non-break periods from the group's bell schedule, in order.
+    pub periods: Vec<LessonPeriod>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentAttendanceResponse {
+    pub student_id: Uuid,
+    /// Percentage of counted days attended (present + late, with every
+    /// `tardies_to_absence` lates counted as an absence). Excused days are
+    /// excluded from the denominator. Null when nothing has been recorded.
+    pub attendance_rate: Option<f64>,
+    pub counts: AttendanceCounts,
+    pub records: Vec<AttendanceEntry>,
+}
+
+/// A student flagged by the attendance alert evaluator.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct AttendanceFlagResponse {
+    pub id: Uuid,
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// consecutive_absence | below_threshold
+    pub kind: String,
+    /// Consecutive absent days, or the attendance rate, at the latest evaluation.
+    pub value: f64,
+    /// `consecutive_absence_alert` or `attendance_threshold` when flagged.
+    pub threshold: f64,
+    pub flagged_at: DateTime<Utc>,
+    pub last_evaluated_at: DateTime<Utc>,
+    /// Set once the student no longer meets the condition.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct AttendanceAlertRunResponse {
+    /// Flags opened by this run.
+    pub flagged: u64,
+    /// Open flags closed because the student no longer qualifies.
+    pub resolved: u64,
+    /// Guardian notifications queued for the newly opened flags.
+    pub notifications_queued: u64,
+}
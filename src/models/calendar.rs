@@ -0,0 +1,161 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+// ── DB Row Models ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CalendarEventRow {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub kind: String,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub notes: Option<String>,
+    pub created_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// ── Request DTOs ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCalendarEventRequest {
+    /// mid_term_break | public_holiday | closure
+    pub kind: String,
+    pub name: String,
+    pub start_date: NaiveDate,
+    /// Inclusive. Defaults to `start_date` for a single-day event.
+    #[serde(default)]
+    pub end_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCalendarEventRequest {
+    #[serde(default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub end_date: Option<NaiveDate>,
+    /// `null` or an empty string clears the notes.
+    #[serde(default, deserialize_with = "present")]
+    #[schema(value_type = Option<String>, nullable)]
+    pub notes: Option<Option<String>>,
+}
+
+/// Wraps a present value, `null` included, in `Some`, so an update can tell
+/// `null` apart from a missing field.
+fn present<'de, D, T>(d: D) -> Result<Option<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(d).map(Some)
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CalendarEventQuery {
+    /// Events ending on or after this date.
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    /// Events starting on or before this date.
+    #[serde(default)]
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CalendarDayQuery {
+    /// Defaults to today in the school's timezone.
+    #[serde(default)]
+    pub date: Option<NaiveDate>,
+}
+
+// ── Response DTOs ───────────────────────────────────────────────────────
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarEventResponse {
+    pub id: Uuid,
+    pub kind: String,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CalendarEventRow> for CalendarEventResponse {
+    fn from(r: CalendarEventRow) -> Self {
+        Self {
+            id: r.id,
+            kind: r.kind,
+            name: r.name,
+            start_date: r.start_date,
+            end_date: r.end_date,
+            notes: r.notes,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+/// A term from the academic calendar section of school setup.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TermSummary {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    /// 0-based order within the academic year.
+    pub position: i16,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CalendarDayResponse {
+    pub date: NaiveDate,
+    /// Lowercase weekday name, e.g. "monday".
+    pub weekday: String,
+    pub instructional: bool,
+    /// Why the school is closed: outside_term | non_school_day | mid_term_break |
+    /// public_holiday | closure. Absent on instructional days.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Term the date falls in, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term: Option<TermSummary>,
+    /// Calendar events covering the date.
+    pub events: Vec<CalendarEventResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InstructionalDayCount {
+    /// Instructional days in the whole term.
+    pub total: i64,
+    /// Instructional days from the start of term up to and including today.
+    pub elapsed: i64,
+    pub remaining: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CurrentTermResponse {
+    /// Today in the school's timezone.
+    pub today: NaiveDate,
+    /// Term containing today; null during holidays between terms.
+    pub term: Option<TermSummary>,
+    /// Next term to start after today, if one is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_term: Option<TermSummary>,
+    /// Present when `term` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instructional_days: Option<InstructionalDayCount>,
+}
@@ -0,0 +1,357 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::models::students::PaginationInfo;
+
+// ── Request DTOs ────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct GenerateInvoicesRequest {
+    /// Defaults to the school's `current_academic_year`.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    /// Term name from school setup.
+    pub term: String,
+    /// Only students in this grade level.
+    #[serde(default)]
+    pub grade_level: Option<String>,
+    /// Only students in this section.
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Only these students.
+    #[serde(default)]
+    pub student_ids: Option<Vec<Uuid>>,
+    /// Non-mandatory categories to bill, by name. Mandatory categories are
+    /// always billed where they apply.
+    #[serde(default)]
+    pub optional_categories: Vec<String>,
+    /// Defaults to today in the school's timezone.
+    #[serde(default)]
+    pub issue_date: Option<NaiveDate>,
+    /// Defaults to the next `fee_payment_due_day` from the later of the term
+    /// start and the issue date.
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct VoidInvoiceRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RegenerateInvoiceRequest {
+    /// Not before today. Defaults to the voided invoice's due date, or today
+    /// if that has passed.
+    #[serde(default)]
+    pub due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct InvoiceListQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    #[serde(default)]
+    pub term: Option<String>,
+    #[serde(default)]
+    pub student_id: Option<Uuid>,
+    #[serde(default)]
+    pub grade_level: Option<String>,
+    /// issued | void. Default: both.
+    #[serde(default)]
+    pub status: Option<String>,
+    #[serde(default)]
+    pub page: Option<i64>,
+    #[serde(default)]
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct AssignDiscountRequest {
+    /// A discount type from the fees section, or the scholarship's name.
+    pub name: String,
+    /// discount | scholarship. Default: discount.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Leave out to use the discount type's percentage and applies_to.
+    #[serde(default)]
+    pub percentage: Option<f64>,
+    /// all, a fee type or a fee category name. Only with `percentage`;
+    /// default all.
+    #[serde(default)]
+    pub applies_to: Option<String>,
+    /// Only this academic year. Default: every year.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RecordPaymentRequest {
+    pub amount: f64,
+    /// cash | bank_transfer | card
+    pub method: String,
+    /// Invoice to settle first. Defaults to the student's open invoices,
+    /// oldest due first.
+    #[serde(default)]
+    pub invoice_id: Option<Uuid>,
+    /// Bank or card reference, teller number, etc.
+    #[serde(default)]
+    pub reference: Option<String>,
+    /// Defaults to today.
+    #[serde(default)]
+    pub paid_on: Option<NaiveDate>,
+    #[serde(default)]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PaymentListQuery {
+    #[serde(default)]
+    pub student_id: Option<Uuid>,
+    /// cash | bank_transfer | card
+    #[serde(default)]
+    pub method: Option<String>,
+    /// Paid on or after (YYYY-MM-DD).
+    #[serde(default)]
+    pub from: Option<NaiveDate>,
+    /// Paid on or before (YYYY-MM-DD).
+    #[serde(default)]
+    pub to: Option<NaiveDate>,
+    #[serde(default)]
+    pub page: Option<i64>,
+    #[serde(default)]
+    pub page_size: Option<i64>,
+}
+
+// ── Response DTOs ───────────────────────────────────────────────────────
+
+/// One fee on an invoice.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct InvoiceLine {
+    pub category: String,
+    pub fee_type: Option<String>,
+    pub frequency: Option<String>,
+    /// Billing periods covered, e.g. months in the term for a monthly fee.
+    pub quantity: f64,
+    pub unit_amount: f64,
+    /// `quantity × unit_amount`, before discounts.
+    pub amount: f64,
+    pub discount_amount: f64,
+    /// Each discount or scholarship that reduced this line.
+    #[sqlx(skip)]
+    pub discounts: Vec<LineDiscount>,
+}
+
+/// A discount or scholarship applied to an invoice line.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct LineDiscount {
+    pub name: String,
+    /// discount | scholarship
+    pub kind: String,
+    /// assigned (to the student) | sibling (automatic)
+    pub source: String,
+    /// Of the line's `amount`.
+    pub percentage: f64,
+    pub amount: f64,
+    /// Why it applied, e.g. "2nd of 3 children sharing a guardian".
+    pub detail: Option<String>,
+    /// The student's discount assignment it came from.
+    pub student_discount_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct InvoiceSummary {
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub grade_level: String,
+    pub academic_year: String,
+    pub term: String,
+    /// issued | void
+    pub status: String,
+    pub currency: Option<String>,
+    pub total_amount: f64,
+    pub amount_paid: f64,
+    /// Still to pay; 0 once paid or void.
+    pub balance: f64,
+    pub issue_date: NaiveDate,
+    pub due_date: NaiveDate,
+}
+
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct Invoice {
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Class and boarding status the invoice was priced for.
+    pub grade_level: String,
+    pub section: Option<String>,
+    pub boarding_status: Option<String>,
+    pub academic_year: String,
+    pub term: String,
+    /// issued | void
+    pub status: String,
+    pub currency: Option<String>,
+    /// Sum of the lines after discounts.
+    pub total_amount: f64,
+    /// Taken off the lines by discounts and scholarships.
+    pub discount_amount: f64,
+    pub amount_paid: f64,
+    /// Still to pay; 0 once paid or void.
+    pub balance: f64,
+    pub issue_date: NaiveDate,
+    pub due_date: NaiveDate,
+    pub void_reason: Option<String>,
+    pub voided_at: Option<DateTime<Utc>>,
+    /// The voided invoice this one was regenerated from.
+    pub replaces_invoice_id: Option<Uuid>,
+    /// The invoice regenerated from this one, once voided that way.
+    pub replaced_by_invoice_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub lines: Vec<InvoiceLine>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvoiceListResponse {
+    pub data: Vec<InvoiceSummary>,
+    pub pagination: PaginationInfo,
+}
+
+/// A student the generation run issued no invoice for.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SkippedStudent {
+    pub student_id: Uuid,
+    /// Absent for a requested student who isn't an active student here.
+    pub admission_number: Option<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GenerateInvoicesResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub generated: i64,
+    pub total_amount: f64,
+    /// Invoices issued, with their lines.
+    pub invoices: Vec<Invoice>,
+    pub skipped: Vec<SkippedStudent>,
+}
+
+/// The part of a payment applied to one invoice.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct PaymentAllocation {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub amount: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct Payment {
+    pub id: Uuid,
+    pub receipt_number: String,
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub amount: f64,
+    pub currency: Option<String>,
+    /// cash | bank_transfer | card
+    pub method: String,
+    pub reference: Option<String>,
+    pub paid_on: NaiveDate,
+    pub notes: Option<String>,
+    /// Not yet applied to an invoice; held as credit.
+    pub unallocated: f64,
+    pub created_at: DateTime<Utc>,
+    #[sqlx(skip)]
+    pub allocations: Vec<PaymentAllocation>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PaymentListResponse {
+    pub data: Vec<Payment>,
+    /// Sum of every matching payment, not just this page.
+    pub total_amount: f64,
+    pub pagination: PaginationInfo,
+}
+
+/// A payment as listed on a student record.
+#[derive(Debug, Clone, Serialize, ToSchema, FromRow)]
+pub struct RecentPayment {
+    pub id: Uuid,
+    pub receipt_number: String,
+    pub amount: f64,
+    pub currency: Option<String>,
+    pub method: String,
+    pub reference: Option<String>,
+    pub paid_on: NaiveDate,
+}
+
+/// A student's fee account: every invoice and payment, and what's owed.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentFeeStatement {
+    pub student_id: Uuid,
+    /// paid | partial | owing | overdue, or unknown before the first invoice.
+    pub fee_status: String,
+    pub currency: Option<String>,
+    /// Sum of issued (not void) invoices.
+    pub total_invoiced: f64,
+    pub total_paid: f64,
+    /// Still to pay across issued invoices.
+    pub balance: f64,
+    /// Paid but not yet applied to an invoice.
+    pub credit: f64,
+    /// Newest first, void ones included.
+    pub invoices: Vec<InvoiceSummary>,
+    /// Newest first.
+    pub payments: Vec<Payment>,
+}
+
+/// A discount or scholarship assigned to a student.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct StudentDiscount {
+    pub id: Uuid,
+    /// discount | scholarship
+    pub kind: String,
+    pub name: String,
+    /// Null when the discount type's percentage applies.
+    pub percentage: Option<f64>,
+    pub applies_to: Option<String>,
+    /// Null for every academic year.
+    pub academic_year: Option<String>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// The discount a student gets automatically as a younger sibling.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SiblingDiscount {
+    pub name: String,
+    pub percentage: Option<String>,
+    pub applies_to: Option<String>,
+    /// Among active siblings, eldest first (eldest = 1).
+    pub position: i64,
+    pub family_size: i64,
+    /// The other active students sharing a guardian with this one.
+    pub sibling_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentDiscountsResponse {
+    pub assigned: Vec<StudentDiscount>,
+    /// Absent when no discount type covers the student's sibling position.
+    pub sibling: Option<SiblingDiscount>,
+}
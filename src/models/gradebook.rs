@@ -0,0 +1,506 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::students::ImportRowError;
+
+// ── DB Row Models ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, FromRow)]
+pub struct GradebookSheetRow {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub grade_level: String,
+    pub section: Option<String>,
+    pub max_scores: serde_json::Value,
+    pub status: String,
+    pub published_at: Option<DateTime<Utc>>,
+    pub published_by_user_id: Option<Uuid>,
+    pub created_by_user_id: Option<Uuid>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub submitted_by_user_id: Option<Uuid>,
+    pub approved_at: Option<DateTime<Utc>>,
+    pub approved_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct GradebookScoreRow {
+    pub id: Uuid,
+    pub sheet_id: Uuid,
+    pub org_id: Uuid,
+    pub student_id: Uuid,
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub ca: Option<f64>,
+    pub exam: Option<f64>,
+    pub assignment: Option<f64>,
+    pub test: Option<f64>,
+    pub project: Option<f64>,
+    pub entered_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    /// Graded result snapshotted when the sheet was published.
+    pub frozen_result: Option<serde_json::Value>,
+}
+
+impl GradebookScoreRow {
+    /// Score for one component (`ca`, `exam`, …); None if not entered.
+    pub fn component(&self, key: &str) -> Option<f64> {
+        match key {
+            "ca" => self.ca,
+            "exam" => self.exam,
+            "assignment" => self.assignment,
+            "test" => self.test,
+            "project" => self.project,
+            _ => None,
+        }
+    }
+}
+
+// ── Request DTOs ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScoreEntryInput {
+    pub student_id: Uuid,
+    /// Component → score, e.g. `{"ca": 32, "exam": 51.5}`. Components not
+    /// listed keep their current score; `null` clears one.
+    #[schema(value_type = HashMap<String, Option<f64>>)]
+    pub scores: HashMap<String, Option<f64>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveScoreSheetRequest {
+    /// Defaults to the school's `current_academic_year`.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    /// Term name from the academic calendar section.
+    pub term: String,
+    /// Subject name from the subjects section.
+    pub subject: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Component → max score for this sheet. Components not listed keep
+    /// their current max (initially the component's weight).
+    #[serde(default)]
+    pub max_scores: Option<HashMap<String, f64>>,
+    pub entries: Vec<ScoreEntryInput>,
+}
+
+/// Form fields of a score import that apply to every row unless a column
+/// is mapped to the same field.
+#[derive(Debug, Default)]
+pub struct ScoreImportDefaults {
+    pub academic_year: Option<String>,
+    pub term: Option<String>,
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ScoreSheetQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub subject: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SheetStatusRequest {
+    /// draft | submitted | approved | published
+    pub status: String,
+    /// Kept in the status history.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ResultStatusQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    /// Omit for every sheet in the grade level.
+    #[serde(default)]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetResultStatusRequest {
+    /// Defaults to the school's `current_academic_year`.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    /// Omit for every sheet in the grade level.
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Only this subject's sheet.
+    #[serde(default)]
+    pub subject: Option<String>,
+    /// submitted | approved | published | draft
+    pub status: String,
+    /// Kept in the status history.
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct StudentScoresQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    #[serde(default)]
+    pub term: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassResultsQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Only this subject.
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassStatisticsQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BroadsheetQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// csv (default) | xlsx
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct StudentStatisticsQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+}
+
+// ── Response DTOs ───────────────────────────────────────────────────────
+
+/// An assessment component enabled in the grading section.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScoreComponent {
+    /// ca | exam | assignment | test | project
+    pub key: String,
+    /// Weight from the grading section (percentage of the term total).
+    pub weight: f64,
+    /// Highest score accepted on this sheet.
+    pub max_score: f64,
+}
+
+/// One student's line on a score sheet.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoreSheetEntry {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Entered scores by component; components without a score are omitted.
+    pub scores: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoreSheetResponse {
+    /// Null when no scores have been entered for this class yet.
+    pub id: Option<Uuid>,
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// draft | submitted | approved | published. Only draft sheets can be edited.
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+    pub components: Vec<ScoreComponent>,
+    pub entries: Vec<ScoreSheetEntry>,
+}
+
+/// One subject's scores for a student in one term.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentSubjectScores {
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub sheet_id: Uuid,
+    pub status: String,
+    pub scores: BTreeMap<String, f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentScoresResponse {
+    pub student_id: Uuid,
+    pub subjects: Vec<StudentSubjectScores>,
+}
+
+/// A subject's weighted total for one term, graded on the school's scale.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SubjectResult {
+    pub subject: String,
+    /// Weighted total out of 100, rounded to 2 decimal places.
+    pub total: f64,
+    pub grade: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa_points: Option<f64>,
+    /// Total at or above the school's passmark.
+    pub passed: bool,
+    /// False when some enabled component has no score (counted as 0).
+    pub complete: bool,
+    /// Status of the score sheet the result comes from.
+    pub sheet_status: String,
+    pub scores: BTreeMap<String, f64>,
+}
+
+/// One student's results in a class for a term.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentTermResult {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub subjects: Vec<SubjectResult>,
+    /// Mean of the subject totals.
+    pub average: f64,
+    pub subjects_passed: usize,
+    /// Mean `gpa_points` for the term; present when `gpa_enabled` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassResultsResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub passmark: f64,
+    pub students: Vec<StudentTermResult>,
+}
+
+/// A student's results for one term.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TermResults {
+    pub academic_year: String,
+    pub term: String,
+    pub subjects: Vec<SubjectResult>,
+    pub average: f64,
+    pub subjects_passed: usize,
+    /// Mean `gpa_points` for the term; present when `gpa_enabled` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa: Option<f64>,
+    /// Mean `gpa_points` over every subject up to and including this term.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_gpa: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentResultsResponse {
+    pub student_id: Uuid,
+    pub passmark: f64,
+    /// Over all terms, whatever the filters; matches the student's `gpa`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_gpa: Option<f64>,
+    pub terms: Vec<TermResults>,
+}
+
+/// Class-wide figures for one subject.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SubjectStatistics {
+    pub subject: String,
+    /// Students with a result in this subject.
+    pub students: usize,
+    /// Mean of the subject totals.
+    pub average: f64,
+    pub highest: f64,
+    pub lowest: f64,
+}
+
+/// A student's total and position in one subject.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubjectPosition {
+    pub subject: String,
+    pub total: f64,
+    pub position: u32,
+}
+
+/// A student's overall standing in the class.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentPosition {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Mean of the subject totals; class position is ranked on this.
+    pub average: f64,
+    pub position: u32,
+    pub subjects: Vec<SubjectPosition>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassStatisticsResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// shared | dense | exam_score, from the report_card section.
+    pub tie_break: String,
+    /// Report card toggles, so clients know which figures to print.
+    pub show_position: bool,
+    pub show_class_average: bool,
+    pub show_highest_lowest: bool,
+    /// Students with at least one result.
+    pub class_size: usize,
+    /// Mean of the students' averages; null when nobody has results.
+    pub class_average: Option<f64>,
+    pub subjects: Vec<SubjectStatistics>,
+    /// Ordered by position.
+    pub students: Vec<StudentPosition>,
+}
+
+/// One subject on a student's report: their total and position alongside the
+/// class figures.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentSubjectStatistics {
+    pub subject: String,
+    pub total: f64,
+    pub position: u32,
+    pub students: usize,
+    pub class_average: f64,
+    pub highest: f64,
+    pub lowest: f64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentStatisticsResponse {
+    pub student_id: Uuid,
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub tie_break: String,
+    pub show_position: bool,
+    pub show_class_average: bool,
+    pub show_highest_lowest: bool,
+    pub class_size: usize,
+    pub average: f64,
+    pub position: u32,
+    pub class_average: Option<f64>,
+    pub subjects: Vec<StudentSubjectStatistics>,
+}
+
+/// Where one score sheet is in the publication workflow.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ResultSheetStatus {
+    pub sheet_id: Uuid,
+    pub subject: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submitted_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approved_at: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published_at: Option<DateTime<Utc>>,
+}
+
+/// One entry in a class's result status history.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct ResultStatusChange {
+    pub sheet_id: Option<Uuid>,
+    pub subject: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub from_status: String,
+    pub to_status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changed_by_user_id: Option<Uuid>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// A class's results for one term in the publication workflow.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassResultStatusResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// The least advanced status among the sheets; absent without sheets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    pub sheets: Vec<ResultSheetStatus>,
+    /// Guardian notifications queued by this change.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notifications_queued: Option<u64>,
+    /// Most recent first.
+    pub history: Vec<ResultStatusChange>,
+}
+
+/// A score sheet written by an import.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ImportedSheet {
+    pub sheet_id: Uuid,
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Rows imported onto this sheet.
+    pub rows: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScoreImportResponse {
+    /// Rows whose scores were saved.
+    pub imported: usize,
+    pub skipped: usize,
+    pub errors: Vec<ImportRowError>,
+    pub sheets: Vec<ImportedSheet>,
+}
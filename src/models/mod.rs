@@ -1,6 +1,11 @@
+pub mod attendance;
 pub mod auth;
+pub mod calendar;
+pub mod fees;
+pub mod gradebook;
 pub mod health;
 pub mod organization;
+pub mod report_cards;
 pub mod school_setup;
 pub mod students;
 pub mod user;
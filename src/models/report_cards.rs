@@ -0,0 +1,497 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use super::attendance::AttendanceCounts;
+
+// ── Request DTOs ────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ReportCardQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    /// pdf (default) | html | json
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassReportCardsQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// pdf (default) | html
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+// ── Report card ─────────────────────────────────────────────────────────
+
+/// School identity printed in the report card header.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReportCardSchool {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub motto: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_color: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCardStudent {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    pub last_name: String,
+    pub gender: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+}
+
+/// One subject line. Optional figures are left out when the report_card
+/// section turns them off.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCardSubject {
+    pub subject: String,
+    /// Component scores; present with the assessment breakdown.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scores: Option<BTreeMap<String, f64>>,
+    pub total: f64,
+    pub grade: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor: Option<String>,
+    pub passed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_average: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub highest: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lowest: Option<f64>,
+    /// Subject teacher's comment, with `show_teacher_comments`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCardSummary {
+    pub average: f64,
+    pub subjects_taken: usize,
+    pub subjects_passed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_size: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_average: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_gpa: Option<f64>,
+}
+
+/// A grading scale band for the legend.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReportCardLegendBand {
+    pub grade: String,
+    pub min_score: f64,
+    pub max_score: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub descriptor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCardAttendance {
+    /// Instructional days in the term, from the school calendar.
+    pub days_opened: i64,
+    #[serde(flatten)]
+    pub counts: AttendanceCounts,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attendance_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ReportCardNextTerm {
+    pub name: String,
+    pub start_date: NaiveDate,
+}
+
+/// A psychomotor or affective trait with the student's rating, if any.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCardTrait {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rating: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReportCard {
+    pub school: ReportCardSchool,
+    /// Template from the report_card section; `standard` when unset.
+    pub template: String,
+    pub academic_year: String,
+    pub term: String,
+    pub student: ReportCardStudent,
+    /// True while any subject comes from an unpublished score sheet.
+    pub provisional: bool,
+    pub passmark: f64,
+    /// Components in the breakdown, in display order. Empty without it.
+    pub components: Vec<String>,
+    pub subjects: Vec<ReportCardSubject>,
+    pub summary: ReportCardSummary,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grading_legend: Option<Vec<ReportCardLegendBand>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attendance: Option<ReportCardAttendance>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_term: Option<ReportCardNextTerm>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub psychomotor: Option<Vec<ReportCardTrait>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub affective: Option<Vec<ReportCardTrait>>,
+    /// Key to trait ratings; present with either trait table.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trait_scale: Option<Vec<TraitRatingLevel>>,
+    /// With `show_class_teacher_comment`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub class_teacher_comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub principal_comment: Option<String>,
+    /// Print a teacher signature column on the subject table.
+    pub subject_teacher_signature: bool,
+    /// Signature lines under the report, e.g. "Principal".
+    pub signatures: Vec<String>,
+}
+
+// ── Trait ratings ───────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TraitSheetQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TraitRatingInput {
+    pub student_id: Uuid,
+    /// Trait → rating, e.g. `{"Handwriting": "4"}`. Traits not listed keep
+    /// their rating; `null` clears one.
+    #[serde(default)]
+    #[schema(value_type = HashMap<String, Option<String>>)]
+    pub psychomotor: HashMap<String, Option<String>>,
+    #[serde(default)]
+    #[schema(value_type = HashMap<String, Option<String>>)]
+    pub affective: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveTraitSheetRequest {
+    /// Defaults to the school's `current_academic_year`.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    /// Term name from the academic calendar section.
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    pub entries: Vec<TraitRatingInput>,
+}
+
+/// One step of the trait rating scale.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TraitRatingLevel {
+    pub rating: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub descriptor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TraitSheetEntry {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Trait → rating for the configured traits rated so far.
+    pub psychomotor: BTreeMap<String, String>,
+    pub affective: BTreeMap<String, String>,
+}
+
+/// A class's trait ratings for one term.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TraitSheetResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// Allowed ratings, highest first.
+    pub scale: Vec<TraitRatingLevel>,
+    pub psychomotor_traits: Vec<String>,
+    pub affective_traits: Vec<String>,
+    pub entries: Vec<TraitSheetEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentTraitTerm {
+    pub academic_year: String,
+    pub term: String,
+    pub psychomotor: BTreeMap<String, String>,
+    pub affective: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentTraitRatingsResponse {
+    pub student_id: Uuid,
+    pub terms: Vec<StudentTraitTerm>,
+}
+
+// ── Comments ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CommentBankRow {
+    pub id: Uuid,
+    pub org_id: Uuid,
+    pub kind: Option<String>,
+    pub grade: Option<String>,
+    pub comment: String,
+    pub created_by_user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CommentSheetQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// subject_teacher | class_teacher | principal
+    pub kind: String,
+    /// Required for subject_teacher comments.
+    #[serde(default)]
+    pub subject: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CommentInput {
+    pub student_id: Uuid,
+    /// `null` or blank clears the comment.
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SaveCommentSheetRequest {
+    /// Defaults to the school's `current_academic_year`.
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    /// Term name from the academic calendar section.
+    pub term: String,
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// subject_teacher | class_teacher | principal
+    pub kind: String,
+    /// Subject name from the subjects section; required for subject_teacher.
+    #[serde(default)]
+    pub subject: Option<String>,
+    pub entries: Vec<CommentInput>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateCommentBankRequest {
+    /// subject_teacher | class_teacher | principal; omit for any.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Grade from the grading scale; omit for any.
+    #[serde(default)]
+    pub grade: Option<String>,
+    pub comment: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateCommentBankRequest {
+    /// An empty string sets it back to any kind.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// An empty string sets it back to any grade.
+    #[serde(default)]
+    pub grade: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct CommentBankQuery {
+    /// Entries for this kind, including those for any kind.
+    #[serde(default)]
+    pub kind: Option<String>,
+    /// Entries for this grade, including those for any grade.
+    #[serde(default)]
+    pub grade: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentBankResponse {
+    pub id: Uuid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grade: Option<String>,
+    pub comment: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl From<CommentBankRow> for CommentBankResponse {
+    fn from(r: CommentBankRow) -> Self {
+        Self {
+            id: r.id,
+            kind: r.kind,
+            grade: r.grade,
+            comment: r.comment,
+            created_at: r.created_at,
+            updated_at: r.updated_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentSheetEntry {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// The subject grade for subject_teacher comments, otherwise the grade of
+    /// the student's average. Absent without results.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grade: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    /// Comment bank entries for this kind and grade that fit the limit.
+    pub suggestions: Vec<String>,
+}
+
+/// A class's comments of one kind for a term.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CommentSheetResponse {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    /// `comment_char_limit` from the report_card section.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub char_limit: Option<usize>,
+    pub entries: Vec<CommentSheetEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentComment {
+    pub academic_year: String,
+    pub term: String,
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subject: Option<String>,
+    pub comment: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StudentCommentsResponse {
+    pub student_id: Uuid,
+    pub comments: Vec<StudentComment>,
+}
+
+// ── Transcript ──────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct TranscriptQuery {
+    /// pdf (default) | json
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TranscriptStudent {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub middle_name: Option<String>,
+    pub last_name: String,
+    pub gender: String,
+    pub date_of_birth: NaiveDate,
+    pub enrollment_date: NaiveDate,
+    pub status: String,
+    /// Graduation, transfer or withdrawal date for students who have left.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TranscriptSubject {
+    pub subject: String,
+    pub total: f64,
+    pub grade: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa_points: Option<f64>,
+    pub passed: bool,
+}
+
+/// One term of results, with the class the student was in at the time.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TranscriptTerm {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// True while any subject comes from an unpublished score sheet.
+    pub provisional: bool,
+    pub subjects: Vec<TranscriptSubject>,
+    pub average: f64,
+    pub subjects_passed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpa: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_gpa: Option<f64>,
+}
+
+/// A student's results across every term and academic year they attended.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Transcript {
+    pub school: ReportCardSchool,
+    pub student: TranscriptStudent,
+    pub passmark: f64,
+    /// Over all terms; present when `gpa_enabled` is on.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cumulative_gpa: Option<f64>,
+    /// Oldest first.
+    pub terms: Vec<TranscriptTerm>,
+    /// Date the transcript was produced, in the school's timezone.
+    pub issued_on: NaiveDate,
+}
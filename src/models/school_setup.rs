@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Serialize;
 use sqlx::FromRow;
 use utoipa::ToSchema;
@@ -37,6 +37,8 @@ pub struct SchoolConfigRow {
     // Academic Calendar
     pub calendar_type: Option<String>,
     pub current_academic_year: Option<String>,
+    /// Weekdays the school is open ("monday" … "sunday"); empty = Monday–Friday.
+    pub school_days: serde_json::Value,
     // Grade Levels scalars
     pub grade_level_structure_id: Option<String>,
     pub group_sections: serde_json::Value,
@@ -64,6 +66,8 @@ pub struct SchoolConfigRow {
     pub show_highest_lowest: Option<bool>,
     pub show_grading_legend: Option<bool>,
     pub show_position: Option<bool>,
+    /// shared | dense | exam_score; unset behaves as shared.
+    pub position_tie_break: Option<String>,
     pub show_gpa: Option<bool>,
     pub show_effort_grades: Option<bool>,
     pub show_behavior_rating: Option<bool>,
@@ -71,6 +75,8 @@ pub struct SchoolConfigRow {
     pub psychomotor_traits: serde_json::Value,
     pub show_affective: Option<bool>,
     pub affective_traits: serde_json::Value,
+    /// `[{"rating": "5", "descriptor": "Excellent"}, …]`; empty means the default scale.
+    pub trait_rating_scale: serde_json::Value,
     pub show_teacher_comments: Option<bool>,
     pub show_class_teacher_comment: Option<bool>,
     pub show_principal_signature: Option<bool>,
@@ -122,9 +128,11 @@ pub struct TermRow {
     pub id: Uuid,
     pub org_id: Uuid,
     pub name: String,
-    pub start_date: Option<String>,
-    pub end_date: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
     pub position: i16,
+    pub legacy_start_date: Option<String>,
+    pub legacy_end_date: Option<String>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -167,6 +175,8 @@ pub struct FeeDiscountRow {
     pub percentage: Option<String>,
     pub applies_to: Option<String>,
     pub position: i16,
+    /// Given automatically to the child in this position among siblings.
+    pub sibling_position: Option<i16>,
 }
 
 #[derive(Debug, Clone, FromRow)]
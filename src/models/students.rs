@@ -4,6 +4,9 @@ use sqlx::FromRow;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+use crate::models::attendance::AttendanceEntry;
+use crate::models::fees::RecentPayment;
+
 // ── DB Row Models ──────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, FromRow)]
@@ -72,6 +75,7 @@ pub struct StudentStatusHistoryRow {
     pub effective_date: NaiveDate,
     pub changed_by_user_id: Option<Uuid>,
     pub changed_at: DateTime<Utc>,
+    pub promotion_batch_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone, FromRow)]
@@ -91,6 +95,7 @@ pub struct StudentClassHistoryRow {
     pub changed_by_user_id: Option<Uuid>,
     pub promotion_batch_id: Option<Uuid>,
     pub changed_at: DateTime<Utc>,
+    pub academic_year: Option<String>,
 }
 
 // ── Request DTOs ────────────────────────────────────────────────────────
@@ -260,6 +265,78 @@ pub struct PromoteRequest {
     pub academic_year: Option<String>,
     #[serde(default)]
     pub effective_date: Option<NaiveDate>,
+    /// Validate and report what would change without saving anything.
+    #[serde(default)]
+    pub dry_run: bool,
+    /// Apply every decision that can be applied and report the rest in
+    /// `errors`, instead of rejecting the whole batch.
+    #[serde(default)]
+    pub skip_invalid: bool,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RevertPromotionRequest {
+    #[serde(default)]
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub effective_date: Option<NaiveDate>,
+}
+
+/// A term of the new academic year.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema, FromRow)]
+pub struct RolloverTerm {
+    pub name: String,
+    #[serde(default)]
+    pub start_date: Option<NaiveDate>,
+    #[serde(default)]
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct RolloverRequest {
+    /// Defaults to the year after `current_academic_year`, e.g. 2026/2027
+    /// after 2025/2026.
+    #[serde(default)]
+    pub new_academic_year: Option<String>,
+    /// Defaults to the current terms, each a year later.
+    #[serde(default)]
+    pub terms: Option<Vec<RolloverTerm>>,
+    /// Decisions for students who shouldn't simply move up a grade, e.g.
+    /// `retain`. Every other active student is promoted to the next grade
+    /// level, or graduated from the last one.
+    #[serde(default)]
+    pub decisions: Vec<PromoteDecision>,
+    #[serde(default)]
+    pub effective_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ClassRollQuery {
+    pub academic_year: String,
+    #[serde(default)]
+    pub grade_level: Option<String>,
+    #[serde(default)]
+    pub section: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct PromotionBatchListQuery {
+    #[serde(default)]
+    pub academic_year: Option<String>,
+    #[serde(default)]
+    pub page: Option<i64>,
+    #[serde(default)]
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PromotionSuggestionsQuery {
+    pub grade_level: String,
+    #[serde(default)]
+    pub section: Option<String>,
+    /// Defaults to the current academic year.
+    #[serde(default)]
+    pub academic_year: Option<String>,
 }
 
 #[derive(Debug, Default, Deserialize, ToSchema)]
@@ -280,6 +357,12 @@ pub struct StudentListQuery {
     pub page: Option<i64>,
     #[serde(default)]
     pub page_size: Option<i64>,
+    /// Only students with a cumulative GPA of at least this.
+    #[serde(default)]
+    pub min_gpa: Option<f64>,
+    /// Only students with a cumulative GPA of at most this.
+    #[serde(default)]
+    pub max_gpa: Option<f64>,
     #[serde(default)]
     pub sort: Option<String>,
     #[serde(default)]
@@ -379,20 +462,23 @@ pub struct StudentResponse {
     pub tribe: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub avatar_url: Option<String>,
-    /// Computed; null until grades module exists.
+    /// Cumulative GPA over every graded subject; null when `gpa_enabled` is
+    /// off or the student has no graded scores.
     pub gpa: Option<f64>,
-    /// Computed; null until attendance module exists.
+    /// Computed from attendance marks (present + late over counted days);
+    /// null when no attendance has been recorded.
     pub attendance_rate: Option<f64>,
-    /// Derived; "unknown" until fees module exists.
+    /// paid | partial | owing | overdue, from the student's fee invoices and
+    /// payments; "unknown" before their first invoice.
     pub fee_status: String,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-    /// Empty array until fees module exists. Populated when ?include=recent_payments.
+    /// Latest fee payments, newest first. Populated when ?include=recent_payments.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub recent_payments: Option<Vec<serde_json::Value>>,
-    /// Empty array until attendance module exists. Populated when ?include=recent_attendance.
+    pub recent_payments: Option<Vec<RecentPayment>>,
+    /// Latest attendance marks, newest first. Populated when ?include=recent_attendance.
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub recent_attendance: Option<Vec<serde_json::Value>>,
+    pub recent_attendance: Option<Vec<AttendanceEntry>>,
 }
 
 impl StudentResponse {
@@ -500,8 +586,192 @@ pub struct PromoteSummary {
     pub promoted: i64,
     pub retained: i64,
     pub graduated: i64,
+    /// Left out on a dry run, which records nothing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_id: Option<Uuid>,
+    /// Decisions left out with `skip_invalid`.
+    pub errors: Vec<PromoteError>,
+    pub dry_run: bool,
+    /// Per-student before and after, on a dry run only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changes: Option<Vec<PromoteChange>>,
+}
+
+/// Decisions undone by reverting a promotion batch, by original action.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevertPromotionSummary {
     pub batch_id: Uuid,
-    pub errors: Vec<String>,
+    pub promoted: i64,
+    pub retained: i64,
+    pub graduated: i64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RolloverSummary {
+    /// The academic year that was closed and archived.
+    pub closed_academic_year: String,
+    pub academic_year: String,
+    /// The promotion batch holding every student's decision.
+    pub batch_id: Uuid,
+    pub promoted: i64,
+    pub retained: i64,
+    pub graduated: i64,
+    /// Students captured in the closed year's class rolls.
+    pub class_roll_size: i64,
+    pub terms: Vec<RolloverTerm>,
+    /// Year printed in admission numbers from now on; the sequence restarts.
+    pub admission_number_year: i16,
+}
+
+/// A school's configuration as it stood when an academic year was closed.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct AcademicYearArchive {
+    pub academic_year: String,
+    pub next_academic_year: String,
+    /// `config` (the school setup row) and `terms`, `grade_levels`,
+    /// `grading_scales`, `subjects`, `fee_categories` and `fee_discounts`.
+    pub configuration: serde_json::Value,
+    pub promotion_batch_id: Uuid,
+    pub archived_by: Option<Uuid>,
+    pub archived_at: DateTime<Utc>,
+}
+
+/// A student's class when an academic year was closed.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct ClassRollEntry {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub grade_level: String,
+    pub section: Option<String>,
+    pub stream: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassRollResponse {
+    pub academic_year: String,
+    pub data: Vec<ClassRollEntry>,
+}
+
+/// A past `POST /students/promote` call, from its class history rows.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct PromotionBatch {
+    pub batch_id: Uuid,
+    /// Absent for batches run before the academic year was recorded.
+    pub academic_year: Option<String>,
+    pub effective_date: NaiveDate,
+    pub run_at: DateTime<Utc>,
+    pub run_by: Option<Uuid>,
+    pub run_by_name: Option<String>,
+    pub promoted: i64,
+    pub retained: i64,
+    pub graduated: i64,
+    pub reverted: bool,
+    pub reverted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromotionBatchListResponse {
+    pub data: Vec<PromotionBatch>,
+    pub pagination: PaginationInfo,
+}
+
+/// One student's decision in a promotion batch.
+#[derive(Debug, Serialize, ToSchema, FromRow)]
+pub struct PromotionBatchDecision {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// promote | retain | graduate
+    pub action: String,
+    pub from_grade: Option<String>,
+    pub from_section: Option<String>,
+    pub from_stream: Option<String>,
+    /// Absent for graduates.
+    pub to_grade: Option<String>,
+    pub to_section: Option<String>,
+    pub to_stream: Option<String>,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromotionBatchDetail {
+    #[serde(flatten)]
+    pub batch: PromotionBatch,
+    pub decisions: Vec<PromotionBatchDecision>,
+}
+
+/// A decision that was left out of a `skip_invalid` promotion.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromoteError {
+    pub student_id: Uuid,
+    pub action: String,
+    pub message: String,
+}
+
+/// One student's class and status before and after a promotion decision.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromoteChange {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    pub action: String,
+    pub from_grade: String,
+    pub from_section: Option<String>,
+    pub from_status: String,
+    pub to_grade: String,
+    pub to_section: Option<String>,
+    pub to_status: String,
+}
+
+/// A proposed decision, shaped like [`PromoteDecision`] so it can be edited
+/// and submitted to `POST /students/promote`, plus the figures behind it.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SuggestedDecision {
+    pub student_id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// promote | retain | graduate
+    pub action: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_grade: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to_section: Option<String>,
+    /// `reasons` joined into one line.
+    pub reason: String,
+    pub reasons: Vec<String>,
+    /// Promoted despite failing, on condition of supplementary exams.
+    pub conditional: bool,
+    /// Mean of the annual subject totals.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub average: Option<f64>,
+    pub subjects_taken: usize,
+    pub subjects_passed: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attendance_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PromotionSuggestionsResponse {
+    pub academic_year: String,
+    pub grade_level: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<String>,
+    /// `promotion_criteria` from the policies section.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub criteria: Option<String>,
+    /// Grade level after this one; absent for the final grade, whose
+    /// students graduate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_grade: Option<String>,
+    pub promote: usize,
+    pub retain: usize,
+    pub graduate: usize,
+    pub decisions: Vec<SuggestedDecision>,
 }
 
 #[derive(Debug, Serialize, ToSchema)]
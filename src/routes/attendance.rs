@@ -0,0 +1,22 @@
+use axum::middleware as axum_mw;
+use axum::routing::{get, post};
+use axum::Router;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::handlers::attendance;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/registers",
+            get(attendance::get_register).put(attendance::mark_register),
+        )
+        .route("/periods", get(attendance::class_periods))
+        .route("/alerts/run", post(attendance::run_alerts))
+        .layer(RequestBodyLimitLayer::new(1024 * 1024))
+        .layer(axum_mw::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_auth,
+        ))
+}
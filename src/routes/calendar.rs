@@ -0,0 +1,26 @@
+use axum::middleware as axum_mw;
+use axum::routing::{get, patch};
+use axum::Router;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::handlers::calendar;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route(
+            "/events",
+            get(calendar::list_events).post(calendar::create_event),
+        )
+        .route(
+            "/events/{id}",
+            patch(calendar::update_event).delete(calendar::delete_event),
+        )
+        .route("/day", get(calendar::day))
+        .route("/current-term", get(calendar::current_term))
+        .layer(RequestBodyLimitLayer::new(1024 * 1024))
+        .layer(axum_mw::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_auth,
+        ))
+}
@@ -0,0 +1,23 @@
+use axum::middleware as axum_mw;
+use axum::routing::{get, post};
+use axum::Router;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::handlers::fees;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/invoices", get(fees::list_invoices))
+        .route("/invoices/generate", post(fees::generate_invoices))
+        .route("/invoices/{id}", get(fees::get_invoice))
+        .route("/invoices/{id}/void", post(fees::void_invoice))
+        .route("/invoices/{id}/regenerate", post(fees::regenerate_invoice))
+        .route("/payments", get(fees::list_payments))
+        .route("/payments/{id}", get(fees::get_payment))
+        .layer(RequestBodyLimitLayer::new(1024 * 1024))
+        .layer(axum_mw::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_auth,
+        ))
+}
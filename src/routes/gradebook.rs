@@ -0,0 +1,34 @@
+use axum::middleware as axum_mw;
+use axum::routing::{get, patch, post};
+use axum::Router;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::handlers::gradebook;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    // Score import accepts CSV uploads — up to ~5000 rows plus multipart overhead.
+    let upload = Router::new()
+        .route("/bulk-import", post(gradebook::bulk_import))
+        .layer(RequestBodyLimitLayer::new(10 * 1024 * 1024));
+
+    let standard = Router::new()
+        .route(
+            "/sheets",
+            get(gradebook::get_sheet).put(gradebook::save_sheet),
+        )
+        .route("/sheets/{id}/status", patch(gradebook::set_sheet_status))
+        .route("/results", get(gradebook::class_results))
+        .route(
+            "/results/status",
+            get(gradebook::result_status).patch(gradebook::set_result_status),
+        )
+        .route("/statistics", get(gradebook::class_statistics))
+        .route("/broadsheet", get(gradebook::broadsheet))
+        .layer(RequestBodyLimitLayer::new(1024 * 1024));
+
+    standard.merge(upload).layer(axum_mw::from_fn_with_state(
+        state,
+        crate::middleware::auth::require_auth,
+    ))
+}
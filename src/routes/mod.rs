@@ -2,8 +2,13 @@ use axum::Router;
 
 use crate::state::AppState;
 
+mod attendance;
 mod auth;
+mod calendar;
+mod fees;
+mod gradebook;
 mod health;
+mod report_cards;
 mod schools;
 mod students;
 
@@ -11,6 +16,11 @@ pub fn build(state: AppState) -> Router<AppState> {
     Router::new()
         .nest("/api/v1/auth", auth::router(state.clone()))
         .nest("/api/v1/schools", schools::router(state.clone()))
+        .nest("/api/v1/attendance", attendance::router(state.clone()))
+        .nest("/api/v1/calendar", calendar::router(state.clone()))
+        .nest("/api/v1/gradebook", gradebook::router(state.clone()))
+        .nest("/api/v1/report-cards", report_cards::router(state.clone()))
+        .nest("/api/v1/fees", fees::router(state.clone()))
         .nest("/api/v1/students", students::router(state))
         .nest("/health", health::router())
 }
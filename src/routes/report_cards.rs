@@ -0,0 +1,34 @@
+use axum::middleware as axum_mw;
+use axum::routing::{get, patch};
+use axum::Router;
+use tower_http::limit::RequestBodyLimitLayer;
+
+use crate::handlers::report_cards;
+use crate::state::AppState;
+
+pub fn router(state: AppState) -> Router<AppState> {
+    Router::new()
+        .route("/", get(report_cards::class_report_cards))
+        .route(
+            "/traits",
+            get(report_cards::get_trait_sheet).put(report_cards::save_trait_sheet),
+        )
+        .route(
+            "/comments",
+            get(report_cards::get_comment_sheet).put(report_cards::save_comment_sheet),
+        )
+        .route(
+            "/comment-bank",
+            get(report_cards::list_comment_bank).post(report_cards::create_comment_bank_entry),
+        )
+        .route(
+            "/comment-bank/{id}",
+            patch(report_cards::update_comment_bank_entry)
+                .delete(report_cards::delete_comment_bank_entry),
+        )
+        .layer(RequestBodyLimitLayer::new(1024 * 1024))
+        .layer(axum_mw::from_fn_with_state(
+            state,
+            crate::middleware::auth::require_auth,
+        ))
+}
@@ -1,9 +1,9 @@
 use axum::middleware as axum_mw;
-use axum::routing::{get, patch, post};
+use axum::routing::{delete, get, patch, post};
 use axum::Router;
 use tower_http::limit::RequestBodyLimitLayer;
 
-use crate::handlers::students;
+use crate::handlers::{attendance, fees, gradebook, report_cards, students};
 use crate::state::AppState;
 
 pub fn router(state: AppState) -> Router<AppState> {
@@ -19,7 +19,22 @@ pub fn router(state: AppState) -> Router<AppState> {
             get(students::list_students).post(students::create_student),
         )
         .route("/promote", post(students::promote))
+        .route("/promotion-suggestions", get(students::promotion_suggestions))
+        .route("/promotions", get(students::list_promotion_batches))
+        .route("/promotions/{batch_id}", get(students::get_promotion_batch))
+        .route(
+            "/promotions/{batch_id}/export",
+            get(students::export_promotion_batch),
+        )
+        .route(
+            "/promotions/{batch_id}/revert",
+            post(students::revert_promotion),
+        )
+        .route("/rollover", post(students::rollover))
+        .route("/rollover/archives", get(students::academic_year_archives))
+        .route("/class-rolls", get(students::class_rolls))
         .route("/export", get(students::export))
+        .route("/attendance-flags", get(attendance::list_flags))
         .route(
             "/{id}",
             get(students::get_student)
@@ -28,6 +43,21 @@ pub fn router(state: AppState) -> Router<AppState> {
         )
         .route("/{id}/status", patch(students::change_status))
         .route("/{id}/class", patch(students::change_class))
+        .route("/{id}/attendance", get(attendance::student_attendance))
+        .route("/{id}/scores", get(gradebook::student_scores))
+        .route("/{id}/results", get(gradebook::student_results))
+        .route("/{id}/statistics", get(gradebook::student_statistics))
+        .route("/{id}/report-card", get(report_cards::student_report_card))
+        .route("/{id}/transcript", get(report_cards::student_transcript))
+        .route("/{id}/traits", get(report_cards::student_traits))
+        .route("/{id}/comments", get(report_cards::student_comments))
+        .route("/{id}/fees", get(fees::student_fees))
+        .route("/{id}/fees/payments", post(fees::record_payment))
+        .route(
+            "/{id}/fees/discounts",
+            get(fees::student_discounts).post(fees::assign_discount),
+        )
+        .route("/{id}/fees/discounts/{discount_id}", delete(fees::remove_discount))
         .layer(RequestBodyLimitLayer::new(1024 * 1024));
 
     standard.merge(upload).layer(axum_mw::from_fn_with_state(
@@ -0,0 +1,330 @@
+use sqlx::{PgConnection, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::attendance::{
+    AttendanceAlertRunResponse, AttendanceFlagQuery, AttendanceFlagResponse,
+};
+use crate::services::notifications::{self, GuardianNotice};
+
+use super::stats::closed_mark_dates;
+use super::{parse_count, AttendanceService};
+
+pub(crate) const FLAG_CONSECUTIVE_ABSENCE: &str = "consecutive_absence";
+pub(crate) const FLAG_BELOW_THRESHOLD: &str = "below_threshold";
+
+/// Counted days (present + late + absent) a student needs before the
+/// threshold check applies, so one absence in the first week doesn't flag.
+pub(crate) const THRESHOLD_MIN_DAYS: i64 = 5;
+
+#[derive(Default, sqlx::FromRow)]
+struct AlertSettingsRow {
+    attendance_alerts: Option<bool>,
+    consecutive_absence_alert: Option<String>,
+    attendance_threshold: Option<String>,
+}
+
+/// Resolve the school's open flags if `attendance_alerts` is off, so flags
+/// raised while it was on don't linger. Returns how many were resolved.
+pub(crate) async fn resolve_if_disabled(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+) -> Result<u64, AppError> {
+    Ok(sqlx::query(
+        r#"
+        UPDATE attendance_flags f SET resolved_at = NOW(), last_evaluated_at = NOW()
+        FROM school_configs c
+        WHERE f.org_id = $1 AND f.resolved_at IS NULL
+          AND c.org_id = f.org_id AND c.attendance_alerts IS NOT TRUE
+        "#,
+    )
+    .bind(org_id)
+    .execute(conn)
+    .await?
+    .rows_affected())
+}
+
+impl AttendanceService {
+    /// Evaluate every school that has `attendance_alerts` turned on. A failing
+    /// school is logged and skipped so it can't block the rest.
+    pub async fn evaluate_alerts_for_all(&self) -> Result<AttendanceAlertRunResponse, AppError> {
+        let org_ids: Vec<Uuid> = sqlx::query_scalar(
+            "SELECT org_id FROM school_configs WHERE attendance_alerts IS TRUE",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut total = AttendanceAlertRunResponse::default();
+        for org_id in org_ids {
+            match self.evaluate_alerts(org_id).await {
+                Ok(run) => {
+                    total.flagged += run.flagged;
+                    total.resolved += run.resolved;
+                    total.notifications_queued += run.notifications_queued;
+                }
+                Err(e) => tracing::warn!(%org_id, error = %e, "Attendance alert evaluation failed"),
+            }
+        }
+        Ok(total)
+    }
+
+    /// Open, refresh and resolve one school's attendance flags. Guardians are
+    /// notified only when a flag is first opened, not on every run.
+    pub async fn evaluate_alerts(&self, org_id: Uuid) -> Result<AttendanceAlertRunResponse, AppError> {
+        let settings: AlertSettingsRow = sqlx::query_as(
+            r#"
+            SELECT attendance_alerts, consecutive_absence_alert, attendance_threshold
+            FROM school_configs WHERE org_id = $1
+            "#,
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or_default();
+        if settings.attendance_alerts != Some(true) {
+            return Err(AppError::BadRequest(
+                "Attendance alerts are turned off for this school".into(),
+            ));
+        }
+        let streak_limit = parse_count(settings.consecutive_absence_alert.as_deref()).filter(|n| *n > 0);
+        let threshold = parse_rate(settings.attendance_threshold.as_deref());
+
+        let mut tx = self.pool.begin().await?;
+        let mut run = AttendanceAlertRunResponse::default();
+
+        let streaks = match streak_limit {
+            Some(n) => absence_streaks(&mut tx, org_id, n).await?,
+            None => Vec::new(),
+        };
+        apply_flags(
+            &mut tx,
+            org_id,
+            FLAG_CONSECUTIVE_ABSENCE,
+            streak_limit.unwrap_or(0) as f64,
+            &streaks,
+            &mut run,
+        )
+        .await?;
+
+        let below = match threshold {
+            Some(t) => below_threshold(&mut tx, org_id, t).await?,
+            None => Vec::new(),
+        };
+        apply_flags(
+            &mut tx,
+            org_id,
+            FLAG_BELOW_THRESHOLD,
+            threshold.unwrap_or(0.0),
+            &below,
+            &mut run,
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(run)
+    }
+
+    /// Flags for the school, newest first. Defaults to open flags only.
+    pub async fn list_flags(
+        &self,
+        org_id: Uuid,
+        q: AttendanceFlagQuery,
+    ) -> Result<Vec<AttendanceFlagResponse>, AppError> {
+        let mut qb = QueryBuilder::new(
+            r#"
+            SELECT f.id, f.student_id, s.admission_number, s.first_name, s.last_name,
+                   s.grade_level, s.section, f.kind, f.value, f.threshold,
+                   f.flagged_at, f.last_evaluated_at, f.resolved_at
+            FROM attendance_flags f
+            JOIN students s ON s.id = f.student_id AND s.org_id = f.org_id
+            WHERE f.org_id = "#,
+        );
+        qb.push_bind(org_id);
+
+        match q.status.as_deref().map(str::trim).unwrap_or("open") {
+            "open" => {
+                qb.push(" AND f.resolved_at IS NULL");
+            }
+            "resolved" => {
+                qb.push(" AND f.resolved_at IS NOT NULL");
+            }
+            "all" => {}
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid status '{other}'. Use open, resolved or all"
+                )));
+            }
+        }
+        if let Some(kind) = q.kind.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            if kind != FLAG_CONSECUTIVE_ABSENCE && kind != FLAG_BELOW_THRESHOLD {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid kind '{kind}'. Use {FLAG_CONSECUTIVE_ABSENCE} or {FLAG_BELOW_THRESHOLD}"
+                )));
+            }
+            qb.push(" AND f.kind = ").push_bind(kind.to_string());
+        }
+        if let Some(level) = q.grade_level.as_deref().map(str::trim).filter(|l| !l.is_empty()) {
+            qb.push(" AND s.grade_level = ").push_bind(level.to_string());
+        }
+        qb.push(" ORDER BY f.flagged_at DESC, s.last_name, s.first_name");
+
+        let flags = qb
+            .build_query_as::<AttendanceFlagResponse>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(flags)
+    }
+}
+
+/// `attendance_threshold` is stored as TEXT (`"75"`); valid values are (0, 100].
+fn parse_rate(s: Option<&str>) -> Option<f64> {
+    s.and_then(|s| s.trim().trim_end_matches('%').parse::<f64>().ok())
+        .filter(|t| *t > 0.0 && *t <= 100.0)
+}
+
+/// Active students whose most recent daily marks are an unbroken run of at
+/// least `min` absences. Only marks on instructional days count, so
+/// weekends and holidays neither break nor extend a run; any non-absent
+/// mark (including excused) breaks it.
+async fn absence_streaks(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    min: i64,
+) -> Result<Vec<(Uuid, f64)>, AppError> {
+    let closed = closed_mark_dates(conn, org_id, None).await?;
+    let rows: Vec<(Uuid, i64)> = sqlx::query_as(
+        r#"
+        SELECT t.student_id, COUNT(*)
+        FROM (
+            SELECT r.student_id,
+                   COUNT(*) FILTER (WHERE r.status <> 'absent') OVER (
+                       PARTITION BY r.student_id ORDER BY r.attendance_date DESC
+                   ) AS breaks
+            FROM attendance_records r
+            JOIN students s ON s.id = r.student_id AND s.org_id = r.org_id
+            WHERE r.org_id = $1 AND r.period_label IS NULL AND s.status = 'active'
+              AND NOT (r.attendance_date = ANY($3))
+        ) t
+        WHERE t.breaks = 0
+        GROUP BY t.student_id
+        HAVING COUNT(*) >= $2
+        "#,
+    )
+    .bind(org_id)
+    .bind(min)
+    .bind(&closed)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows.into_iter().map(|(id, n)| (id, n as f64)).collect())
+}
+
+/// Active students whose stored attendance rate is below `threshold`, once
+/// they have [`THRESHOLD_MIN_DAYS`] counted days.
+async fn below_threshold(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    threshold: f64,
+) -> Result<Vec<(Uuid, f64)>, AppError> {
+    let rows = sqlx::query_as(
+        r#"
+        SELECT a.student_id, a.attendance_rate
+        FROM attendance_summaries a
+        JOIN students s ON s.id = a.student_id AND s.org_id = a.org_id
+        WHERE a.org_id = $1 AND s.status = 'active'
+          AND a.attendance_rate < $2
+          AND a.present + a.late + a.absent >= $3
+        "#,
+    )
+    .bind(org_id)
+    .bind(threshold)
+    .bind(THRESHOLD_MIN_DAYS)
+    .fetch_all(&mut *conn)
+    .await?;
+    Ok(rows)
+}
+
+/// Bring the open flags of one kind in line with `hits`: resolve the ones no
+/// longer hit, refresh the ones still hit, and open (and notify) new ones.
+async fn apply_flags(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    kind: &str,
+    threshold: f64,
+    hits: &[(Uuid, f64)],
+    run: &mut AttendanceAlertRunResponse,
+) -> Result<(), AppError> {
+    let ids: Vec<Uuid> = hits.iter().map(|(id, _)| *id).collect();
+    run.resolved += sqlx::query(
+        r#"
+        UPDATE attendance_flags SET resolved_at = NOW(), last_evaluated_at = NOW()
+        WHERE org_id = $1 AND kind = $2 AND resolved_at IS NULL
+          AND NOT (student_id = ANY($3))
+        "#,
+    )
+    .bind(org_id)
+    .bind(kind)
+    .bind(&ids)
+    .execute(&mut *conn)
+    .await?
+    .rows_affected();
+
+    for &(student_id, value) in hits {
+        let (flag_id, inserted): (Uuid, bool) = sqlx::query_as(
+            r#"
+            INSERT INTO attendance_flags (org_id, student_id, kind, value, threshold)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (student_id, kind) WHERE resolved_at IS NULL
+            DO UPDATE SET value = EXCLUDED.value, threshold = EXCLUDED.threshold,
+                          last_evaluated_at = NOW()
+            RETURNING id, (xmax = 0)
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(kind)
+        .bind(value)
+        .bind(threshold)
+        .fetch_one(&mut *conn)
+        .await?;
+        if !inserted {
+            continue;
+        }
+        run.flagged += 1;
+
+        let (first_name, last_name, school): (String, String, String) = sqlx::query_as(
+            r#"
+            SELECT s.first_name, s.last_name, o.name
+            FROM students s JOIN organizations o ON o.id = s.org_id
+            WHERE s.id = $1 AND s.org_id = $2
+            "#,
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&mut *conn)
+        .await?;
+        let subject = format!("Attendance alert: {first_name} {last_name}");
+        let body = if kind == FLAG_CONSECUTIVE_ABSENCE {
+            format!(
+                "{first_name} {last_name} has been absent from {school} for {} consecutive school days. Please contact the school.",
+                value as i64
+            )
+        } else {
+            format!(
+                "{first_name} {last_name}'s attendance at {school} is {value}%, below the required {threshold}%. Please contact the school."
+            )
+        };
+        run.notifications_queued += notifications::queue_for_primary_guardian(
+            conn,
+            org_id,
+            &GuardianNotice {
+                student_id,
+                kind,
+                subject: &subject,
+                body: &body,
+                source_id: Some(flag_id),
+            },
+        )
+        .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use chrono_tz::Tz;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+pub(super) mod alerts;
+pub(super) mod periods;
+pub(super) mod register;
+pub(crate) mod stats;
+
+pub struct AttendanceService {
+    pub(super) pool: PgPool,
+}
+
+impl AttendanceService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Attendance-relevant slice of `school_configs`, parsed once per request.
+pub(super) struct AttendancePolicy {
+    pub absence_categories: Vec<String>,
+    pub timezone: Tz,
+    /// `attendance_tracking_methods`: group name → daily | per_period | both.
+    pub tracking_methods: HashMap<String, String>,
+    /// `custom_group_levels`: group name → grade levels in that group.
+    pub custom_group_levels: HashMap<String, Vec<String>>,
+    /// Minutes after the start time an arrival still counts as present.
+    pub late_grace_minutes: i64,
+    /// Every N lates count as one absence; None disables the conversion.
+    pub tardies_to_absence: Option<i64>,
+}
+
+impl AttendancePolicy {
+    /// Today's date in the school's timezone (UTC if unset/invalid), so a
+    /// register taken just after local midnight isn't rejected as "future".
+    pub fn today(&self) -> NaiveDate {
+        chrono::Utc::now().with_timezone(&self.timezone).date_naive()
+    }
+
+    /// Match a free-text reason against the configured categories
+    /// (case-insensitive) and return the canonical spelling.
+    pub fn canonical_reason(&self, reason: &str) -> Option<&str> {
+        let wanted = reason.trim();
+        self.absence_categories
+            .iter()
+            .find(|c| c.eq_ignore_ascii_case(wanted))
+            .map(String::as_str)
+    }
+}
+
+#[derive(Default, sqlx::FromRow)]
+struct PolicyRow {
+    absence_categories: serde_json::Value,
+    timezone: Option<String>,
+    attendance_tracking_methods: serde_json::Value,
+    custom_group_levels: serde_json::Value,
+    late_grace_period: Option<String>,
+    tardies_to_absence: Option<String>,
+}
+
+pub(super) async fn load_policy(pool: &PgPool, org_id: Uuid) -> Result<AttendancePolicy, AppError> {
+    let row: PolicyRow = sqlx::query_as(
+        r#"
+        SELECT absence_categories, timezone, attendance_tracking_methods, custom_group_levels,
+               late_grace_period, tardies_to_absence
+        FROM school_configs WHERE org_id = $1
+        "#,
+    )
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await?
+    .unwrap_or_default();
+
+    let absence_categories = row
+        .absence_categories
+        .as_array()
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|v| v.as_str())
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+                .collect()
+        })
+        .unwrap_or_default();
+    let timezone = row
+        .timezone
+        .as_deref()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(chrono_tz::UTC);
+
+    let tracking_methods = row
+        .attendance_tracking_methods
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .filter_map(|(group, v)| Some((group.clone(), v.as_str()?.trim().to_string())))
+                .collect()
+        })
+        .unwrap_or_default();
+    let custom_group_levels = row
+        .custom_group_levels
+        .as_object()
+        .map(|obj| {
+            obj.iter()
+                .map(|(group, v)| {
+                    let levels = v
+                        .as_array()
+                        .map(|arr| arr.iter().filter_map(|l| l.as_str().map(String::from)).collect())
+                        .unwrap_or_default();
+                    (group.clone(), levels)
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(AttendancePolicy {
+        absence_categories,
+        timezone,
+        tracking_methods,
+        custom_group_levels,
+        late_grace_minutes: parse_count(row.late_grace_period.as_deref()).unwrap_or(0),
+        tardies_to_absence: parse_count(row.tardies_to_absence.as_deref()).filter(|n| *n > 0),
+    })
+}
+
+/// Group a grade level belongs to, for settings keyed by group.
+pub(crate) async fn grade_group(
+    pool: &PgPool,
+    org_id: Uuid,
+    grade_level: &str,
+) -> Result<Option<String>, AppError> {
+    let policy = load_policy(pool, org_id).await?;
+    periods::class_group(pool, org_id, &policy, grade_level).await
+}
+
+/// Policy counts are stored as TEXT (`"15"`, `"3"`); blank or junk means unset.
+pub(super) fn parse_count(s: Option<&str>) -> Option<i64> {
+    s.and_then(|s| s.trim().parse::<i64>().ok()).filter(|n| *n >= 0)
+}
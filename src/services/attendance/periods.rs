@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveTime};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::attendance::{ClassPeriodsQuery, ClassPeriodsResponse, LessonPeriod};
+use crate::services::students::validate_grade_level;
+
+use super::{load_policy, AttendancePolicy, AttendanceService};
+
+/// How a group of classes takes attendance, from `attendance_tracking_methods`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TrackingMode {
+    Daily,
+    PerPeriod,
+    Both,
+}
+
+impl TrackingMode {
+    /// `per_subject` is what the setup wizard stores for lesson-level tracking.
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "daily" => Some(Self::Daily),
+            "per_period" | "per_subject" => Some(Self::PerPeriod),
+            "both" => Some(Self::Both),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::PerPeriod => "per_period",
+            Self::Both => "both",
+        }
+    }
+
+    pub fn allows_daily(self) -> bool {
+        self != Self::PerPeriod
+    }
+
+    pub fn allows_periods(self) -> bool {
+        self != Self::Daily
+    }
+}
+
+impl AttendancePolicy {
+    /// Tracking mode for a group; daily when the group is unknown or unset.
+    pub(super) fn tracking_mode(&self, group: Option<&str>) -> TrackingMode {
+        group
+            .and_then(|g| self.tracking_methods.get(g))
+            .and_then(|m| TrackingMode::parse(m))
+            .unwrap_or(TrackingMode::Daily)
+    }
+}
+
+/// A lesson period resolved from the current bell schedule, snapshotted onto
+/// the register since schedule rows are rebuilt on every setup save.
+pub(super) struct ResolvedPeriod {
+    pub label: String,
+    pub position: i16,
+    pub start_time: Option<NaiveTime>,
+}
+
+#[derive(sqlx::FromRow)]
+struct PeriodRow {
+    id: Uuid,
+    label: String,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    is_break: bool,
+    position: i16,
+    group_id: Uuid,
+}
+
+impl AttendanceService {
+    /// Tracking mode and attendable (non-break) periods for a grade level.
+    pub async fn class_periods(
+        &self,
+        org_id: Uuid,
+        q: ClassPeriodsQuery,
+    ) -> Result<ClassPeriodsResponse, AppError> {
+        validate_grade_level(&self.pool, org_id, &q.grade_level).await?;
+        let policy = load_policy(&self.pool, org_id).await?;
+        let group = class_group(&self.pool, org_id, &policy, &q.grade_level).await?;
+        let mode = policy.tracking_mode(group.as_deref());
+
+        let periods = match schedule_group_id(&self.pool, org_id, group.as_deref()).await? {
+            Some(group_id) => {
+                let rows: Vec<PeriodRow> = sqlx::query_as(
+                    r#"
+                    SELECT id, label, start_time, end_time, is_break, position, group_id
+                    FROM school_schedule_periods
+                    WHERE group_id = $1 AND NOT is_break
+                    ORDER BY position
+                    "#,
+                )
+                .bind(group_id)
+                .fetch_all(&self.pool)
+                .await?;
+                rows.into_iter()
+                    .map(|p| LessonPeriod {
+                        id: p.id,
+                        label: p.label,
+                        start_time: p.start_time,
+                        end_time: p.end_time,
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(ClassPeriodsResponse {
+            grade_level: q.grade_level,
+            group,
+            tracking_mode: mode.as_str().to_string(),
+            periods,
+        })
+    }
+}
+
+/// Group a grade level belongs to: `custom_group_levels` first, then the
+/// `group_name` stored on the grade level itself.
+pub(super) async fn class_group(
+    pool: &PgPool,
+    org_id: Uuid,
+    policy: &AttendancePolicy,
+    grade_level: &str,
+) -> Result<Option<String>, AppError> {
+    if let Some((group, _)) = policy
+        .custom_group_levels
+        .iter()
+        .find(|(_, levels)| levels.iter().any(|l| l == grade_level))
+    {
+        return Ok(Some(group.clone()));
+    }
+    let group: Option<Option<String>> = sqlx::query_scalar(
+        "SELECT group_name FROM school_grade_levels WHERE org_id = $1 AND name = $2 LIMIT 1",
+    )
+    .bind(org_id)
+    .bind(grade_level)
+    .fetch_optional(pool)
+    .await?;
+    Ok(group.flatten().filter(|g| !g.trim().is_empty()))
+}
+
+/// Bell schedule for a group. A school with a single schedule uses it for
+/// every class.
+async fn schedule_group_id(
+    pool: &PgPool,
+    org_id: Uuid,
+    group: Option<&str>,
+) -> Result<Option<Uuid>, AppError> {
+    let groups: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, group_name FROM school_schedule_groups WHERE org_id = $1 ORDER BY position",
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    if let Some(g) = group
+        && let Some((id, _)) = groups.iter().find(|(_, name)| name == g)
+    {
+        return Ok(Some(*id));
+    }
+    Ok(match groups.as_slice() {
+        [(id, _)] => Some(*id),
+        _ => None,
+    })
+}
+
+/// Look up a period of the class's bell schedule, rejecting breaks and
+/// periods belonging to another group's schedule.
+pub(super) async fn resolve_period(
+    pool: &PgPool,
+    org_id: Uuid,
+    group: Option<&str>,
+    period_id: Uuid,
+) -> Result<ResolvedPeriod, AppError> {
+    let period: PeriodRow = sqlx::query_as(
+        r#"
+        SELECT p.id, p.label, p.start_time, p.end_time, p.is_break, p.position, p.group_id
+        FROM school_schedule_periods p
+        JOIN school_schedule_groups g ON g.id = p.group_id
+        WHERE p.id = $1 AND g.org_id = $2
+        "#,
+    )
+    .bind(period_id)
+    .bind(org_id)
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Schedule period not found".into()))?;
+
+    if period.is_break {
+        return Err(AppError::BadRequest(format!(
+            "'{}' is a break; attendance can't be taken for it",
+            period.label
+        )));
+    }
+    if schedule_group_id(pool, org_id, group).await? != Some(period.group_id) {
+        return Err(AppError::BadRequest(format!(
+            "Period '{}' is not on this class's bell schedule",
+            period.label
+        )));
+    }
+    Ok(ResolvedPeriod {
+        start_time: period.start_time.as_deref().and_then(parse_clock),
+        label: period.label,
+        position: period.position,
+    })
+}
+
+/// When the school day starts for a class: the schedule's `start_time`, or
+/// the first non-break period's start if that isn't set.
+pub(super) async fn day_start_time(
+    pool: &PgPool,
+    org_id: Uuid,
+    group: Option<&str>,
+) -> Result<Option<NaiveTime>, AppError> {
+    let Some(group_id) = schedule_group_id(pool, org_id, group).await? else {
+        return Ok(None);
+    };
+    let times: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT g.start_time,
+               (SELECT p.start_time FROM school_schedule_periods p
+                WHERE p.group_id = g.id AND NOT p.is_break
+                ORDER BY p.position LIMIT 1)
+        FROM school_schedule_groups g WHERE g.id = $1
+        "#,
+    )
+    .bind(group_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(times.and_then(|(group_start, first_period)| {
+        group_start
+            .as_deref()
+            .and_then(parse_clock)
+            .or_else(|| first_period.as_deref().and_then(parse_clock))
+    }))
+}
+
+/// Parse the `HH:MM` (or `HH:MM:SS`) clock strings used by the setup wizard.
+pub(super) fn parse_clock(s: &str) -> Option<NaiveTime> {
+    let s = s.trim();
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .ok()
+}
+
+/// Recompute the daily mark of each student from their period marks for the
+/// day. Marks a teacher took directly on the daily register are left alone.
+pub(super) async fn roll_up_daily(
+    tx: &mut PgConnection,
+    org_id: Uuid,
+    date: NaiveDate,
+    grade_level: &str,
+    section: Option<&str>,
+    student_ids: &[Uuid],
+    marked_by: Option<Uuid>,
+) -> Result<(), AppError> {
+    let rows: Vec<(Uuid, String, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT r.student_id, r.status, r.absence_reason
+        FROM attendance_records r
+        JOIN attendance_registers g ON g.id = r.register_id
+        WHERE r.org_id = $1 AND r.student_id = ANY($2) AND r.attendance_date = $3
+          AND r.period_label IS NOT NULL
+        ORDER BY r.student_id, g.period_position
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .bind(date)
+    .fetch_all(&mut *tx)
+    .await?;
+    let mut per_student: HashMap<Uuid, Vec<(String, Option<String>)>> = HashMap::new();
+    for (student_id, status, reason) in rows {
+        per_student.entry(student_id).or_default().push((status, reason));
+    }
+    if per_student.is_empty() {
+        return Ok(());
+    }
+
+    let register_id: Uuid = sqlx::query_scalar(
+        r#"
+        INSERT INTO attendance_registers (org_id, register_date, grade_level, section, taken_by_user_id)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (org_id, register_date, grade_level, COALESCE(section, ''), COALESCE(period_label, ''))
+        DO UPDATE SET taken_by_user_id = COALESCE(attendance_registers.taken_by_user_id, EXCLUDED.taken_by_user_id)
+        RETURNING id
+        "#,
+    )
+    .bind(org_id)
+    .bind(date)
+    .bind(grade_level)
+    .bind(section)
+    .bind(marked_by)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for (student_id, marks) in per_student {
+        let (status, reason) = daily_status(&marks);
+        sqlx::query(
+            r#"
+            INSERT INTO attendance_records
+                (register_id, student_id, org_id, attendance_date, status,
+                 absence_reason, marked_by_user_id, is_derived)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, TRUE)
+            ON CONFLICT (register_id, student_id) DO UPDATE SET
+                status = EXCLUDED.status,
+                absence_reason = EXCLUDED.absence_reason,
+                marked_by_user_id = EXCLUDED.marked_by_user_id
+            WHERE attendance_records.is_derived
+            "#,
+        )
+        .bind(register_id)
+        .bind(student_id)
+        .bind(org_id)
+        .bind(date)
+        .bind(status)
+        .bind(reason)
+        .bind(marked_by)
+        .execute(&mut *tx)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Daily status from one student's period marks, in period order:
+/// - attended no period → `excused` if every miss was excused, else `absent`;
+/// - attended, but missed or was late to the first marked period → `late`;
+/// - otherwise `present`.
+///
+/// For absent/excused days the reason of the first missed period carries over.
+pub(super) fn daily_status(marks: &[(String, Option<String>)]) -> (&'static str, Option<String>) {
+    let attended = |s: &str| matches!(s, "present" | "late");
+    if !marks.iter().any(|(s, _)| attended(s)) {
+        let status = if marks.iter().all(|(s, _)| s == "excused") {
+            "excused"
+        } else {
+            "absent"
+        };
+        let reason = marks.iter().find_map(|(_, r)| r.clone());
+        return (status, reason);
+    }
+    match marks.first().map(|(s, _)| s.as_str()) {
+        Some("present") => ("present", None),
+        _ => ("late", None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marks(statuses: &[&str]) -> Vec<(String, Option<String>)> {
+        statuses.iter().map(|s| (s.to_string(), None)).collect()
+    }
+
+    #[test]
+    fn daily_status_rolls_up_period_marks() {
+        assert_eq!(daily_status(&marks(&["present", "present"])).0, "present");
+        assert_eq!(daily_status(&marks(&["present", "absent"])).0, "present");
+        assert_eq!(daily_status(&marks(&["absent", "present"])).0, "late");
+        assert_eq!(daily_status(&marks(&["late", "present"])).0, "late");
+        assert_eq!(daily_status(&marks(&["absent", "excused"])).0, "absent");
+        assert_eq!(daily_status(&marks(&["excused", "excused"])).0, "excused");
+    }
+
+    #[test]
+    fn daily_status_keeps_first_reason_for_missed_days() {
+        let m = vec![
+            ("absent".to_string(), None),
+            ("absent".to_string(), Some("Sick".to_string())),
+        ];
+        assert_eq!(daily_status(&m), ("absent", Some("Sick".to_string())));
+    }
+}
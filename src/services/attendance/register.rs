@@ -0,0 +1,431 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{Duration, NaiveTime};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::attendance::{
+    AttendanceCounts, AttendanceRegisterRow, MarkRegisterRequest, RegisterEntry, RegisterQuery,
+    RegisterResponse,
+};
+use crate::services::calendar::SchoolCalendar;
+use crate::services::students::validate_grade_level;
+
+use super::periods::{
+    class_group, day_start_time, parse_clock, resolve_period, roll_up_daily, ResolvedPeriod,
+};
+use super::stats::refresh_summaries;
+use super::{load_policy, AttendancePolicy, AttendanceService};
+
+const ALLOWED_MARKS: &[&str] = &["present", "absent", "late", "excused"];
+
+#[derive(sqlx::FromRow)]
+struct RosterRow {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    last_name: String,
+    status: Option<String>,
+    absence_reason: Option<String>,
+    note: Option<String>,
+    is_derived: Option<bool>,
+    arrival_time: Option<NaiveTime>,
+}
+
+/// One validated line of a register submission.
+struct CheckedMark {
+    status: String,
+    reason: Option<String>,
+    arrival_time: Option<NaiveTime>,
+}
+
+impl AttendanceService {
+    /// Take (or re-take) a class register for one day, or for one lesson period
+    /// of that day when `period_id` is set. Marks are upserted per student, so a
+    /// teacher can submit the register in several passes; students not listed
+    /// in `records` keep whatever mark they already had. Period marks are rolled
+    /// up into each student's daily mark.
+    pub async fn mark_register(
+        &self,
+        org_id: Uuid,
+        req: MarkRegisterRequest,
+        marked_by: Option<Uuid>,
+    ) -> Result<RegisterResponse, AppError> {
+        if req.records.is_empty() {
+            return Err(AppError::BadRequest("records array is empty".into()));
+        }
+        validate_grade_level(&self.pool, org_id, &req.grade_level).await?;
+        let section = req.section.as_deref().map(str::trim).filter(|s| !s.is_empty());
+
+        let policy = load_policy(&self.pool, org_id).await?;
+        if req.date > policy.today() {
+            return Err(AppError::BadRequest(
+                "Cannot take attendance for a future date".into(),
+            ));
+        }
+        let calendar = SchoolCalendar::load(
+            &mut *self.pool.acquire().await?,
+            org_id,
+            Some((req.date, req.date)),
+        )
+        .await?;
+        if let Some(reason) = calendar.closed_reason(req.date) {
+            return Err(AppError::BadRequest(format!(
+                "{} is not an instructional day ({reason}); attendance can't be taken",
+                req.date
+            )));
+        }
+        let period =
+            register_period(&self.pool, org_id, &policy, &req.grade_level, req.period_id, true)
+                .await?;
+
+        // Arrivals are judged against the period's start, or the school day's
+        // start for a daily register. Only looked up when an arrival is sent.
+        let start_time = if req.records.iter().any(|r| r.arrival_time.is_some()) {
+            let start = match &period {
+                Some(p) => p.start_time,
+                None => {
+                    let group = class_group(&self.pool, org_id, &policy, &req.grade_level).await?;
+                    day_start_time(&self.pool, org_id, group.as_deref()).await?
+                }
+            };
+            Some(start.ok_or_else(|| {
+                AppError::BadRequest(
+                    "No start time is configured on this class's bell schedule; send status instead of arrival_time".into(),
+                )
+            })?)
+        } else {
+            None
+        };
+
+        // Validate every mark up front so a bad row doesn't leave a half-written register.
+        let mut seen: HashSet<Uuid> = HashSet::with_capacity(req.records.len());
+        let mut marks: Vec<CheckedMark> = Vec::with_capacity(req.records.len());
+        for r in &req.records {
+            if !seen.insert(r.student_id) {
+                return Err(AppError::BadRequest(format!(
+                    "duplicate student_id {} in records array",
+                    r.student_id
+                )));
+            }
+            let arrival_time = match r.arrival_time.as_deref() {
+                None => None,
+                Some(raw) => Some(parse_clock(raw).ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Invalid arrival_time '{raw}'; expected HH:MM (student {})",
+                        r.student_id
+                    ))
+                })?),
+            };
+            let status = match (r.status.as_deref(), arrival_time, start_time) {
+                (Some("absent" | "excused"), Some(_), _) => {
+                    return Err(AppError::BadRequest(format!(
+                        "arrival_time can't be set on an absent/excused mark (student {})",
+                        r.student_id
+                    )));
+                }
+                (_, Some(arrived), Some(start)) => {
+                    classify_arrival(arrived, start, policy.late_grace_minutes).to_string()
+                }
+                (Some(s), _, _) if ALLOWED_MARKS.contains(&s) => s.to_string(),
+                (Some(s), _, _) => {
+                    return Err(AppError::BadRequest(format!(
+                        "Invalid status '{s}'; must be one of {ALLOWED_MARKS:?}"
+                    )));
+                }
+                (None, _, _) => {
+                    return Err(AppError::BadRequest(format!(
+                        "status or arrival_time is required (student {})",
+                        r.student_id
+                    )));
+                }
+            };
+            let reason = match r.absence_reason.as_deref().map(str::trim) {
+                None | Some("") => None,
+                Some(_) if !matches!(status.as_str(), "absent" | "excused") => {
+                    return Err(AppError::BadRequest(format!(
+                        "absence_reason is only allowed for absent/excused marks (student {})",
+                        r.student_id
+                    )));
+                }
+                Some(raw) => match policy.canonical_reason(raw) {
+                    Some(c) => Some(c.to_string()),
+                    None if policy.absence_categories.is_empty() => {
+                        return Err(AppError::BadRequest(
+                            "No absence_categories are configured for this school; set them in the policies section first".into(),
+                        ));
+                    }
+                    None => {
+                        return Err(AppError::BadRequest(format!(
+                            "Invalid absence_reason '{raw}'; must be one of {:?}",
+                            policy.absence_categories
+                        )));
+                    }
+                },
+            };
+            marks.push(CheckedMark {
+                status,
+                reason,
+                arrival_time,
+            });
+        }
+
+        let ids: Vec<Uuid> = req.records.iter().map(|r| r.student_id).collect();
+        let placements: Vec<(Uuid, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, grade_level, section FROM students WHERE org_id = $1 AND id = ANY($2)",
+        )
+        .bind(org_id)
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let placements: HashMap<Uuid, (String, Option<String>)> = placements
+            .into_iter()
+            .map(|(id, g, s)| (id, (g, s)))
+            .collect();
+        for id in &ids {
+            let (grade, student_section) = placements
+                .get(id)
+                .ok_or_else(|| AppError::NotFound(format!("Student {id} not found")))?;
+            let in_class = grade == &req.grade_level
+                && section.is_none_or(|s| student_section.as_deref() == Some(s));
+            if !in_class {
+                return Err(AppError::BadRequest(format!(
+                    "Student {id} is not in {}{}",
+                    req.grade_level,
+                    section.map(|s| format!(" {s}")).unwrap_or_default()
+                )));
+            }
+        }
+
+        let mut tx = self.pool.begin().await?;
+
+        let register: AttendanceRegisterRow = sqlx::query_as(
+            r#"
+            INSERT INTO attendance_registers
+                (org_id, register_date, grade_level, section, period_label, period_position, taken_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (org_id, register_date, grade_level, COALESCE(section, ''), COALESCE(period_label, ''))
+            DO UPDATE SET period_position = EXCLUDED.period_position,
+                          taken_by_user_id = COALESCE(EXCLUDED.taken_by_user_id, attendance_registers.taken_by_user_id)
+            RETURNING *
+            "#,
+        )
+        .bind(org_id)
+        .bind(req.date)
+        .bind(&req.grade_level)
+        .bind(section)
+        .bind(period.as_ref().map(|p| &p.label))
+        .bind(period.as_ref().map(|p| p.position))
+        .bind(marked_by)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for (r, mark) in req.records.iter().zip(marks) {
+            sqlx::query(
+                r#"
+                INSERT INTO attendance_records
+                    (register_id, student_id, org_id, attendance_date, status,
+                     absence_reason, note, marked_by_user_id, period_label, arrival_time)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT (register_id, student_id) DO UPDATE SET
+                    status = EXCLUDED.status,
+                    absence_reason = EXCLUDED.absence_reason,
+                    note = EXCLUDED.note,
+                    arrival_time = EXCLUDED.arrival_time,
+                    marked_by_user_id = EXCLUDED.marked_by_user_id,
+                    is_derived = FALSE
+                "#,
+            )
+            .bind(register.id)
+            .bind(r.student_id)
+            .bind(org_id)
+            .bind(register.register_date)
+            .bind(&mark.status)
+            .bind(mark.reason)
+            .bind(&r.note)
+            .bind(marked_by)
+            .bind(&register.period_label)
+            .bind(mark.arrival_time)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        if period.is_some() {
+            roll_up_daily(
+                &mut tx,
+                org_id,
+                req.date,
+                &req.grade_level,
+                section,
+                &ids,
+                marked_by,
+            )
+            .await?;
+        }
+        refresh_summaries(&mut tx, org_id, Some(&ids)).await?;
+
+        tx.commit().await?;
+
+        self.get_register(
+            org_id,
+            RegisterQuery {
+                date: req.date,
+                grade_level: req.grade_level,
+                section: section.map(String::from),
+                period_id: req.period_id,
+            },
+        )
+        .await
+    }
+
+    /// Class roster for a day with each student's mark (null if unmarked).
+    /// Includes active students currently in the class plus anyone already
+    /// marked on this register (e.g. a student moved out of the class since).
+    pub async fn get_register(
+        &self,
+        org_id: Uuid,
+        q: RegisterQuery,
+    ) -> Result<RegisterResponse, AppError> {
+        validate_grade_level(&self.pool, org_id, &q.grade_level).await?;
+        let section = q.section.as_deref().map(str::trim).filter(|s| !s.is_empty());
+        let policy = load_policy(&self.pool, org_id).await?;
+        let period =
+            register_period(&self.pool, org_id, &policy, &q.grade_level, q.period_id, false)
+                .await?;
+        let period_label = period.map(|p| p.label);
+
+        let register: Option<AttendanceRegisterRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM attendance_registers
+            WHERE org_id = $1 AND register_date = $2 AND grade_level = $3
+              AND COALESCE(section, '') = COALESCE($4, '')
+              AND COALESCE(period_label, '') = COALESCE($5, '')
+            "#,
+        )
+        .bind(org_id)
+        .bind(q.date)
+        .bind(&q.grade_level)
+        .bind(section)
+        .bind(&period_label)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let rows: Vec<RosterRow> =
+            sqlx::query_as(
+                r#"
+                SELECT s.id, s.admission_number, s.first_name, s.last_name,
+                       r.status, r.absence_reason, r.note, r.is_derived, r.arrival_time
+                FROM students s
+                LEFT JOIN attendance_records r
+                       ON r.student_id = s.id AND r.register_id = $4
+                WHERE s.org_id = $1
+                  AND ((s.status = 'active' AND s.grade_level = $2
+                        AND ($3::text IS NULL OR s.section = $3))
+                       OR r.id IS NOT NULL)
+                ORDER BY s.last_name, s.first_name
+                "#,
+            )
+            .bind(org_id)
+            .bind(&q.grade_level)
+            .bind(section)
+            .bind(register.as_ref().map(|r| r.id))
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut counts = AttendanceCounts::default();
+        let entries: Vec<RegisterEntry> = rows
+            .into_iter()
+            .map(|r| {
+                if let Some(s) = r.status.as_deref() {
+                    counts.add(s, 1);
+                }
+                RegisterEntry {
+                    student_id: r.id,
+                    admission_number: r.admission_number,
+                    first_name: r.first_name,
+                    last_name: r.last_name,
+                    status: r.status,
+                    absence_reason: r.absence_reason,
+                    note: r.note,
+                    arrival_time: r.arrival_time.map(|t| t.format("%H:%M").to_string()),
+                    is_derived: r.is_derived.unwrap_or(false),
+                }
+            })
+            .collect();
+
+        Ok(RegisterResponse {
+            id: register.as_ref().map(|r| r.id),
+            date: q.date,
+            grade_level: q.grade_level,
+            section: section.map(String::from),
+            period_label,
+            taken_by: register.and_then(|r| r.taken_by_user_id),
+            entries,
+            counts,
+        })
+    }
+}
+
+/// Resolve the register's lesson period (if any) and check the class's
+/// tracking mode permits it. Reads are allowed in any mode so that a daily
+/// register can still show rolled-up marks in a per-period school.
+async fn register_period(
+    pool: &sqlx::PgPool,
+    org_id: Uuid,
+    policy: &AttendancePolicy,
+    grade_level: &str,
+    period_id: Option<Uuid>,
+    for_marking: bool,
+) -> Result<Option<ResolvedPeriod>, AppError> {
+    let group = class_group(pool, org_id, policy, grade_level).await?;
+    let mode = policy.tracking_mode(group.as_deref());
+    match period_id {
+        Some(id) => {
+            if !mode.allows_periods() {
+                return Err(AppError::BadRequest(format!(
+                    "{grade_level} takes attendance daily; period registers are not enabled"
+                )));
+            }
+            Ok(Some(resolve_period(pool, org_id, group.as_deref(), id).await?))
+        }
+        None if for_marking && !mode.allows_daily() => Err(AppError::BadRequest(format!(
+            "{grade_level} takes attendance per period; pass a period_id (the daily mark is derived from period marks)"
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Present if the student arrived within `grace_minutes` of the start time,
+/// late otherwise. A grace period running past midnight covers the rest of
+/// the day.
+fn classify_arrival(arrived: NaiveTime, start: NaiveTime, grace_minutes: i64) -> &'static str {
+    let on_time = match Duration::try_minutes(grace_minutes) {
+        Some(grace) => match start.overflowing_add_signed(grace) {
+            (deadline, 0) => arrived <= deadline,
+            _ => true,
+        },
+        None => true,
+    };
+    if on_time { "present" } else { "late" }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    #[test]
+    fn classify_arrival_applies_grace() {
+        assert_eq!(classify_arrival(at(8, 10), at(8, 0), 10), "present");
+        assert_eq!(classify_arrival(at(8, 11), at(8, 0), 10), "late");
+    }
+
+    #[test]
+    fn classify_arrival_grace_past_midnight_covers_the_day() {
+        assert_eq!(classify_arrival(at(23, 59), at(23, 0), 120), "present");
+        assert_eq!(classify_arrival(at(23, 59), at(8, 0), i64::MAX), "present");
+    }
+}
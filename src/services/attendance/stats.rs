@@ -0,0 +1,315 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::attendance::{
+    AttendanceCounts, AttendanceEntry, AttendanceRecordRow, StudentAttendanceQuery,
+    StudentAttendanceResponse,
+};
+use crate::services::calendar::SchoolCalendar;
+
+use super::{load_policy, parse_count, AttendanceService};
+
+/// How many entries `?include=recent_attendance` returns per student.
+pub(crate) const RECENT_ATTENDANCE_LIMIT: i64 = 10;
+
+impl AttendanceService {
+    /// Attendance history for one student with counts and rate over the window.
+    pub async fn student_history(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentAttendanceQuery,
+    ) -> Result<StudentAttendanceResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        if let (Some(from), Some(to)) = (q.from, q.to)
+            && from > to
+        {
+            return Err(AppError::BadRequest("'from' must not be after 'to'".into()));
+        }
+
+        let rows: Vec<AttendanceRecordRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM attendance_records
+            WHERE org_id = $1 AND student_id = $2 AND period_label IS NULL
+              AND ($3::date IS NULL OR attendance_date >= $3)
+              AND ($4::date IS NULL OR attendance_date <= $4)
+            ORDER BY attendance_date DESC
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(q.from)
+        .bind(q.to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let closed = closed_mark_dates(
+            &mut *self.pool.acquire().await?,
+            org_id,
+            Some(&[student_id]),
+        )
+        .await?;
+        let mut counts = AttendanceCounts::default();
+        for r in rows.iter().filter(|r| !closed.contains(&r.attendance_date)) {
+            counts.add(&r.status, 1);
+        }
+        let policy = load_policy(&self.pool, org_id).await?;
+        counts.apply_tardy_conversion(policy.tardies_to_absence);
+
+        Ok(StudentAttendanceResponse {
+            student_id,
+            attendance_rate: counts.rate(),
+            counts,
+            records: rows.into_iter().map(AttendanceEntry::from).collect(),
+        })
+    }
+}
+
+/// Rebuild the cached daily-mark totals (with tardy conversion applied) for
+/// the given students, or for the whole school when `student_ids` is None.
+/// Runs inside the caller's transaction so marks and summaries never disagree.
+pub(crate) async fn refresh_summaries(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    student_ids: Option<&[Uuid]>,
+) -> Result<(), AppError> {
+    let tardies: Option<Option<String>> =
+        sqlx::query_scalar("SELECT tardies_to_absence FROM school_configs WHERE org_id = $1")
+            .bind(org_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+    let tardies_to_absence = parse_count(tardies.flatten().as_deref()).filter(|n| *n > 0);
+    let closed = closed_mark_dates(conn, org_id, student_ids).await?;
+
+    let rows: Vec<(Uuid, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT student_id, status, COUNT(*)::bigint
+        FROM attendance_records
+        WHERE org_id = $1 AND period_label IS NULL
+          AND ($2::uuid[] IS NULL OR student_id = ANY($2))
+          AND NOT (attendance_date = ANY($3))
+        GROUP BY student_id, status
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .bind(&closed)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut per_student: HashMap<Uuid, AttendanceCounts> = HashMap::new();
+    for (student_id, status, n) in rows {
+        per_student.entry(student_id).or_default().add(&status, n);
+    }
+
+    sqlx::query(
+        "DELETE FROM attendance_summaries WHERE org_id = $1 AND ($2::uuid[] IS NULL OR student_id = ANY($2))",
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .execute(&mut *conn)
+    .await?;
+    if per_student.is_empty() {
+        return Ok(());
+    }
+
+    let len = per_student.len();
+    let (mut ids, mut present, mut late, mut absent, mut excused, mut converted, mut rates) = (
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+    );
+    for (id, mut c) in per_student {
+        c.apply_tardy_conversion(tardies_to_absence);
+        ids.push(id);
+        present.push(c.present as i32);
+        late.push(c.late as i32);
+        absent.push(c.absent as i32);
+        excused.push(c.excused as i32);
+        converted.push(c.converted_lates as i32);
+        rates.push(c.rate());
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO attendance_summaries
+            (student_id, org_id, present, late, absent, excused, converted_lates, attendance_rate)
+        SELECT u.student_id, $1, u.present, u.late, u.absent, u.excused, u.converted, u.rate
+        FROM UNNEST($2::uuid[], $3::int[], $4::int[], $5::int[], $6::int[], $7::int[], $8::float8[])
+             AS u(student_id, present, late, absent, excused, converted, rate)
+        "#,
+    )
+    .bind(org_id)
+    .bind(&ids)
+    .bind(&present)
+    .bind(&late)
+    .bind(&absent)
+    .bind(&excused)
+    .bind(&converted)
+    .bind(&rates)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Dates with daily marks, for the given students or the whole school, that
+/// aren't instructional days. Marks left on a day that was later made a
+/// holiday or closure don't count towards rates or absence runs.
+pub(super) async fn closed_mark_dates(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    student_ids: Option<&[Uuid]>,
+) -> Result<Vec<NaiveDate>, AppError> {
+    let dates: Vec<NaiveDate> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT attendance_date FROM attendance_records
+        WHERE org_id = $1 AND period_label IS NULL
+          AND ($2::uuid[] IS NULL OR student_id = ANY($2))
+        ORDER BY attendance_date
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let (Some(&from), Some(&to)) = (dates.first(), dates.last()) else {
+        return Ok(Vec::new());
+    };
+    let calendar = SchoolCalendar::load(conn, org_id, Some((from, to))).await?;
+    Ok(dates.into_iter().filter(|d| !calendar.is_instructional(*d)).collect())
+}
+
+/// Attendance rate per student (students with no marks are absent from the map).
+pub(crate) async fn rates_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+) -> Result<HashMap<Uuid, f64>, AppError> {
+    if student_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows: Vec<(Uuid, f64)> = sqlx::query_as(
+        r#"
+        SELECT student_id, attendance_rate FROM attendance_summaries
+        WHERE org_id = $1 AND student_id = ANY($2) AND attendance_rate IS NOT NULL
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Mean of per-student attendance rates across the school's active students.
+/// Students with no marks yet don't drag the average down.
+pub(crate) async fn school_average_rate(
+    pool: &PgPool,
+    org_id: Uuid,
+) -> Result<Option<f64>, AppError> {
+    let mean: Option<f64> = sqlx::query_scalar(
+        r#"
+        SELECT AVG(a.attendance_rate)::float8
+        FROM attendance_summaries a
+        JOIN students s ON s.id = a.student_id
+        WHERE a.org_id = $1 AND s.status = 'active'
+        "#,
+    )
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(mean.map(|m| (m * 10.0).round() / 10.0))
+}
+
+/// Most recent `limit` marks per student, newest first.
+pub(crate) async fn recent_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+    limit: i64,
+) -> Result<HashMap<Uuid, Vec<AttendanceEntry>>, AppError> {
+    let mut map: HashMap<Uuid, Vec<AttendanceEntry>> = HashMap::new();
+    if student_ids.is_empty() {
+        return Ok(map);
+    }
+    let rows: Vec<AttendanceRecordRow> = sqlx::query_as(
+        r#"
+        SELECT id, register_id, student_id, org_id, attendance_date, status,
+               absence_reason, note, marked_by_user_id, created_at, updated_at,
+               period_label, is_derived, arrival_time
+        FROM (
+            SELECT r.*, ROW_NUMBER() OVER (
+                PARTITION BY r.student_id ORDER BY r.attendance_date DESC, r.created_at DESC
+            ) AS rn
+            FROM attendance_records r
+            WHERE r.org_id = $1 AND r.student_id = ANY($2) AND r.period_label IS NULL
+        ) ranked
+        WHERE rn <= $3
+        ORDER BY student_id, attendance_date DESC
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    for r in rows {
+        map.entry(r.student_id).or_default().push(AttendanceEntry::from(r));
+    }
+    Ok(map)
+}
+
+/// Daily-mark counts per student within `from..=to`, with the school's tardy
+/// conversion applied. Students with no marks are absent from the map.
+pub(crate) async fn counts_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<HashMap<Uuid, AttendanceCounts>, AppError> {
+    let mut map: HashMap<Uuid, AttendanceCounts> = HashMap::new();
+    if student_ids.is_empty() {
+        return Ok(map);
+    }
+    let closed = closed_mark_dates(&mut *pool.acquire().await?, org_id, Some(student_ids)).await?;
+    let rows: Vec<(Uuid, String, i64)> = sqlx::query_as(
+        r#"
+        SELECT student_id, status, COUNT(*) FROM attendance_records
+        WHERE org_id = $1 AND student_id = ANY($2) AND period_label IS NULL
+          AND attendance_date BETWEEN $3 AND $4
+          AND NOT (attendance_date = ANY($5))
+        GROUP BY student_id, status
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .bind(from)
+    .bind(to)
+    .bind(&closed)
+    .fetch_all(pool)
+    .await?;
+    for (student_id, status, n) in rows {
+        map.entry(student_id).or_default().add(&status, n);
+    }
+    let policy = load_policy(pool, org_id).await?;
+    for counts in map.values_mut() {
+        counts.apply_tardy_conversion(policy.tardies_to_absence);
+    }
+    Ok(map)
+}
@@ -0,0 +1,524 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use chrono_tz::Tz;
+use sqlx::{PgConnection, PgPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::calendar::{
+    CalendarDayQuery, CalendarDayResponse, CalendarEventQuery, CalendarEventResponse,
+    CalendarEventRow, CreateCalendarEventRequest, CurrentTermResponse, InstructionalDayCount,
+    TermSummary, UpdateCalendarEventRequest,
+};
+use crate::services::attendance::stats::refresh_summaries;
+
+pub(crate) const EVENT_KINDS: [&str; 3] = ["mid_term_break", "public_holiday", "closure"];
+
+/// Open days when `school_days` is empty.
+const DEFAULT_SCHOOL_DAYS: [Weekday; 5] =
+    [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri];
+
+pub struct CalendarService {
+    pool: PgPool,
+}
+
+impl CalendarService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Calendar events overlapping the window, in date order.
+    pub async fn list_events(
+        &self,
+        org_id: Uuid,
+        q: CalendarEventQuery,
+    ) -> Result<Vec<CalendarEventResponse>, AppError> {
+        if let (Some(from), Some(to)) = (q.from, q.to)
+            && from > to
+        {
+            return Err(AppError::BadRequest("'from' must not be after 'to'".into()));
+        }
+        let mut qb = QueryBuilder::new("SELECT * FROM school_calendar_events WHERE org_id = ");
+        qb.push_bind(org_id);
+        if let Some(from) = q.from {
+            qb.push(" AND end_date >= ").push_bind(from);
+        }
+        if let Some(to) = q.to {
+            qb.push(" AND start_date <= ").push_bind(to);
+        }
+        if let Some(kind) = q.kind.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            qb.push(" AND kind = ").push_bind(validate_kind(kind)?);
+        }
+        qb.push(" ORDER BY start_date, end_date, name");
+
+        let rows = qb
+            .build_query_as::<CalendarEventRow>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    pub async fn create_event(
+        &self,
+        org_id: Uuid,
+        req: CreateCalendarEventRequest,
+        created_by: Option<Uuid>,
+    ) -> Result<CalendarEventResponse, AppError> {
+        let kind = validate_kind(req.kind.trim())?;
+        let name = validate_name(&req.name)?;
+        let end_date = req.end_date.unwrap_or(req.start_date);
+        validate_range(req.start_date, end_date)?;
+
+        let mut tx = self.pool.begin().await?;
+        let row: CalendarEventRow = sqlx::query_as(
+            r#"
+            INSERT INTO school_calendar_events
+                (org_id, kind, name, start_date, end_date, notes, created_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#,
+        )
+        .bind(org_id)
+        .bind(kind)
+        .bind(name)
+        .bind(req.start_date)
+        .bind(end_date)
+        .bind(req.notes.as_deref().map(str::trim).filter(|n| !n.is_empty()))
+        .bind(created_by)
+        .fetch_one(&mut *tx)
+        .await?;
+        // Marks on a newly closed day stop counting towards attendance rates.
+        refresh_summaries(&mut tx, org_id, None).await?;
+        tx.commit().await?;
+        Ok(row.into())
+    }
+
+    pub async fn update_event(
+        &self,
+        org_id: Uuid,
+        id: Uuid,
+        req: UpdateCalendarEventRequest,
+    ) -> Result<CalendarEventResponse, AppError> {
+        let existing = self.fetch_event(org_id, id).await?;
+        let kind = match req.kind.as_deref() {
+            Some(k) => validate_kind(k.trim())?,
+            None => existing.kind.as_str(),
+        };
+        let name = match req.name.as_deref() {
+            Some(n) => validate_name(n)?,
+            None => existing.name.as_str(),
+        };
+        let start_date = req.start_date.unwrap_or(existing.start_date);
+        let end_date = req.end_date.unwrap_or(existing.end_date);
+        validate_range(start_date, end_date)?;
+        let notes = match &req.notes {
+            Some(n) => n.as_deref().map(str::trim).filter(|n| !n.is_empty()),
+            None => existing.notes.as_deref(),
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let row: CalendarEventRow = sqlx::query_as(
+            r#"
+            UPDATE school_calendar_events SET
+                kind = $3, name = $4, start_date = $5, end_date = $6,
+                notes = $7
+            WHERE id = $1 AND org_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(kind)
+        .bind(name)
+        .bind(start_date)
+        .bind(end_date)
+        .bind(notes)
+        .fetch_one(&mut *tx)
+        .await?;
+        refresh_summaries(&mut tx, org_id, None).await?;
+        tx.commit().await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete_event(&self, org_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let mut tx = self.pool.begin().await?;
+        let deleted = sqlx::query("DELETE FROM school_calendar_events WHERE id = $1 AND org_id = $2")
+            .bind(id)
+            .bind(org_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(AppError::NotFound("Calendar event not found".into()));
+        }
+        refresh_summaries(&mut tx, org_id, None).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Whether the school is open for teaching on a date, and why not.
+    pub async fn day(&self, org_id: Uuid, q: CalendarDayQuery) -> Result<CalendarDayResponse, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        let calendar = SchoolCalendar::load(&mut conn, org_id, None).await?;
+        let date = q.date.unwrap_or_else(|| calendar.today());
+        let calendar = calendar.with_events(&mut conn, org_id, date, date).await?;
+
+        let reason = calendar.closed_reason(date);
+        Ok(CalendarDayResponse {
+            date,
+            weekday: weekday_name(date.weekday()).to_string(),
+            instructional: reason.is_none(),
+            reason: reason.map(String::from),
+            term: calendar.term_for(date).cloned(),
+            events: calendar
+                .events
+                .into_iter()
+                .filter(|e| e.start_date <= date && date <= e.end_date)
+                .map(Into::into)
+                .collect(),
+        })
+    }
+
+    /// The term containing today in the school's timezone, the next one to
+    /// start, and how many instructional days the current term has.
+    pub async fn current_term(&self, org_id: Uuid) -> Result<CurrentTermResponse, AppError> {
+        let mut conn = self.pool.acquire().await?;
+        let calendar = SchoolCalendar::load(&mut conn, org_id, None).await?;
+        let today = calendar.today();
+        let term = calendar.term_for(today).cloned();
+        let next_term = calendar
+            .terms
+            .iter()
+            .filter(|t| t.start_date > today)
+            .min_by_key(|t| t.start_date)
+            .cloned();
+
+        let instructional_days = match &term {
+            Some(t) => {
+                let calendar = calendar.with_events(&mut conn, org_id, t.start_date, t.end_date).await?;
+                let total = calendar.instructional_days(t.start_date, t.end_date);
+                let elapsed = calendar.instructional_days(t.start_date, today);
+                Some(InstructionalDayCount {
+                    total,
+                    elapsed,
+                    remaining: total - elapsed,
+                })
+            }
+            None => None,
+        };
+
+        Ok(CurrentTermResponse {
+            today,
+            term,
+            next_term,
+            instructional_days,
+        })
+    }
+
+    async fn fetch_event(&self, org_id: Uuid, id: Uuid) -> Result<CalendarEventRow, AppError> {
+        sqlx::query_as("SELECT * FROM school_calendar_events WHERE id = $1 AND org_id = $2")
+            .bind(id)
+            .bind(org_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Calendar event not found".into()))
+    }
+}
+
+// ── Calendar model ────────────────────────────────────────────────────────
+
+/// One school's calendar: its week, its dated terms, and the events loaded
+/// for a window. Other modules use it to count school days.
+pub(crate) struct SchoolCalendar {
+    timezone: Tz,
+    school_days: Vec<Weekday>,
+    /// Terms with both dates set, in order.
+    terms: Vec<TermSummary>,
+    events: Vec<CalendarEventRow>,
+}
+
+impl SchoolCalendar {
+    /// Load the school's week and terms, plus events overlapping `window`.
+    pub(crate) async fn load(
+        conn: &mut PgConnection,
+        org_id: Uuid,
+        window: Option<(NaiveDate, NaiveDate)>,
+    ) -> Result<Self, AppError> {
+        let config: Option<(Option<String>, serde_json::Value)> = sqlx::query_as(
+            "SELECT timezone, school_days FROM school_configs WHERE org_id = $1",
+        )
+        .bind(org_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+        let (timezone, days) = config.unwrap_or((None, serde_json::Value::Null));
+        let timezone = timezone
+            .as_deref()
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(chrono_tz::UTC);
+        let mut school_days: Vec<Weekday> = days
+            .as_array()
+            .map(|arr| arr.iter().filter_map(|d| d.as_str().and_then(parse_weekday)).collect())
+            .unwrap_or_default();
+        if school_days.is_empty() {
+            school_days = DEFAULT_SCHOOL_DAYS.to_vec();
+        }
+
+        let terms: Vec<(String, NaiveDate, NaiveDate, i16)> = sqlx::query_as(
+            r#"
+            SELECT name, start_date, end_date, position FROM school_terms
+            WHERE org_id = $1 AND start_date IS NOT NULL AND end_date IS NOT NULL
+            ORDER BY start_date, position
+            "#,
+        )
+        .bind(org_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let calendar = Self {
+            timezone,
+            school_days,
+            terms: terms
+                .into_iter()
+                .map(|(name, start_date, end_date, position)| TermSummary {
+                    name,
+                    start_date,
+                    end_date,
+                    position,
+                })
+                .collect(),
+            events: Vec::new(),
+        };
+        match window {
+            Some((from, to)) => calendar.with_events(conn, org_id, from, to).await,
+            None => Ok(calendar),
+        }
+    }
+
+    async fn with_events(
+        mut self,
+        conn: &mut PgConnection,
+        org_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> Result<Self, AppError> {
+        self.events = sqlx::query_as(
+            r#"
+            SELECT * FROM school_calendar_events
+            WHERE org_id = $1 AND end_date >= $2 AND start_date <= $3
+            ORDER BY start_date, end_date, name
+            "#,
+        )
+        .bind(org_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(conn)
+        .await?;
+        Ok(self)
+    }
+
+    /// Today's date in the school's timezone (UTC if unset/invalid).
+    pub(crate) fn today(&self) -> NaiveDate {
+        chrono::Utc::now().with_timezone(&self.timezone).date_naive()
+    }
+
+    pub(crate) fn term_for(&self, date: NaiveDate) -> Option<&TermSummary> {
+        self.terms
+            .iter()
+            .find(|t| t.start_date <= date && date <= t.end_date)
+    }
+
+    /// None when `date` is an instructional day; otherwise why it isn't.
+    /// Events take precedence over the weekday, which takes precedence over
+    /// term dates. A school with no dated terms is treated as always in term.
+    /// Only events loaded for a window covering `date` are considered.
+    pub(crate) fn closed_reason(&self, date: NaiveDate) -> Option<&str> {
+        if let Some(event) = self
+            .events
+            .iter()
+            .find(|e| e.start_date <= date && date <= e.end_date)
+        {
+            return Some(event.kind.as_str());
+        }
+        if !self.school_days.contains(&date.weekday()) {
+            return Some("non_school_day");
+        }
+        if !self.terms.is_empty() && self.term_for(date).is_none() {
+            return Some("outside_term");
+        }
+        None
+    }
+
+    pub(crate) fn is_instructional(&self, date: NaiveDate) -> bool {
+        self.closed_reason(date).is_none()
+    }
+
+    /// Instructional days in `from..=to`; 0 when `to` is before `from`.
+    pub(crate) fn instructional_days(&self, from: NaiveDate, to: NaiveDate) -> i64 {
+        let mut count = 0;
+        let mut date = from;
+        while date <= to {
+            if self.is_instructional(date) {
+                count += 1;
+            }
+            date += Duration::days(1);
+        }
+        count
+    }
+
+    /// The first instructional day in `date..=until`, or `date` itself when
+    /// there is none. Events must be loaded for the whole range.
+    pub(crate) fn next_instructional_day(&self, date: NaiveDate, until: NaiveDate) -> NaiveDate {
+        date.iter_days()
+            .take_while(|d| *d <= until)
+            .find(|d| self.is_instructional(*d))
+            .unwrap_or(date)
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────
+
+/// Parse a weekday name: full or three-letter, any case.
+pub(crate) fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "monday" | "mon" => Some(Weekday::Mon),
+        "tuesday" | "tue" => Some(Weekday::Tue),
+        "wednesday" | "wed" => Some(Weekday::Wed),
+        "thursday" | "thu" => Some(Weekday::Thu),
+        "friday" | "fri" => Some(Weekday::Fri),
+        "saturday" | "sat" => Some(Weekday::Sat),
+        "sunday" | "sun" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+pub(crate) fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "monday",
+        Weekday::Tue => "tuesday",
+        Weekday::Wed => "wednesday",
+        Weekday::Thu => "thursday",
+        Weekday::Fri => "friday",
+        Weekday::Sat => "saturday",
+        Weekday::Sun => "sunday",
+    }
+}
+
+fn validate_kind(kind: &str) -> Result<&'static str, AppError> {
+    EVENT_KINDS
+        .iter()
+        .copied()
+        .find(|k| *k == kind)
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Invalid kind '{kind}'. Use mid_term_break, public_holiday or closure"
+            ))
+        })
+}
+
+fn validate_name(name: &str) -> Result<&str, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(AppError::BadRequest("name is required".into()));
+    }
+    Ok(name)
+}
+
+fn validate_range(start: NaiveDate, end: NaiveDate) -> Result<(), AppError> {
+    if end < start {
+        return Err(AppError::BadRequest("end_date must not be before start_date".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    fn calendar(terms: Vec<(&str, &str)>, events: Vec<(&str, &str, &str)>) -> SchoolCalendar {
+        SchoolCalendar {
+            timezone: chrono_tz::UTC,
+            school_days: DEFAULT_SCHOOL_DAYS.to_vec(),
+            terms: terms
+                .into_iter()
+                .enumerate()
+                .map(|(i, (start, end))| TermSummary {
+                    name: format!("Term {}", i + 1),
+                    start_date: date(start),
+                    end_date: date(end),
+                    position: i as i16,
+                })
+                .collect(),
+            events: events
+                .into_iter()
+                .map(|(kind, start, end)| CalendarEventRow {
+                    id: Uuid::nil(),
+                    org_id: Uuid::nil(),
+                    kind: kind.into(),
+                    name: kind.into(),
+                    start_date: date(start),
+                    end_date: date(end),
+                    notes: None,
+                    created_by_user_id: None,
+                    created_at: chrono::Utc::now(),
+                    updated_at: chrono::Utc::now(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn closed_reason_precedence() {
+        // 2025-10-13 is a Monday.
+        let cal = calendar(
+            vec![("2025-09-08", "2025-12-12")],
+            vec![("mid_term_break", "2025-10-23", "2025-10-27")],
+        );
+        assert_eq!(cal.closed_reason(date("2025-10-13")), None);
+        assert_eq!(cal.closed_reason(date("2025-10-18")), Some("non_school_day"));
+        assert_eq!(cal.closed_reason(date("2025-10-25")), Some("mid_term_break"));
+        assert_eq!(cal.closed_reason(date("2025-12-15")), Some("outside_term"));
+    }
+
+    #[test]
+    fn no_terms_means_always_in_term() {
+        let cal = calendar(vec![], vec![("public_holiday", "2025-10-01", "2025-10-01")]);
+        assert!(cal.is_instructional(date("2025-09-30")));
+        assert!(!cal.is_instructional(date("2025-10-01")));
+    }
+
+    #[test]
+    fn counts_instructional_days() {
+        // Two full weeks, less one holiday.
+        let cal = calendar(
+            vec![("2025-10-13", "2025-10-26")],
+            vec![("public_holiday", "2025-10-15", "2025-10-15")],
+        );
+        assert_eq!(cal.instructional_days(date("2025-10-13"), date("2025-10-26")), 9);
+        assert_eq!(cal.instructional_days(date("2025-10-20"), date("2025-10-13")), 0);
+    }
+
+    #[test]
+    fn next_instructional_day_skips_closed_days() {
+        // 2025-10-17 is a Friday holiday, so the next open day is Monday.
+        let cal = calendar(
+            vec![("2025-09-08", "2025-12-12")],
+            vec![("public_holiday", "2025-10-17", "2025-10-17")],
+        );
+        assert_eq!(
+            cal.next_instructional_day(date("2025-10-16"), date("2025-11-16")),
+            date("2025-10-16")
+        );
+        assert_eq!(
+            cal.next_instructional_day(date("2025-10-17"), date("2025-11-17")),
+            date("2025-10-20")
+        );
+        // Nothing open before `until`: keep the date.
+        assert_eq!(
+            cal.next_instructional_day(date("2025-12-13"), date("2025-12-31")),
+            date("2025-12-13")
+        );
+    }
+}
@@ -0,0 +1,253 @@
+//! How a fee category prices a student for a term. Pure functions over the
+//! `school_fee_categories` row and the school's terms.
+
+use chrono::{Datelike, Months, NaiveDate};
+
+use crate::errors::AppError;
+use crate::models::school_setup::FeeCategoryRow;
+use crate::services::gradebook::parse_number;
+use crate::services::gradebook::scale::round2;
+
+/// `amounts` key used for every grade level without its own amount.
+const FLAT_AMOUNT: &str = "_flat";
+
+const AVERAGE_MONTH_DAYS: f64 = 365.25 / 12.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Frequency {
+    PerTerm,
+    Monthly,
+    Quarterly,
+    SemiAnnual,
+    Annual,
+    OneTime,
+}
+
+impl Frequency {
+    /// A category's billing frequency. Blank means per term; the
+    /// `one_time_onboarding` fee type is one-time whatever the frequency.
+    pub(crate) fn of(cat: &FeeCategoryRow) -> Result<Self, AppError> {
+        if cat.fee_type.as_deref().map(str::trim) == Some("one_time_onboarding") {
+            return Ok(Self::OneTime);
+        }
+        match cat.frequency.as_deref().map(str::trim).unwrap_or("") {
+            "" | "per_term" => Ok(Self::PerTerm),
+            "monthly" => Ok(Self::Monthly),
+            "quarterly" => Ok(Self::Quarterly),
+            "semi_annual" => Ok(Self::SemiAnnual),
+            "annual" => Ok(Self::Annual),
+            "one_time" => Ok(Self::OneTime),
+            other => Err(AppError::BadRequest(format!(
+                "Fee category '{}' has an unknown frequency '{other}'",
+                cat.name
+            ))),
+        }
+    }
+}
+
+/// A term of the academic year, in school setup order.
+#[derive(Debug, Clone)]
+pub(crate) struct Term {
+    pub(crate) name: String,
+    pub(crate) start_date: Option<NaiveDate>,
+    pub(crate) end_date: Option<NaiveDate>,
+}
+
+/// Whether a category is charged to a student in this grade level with this
+/// boarding status. `applies_to` is `all` (or blank), `specific` (the
+/// category's `grade_levels`), `boarding` or `day`; a `boarding` fee type
+/// only ever applies to boarders.
+pub(crate) fn applies(
+    cat: &FeeCategoryRow,
+    grade_level: &str,
+    boarding_status: Option<&str>,
+) -> Result<bool, AppError> {
+    let boarder = matches!(boarding_status, Some("boarding" | "weekly_boarding"));
+    if cat.fee_type.as_deref().map(str::trim) == Some("boarding") && !boarder {
+        return Ok(false);
+    }
+    match cat.applies_to.as_deref().map(str::trim).unwrap_or("") {
+        "" | "all" => Ok(true),
+        "specific" => Ok(cat
+            .grade_levels
+            .as_array()
+            .is_some_and(|levels| levels.iter().any(|g| g.as_str() == Some(grade_level)))),
+        "boarding" => Ok(boarder),
+        "day" => Ok(!boarder),
+        other => Err(AppError::BadRequest(format!(
+            "Fee category '{}' has an unknown applies_to '{other}'",
+            cat.name
+        ))),
+    }
+}
+
+/// The amount per billing period for a grade level: its own entry in
+/// `amounts`, else `_flat`. Blank, zero and junk amounts mean no charge.
+pub(crate) fn unit_amount(cat: &FeeCategoryRow, grade_level: &str) -> Option<f64> {
+    let amounts = cat.amounts.as_object()?;
+    let value = amounts.get(grade_level).or_else(|| amounts.get(FLAT_AMOUNT))?;
+    let n = match value {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => parse_number(Some(s)),
+        _ => None,
+    }?;
+    (n.is_finite() && n > 0.0).then(|| round2(n))
+}
+
+/// Billing periods a term covers: whole months in the term for monthly fees,
+/// quarters for quarterly ones (at least one of each), and one otherwise.
+pub(crate) fn quantity(freq: Frequency, term: &Term) -> Result<f64, AppError> {
+    let months = || -> Result<f64, AppError> {
+        let (Some(start), Some(end)) = (term.start_date, term.end_date) else {
+            return Err(AppError::BadRequest(format!(
+                "Term '{}' needs start and end dates to bill monthly or quarterly fees",
+                term.name
+            )));
+        };
+        let days = (end - start).num_days() + 1;
+        Ok((days as f64 / AVERAGE_MONTH_DAYS).round().max(1.0))
+    };
+    match freq {
+        Frequency::Monthly => months(),
+        Frequency::Quarterly => Ok((months()? / 3.0).round().max(1.0)),
+        _ => Ok(1.0),
+    }
+}
+
+/// Whether a once-a-period fee has already been billed. `history` is the
+/// (academic year, term) of each live invoice that charged the student this
+/// category. Semi-annual fees are billed once in each half of the year's
+/// terms; a term no longer in setup counts as the current half.
+pub(crate) fn already_billed(
+    freq: Frequency,
+    academic_year: &str,
+    term: &str,
+    terms: &[Term],
+    history: &[(String, String)],
+) -> bool {
+    let half = |name: &str| {
+        let position = terms.iter().position(|t| t.name == name)?;
+        Some(position >= terms.len().div_ceil(2))
+    };
+    let this_year = || history.iter().filter(|(year, _)| year == academic_year);
+    match freq {
+        Frequency::OneTime => !history.is_empty(),
+        Frequency::Annual => this_year().next().is_some(),
+        Frequency::SemiAnnual => {
+            let current = half(term);
+            this_year().any(|(_, t)| {
+                let billed = half(t);
+                billed.is_none() || billed == current
+            })
+        }
+        _ => false,
+    }
+}
+
+/// The first date on or after `from` falling on `due_day` of the month,
+/// clamped to the month's last day. Without a due day, `from` itself.
+pub(crate) fn due_date(from: NaiveDate, due_day: Option<u32>) -> NaiveDate {
+    let Some(day) = due_day.filter(|d| (1..=31).contains(d)) else {
+        return from;
+    };
+    let on_day = |month_start: NaiveDate| {
+        let last = (month_start + Months::new(1)).pred_opt().unwrap_or(month_start);
+        month_start.with_day(day.min(last.day())).unwrap_or(last)
+    };
+    let this_month = from.with_day(1).unwrap_or(from);
+    let candidate = on_day(this_month);
+    if candidate >= from {
+        candidate
+    } else {
+        on_day(this_month + Months::new(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use uuid::Uuid;
+
+    use super::*;
+
+    fn category(frequency: &str, applies_to: &str, amounts: serde_json::Value) -> FeeCategoryRow {
+        FeeCategoryRow {
+            id: Uuid::nil(),
+            org_id: Uuid::nil(),
+            name: "Tuition".into(),
+            mandatory: true,
+            frequency: Some(frequency.into()),
+            fee_type: Some("tuition".into()),
+            applies_to: Some(applies_to.into()),
+            grade_levels: json!(["JSS 1"]),
+            amounts,
+            position: 0,
+        }
+    }
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn prices_by_grade_level_with_flat_fallback() {
+        let cat = category("per_term", "all", json!({ "_flat": "50000", "JSS 1": 65000.5, "JSS 2": "" }));
+        assert_eq!(unit_amount(&cat, "Primary 1"), Some(50000.0));
+        assert_eq!(unit_amount(&cat, "JSS 1"), Some(65000.5));
+        assert_eq!(unit_amount(&cat, "JSS 2"), None);
+        assert_eq!(unit_amount(&category("per_term", "all", json!({})), "JSS 1"), None);
+    }
+
+    #[test]
+    fn applies_by_grade_and_boarding_status() {
+        let specific = category("per_term", "specific", json!({}));
+        assert!(applies(&specific, "JSS 1", None).unwrap());
+        assert!(!applies(&specific, "JSS 2", None).unwrap());
+        let day = category("per_term", "day", json!({}));
+        assert!(applies(&day, "JSS 2", Some("day")).unwrap());
+        assert!(!applies(&day, "JSS 2", Some("weekly_boarding")).unwrap());
+        let mut boarding = category("per_term", "all", json!({}));
+        boarding.fee_type = Some("boarding".into());
+        assert!(!applies(&boarding, "JSS 1", None).unwrap());
+        assert!(applies(&boarding, "JSS 1", Some("boarding")).unwrap());
+        assert!(applies(&category("per_term", "everyone", json!({})), "JSS 1", None).is_err());
+    }
+
+    #[test]
+    fn counts_months_and_quarters_in_a_term() {
+        let term = Term {
+            name: "First Term".into(),
+            start_date: Some(d(2026, 9, 7)),
+            end_date: Some(d(2026, 12, 11)),
+        };
+        assert_eq!(quantity(Frequency::Monthly, &term).unwrap(), 3.0);
+        assert_eq!(quantity(Frequency::Quarterly, &term).unwrap(), 1.0);
+        assert_eq!(quantity(Frequency::Annual, &term).unwrap(), 1.0);
+        let undated = Term { start_date: None, ..term };
+        assert!(quantity(Frequency::Monthly, &undated).is_err());
+    }
+
+    #[test]
+    fn bills_periodic_fees_once_per_period() {
+        let terms: Vec<Term> = ["First", "Second", "Third"]
+            .iter()
+            .map(|n| Term { name: n.to_string(), start_date: None, end_date: None })
+            .collect();
+        let history = vec![("2026/2027".to_string(), "First".to_string())];
+        let billed = |freq, year, term| already_billed(freq, year, term, &terms, &history);
+        assert!(billed(Frequency::Annual, "2026/2027", "Third"));
+        assert!(!billed(Frequency::Annual, "2027/2028", "First"));
+        assert!(billed(Frequency::SemiAnnual, "2026/2027", "Second"));
+        assert!(!billed(Frequency::SemiAnnual, "2026/2027", "Third"));
+        assert!(billed(Frequency::OneTime, "2027/2028", "First"));
+        assert!(!billed(Frequency::PerTerm, "2026/2027", "First"));
+    }
+
+    #[test]
+    fn due_date_is_the_next_due_day() {
+        assert_eq!(due_date(d(2026, 9, 7), Some(5)), d(2026, 10, 5));
+        assert_eq!(due_date(d(2026, 9, 1), Some(5)), d(2026, 9, 5));
+        assert_eq!(due_date(d(2027, 2, 1), Some(31)), d(2027, 2, 28));
+        assert_eq!(due_date(d(2026, 9, 7), None), d(2026, 9, 7));
+    }
+}
@@ -0,0 +1,500 @@
+//! Discounts and scholarships on invoice lines: which apply to a student,
+//! what each takes off, and the sibling groups behind automatic sibling
+//! discounts. Amounts are worked in whole cents, as in the ledger.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::fees::{InvoiceLine, LineDiscount};
+use crate::models::school_setup::FeeDiscountRow;
+use crate::services::gradebook::parse_number;
+
+use super::ledger::{cents, from_cents};
+
+pub(super) const FEE_TYPES: &[&str] = &[
+    "tuition",
+    "facility",
+    "boarding",
+    "transport",
+    "exam",
+    "admin",
+    "co_curricular",
+    "one_time_onboarding",
+];
+pub(super) const KINDS: &[&str] = &["discount", "scholarship"];
+
+/// The invoice lines a discount reduces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Target {
+    All,
+    /// Lines of this fee type.
+    FeeType(String),
+    /// Lines of this fee category, by lowercased name.
+    Category(String),
+}
+
+impl Target {
+    /// `applies_to` of discount `name`: blank or `all`, a fee type, or the
+    /// name of one of the school's fee categories.
+    pub(super) fn parse(
+        name: &str,
+        applies_to: Option<&str>,
+        categories: &[String],
+    ) -> Result<Self, AppError> {
+        let value = applies_to.map(str::trim).unwrap_or("");
+        let lower = value.to_lowercase();
+        if lower.is_empty() || lower == "all" {
+            Ok(Self::All)
+        } else if FEE_TYPES.contains(&lower.as_str()) {
+            Ok(Self::FeeType(lower))
+        } else if categories.iter().any(|c| c.to_lowercase() == lower) {
+            Ok(Self::Category(lower))
+        } else {
+            Err(AppError::BadRequest(format!(
+                "Discount '{name}' applies_to '{value}' is not all, a fee type or a fee category"
+            )))
+        }
+    }
+
+    fn covers(&self, line: &InvoiceLine) -> bool {
+        match self {
+            Self::All => true,
+            Self::FeeType(t) => line.fee_type.as_deref().map(str::trim) == Some(t.as_str()),
+            Self::Category(c) => line.category.to_lowercase() == *c,
+        }
+    }
+}
+
+/// A discount as it applies to one student's invoice.
+#[derive(Debug, Clone)]
+pub(super) struct Rule {
+    pub(super) name: String,
+    pub(super) kind: String,
+    pub(super) source: &'static str,
+    pub(super) percentage: f64,
+    pub(super) target: Target,
+    pub(super) detail: Option<String>,
+    pub(super) student_discount_id: Option<Uuid>,
+}
+
+/// A discount or scholarship assigned to a student, as stored.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub(super) struct Assigned {
+    pub(super) id: Uuid,
+    pub(super) kind: String,
+    pub(super) name: String,
+    pub(super) percentage: Option<f64>,
+    pub(super) applies_to: Option<String>,
+}
+
+/// Where a student stands among their active siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct Family {
+    /// Eldest = 1.
+    pub(super) position: usize,
+    pub(super) size: usize,
+    /// The other children, eldest first.
+    pub(super) siblings: Vec<Uuid>,
+}
+
+/// An active student and their guardians' contacts, for sibling groups.
+#[derive(Debug, Clone)]
+pub(super) struct Child {
+    pub(super) id: Uuid,
+    pub(super) date_of_birth: NaiveDate,
+    pub(super) admission_number: String,
+    pub(super) contacts: Vec<String>,
+}
+
+/// A discount type's percentage, which must be over 0 and at most 100.
+pub(super) fn percentage(d: &FeeDiscountRow) -> Result<f64, AppError> {
+    parse_number(d.percentage.as_deref())
+        .filter(|p| *p > 0.0 && *p <= 100.0)
+        .ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "Discount '{}' needs a percentage over 0 and at most 100 in school setup",
+                d.name
+            ))
+        })
+}
+
+/// Checks every discount type in school setup has a valid percentage and
+/// `applies_to`, before any student is priced with one.
+pub(super) fn check_types(types: &[FeeDiscountRow], categories: &[String]) -> Result<(), AppError> {
+    for d in types {
+        percentage(d)?;
+        Target::parse(&d.name, d.applies_to.as_deref(), categories)?;
+    }
+    Ok(())
+}
+
+/// The discount type a student in this sibling position gets: the one with
+/// the highest `sibling_position` not past theirs.
+pub(super) fn sibling_type(types: &[FeeDiscountRow], position: usize) -> Option<&FeeDiscountRow> {
+    types
+        .iter()
+        .filter(|d| d.sibling_position.is_some_and(|p| p as usize <= position))
+        .max_by_key(|d| d.sibling_position)
+}
+
+/// The rules for one student: what's assigned to them, then their sibling
+/// discount unless a discount of the same name is already assigned.
+pub(super) fn rules_for(
+    types: &[FeeDiscountRow],
+    categories: &[String],
+    assigned: &[Assigned],
+    family: Option<&Family>,
+) -> Result<Vec<Rule>, AppError> {
+    let setup_type = |name: &str| types.iter().find(|d| d.name.to_lowercase() == name.to_lowercase());
+    let mut rules = Vec::with_capacity(assigned.len() + 1);
+    for a in assigned {
+        let rule = match a.percentage {
+            Some(p) => Rule {
+                name: a.name.clone(),
+                kind: a.kind.clone(),
+                source: "assigned",
+                percentage: p,
+                target: Target::parse(&a.name, a.applies_to.as_deref(), categories)?,
+                detail: None,
+                student_discount_id: Some(a.id),
+            },
+            None => {
+                let d = setup_type(&a.name).ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Discount '{}' is assigned to students but no longer in school setup; \
+                         add it back or remove the assignments",
+                        a.name
+                    ))
+                })?;
+                Rule {
+                    name: d.name.clone(),
+                    kind: a.kind.clone(),
+                    source: "assigned",
+                    percentage: percentage(d)?,
+                    target: Target::parse(&d.name, d.applies_to.as_deref(), categories)?,
+                    detail: None,
+                    student_discount_id: Some(a.id),
+                }
+            }
+        };
+        rules.push(rule);
+    }
+    if let Some(family) = family
+        && let Some(d) = sibling_type(types, family.position)
+        && !rules.iter().any(|r| r.name.to_lowercase() == d.name.to_lowercase())
+    {
+        rules.push(Rule {
+            name: d.name.clone(),
+            kind: "discount".into(),
+            source: "sibling",
+            percentage: percentage(d)?,
+            target: Target::parse(&d.name, d.applies_to.as_deref(), categories)?,
+            detail: Some(sibling_detail(family)),
+            student_discount_id: None,
+        });
+    }
+    Ok(rules)
+}
+
+/// Takes each rule's percentage of the line amounts it covers. Discounts on
+/// a line add up but never past the line amount; `discount_amount` and
+/// `discounts` record what each took.
+pub(super) fn apply(lines: &mut [InvoiceLine], rules: &[Rule]) {
+    for line in lines {
+        let gross = cents(line.amount);
+        let mut left = gross;
+        line.discounts.clear();
+        for rule in rules {
+            if !rule.target.covers(line) {
+                continue;
+            }
+            let amount = ((gross as f64) * rule.percentage / 100.0).round() as i64;
+            let amount = amount.min(left);
+            if amount <= 0 {
+                continue;
+            }
+            left -= amount;
+            line.discounts.push(LineDiscount {
+                name: rule.name.clone(),
+                kind: rule.kind.clone(),
+                source: rule.source.into(),
+                percentage: rule.percentage,
+                amount: from_cents(amount),
+                detail: rule.detail.clone(),
+                student_discount_id: rule.student_discount_id,
+            });
+        }
+        line.discount_amount = from_cents(gross - left);
+    }
+}
+
+/// Ways to tell a guardian is shared: the email lowercased, and the phone
+/// as digits only (at least 7).
+pub(super) fn contact_keys(phone: Option<&str>, email: Option<&str>) -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Some(email) = email.map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty()) {
+        keys.push(format!("email:{email}"));
+    }
+    let digits: String = phone.unwrap_or("").chars().filter(char::is_ascii_digit).collect();
+    if digits.len() >= 7 {
+        keys.push(format!("phone:{digits}"));
+    }
+    keys
+}
+
+/// Groups children who share a guardian contact, directly or through
+/// another child, and orders each group eldest first. Only children with
+/// siblings are returned.
+pub(super) fn sibling_positions(children: &[Child]) -> HashMap<Uuid, Family> {
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    let mut parent: Vec<usize> = (0..children.len()).collect();
+    let mut first_with: HashMap<&str, usize> = HashMap::new();
+    for (i, child) in children.iter().enumerate() {
+        for key in &child.contacts {
+            match first_with.get(key.as_str()) {
+                Some(&j) => {
+                    let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                    parent[a] = b;
+                }
+                None => {
+                    first_with.insert(key, i);
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<&Child>> = HashMap::new();
+    for (i, child) in children.iter().enumerate() {
+        groups.entry(root(&mut parent, i)).or_default().push(child);
+    }
+    let mut families = HashMap::new();
+    for mut group in groups.into_values().filter(|g| g.len() > 1) {
+        group.sort_by(|a, b| {
+            (a.date_of_birth, &a.admission_number, a.id)
+                .cmp(&(b.date_of_birth, &b.admission_number, b.id))
+        });
+        let ids: Vec<Uuid> = group.iter().map(|c| c.id).collect();
+        for (i, id) in ids.iter().enumerate() {
+            families.insert(
+                *id,
+                Family {
+                    position: i + 1,
+                    size: ids.len(),
+                    siblings: ids.iter().copied().filter(|s| s != id).collect(),
+                },
+            );
+        }
+    }
+    families
+}
+
+/// The school's active students and their guardians' contacts.
+pub(super) async fn load_children(pool: &PgPool, org_id: Uuid) -> Result<Vec<Child>, AppError> {
+    let rows: Vec<ContactRow> = sqlx::query_as(
+        r#"
+        SELECT s.id, s.date_of_birth, s.admission_number, g.phone, g.email
+        FROM students s
+        LEFT JOIN student_guardians g ON g.student_id = s.id AND g.org_id = s.org_id
+        WHERE s.org_id = $1 AND s.status = 'active'
+        ORDER BY s.id
+        "#,
+    )
+    .bind(org_id)
+    .fetch_all(pool)
+    .await?;
+    let mut children: Vec<Child> = Vec::new();
+    for row in rows {
+        if children.last().is_none_or(|c| c.id != row.id) {
+            children.push(Child {
+                id: row.id,
+                date_of_birth: row.date_of_birth,
+                admission_number: row.admission_number,
+                contacts: Vec::new(),
+            });
+        }
+        if let Some(child) = children.last_mut() {
+            child.contacts.extend(contact_keys(row.phone.as_deref(), row.email.as_deref()));
+        }
+    }
+    Ok(children)
+}
+
+#[derive(sqlx::FromRow)]
+struct ContactRow {
+    id: Uuid,
+    date_of_birth: NaiveDate,
+    admission_number: String,
+    phone: Option<String>,
+    email: Option<String>,
+}
+
+fn sibling_detail(family: &Family) -> String {
+    let suffix = match (family.position % 10, family.position % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!(
+        "{}{suffix} of {} children sharing a guardian",
+        family.position, family.size
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(category: &str, fee_type: &str, amount: f64) -> InvoiceLine {
+        InvoiceLine {
+            category: category.into(),
+            fee_type: Some(fee_type.into()),
+            frequency: None,
+            quantity: 1.0,
+            unit_amount: amount,
+            amount,
+            discount_amount: 0.0,
+            discounts: Vec::new(),
+        }
+    }
+
+    fn rule(name: &str, percentage: f64, target: Target) -> Rule {
+        Rule {
+            name: name.into(),
+            kind: "discount".into(),
+            source: "assigned",
+            percentage,
+            target,
+            detail: None,
+            student_discount_id: None,
+        }
+    }
+
+    fn discount_type(name: &str, percentage: &str, sibling_position: Option<i16>) -> FeeDiscountRow {
+        FeeDiscountRow {
+            id: Uuid::nil(),
+            org_id: Uuid::nil(),
+            name: name.into(),
+            percentage: Some(percentage.into()),
+            applies_to: Some("tuition".into()),
+            position: 0,
+            sibling_position,
+        }
+    }
+
+    #[test]
+    fn parses_discount_targets() {
+        let categories = vec!["Tuition".to_string(), "School Bus".to_string()];
+        let parse = |v| Target::parse("Bursary", v, &categories);
+        assert_eq!(parse(None).unwrap(), Target::All);
+        assert_eq!(parse(Some(" ALL ")).unwrap(), Target::All);
+        assert_eq!(parse(Some("tuition")).unwrap(), Target::FeeType("tuition".into()));
+        assert_eq!(parse(Some("school bus")).unwrap(), Target::Category("school bus".into()));
+        assert!(parse(Some("lunch")).is_err());
+    }
+
+    #[test]
+    fn checks_every_discount_type_in_setup() {
+        let categories = vec!["Tuition".to_string()];
+        let mut types =
+            vec![discount_type("Sibling", "10", Some(2)), discount_type("Staff", "25", None)];
+        assert!(check_types(&types, &categories).is_ok());
+        types[1].applies_to = Some("School Bus".into());
+        assert!(check_types(&types, &categories).is_err());
+        types[1].applies_to = None;
+        types[0].percentage = Some("0".into());
+        assert!(check_types(&types, &categories).is_err());
+    }
+
+    #[test]
+    fn discounts_add_up_to_at_most_the_line() {
+        let mut lines = vec![line("Tuition", "tuition", 50000.0), line("Bus", "transport", 333.33)];
+        apply(
+            &mut lines,
+            &[
+                rule("Sibling", 10.0, Target::FeeType("tuition".into())),
+                rule("Scholarship", 50.0, Target::All),
+            ],
+        );
+        assert_eq!(lines[0].discount_amount, 30000.0);
+        assert_eq!(lines[0].discounts.len(), 2);
+        assert_eq!(lines[0].discounts[0].amount, 5000.0);
+        assert_eq!(lines[1].discount_amount, 166.67);
+        assert_eq!(lines[1].discounts[0].name, "Scholarship");
+
+        let mut lines = vec![line("Tuition", "tuition", 100.0)];
+        apply(&mut lines, &[rule("Full", 100.0, Target::All), rule("Sibling", 10.0, Target::All)]);
+        assert_eq!(lines[0].discount_amount, 100.0);
+        assert_eq!(lines[0].discounts.len(), 1);
+    }
+
+    #[test]
+    fn picks_the_sibling_discount_for_a_position() {
+        let types = vec![
+            discount_type("Early payment", "5", None),
+            discount_type("2nd child", "10", Some(2)),
+            discount_type("3rd child", "15", Some(3)),
+        ];
+        assert!(sibling_type(&types, 1).is_none());
+        assert_eq!(sibling_type(&types, 2).unwrap().name, "2nd child");
+        assert_eq!(sibling_type(&types, 5).unwrap().name, "3rd child");
+
+        let family = Family { position: 2, size: 2, siblings: vec![Uuid::nil()] };
+        let rules = rules_for(&types, &[], &[], Some(&family)).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].source, "sibling");
+        assert_eq!(rules[0].detail.as_deref(), Some("2nd of 2 children sharing a guardian"));
+
+        // Assigned by hand, it isn't given twice.
+        let assigned = Assigned {
+            id: Uuid::nil(),
+            kind: "discount".into(),
+            name: "2ND CHILD".into(),
+            percentage: None,
+            applies_to: None,
+        };
+        let rules = rules_for(&types, &[], &[assigned], Some(&family)).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!((rules[0].source, rules[0].percentage), ("assigned", 10.0));
+    }
+
+    #[test]
+    fn groups_children_by_shared_guardian_contacts() {
+        assert_eq!(
+            contact_keys(Some("+234 (803) 555-0101"), Some(" Ada@Example.com ")),
+            ["email:ada@example.com", "phone:2348035550101"]
+        );
+        assert!(contact_keys(Some("n/a"), Some("")).is_empty());
+
+        let child = |n: u8, born: i32, contacts: &[&str]| Child {
+            id: Uuid::from_u128(n as u128),
+            date_of_birth: NaiveDate::from_ymd_opt(born, 1, 1).unwrap(),
+            admission_number: format!("ADM{n}"),
+            contacts: contacts.iter().map(|c| c.to_string()).collect(),
+        };
+        // 1 and 2 share a phone, 2 and 3 an email; 4 is on their own.
+        let families = sibling_positions(&[
+            child(1, 2014, &["phone:1"]),
+            child(2, 2012, &["phone:1", "email:a"]),
+            child(3, 2016, &["email:a"]),
+            child(4, 2013, &["phone:2"]),
+        ]);
+        assert_eq!(families.len(), 3);
+        let eldest = &families[&Uuid::from_u128(2)];
+        assert_eq!((eldest.position, eldest.size), (1, 3));
+        assert_eq!(eldest.siblings, [Uuid::from_u128(1), Uuid::from_u128(3)]);
+        assert_eq!(families[&Uuid::from_u128(3)].position, 3);
+        assert!(!families.contains_key(&Uuid::from_u128(4)));
+    }
+}
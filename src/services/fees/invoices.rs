@@ -0,0 +1,931 @@
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDate};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::fees::{
+    GenerateInvoicesRequest, GenerateInvoicesResponse, Invoice, InvoiceLine, InvoiceListQuery,
+    InvoiceListResponse, InvoiceSummary, LineDiscount, RegenerateInvoiceRequest, SkippedStudent,
+    VoidInvoiceRequest,
+};
+use crate::models::school_setup::{FeeCategoryRow, FeeDiscountRow};
+use crate::models::students::PaginationInfo;
+use crate::services::calendar::SchoolCalendar;
+use crate::services::gradebook::scale::round2;
+use crate::services::students::{validate_grade_level, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+use super::billing::{already_billed, applies, due_date, quantity, unit_amount, Frequency, Term};
+use super::discounts::{
+    apply, check_types, load_children, rules_for, sibling_positions, Assigned, Family, Rule,
+};
+use super::ledger::{allocate, release, school_today};
+use super::{trimmed, FeesService};
+
+const STATUSES: &[&str] = &["issued", "void"];
+
+/// How many days a due date on a closed day may move forward. A longer
+/// closure leaves it where it fell.
+const DUE_DATE_SEARCH_DAYS: i64 = 31;
+
+/// Invoice columns with the student's names, for [`Invoice`].
+const INVOICE_COLUMNS: &str = r#"
+    i.id, i.invoice_number, i.student_id, s.admission_number, s.first_name, s.last_name,
+    i.grade_level, i.section, i.boarding_status, i.academic_year, i.term, i.status,
+    i.currency, i.total_amount::float8 AS total_amount,
+    i.discount_amount::float8 AS discount_amount,
+    COALESCE(
+        (SELECT SUM(a.amount) FROM fee_payment_allocations a WHERE a.invoice_id = i.id), 0
+    )::float8 AS amount_paid,
+    (CASE WHEN i.status = 'void' THEN 0 ELSE i.total_amount - COALESCE(
+        (SELECT SUM(a.amount) FROM fee_payment_allocations a WHERE a.invoice_id = i.id), 0
+    ) END)::float8 AS balance,
+    i.issue_date, i.due_date, i.void_reason, i.voided_at, i.replaces_invoice_id,
+    (SELECT r.id FROM fee_invoices r WHERE r.replaces_invoice_id = i.id LIMIT 1) AS replaced_by_invoice_id,
+    i.created_at
+"#;
+
+/// Columns for [`InvoiceSummary`], over the same joins.
+pub(super) const INVOICE_SUMMARY_COLUMNS: &str = r#"
+    i.id, i.invoice_number, i.student_id, s.admission_number, s.first_name, s.last_name,
+    i.grade_level, i.academic_year, i.term, i.status, i.currency,
+    i.total_amount::float8 AS total_amount,
+    COALESCE(
+        (SELECT SUM(a.amount) FROM fee_payment_allocations a WHERE a.invoice_id = i.id), 0
+    )::float8 AS amount_paid,
+    (CASE WHEN i.status = 'void' THEN 0 ELSE i.total_amount - COALESCE(
+        (SELECT SUM(a.amount) FROM fee_payment_allocations a WHERE a.invoice_id = i.id), 0
+    ) END)::float8 AS balance,
+    i.issue_date, i.due_date
+"#;
+
+/// What every invoice for one term is priced from.
+struct Pricing {
+    academic_year: String,
+    term: Term,
+    terms: Vec<Term>,
+    categories: Vec<(FeeCategoryRow, Frequency)>,
+    discount_types: Vec<FeeDiscountRow>,
+    currency: Option<String>,
+    due_day: Option<u32>,
+}
+
+/// The discounts the students being invoiced are entitled to.
+#[derive(Default)]
+struct Discounts {
+    assigned: HashMap<Uuid, Vec<Assigned>>,
+    families: HashMap<Uuid, Family>,
+}
+
+/// The student facts an invoice is priced for.
+#[derive(sqlx::FromRow)]
+struct Billable {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    last_name: String,
+    grade_level: String,
+    section: Option<String>,
+    boarding_status: Option<String>,
+}
+
+/// Per student, per lowercased category name: the (academic year, term) of
+/// each live invoice that charged it.
+type BillingHistory = HashMap<Uuid, HashMap<String, Vec<(String, String)>>>;
+
+impl FeesService {
+    /// Issue an invoice to every matching active student for a term, priced
+    /// from `school_fee_categories`. Students who already have a live
+    /// invoice for the term, or to whom no fee applies, are skipped.
+    /// All-or-nothing.
+    pub async fn generate_invoices(
+        &self,
+        org_id: Uuid,
+        req: GenerateInvoicesRequest,
+        created_by: Option<Uuid>,
+    ) -> Result<GenerateInvoicesResponse, AppError> {
+        let pricing = self.load_pricing(org_id, req.academic_year.as_deref(), &req.term).await?;
+        // A broken discount type fails the run rather than holding back
+        // every family it reaches.
+        check_types(&pricing.discount_types, &pricing.category_names())?;
+
+        let mut optional = Vec::with_capacity(req.optional_categories.len());
+        for name in &req.optional_categories {
+            let wanted = name.trim().to_lowercase();
+            if !pricing.categories.iter().any(|(c, _)| c.name.to_lowercase() == wanted) {
+                return Err(AppError::BadRequest(format!(
+                    "Fee category '{}' is not configured for this school",
+                    name.trim()
+                )));
+            }
+            optional.push(wanted);
+        }
+        let grade_level = trimmed(&req.grade_level);
+        if let Some(g) = grade_level {
+            validate_grade_level(&self.pool, org_id, g).await?;
+        }
+
+        let students: Vec<Billable> = sqlx::query_as(
+            r#"
+            SELECT id, admission_number, first_name, last_name, grade_level, section, boarding_status
+            FROM students
+            WHERE org_id = $1 AND status = 'active'
+              AND ($2::text IS NULL OR grade_level = $2)
+              AND ($3::text IS NULL OR section = $3)
+              AND ($4::uuid[] IS NULL OR id = ANY($4))
+            ORDER BY grade_level, section NULLS FIRST, last_name, first_name, id
+            "#,
+        )
+        .bind(org_id)
+        .bind(grade_level)
+        .bind(trimmed(&req.section))
+        .bind(&req.student_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut skipped = Vec::new();
+        for id in req.student_ids.iter().flatten() {
+            if !students.iter().any(|s| s.id == *id) {
+                skipped.push(SkippedStudent {
+                    student_id: *id,
+                    admission_number: None,
+                    reason: "Not an active student of this school".into(),
+                });
+            }
+        }
+
+        let issue_date = match req.issue_date {
+            Some(date) => date,
+            None => school_today(&self.pool, org_id).await?,
+        };
+        let due = match req.due_date {
+            Some(due) => due,
+            None => {
+                let from = pricing.term.start_date.map_or(issue_date, |s| s.max(issue_date));
+                open_due_date(
+                    &mut *self.pool.acquire().await?,
+                    org_id,
+                    due_date(from, pricing.due_day),
+                )
+                .await?
+            }
+        };
+        if due < issue_date {
+            return Err(AppError::BadRequest(
+                "due_date can't be before issue_date".into(),
+            ));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        // Runs for the same school queue here, so each sees what the one
+        // before it invoiced.
+        lock_invoice_numbers(&mut tx, org_id).await?;
+        let ids: Vec<Uuid> = students.iter().map(|s| s.id).collect();
+        let invoiced: HashMap<Uuid, String> = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT student_id, invoice_number FROM fee_invoices
+            WHERE org_id = $1 AND academic_year = $2 AND term = $3
+              AND status = 'issued' AND student_id = ANY($4)
+            "#,
+        )
+        .bind(org_id)
+        .bind(&pricing.academic_year)
+        .bind(&pricing.term.name)
+        .bind(&ids)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+        let history = self.billing_history(org_id, &ids, None).await?;
+        let discounts = self.load_discounts(org_id, &pricing, &ids).await?;
+
+        let mut invoices = Vec::new();
+        for student in &students {
+            if let Some(number) = invoiced.get(&student.id) {
+                skipped.push(skip(
+                    student,
+                    format!("Already invoiced as {number}; regenerate or void it instead"),
+                ));
+                continue;
+            }
+            let mut lines = pricing.lines_for(student, &optional, history.get(&student.id))?;
+            if lines.is_empty() {
+                skipped.push(skip(student, "No fees apply".into()));
+                continue;
+            }
+            // A stale discount assignment holds back only that student.
+            let rules = match pricing.rules_for(student.id, &discounts) {
+                Ok(rules) => rules,
+                Err(AppError::BadRequest(reason)) => {
+                    skipped.push(skip(student, reason));
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            apply(&mut lines, &rules);
+            let number = next_invoice_number(&mut tx, org_id).await?;
+            let mut invoice = insert_invoice(
+                &mut tx,
+                NewInvoice {
+                    org_id,
+                    number,
+                    student,
+                    pricing: &pricing,
+                    lines,
+                    issue_date,
+                    due_date: due,
+                    replaces: None,
+                    created_by,
+                },
+            )
+            .await?;
+            // Credit from earlier payments settles the new invoice.
+            let applied = allocate(&mut tx, org_id, student.id, Some(invoice.id)).await?;
+            if let Some(paid) = applied.get(&invoice.id) {
+                invoice.amount_paid = *paid;
+                invoice.balance = round2(invoice.total_amount - paid);
+            }
+            invoices.push(invoice);
+        }
+
+        tx.commit().await?;
+
+        Ok(GenerateInvoicesResponse {
+            academic_year: pricing.academic_year,
+            term: pricing.term.name,
+            generated: invoices.len() as i64,
+            total_amount: round2(invoices.iter().map(|i| i.total_amount).sum()),
+            invoices,
+            skipped,
+        })
+    }
+
+    /// Void an invoice and issue a replacement priced from the current fee
+    /// categories and the student's current class and boarding status. The
+    /// optional categories it billed are kept where still configured.
+    pub async fn regenerate_invoice(
+        &self,
+        org_id: Uuid,
+        id: Uuid,
+        req: RegenerateInvoiceRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<Invoice, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let (student_id, academic_year, term, status, old_due): (Uuid, String, String, String, NaiveDate) =
+            sqlx::query_as(
+                r#"
+                SELECT student_id, academic_year, term, status, due_date FROM fee_invoices
+                WHERE id = $1 AND org_id = $2
+                FOR UPDATE
+                "#,
+            )
+            .bind(id)
+            .bind(org_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+        if status == "void" {
+            return Err(AppError::Conflict("Invoice is void".into()));
+        }
+
+        let pricing = self.load_pricing(org_id, Some(&academic_year), &term).await?;
+        let student: Billable = sqlx::query_as(
+            r#"
+            SELECT id, admission_number, first_name, last_name, grade_level, section, boarding_status
+            FROM students WHERE id = $1 AND org_id = $2
+            "#,
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        let optional: Vec<String> = sqlx::query_scalar(
+            "SELECT LOWER(category) FROM fee_invoice_lines WHERE invoice_id = $1",
+        )
+        .bind(id)
+        .fetch_all(&mut *tx)
+        .await?;
+        let history = self.billing_history(org_id, &[student_id], Some(id)).await?;
+        let mut lines = pricing.lines_for(&student, &optional, history.get(&student_id))?;
+        if lines.is_empty() {
+            return Err(AppError::BadRequest(
+                "No fees apply to this student any more; void the invoice instead".into(),
+            ));
+        }
+        let discounts = self.load_discounts(org_id, &pricing, &[student_id]).await?;
+        apply(&mut lines, &pricing.rules_for(student_id, &discounts)?);
+
+        let issue_date = school_today(&self.pool, org_id).await?;
+        // The old due date may have passed; an explicit one may not.
+        let due = match req.due_date {
+            Some(due) if due < issue_date => {
+                return Err(AppError::BadRequest(
+                    "due_date can't be before the issue date, today".into(),
+                ));
+            }
+            Some(due) => due,
+            None => old_due.max(issue_date),
+        };
+        let number = next_invoice_number(&mut tx, org_id).await?;
+        // Void first: only one live invoice per student and term is allowed.
+        void(&mut tx, org_id, id, Some(&format!("Regenerated as {number}")), changed_by).await?;
+        let invoice = insert_invoice(
+            &mut tx,
+            NewInvoice {
+                org_id,
+                number,
+                student: &student,
+                pricing: &pricing,
+                lines,
+                issue_date,
+                due_date: due,
+                replaces: Some(id),
+                created_by: changed_by,
+            },
+        )
+        .await?;
+        // What was paid on the old invoice moves to the replacement.
+        allocate(&mut tx, org_id, student_id, Some(invoice.id)).await?;
+        tx.commit().await?;
+        self.invoice(org_id, invoice.id).await
+    }
+
+    /// Void an invoice. It stays on record but no longer counts as billed.
+    pub async fn void_invoice(
+        &self,
+        org_id: Uuid,
+        id: Uuid,
+        req: VoidInvoiceRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<Invoice, AppError> {
+        let mut tx = self.pool.begin().await?;
+        let (student_id, status): (Uuid, String) = sqlx::query_as(
+            "SELECT student_id, status FROM fee_invoices WHERE id = $1 AND org_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+        if status == "void" {
+            return Err(AppError::Conflict("Invoice is already void".into()));
+        }
+        let reason = trimmed(&req.reason);
+        void(&mut tx, org_id, id, reason, changed_by).await?;
+        // Anything paid on it goes to the student's other open invoices, or
+        // stays as credit.
+        allocate(&mut tx, org_id, student_id, None).await?;
+        tx.commit().await?;
+        self.invoice(org_id, id).await
+    }
+
+    /// One invoice with its lines and the discounts on each.
+    pub async fn invoice(&self, org_id: Uuid, id: Uuid) -> Result<Invoice, AppError> {
+        let mut invoice: Invoice = sqlx::query_as(&format!(
+            r#"
+            SELECT {INVOICE_COLUMNS}
+            FROM fee_invoices i
+            JOIN students s ON s.id = i.student_id
+            WHERE i.id = $1 AND i.org_id = $2
+            "#
+        ))
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Invoice not found".into()))?;
+
+        invoice.lines = sqlx::query_as(
+            r#"
+            SELECT category, fee_type, frequency, quantity::float8 AS quantity,
+                   unit_amount::float8 AS unit_amount, amount::float8 AS amount,
+                   discount_amount::float8 AS discount_amount
+            FROM fee_invoice_lines
+            WHERE invoice_id = $1
+            ORDER BY position
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        let discounts: Vec<LineDiscountRow> = sqlx::query_as(
+            r#"
+            SELECT l.position AS line_position, d.name, d.kind, d.source,
+                   d.percentage::float8 AS percentage, d.amount::float8 AS amount,
+                   d.detail, d.student_discount_id
+            FROM fee_invoice_line_discounts d
+            JOIN fee_invoice_lines l ON l.id = d.line_id
+            WHERE l.invoice_id = $1
+            ORDER BY l.position, d.position
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in discounts {
+            if let Some(line) = invoice.lines.get_mut(row.line_position as usize) {
+                line.discounts.push(row.discount);
+            }
+        }
+        Ok(invoice)
+    }
+
+    /// Invoices, newest first.
+    pub async fn list_invoices(
+        &self,
+        org_id: Uuid,
+        q: InvoiceListQuery,
+    ) -> Result<InvoiceListResponse, AppError> {
+        let status = trimmed(&q.status);
+        if let Some(s) = status
+            && !STATUSES.contains(&s)
+        {
+            return Err(AppError::BadRequest(format!(
+                "Invalid status '{s}'; must be issued or void"
+            )));
+        }
+        let page = q.page.unwrap_or(1).max(1);
+        let page_size = q
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = page.saturating_sub(1).saturating_mul(page_size);
+
+        const FILTER: &str = r#"
+            FROM fee_invoices i
+            JOIN students s ON s.id = i.student_id
+            WHERE i.org_id = $1
+              AND ($2::text IS NULL OR i.academic_year = $2)
+              AND ($3::text IS NULL OR LOWER(i.term) = LOWER($3))
+              AND ($4::uuid IS NULL OR i.student_id = $4)
+              AND ($5::text IS NULL OR i.grade_level = $5)
+              AND ($6::text IS NULL OR i.status = $6)
+        "#;
+        let data: Vec<InvoiceSummary> = sqlx::query_as(&format!(
+            r#"
+            SELECT {INVOICE_SUMMARY_COLUMNS}
+            {FILTER}
+            ORDER BY i.created_at DESC, i.invoice_number DESC
+            LIMIT $7 OFFSET $8
+            "#
+        ))
+        .bind(org_id)
+        .bind(trimmed(&q.academic_year))
+        .bind(trimmed(&q.term))
+        .bind(q.student_id)
+        .bind(trimmed(&q.grade_level))
+        .bind(status)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) {FILTER}"))
+            .bind(org_id)
+            .bind(trimmed(&q.academic_year))
+            .bind(trimmed(&q.term))
+            .bind(q.student_id)
+            .bind(trimmed(&q.grade_level))
+            .bind(status)
+            .fetch_one(&self.pool)
+            .await?;
+
+        let total_pages = if total == 0 {
+            0
+        } else {
+            ((total as f64) / (page_size as f64)).ceil() as i64
+        };
+        Ok(InvoiceListResponse {
+            data,
+            pagination: PaginationInfo {
+                page,
+                page_size,
+                total,
+                total_pages,
+            },
+        })
+    }
+
+    async fn load_pricing(
+        &self,
+        org_id: Uuid,
+        academic_year: Option<&str>,
+        term: &str,
+    ) -> Result<Pricing, AppError> {
+        let (current_year, currency, due_day): (Option<String>, Option<String>, Option<String>) =
+            sqlx::query_as(
+                r#"
+                SELECT current_academic_year, currency, fee_payment_due_day
+                FROM school_configs WHERE org_id = $1
+                "#,
+            )
+            .bind(org_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .unwrap_or_default();
+        let academic_year = academic_year
+            .map(str::trim)
+            .filter(|y| !y.is_empty())
+            .or(current_year.as_deref().map(str::trim).filter(|y| !y.is_empty()))
+            .map(String::from)
+            .ok_or_else(|| {
+                AppError::BadRequest(
+                    "academic_year is required when current_academic_year isn't set in school setup".into(),
+                )
+            })?;
+
+        let terms: Vec<Term> = sqlx::query_as::<_, (String, Option<NaiveDate>, Option<NaiveDate>)>(
+            "SELECT name, start_date, end_date FROM school_terms WHERE org_id = $1 ORDER BY position",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|(name, start_date, end_date)| Term {
+            name,
+            start_date,
+            end_date,
+        })
+        .collect();
+        let wanted = term.trim();
+        let term = terms
+            .iter()
+            .find(|t| t.name.to_lowercase() == wanted.to_lowercase())
+            .cloned()
+            .ok_or_else(|| {
+                AppError::BadRequest(format!("term '{wanted}' is not configured for this school"))
+            })?;
+
+        let rows: Vec<FeeCategoryRow> = sqlx::query_as(
+            "SELECT * FROM school_fee_categories WHERE org_id = $1 ORDER BY position",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+        if rows.is_empty() {
+            return Err(AppError::BadRequest(
+                "No fee categories are configured; set them in the fees section first".into(),
+            ));
+        }
+        let mut categories = Vec::with_capacity(rows.len());
+        for row in rows {
+            let freq = Frequency::of(&row)?;
+            categories.push((row, freq));
+        }
+        let discount_types: Vec<FeeDiscountRow> = sqlx::query_as(
+            "SELECT * FROM school_fee_discounts WHERE org_id = $1 ORDER BY position",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Pricing {
+            academic_year,
+            term,
+            terms,
+            categories,
+            discount_types,
+            currency: currency.filter(|c| !c.trim().is_empty()),
+            due_day: due_day.and_then(|d| d.trim().parse().ok()),
+        })
+    }
+
+    async fn billing_history(
+        &self,
+        org_id: Uuid,
+        student_ids: &[Uuid],
+        excluding: Option<Uuid>,
+    ) -> Result<BillingHistory, AppError> {
+        let rows: Vec<(Uuid, String, String, String)> = sqlx::query_as(
+            r#"
+            SELECT i.student_id, LOWER(l.category), i.academic_year, i.term
+            FROM fee_invoice_lines l
+            JOIN fee_invoices i ON i.id = l.invoice_id
+            WHERE i.org_id = $1 AND i.status = 'issued' AND i.student_id = ANY($2)
+              AND ($3::uuid IS NULL OR i.id <> $3)
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_ids)
+        .bind(excluding)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut history = BillingHistory::new();
+        for (student_id, category, year, term) in rows {
+            history
+                .entry(student_id)
+                .or_default()
+                .entry(category)
+                .or_default()
+                .push((year, term));
+        }
+        Ok(history)
+    }
+
+    /// Discounts assigned to these students for the pricing's academic
+    /// year, and their sibling positions when a discount type needs them.
+    async fn load_discounts(
+        &self,
+        org_id: Uuid,
+        pricing: &Pricing,
+        student_ids: &[Uuid],
+    ) -> Result<Discounts, AppError> {
+        let rows: Vec<(Uuid, Assigned)> = sqlx::query_as::<_, AssignedRow>(
+            r#"
+            SELECT student_id, id, kind, name, percentage::float8 AS percentage, applies_to
+            FROM student_fee_discounts
+            WHERE org_id = $1 AND student_id = ANY($2)
+              AND (academic_year IS NULL OR academic_year = $3)
+            ORDER BY created_at, id
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_ids)
+        .bind(&pricing.academic_year)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|r| (r.student_id, r.discount))
+        .collect();
+        let mut discounts = Discounts::default();
+        for (student_id, assigned) in rows {
+            discounts.assigned.entry(student_id).or_default().push(assigned);
+        }
+        if pricing.discount_types.iter().any(|d| d.sibling_position.is_some()) {
+            discounts.families = sibling_positions(&load_children(&self.pool, org_id).await?);
+        }
+        Ok(discounts)
+    }
+}
+
+impl Pricing {
+    fn category_names(&self) -> Vec<String> {
+        self.categories.iter().map(|(c, _)| c.name.clone()).collect()
+    }
+
+    /// The discounts one student's invoice lines get.
+    fn rules_for(&self, student_id: Uuid, discounts: &Discounts) -> Result<Vec<Rule>, AppError> {
+        rules_for(
+            &self.discount_types,
+            &self.category_names(),
+            discounts.assigned.get(&student_id).map_or(&[][..], Vec::as_slice),
+            discounts.families.get(&student_id),
+        )
+    }
+
+    /// The lines one student is charged. `optional` holds the lowercased
+    /// names of the non-mandatory categories to include.
+    fn lines_for(
+        &self,
+        student: &Billable,
+        optional: &[String],
+        history: Option<&HashMap<String, Vec<(String, String)>>>,
+    ) -> Result<Vec<InvoiceLine>, AppError> {
+        let mut lines = Vec::new();
+        for (cat, freq) in &self.categories {
+            let key = cat.name.to_lowercase();
+            if !cat.mandatory && !optional.contains(&key) {
+                continue;
+            }
+            if !applies(cat, &student.grade_level, student.boarding_status.as_deref())? {
+                continue;
+            }
+            let Some(unit) = unit_amount(cat, &student.grade_level) else {
+                continue;
+            };
+            let billed = history.and_then(|h| h.get(&key)).map_or(&[][..], Vec::as_slice);
+            if already_billed(*freq, &self.academic_year, &self.term.name, &self.terms, billed) {
+                continue;
+            }
+            let quantity = quantity(*freq, &self.term)?;
+            lines.push(InvoiceLine {
+                category: cat.name.clone(),
+                fee_type: cat.fee_type.clone(),
+                frequency: cat.frequency.clone(),
+                quantity,
+                unit_amount: unit,
+                amount: round2(unit * quantity),
+                discount_amount: 0.0,
+                discounts: Vec::new(),
+            });
+        }
+        Ok(lines)
+    }
+}
+
+struct NewInvoice<'a> {
+    org_id: Uuid,
+    number: String,
+    student: &'a Billable,
+    pricing: &'a Pricing,
+    lines: Vec<InvoiceLine>,
+    issue_date: NaiveDate,
+    due_date: NaiveDate,
+    replaces: Option<Uuid>,
+    created_by: Option<Uuid>,
+}
+
+async fn insert_invoice(conn: &mut PgConnection, new: NewInvoice<'_>) -> Result<Invoice, AppError> {
+    let discount = round2(new.lines.iter().map(|l| l.discount_amount).sum());
+    let total = round2(new.lines.iter().map(|l| l.amount).sum::<f64>() - discount);
+    let (id, created_at): (Uuid, chrono::DateTime<chrono::Utc>) = sqlx::query_as(
+        r#"
+        INSERT INTO fee_invoices
+            (org_id, student_id, invoice_number, academic_year, term,
+             grade_level, section, boarding_status, currency, total_amount, discount_amount,
+             issue_date, due_date, replaces_invoice_id, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING id, created_at
+        "#,
+    )
+    .bind(new.org_id)
+    .bind(new.student.id)
+    .bind(&new.number)
+    .bind(&new.pricing.academic_year)
+    .bind(&new.pricing.term.name)
+    .bind(&new.student.grade_level)
+    .bind(&new.student.section)
+    .bind(&new.student.boarding_status)
+    .bind(&new.pricing.currency)
+    .bind(total)
+    .bind(discount)
+    .bind(new.issue_date)
+    .bind(new.due_date)
+    .bind(new.replaces)
+    .bind(new.created_by)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| match e {
+        sqlx::Error::Database(db_err)
+            if db_err.constraint() == Some("idx_fee_invoices_student_term") =>
+        {
+            AppError::Conflict(format!(
+                "{} already has an issued invoice for {} {}",
+                new.student.admission_number, new.pricing.academic_year, new.pricing.term.name
+            ))
+        }
+        other => other.into(),
+    })?;
+
+    for (i, line) in new.lines.iter().enumerate() {
+        let line_id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO fee_invoice_lines
+                (invoice_id, category, fee_type, frequency, quantity, unit_amount, amount,
+                 discount_amount, position)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(id)
+        .bind(&line.category)
+        .bind(&line.fee_type)
+        .bind(&line.frequency)
+        .bind(line.quantity)
+        .bind(line.unit_amount)
+        .bind(line.amount)
+        .bind(line.discount_amount)
+        .bind(i as i16)
+        .fetch_one(&mut *conn)
+        .await?;
+        for (j, d) in line.discounts.iter().enumerate() {
+            sqlx::query(
+                r#"
+                INSERT INTO fee_invoice_line_discounts
+                    (line_id, name, kind, source, percentage, amount, detail,
+                     student_discount_id, position)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                "#,
+            )
+            .bind(line_id)
+            .bind(&d.name)
+            .bind(&d.kind)
+            .bind(&d.source)
+            .bind(d.percentage)
+            .bind(d.amount)
+            .bind(&d.detail)
+            .bind(d.student_discount_id)
+            .bind(j as i16)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(Invoice {
+        id,
+        invoice_number: new.number,
+        student_id: new.student.id,
+        admission_number: new.student.admission_number.clone(),
+        first_name: new.student.first_name.clone(),
+        last_name: new.student.last_name.clone(),
+        grade_level: new.student.grade_level.clone(),
+        section: new.student.section.clone(),
+        boarding_status: new.student.boarding_status.clone(),
+        academic_year: new.pricing.academic_year.clone(),
+        term: new.pricing.term.name.clone(),
+        status: "issued".into(),
+        currency: new.pricing.currency.clone(),
+        total_amount: total,
+        discount_amount: discount,
+        amount_paid: 0.0,
+        balance: total,
+        issue_date: new.issue_date,
+        due_date: new.due_date,
+        void_reason: None,
+        voided_at: None,
+        replaces_invoice_id: new.replaces,
+        replaced_by_invoice_id: None,
+        created_at,
+        lines: new.lines,
+    })
+}
+
+/// Marks an invoice void and returns what was paid on it to credit.
+async fn void(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    id: Uuid,
+    reason: Option<&str>,
+    changed_by: Option<Uuid>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        UPDATE fee_invoices SET
+            status = 'void', void_reason = $3, voided_at = NOW(), voided_by_user_id = $4
+        WHERE id = $1 AND org_id = $2
+        "#,
+    )
+    .bind(id)
+    .bind(org_id)
+    .bind(reason)
+    .bind(changed_by)
+    .execute(&mut *conn)
+    .await?;
+    release(conn, id).await
+}
+
+/// Next invoice number for an org, `INV-000001` onwards. Holds a row lock
+/// on `school_configs` until the enclosing transaction ends.
+/// Takes the school's invoice-number row lock until the transaction ends.
+async fn lock_invoice_numbers(conn: &mut PgConnection, org_id: Uuid) -> Result<(), AppError> {
+    sqlx::query(
+        r#"
+        INSERT INTO school_configs (org_id) VALUES ($1)
+        ON CONFLICT (org_id) DO UPDATE SET invoice_next_seq = school_configs.invoice_next_seq
+        "#,
+    )
+    .bind(org_id)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn next_invoice_number(conn: &mut PgConnection, org_id: Uuid) -> Result<String, AppError> {
+    let seq: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO school_configs (org_id, invoice_next_seq)
+        VALUES ($1, 2)
+        ON CONFLICT (org_id) DO UPDATE
+        SET invoice_next_seq = school_configs.invoice_next_seq + 1
+        RETURNING invoice_next_seq - 1
+        "#,
+    )
+    .bind(org_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(format!("INV-{seq:06}"))
+}
+
+#[derive(sqlx::FromRow)]
+struct AssignedRow {
+    student_id: Uuid,
+    #[sqlx(flatten)]
+    discount: Assigned,
+}
+
+#[derive(sqlx::FromRow)]
+struct LineDiscountRow {
+    line_position: i16,
+    #[sqlx(flatten)]
+    discount: LineDiscount,
+}
+
+/// `due` moved onto the next instructional day when the school is closed.
+async fn open_due_date(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    due: NaiveDate,
+) -> Result<NaiveDate, AppError> {
+    let until = due + Duration::days(DUE_DATE_SEARCH_DAYS);
+    let calendar = SchoolCalendar::load(conn, org_id, Some((due, until))).await?;
+    Ok(calendar.next_instructional_day(due, until))
+}
+
+fn skip(student: &Billable, reason: String) -> SkippedStudent {
+    SkippedStudent {
+        student_id: student.id,
+        admission_number: Some(student.admission_number.clone()),
+        reason,
+    }
+}
@@ -0,0 +1,297 @@
+//! How payments settle invoices, and the fee status derived from that.
+//! Money is handled in whole cents here so allocations always add up.
+
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::fees::RecentPayment;
+use crate::services::calendar::SchoolCalendar;
+
+/// Payments listed under `?include=recent_payments`.
+pub(crate) const RECENT_PAYMENTS_LIMIT: i64 = 5;
+
+pub(super) fn cents(amount: f64) -> i64 {
+    (amount * 100.0).round() as i64
+}
+
+pub(super) fn from_cents(cents: i64) -> f64 {
+    cents as f64 / 100.0
+}
+
+/// An issued invoice as it stands against payments.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct InvoiceBalance {
+    pub(super) paid: i64,
+    pub(super) balance: i64,
+    pub(super) due_date: NaiveDate,
+}
+
+/// paid, partial, owing or overdue from a student's issued invoices:
+/// overdue once anything unpaid is past due, partial while an unpaid
+/// invoice has had something paid on it. Without invoices, unknown.
+pub(super) fn fee_status(invoices: &[InvoiceBalance], today: NaiveDate) -> &'static str {
+    let open: Vec<&InvoiceBalance> = invoices.iter().filter(|i| i.balance > 0).collect();
+    if invoices.is_empty() {
+        "unknown"
+    } else if open.is_empty() {
+        "paid"
+    } else if open.iter().any(|i| i.due_date < today) {
+        "overdue"
+    } else if open.iter().any(|i| i.paid > 0) {
+        "partial"
+    } else {
+        "owing"
+    }
+}
+
+/// Pairs unallocated payment amounts with open invoice balances, both in
+/// the order given. Returns (payment, invoice, cents) for each pairing.
+fn pair_up(credits: &[(Uuid, i64)], open: &[(Uuid, i64)]) -> Vec<(Uuid, Uuid, i64)> {
+    let mut pairs = Vec::new();
+    let mut open = open.iter().copied().filter(|(_, b)| *b > 0).peekable();
+    let mut remaining = open.peek().map_or(0, |(_, b)| *b);
+    for &(payment, mut credit) in credits {
+        while credit > 0 {
+            let Some(&(invoice, _)) = open.peek() else {
+                return pairs;
+            };
+            let applied = credit.min(remaining);
+            pairs.push((payment, invoice, applied));
+            credit -= applied;
+            remaining -= applied;
+            if remaining == 0 {
+                open.next();
+                remaining = open.peek().map_or(0, |(_, b)| *b);
+            }
+        }
+    }
+    pairs
+}
+
+/// Applies a student's unallocated payments, oldest first, to their open
+/// invoices: `first` before the rest, then by due date. Locks the student
+/// row so concurrent payments can't allocate the same balance twice.
+/// Returns what each invoice received.
+pub(super) async fn allocate(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    student_id: Uuid,
+    first: Option<Uuid>,
+) -> Result<HashMap<Uuid, f64>, AppError> {
+    sqlx::query("SELECT 1 FROM students WHERE id = $1 AND org_id = $2 FOR UPDATE")
+        .bind(student_id)
+        .bind(org_id)
+        .execute(&mut *conn)
+        .await?;
+
+    let credits: Vec<(Uuid, f64)> = sqlx::query_as(
+        r#"
+        SELECT p.id, (p.amount - COALESCE(SUM(a.amount), 0))::float8
+        FROM fee_payments p
+        LEFT JOIN fee_payment_allocations a ON a.payment_id = p.id
+        WHERE p.org_id = $1 AND p.student_id = $2
+        GROUP BY p.id
+        HAVING p.amount > COALESCE(SUM(a.amount), 0)
+        ORDER BY p.paid_on, p.created_at, p.id
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_id)
+    .fetch_all(&mut *conn)
+    .await?;
+    if credits.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let open: Vec<(Uuid, f64)> = sqlx::query_as(
+        r#"
+        SELECT i.id, (i.total_amount - COALESCE(SUM(a.amount), 0))::float8
+        FROM fee_invoices i
+        LEFT JOIN fee_payment_allocations a ON a.invoice_id = i.id
+        WHERE i.org_id = $1 AND i.student_id = $2 AND i.status = 'issued'
+        GROUP BY i.id
+        HAVING i.total_amount > COALESCE(SUM(a.amount), 0)
+        ORDER BY (i.id = $3) IS TRUE DESC, i.due_date, i.invoice_number
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_id)
+    .bind(first)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let in_cents = |rows: Vec<(Uuid, f64)>| -> Vec<(Uuid, i64)> {
+        rows.into_iter().map(|(id, amount)| (id, cents(amount))).collect()
+    };
+    let mut applied: HashMap<Uuid, i64> = HashMap::new();
+    for (payment, invoice, amount) in pair_up(&in_cents(credits), &in_cents(open)) {
+        sqlx::query(
+            r#"
+            INSERT INTO fee_payment_allocations (payment_id, invoice_id, amount)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (payment_id, invoice_id)
+            DO UPDATE SET amount = fee_payment_allocations.amount + EXCLUDED.amount
+            "#,
+        )
+        .bind(payment)
+        .bind(invoice)
+        .bind(from_cents(amount))
+        .execute(&mut *conn)
+        .await?;
+        *applied.entry(invoice).or_default() += amount;
+    }
+    Ok(applied.into_iter().map(|(id, c)| (id, from_cents(c))).collect())
+}
+
+/// Returns what was paid on an invoice to credit, e.g. when it's voided.
+pub(super) async fn release(conn: &mut PgConnection, invoice_id: Uuid) -> Result<(), AppError> {
+    sqlx::query("DELETE FROM fee_payment_allocations WHERE invoice_id = $1")
+        .bind(invoice_id)
+        .execute(&mut *conn)
+        .await?;
+    Ok(())
+}
+
+/// Each student's issued invoices, for [`fee_status`].
+pub(super) async fn balances_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+) -> Result<HashMap<Uuid, Vec<InvoiceBalance>>, AppError> {
+    let rows: Vec<(Uuid, f64, f64, NaiveDate)> = sqlx::query_as(
+        r#"
+        SELECT i.student_id, i.total_amount::float8,
+               COALESCE(SUM(a.amount), 0)::float8, i.due_date
+        FROM fee_invoices i
+        LEFT JOIN fee_payment_allocations a ON a.invoice_id = i.id
+        WHERE i.org_id = $1 AND i.student_id = ANY($2) AND i.status = 'issued'
+        GROUP BY i.id
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .fetch_all(pool)
+    .await?;
+    let mut map: HashMap<Uuid, Vec<InvoiceBalance>> = HashMap::new();
+    for (student_id, total, paid, due_date) in rows {
+        map.entry(student_id).or_default().push(InvoiceBalance {
+            paid: cents(paid),
+            balance: cents(total) - cents(paid),
+            due_date,
+        });
+    }
+    Ok(map)
+}
+
+/// Today in the school's timezone, which decides what is overdue.
+pub(super) async fn school_today(pool: &PgPool, org_id: Uuid) -> Result<NaiveDate, AppError> {
+    Ok(SchoolCalendar::load(&mut *pool.acquire().await?, org_id, None).await?.today())
+}
+
+/// Fee status per student; students without invoices are left out.
+pub(crate) async fn statuses_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+) -> Result<HashMap<Uuid, String>, AppError> {
+    if student_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let balances = balances_for_students(pool, org_id, student_ids).await?;
+    if balances.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let today = school_today(pool, org_id).await?;
+    Ok(balances
+        .into_iter()
+        .map(|(id, invoices)| (id, fee_status(&invoices, today).to_string()))
+        .collect())
+}
+
+/// Each student's latest payments, newest first.
+pub(crate) async fn recent_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+    limit: i64,
+) -> Result<HashMap<Uuid, Vec<RecentPayment>>, AppError> {
+    let mut map: HashMap<Uuid, Vec<RecentPayment>> = HashMap::new();
+    if student_ids.is_empty() {
+        return Ok(map);
+    }
+    let rows: Vec<(Uuid, RecentPayment)> = sqlx::query_as::<_, RecentPaymentRow>(
+        r#"
+        SELECT student_id, id, receipt_number, amount, currency, method, reference, paid_on
+        FROM (
+            SELECT p.student_id, p.id, p.receipt_number, p.amount::float8 AS amount,
+                   p.currency, p.method, p.reference, p.paid_on,
+                   ROW_NUMBER() OVER (
+                       PARTITION BY p.student_id ORDER BY p.paid_on DESC, p.created_at DESC
+                   ) AS rn
+            FROM fee_payments p
+            WHERE p.org_id = $1 AND p.student_id = ANY($2)
+        ) ranked
+        WHERE rn <= $3
+        ORDER BY student_id, rn
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|r| (r.student_id, r.payment))
+    .collect();
+    for (student_id, payment) in rows {
+        map.entry(student_id).or_default().push(payment);
+    }
+    Ok(map)
+}
+
+#[derive(sqlx::FromRow)]
+struct RecentPaymentRow {
+    student_id: Uuid,
+    #[sqlx(flatten)]
+    payment: RecentPayment,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn d(y: i32, m: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, day).unwrap()
+    }
+
+    #[test]
+    fn derives_fee_status_from_balances() {
+        let today = d(2026, 10, 18);
+        let invoice = |paid, balance, due_date| InvoiceBalance { paid, balance, due_date };
+        assert_eq!(fee_status(&[], today), "unknown");
+        assert_eq!(fee_status(&[invoice(5000, 0, d(2026, 9, 5))], today), "paid");
+        assert_eq!(fee_status(&[invoice(0, 5000, d(2026, 11, 5))], today), "owing");
+        assert_eq!(fee_status(&[invoice(2000, 3000, d(2026, 11, 5))], today), "partial");
+        assert_eq!(
+            fee_status(&[invoice(2000, 3000, d(2026, 11, 5)), invoice(0, 100, d(2026, 10, 17))], today),
+            "overdue"
+        );
+        // Due today isn't overdue yet.
+        assert_eq!(fee_status(&[invoice(0, 5000, today)], today), "owing");
+    }
+
+    #[test]
+    fn pairs_payments_with_invoices_in_order() {
+        let (p1, p2, i1, i2) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        assert_eq!(
+            pair_up(&[(p1, 7000), (p2, 5000)], &[(i1, 10000), (i2, 1000)]),
+            [(p1, i1, 7000), (p2, i1, 3000), (p2, i2, 1000)]
+        );
+        // Overpayment stays unallocated.
+        assert_eq!(pair_up(&[(p1, 7000)], &[(i1, 5000)]), [(p1, i1, 5000)]);
+        assert!(pair_up(&[(p1, 7000)], &[]).is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use sqlx::PgPool;
+
+pub(crate) mod billing;
+mod discounts;
+mod invoices;
+pub(crate) mod ledger;
+mod payments;
+mod student_discounts;
+
+pub struct FeesService {
+    pool: PgPool,
+}
+
+impl FeesService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// The value with surrounding whitespace removed; None when blank.
+fn trimmed(v: &Option<String>) -> Option<&str> {
+    v.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
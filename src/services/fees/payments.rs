@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::fees::{
+    InvoiceSummary, Payment, PaymentAllocation, PaymentListQuery, PaymentListResponse,
+    RecordPaymentRequest, StudentFeeStatement,
+};
+use crate::models::students::PaginationInfo;
+use crate::services::gradebook::scale::round2;
+use crate::services::students::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+
+use super::invoices::INVOICE_SUMMARY_COLUMNS;
+use super::ledger::{
+    allocate, balances_for_students, cents, fee_status, from_cents, school_today,
+};
+use super::{trimmed, FeesService};
+
+const METHODS: &[&str] = &["cash", "bank_transfer", "card"];
+/// The most `fee_payments.amount` (NUMERIC(14, 2)) holds.
+const MAX_AMOUNT: f64 = 999_999_999_999.99;
+
+/// Payment columns with the student's names, for [`Payment`].
+const PAYMENT_COLUMNS: &str = r#"
+    p.id, p.receipt_number, p.student_id, s.admission_number, s.first_name, s.last_name,
+    p.amount::float8 AS amount, p.currency, p.method, p.reference, p.paid_on, p.notes,
+    (p.amount - COALESCE(
+        (SELECT SUM(a.amount) FROM fee_payment_allocations a WHERE a.payment_id = p.id), 0
+    ))::float8 AS unallocated,
+    p.created_at
+"#;
+
+impl FeesService {
+    /// Record a payment from a student and apply it to their open invoices,
+    /// `invoice_id` first, then oldest due first. Anything left over is
+    /// held as credit for the next invoice.
+    pub async fn record_payment(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        req: RecordPaymentRequest,
+        recorded_by: Option<Uuid>,
+    ) -> Result<Payment, AppError> {
+        let amount = round2(req.amount);
+        if !amount.is_finite() || amount <= 0.0 {
+            return Err(AppError::BadRequest("amount must be greater than 0".into()));
+        }
+        if amount > MAX_AMOUNT {
+            return Err(AppError::BadRequest(format!("amount can't be more than {MAX_AMOUNT:.2}")));
+        }
+        let method = req.method.trim().to_lowercase();
+        if !METHODS.contains(&method.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid method '{}'; must be one of: {}",
+                req.method.trim(),
+                METHODS.join(", ")
+            )));
+        }
+        let today = school_today(&self.pool, org_id).await?;
+        let paid_on = req.paid_on.unwrap_or(today);
+        if paid_on > today {
+            return Err(AppError::BadRequest("paid_on can't be in the future".into()));
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let exists: Option<i32> =
+            sqlx::query_scalar("SELECT 1 FROM students WHERE id = $1 AND org_id = $2")
+                .bind(student_id)
+                .bind(org_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if exists.is_none() {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        if let Some(invoice_id) = req.invoice_id {
+            let status: Option<String> = sqlx::query_scalar(
+                "SELECT status FROM fee_invoices WHERE id = $1 AND org_id = $2 AND student_id = $3",
+            )
+            .bind(invoice_id)
+            .bind(org_id)
+            .bind(student_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            match status.as_deref() {
+                None => {
+                    return Err(AppError::BadRequest(
+                        "invoice_id is not an invoice of this student".into(),
+                    ))
+                }
+                Some("void") => {
+                    return Err(AppError::BadRequest("Invoice is void".into()));
+                }
+                Some(_) => {}
+            }
+        }
+
+        let receipt = next_receipt_number(&mut tx, org_id).await?;
+        let id: Uuid = sqlx::query_scalar(
+            r#"
+            INSERT INTO fee_payments
+                (org_id, student_id, receipt_number, amount, currency, method,
+                 reference, paid_on, notes, recorded_by_user_id)
+            VALUES ($1, $2, $3, $4,
+                    (SELECT NULLIF(TRIM(currency), '') FROM school_configs WHERE org_id = $1),
+                    $5, $6, $7, $8, $9)
+            RETURNING id
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(&receipt)
+        .bind(amount)
+        .bind(&method)
+        .bind(trimmed(&req.reference))
+        .bind(paid_on)
+        .bind(trimmed(&req.notes))
+        .bind(recorded_by)
+        .fetch_one(&mut *tx)
+        .await?;
+        allocate(&mut tx, org_id, student_id, req.invoice_id).await?;
+        tx.commit().await?;
+
+        self.payment(org_id, id).await
+    }
+
+    /// One payment with the invoices it was applied to.
+    pub async fn payment(&self, org_id: Uuid, id: Uuid) -> Result<Payment, AppError> {
+        let payment: Payment = sqlx::query_as(&format!(
+            r#"
+            SELECT {PAYMENT_COLUMNS}
+            FROM fee_payments p
+            JOIN students s ON s.id = p.student_id
+            WHERE p.id = $1 AND p.org_id = $2
+            "#
+        ))
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payment not found".into()))?;
+        let mut payments = vec![payment];
+        self.attach_allocations(&mut payments).await?;
+        Ok(payments.remove(0))
+    }
+
+    /// Payments, newest first.
+    pub async fn list_payments(
+        &self,
+        org_id: Uuid,
+        q: PaymentListQuery,
+    ) -> Result<PaymentListResponse, AppError> {
+        let method = trimmed(&q.method);
+        if let Some(m) = method
+            && !METHODS.contains(&m)
+        {
+            return Err(AppError::BadRequest(format!(
+                "Invalid method '{m}'; must be one of: {}",
+                METHODS.join(", ")
+            )));
+        }
+        if let (Some(from), Some(to)) = (q.from, q.to)
+            && from > to
+        {
+            return Err(AppError::BadRequest("from must not be after to".into()));
+        }
+        let page = q.page.unwrap_or(1).max(1);
+        let page_size = q
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = page.saturating_sub(1).saturating_mul(page_size);
+
+        const FILTER: &str = r#"
+            FROM fee_payments p
+            JOIN students s ON s.id = p.student_id
+            WHERE p.org_id = $1
+              AND ($2::uuid IS NULL OR p.student_id = $2)
+              AND ($3::text IS NULL OR p.method = $3)
+              AND ($4::date IS NULL OR p.paid_on >= $4)
+              AND ($5::date IS NULL OR p.paid_on <= $5)
+        "#;
+        let mut data: Vec<Payment> = sqlx::query_as(&format!(
+            r#"
+            SELECT {PAYMENT_COLUMNS}
+            {FILTER}
+            ORDER BY p.paid_on DESC, p.created_at DESC
+            LIMIT $6 OFFSET $7
+            "#
+        ))
+        .bind(org_id)
+        .bind(q.student_id)
+        .bind(method)
+        .bind(q.from)
+        .bind(q.to)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        self.attach_allocations(&mut data).await?;
+
+        let (total, total_amount): (i64, f64) = sqlx::query_as(&format!(
+            "SELECT COUNT(*), COALESCE(SUM(p.amount), 0)::float8 {FILTER}"
+        ))
+        .bind(org_id)
+        .bind(q.student_id)
+        .bind(method)
+        .bind(q.from)
+        .bind(q.to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let total_pages = if total == 0 {
+            0
+        } else {
+            ((total as f64) / (page_size as f64)).ceil() as i64
+        };
+        Ok(PaymentListResponse {
+            data,
+            total_amount,
+            pagination: PaginationInfo {
+                page,
+                page_size,
+                total,
+                total_pages,
+            },
+        })
+    }
+
+    /// A student's invoices and payments, with what they owe and hold in
+    /// credit.
+    pub async fn student_statement(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+    ) -> Result<StudentFeeStatement, AppError> {
+        let currency: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT NULLIF(TRIM(c.currency), '')
+            FROM students s
+            LEFT JOIN school_configs c ON c.org_id = s.org_id
+            WHERE s.id = $1 AND s.org_id = $2
+            "#,
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Student not found".into()))?;
+
+        let invoices: Vec<InvoiceSummary> = sqlx::query_as(&format!(
+            r#"
+            SELECT {INVOICE_SUMMARY_COLUMNS}
+            FROM fee_invoices i
+            JOIN students s ON s.id = i.student_id
+            WHERE i.org_id = $1 AND i.student_id = $2
+            ORDER BY i.issue_date DESC, i.created_at DESC, i.invoice_number DESC
+            "#
+        ))
+        .bind(org_id)
+        .bind(student_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut payments: Vec<Payment> = sqlx::query_as(&format!(
+            r#"
+            SELECT {PAYMENT_COLUMNS}
+            FROM fee_payments p
+            JOIN students s ON s.id = p.student_id
+            WHERE p.org_id = $1 AND p.student_id = $2
+            ORDER BY p.paid_on DESC, p.created_at DESC
+            "#
+        ))
+        .bind(org_id)
+        .bind(student_id)
+        .fetch_all(&self.pool)
+        .await?;
+        self.attach_allocations(&mut payments).await?;
+
+        let balances = balances_for_students(&self.pool, org_id, &[student_id])
+            .await?
+            .remove(&student_id)
+            .unwrap_or_default();
+        let status = if balances.is_empty() {
+            "unknown"
+        } else {
+            fee_status(&balances, school_today(&self.pool, org_id).await?)
+        };
+        let issued = invoices.iter().filter(|i| i.status == "issued");
+        Ok(StudentFeeStatement {
+            student_id,
+            fee_status: status.to_string(),
+            currency,
+            total_invoiced: from_cents(issued.clone().map(|i| cents(i.total_amount)).sum()),
+            total_paid: from_cents(payments.iter().map(|p| cents(p.amount)).sum()),
+            balance: from_cents(issued.map(|i| cents(i.balance)).sum()),
+            credit: from_cents(payments.iter().map(|p| cents(p.unallocated)).sum()),
+            invoices,
+            payments,
+        })
+    }
+
+    async fn attach_allocations(&self, payments: &mut [Payment]) -> Result<(), AppError> {
+        if payments.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<Uuid> = payments.iter().map(|p| p.id).collect();
+        let rows: Vec<(Uuid, Uuid, String, f64)> = sqlx::query_as(
+            r#"
+            SELECT a.payment_id, a.invoice_id, i.invoice_number, a.amount::float8
+            FROM fee_payment_allocations a
+            JOIN fee_invoices i ON i.id = a.invoice_id
+            WHERE a.payment_id = ANY($1)
+            ORDER BY i.due_date, i.invoice_number
+            "#,
+        )
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut by_payment: HashMap<Uuid, Vec<PaymentAllocation>> = HashMap::new();
+        for (payment_id, invoice_id, invoice_number, amount) in rows {
+            by_payment.entry(payment_id).or_default().push(PaymentAllocation {
+                invoice_id,
+                invoice_number,
+                amount,
+            });
+        }
+        for payment in payments {
+            payment.allocations = by_payment.remove(&payment.id).unwrap_or_default();
+        }
+        Ok(())
+    }
+}
+
+/// Next receipt number for an org, `RCT-000001` onwards. Holds a row lock
+/// on `school_configs` until the enclosing transaction ends.
+async fn next_receipt_number(conn: &mut PgConnection, org_id: Uuid) -> Result<String, AppError> {
+    let seq: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO school_configs (org_id, receipt_next_seq)
+        VALUES ($1, 2)
+        ON CONFLICT (org_id) DO UPDATE
+        SET receipt_next_seq = school_configs.receipt_next_seq + 1
+        RETURNING receipt_next_seq - 1
+        "#,
+    )
+    .bind(org_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    Ok(format!("RCT-{seq:06}"))
+}
@@ -0,0 +1,189 @@
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::fees::{
+    AssignDiscountRequest, SiblingDiscount, StudentDiscount, StudentDiscountsResponse,
+};
+use crate::models::school_setup::FeeDiscountRow;
+
+use super::discounts::{load_children, sibling_positions, sibling_type, Target, KINDS};
+use super::{trimmed, FeesService};
+
+const DISCOUNT_COLUMNS: &str = r#"
+    id, kind, name, percentage::float8 AS percentage, applies_to, academic_year, reason, created_at
+"#;
+
+impl FeesService {
+    /// Give a student a discount type from school setup, or a scholarship
+    /// or one-off discount at its own percentage. Applied to invoices
+    /// generated from now on.
+    pub async fn assign_discount(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        req: AssignDiscountRequest,
+        assigned_by: Option<Uuid>,
+    ) -> Result<StudentDiscount, AppError> {
+        self.ensure_student(org_id, student_id).await?;
+        let kind = trimmed(&req.kind).unwrap_or("discount").to_lowercase();
+        if !KINDS.contains(&kind.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid kind '{kind}'; must be discount or scholarship"
+            )));
+        }
+        let name = req.name.trim();
+        if name.is_empty() {
+            return Err(AppError::BadRequest("name is required".into()));
+        }
+
+        let (name, applies_to) = match req.percentage {
+            None => {
+                if trimmed(&req.applies_to).is_some() {
+                    return Err(AppError::BadRequest(
+                        "applies_to can only be given with a percentage; a discount type \
+                         uses its own"
+                            .into(),
+                    ));
+                }
+                let setup: Option<String> = sqlx::query_scalar(
+                    "SELECT name FROM school_fee_discounts WHERE org_id = $1 AND LOWER(name) = LOWER($2)",
+                )
+                .bind(org_id)
+                .bind(name)
+                .fetch_optional(&self.pool)
+                .await?;
+                let name = setup.ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "'{name}' is not a discount type in school setup; give a percentage \
+                         for a scholarship or one-off discount"
+                    ))
+                })?;
+                (name, None)
+            }
+            Some(p) => {
+                if !p.is_finite() || p <= 0.0 || p > 100.0 {
+                    return Err(AppError::BadRequest(
+                        "percentage must be over 0 and at most 100".into(),
+                    ));
+                }
+                let categories: Vec<String> = sqlx::query_scalar(
+                    "SELECT name FROM school_fee_categories WHERE org_id = $1",
+                )
+                .bind(org_id)
+                .fetch_all(&self.pool)
+                .await?;
+                let applies_to = trimmed(&req.applies_to);
+                Target::parse(name, applies_to, &categories)?;
+                (name.to_string(), applies_to.map(str::to_lowercase))
+            }
+        };
+
+        sqlx::query_as(&format!(
+            r#"
+            INSERT INTO student_fee_discounts
+                (org_id, student_id, kind, name, percentage, applies_to,
+                 academic_year, reason, assigned_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            RETURNING {DISCOUNT_COLUMNS}
+            "#
+        ))
+        .bind(org_id)
+        .bind(student_id)
+        .bind(&kind)
+        .bind(&name)
+        .bind(req.percentage)
+        .bind(applies_to)
+        .bind(trimmed(&req.academic_year))
+        .bind(trimmed(&req.reason))
+        .bind(assigned_by)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::Database(db_err) if db_err.is_unique_violation() => AppError::Conflict(
+                format!("'{name}' is already assigned to this student for that academic year"),
+            ),
+            other => other.into(),
+        })
+    }
+
+    /// A student's assigned discounts and scholarships, and the sibling
+    /// discount they'd get automatically.
+    pub async fn student_discounts(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+    ) -> Result<StudentDiscountsResponse, AppError> {
+        self.ensure_student(org_id, student_id).await?;
+        let assigned: Vec<StudentDiscount> = sqlx::query_as(&format!(
+            r#"
+            SELECT {DISCOUNT_COLUMNS}
+            FROM student_fee_discounts
+            WHERE org_id = $1 AND student_id = $2
+            ORDER BY academic_year NULLS FIRST, created_at
+            "#
+        ))
+        .bind(org_id)
+        .bind(student_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let types: Vec<FeeDiscountRow> = sqlx::query_as(
+            "SELECT * FROM school_fee_discounts WHERE org_id = $1 ORDER BY position",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut sibling = None;
+        if types.iter().any(|d| d.sibling_position.is_some()) {
+            let children = load_children(&self.pool, org_id).await?;
+            if let Some(family) = sibling_positions(&children).remove(&student_id)
+                && let Some(d) = sibling_type(&types, family.position)
+            {
+                sibling = Some(SiblingDiscount {
+                    name: d.name.clone(),
+                    percentage: d.percentage.clone(),
+                    applies_to: d.applies_to.clone(),
+                    position: family.position as i64,
+                    family_size: family.size as i64,
+                    sibling_ids: family.siblings,
+                });
+            }
+        }
+        Ok(StudentDiscountsResponse { assigned, sibling })
+    }
+
+    /// Take a discount or scholarship off a student. Invoices already
+    /// issued keep it until regenerated.
+    pub async fn remove_discount(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        id: Uuid,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            "DELETE FROM student_fee_discounts WHERE id = $1 AND org_id = $2 AND student_id = $3",
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(student_id)
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Discount not found".into()));
+        }
+        Ok(())
+    }
+
+    async fn ensure_student(&self, org_id: Uuid, student_id: Uuid) -> Result<(), AppError> {
+        let exists: Option<i32> =
+            sqlx::query_scalar("SELECT 1 FROM students WHERE id = $1 AND org_id = $2")
+                .bind(student_id)
+                .bind(org_id)
+                .fetch_optional(&self.pool)
+                .await?;
+        if exists.is_none() {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        Ok(())
+    }
+}
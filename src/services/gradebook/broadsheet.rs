@@ -0,0 +1,299 @@
+//! Class broadsheet: every student's component scores, total and grade per
+//! subject, with overall total, average and position, as CSV or XLSX.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{BroadsheetQuery, StudentPosition, SubjectResult};
+use crate::services::report_cards::file_stem;
+use crate::services::students::csv_safe;
+
+use super::results::ResultEngine;
+use super::scale::round2;
+use super::{component_label, GradebookService};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroadsheetFormat {
+    Csv,
+    Xlsx,
+}
+
+impl BroadsheetFormat {
+    pub fn parse(s: Option<&str>) -> Result<Self, AppError> {
+        match s.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("csv") => Ok(Self::Csv),
+            Some("xlsx") => Ok(Self::Xlsx),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Invalid format '{other}'. Use csv or xlsx"
+            ))),
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Xlsx => "xlsx",
+        }
+    }
+}
+
+/// A rendered broadsheet ready to send.
+pub struct BroadsheetFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Text(String),
+    Number(f64),
+    Empty,
+}
+
+impl GradebookService {
+    /// One row per student with results in the class, in roster order.
+    pub async fn broadsheet(
+        &self,
+        org_id: Uuid,
+        q: BroadsheetQuery,
+    ) -> Result<BroadsheetFile, AppError> {
+        let format = BroadsheetFormat::parse(q.format.as_deref())?;
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        let class = self
+            .resolve_class(
+                &engine.config,
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        let rows = self.class_rows(org_id, &class, None).await?;
+        let stats = self.compute_statistics(&engine, org_id, class).await?;
+        if stats.students.is_empty() {
+            return Err(AppError::NotFound("No results for this class and term".into()));
+        }
+
+        let components: Vec<&str> = engine.config.weights.iter().map(|(k, _)| *k).collect();
+        let subjects: Vec<&str> = stats.subjects.iter().map(|s| s.subject.as_str()).collect();
+        let mut headers: Vec<String> =
+            ["Admission No", "Last Name", "First Name"].map(String::from).to_vec();
+        for subject in &subjects {
+            headers.extend(components.iter().map(|c| format!("{subject} {}", component_label(c))));
+            headers.push(format!("{subject} Total"));
+            headers.push(format!("{subject} Grade"));
+        }
+        headers.extend(["Total", "Average", "Position"].map(String::from));
+
+        let standing: HashMap<Uuid, &StudentPosition> =
+            stats.students.iter().map(|s| (s.student_id, s)).collect();
+        let mut table: Vec<Vec<Cell>> = Vec::with_capacity(stats.students.len());
+        let mut results: HashMap<String, SubjectResult> = HashMap::new();
+        let mut rows = rows.into_iter().peekable();
+        while let Some(row) = rows.next() {
+            let student_id = row.score.student_id;
+            if let Some(result) =
+                engine.subject_result(&row.score, &row.max_scores, &row.sheet_status)
+            {
+                results.insert(result.subject.clone(), result);
+            }
+            if rows.peek().is_some_and(|next| next.score.student_id == student_id) {
+                continue;
+            }
+            let Some(position) = standing.get(&student_id) else {
+                results.clear();
+                continue;
+            };
+            let mut cells = vec![
+                Cell::Text(row.admission_number),
+                Cell::Text(row.last_name),
+                Cell::Text(row.first_name),
+            ];
+            for subject in &subjects {
+                match results.get(*subject) {
+                    Some(r) => {
+                        cells.extend(components.iter().map(|c| {
+                            r.scores.get(*c).map_or(Cell::Empty, |s| Cell::Number(*s))
+                        }));
+                        cells.push(Cell::Number(r.total));
+                        cells.push(Cell::Text(r.grade.clone()));
+                    }
+                    None => cells.extend((0..components.len() + 2).map(|_| Cell::Empty)),
+                }
+            }
+            cells.push(Cell::Number(round2(results.values().map(|r| r.total).sum())));
+            cells.push(Cell::Number(position.average));
+            cells.push(Cell::Number(position.position as f64));
+            table.push(cells);
+            results.clear();
+        }
+
+        let bytes = match format {
+            BroadsheetFormat::Csv => to_csv(&headers, &table)?,
+            BroadsheetFormat::Xlsx => to_xlsx(&headers, &table)?,
+        };
+        let mut stem = vec![stats.grade_level.as_str()];
+        stem.extend(stats.section.as_deref());
+        stem.extend([stats.term.as_str(), stats.academic_year.as_str()]);
+        Ok(BroadsheetFile {
+            filename: format!("broadsheet_{}.{}", file_stem(&stem), format.extension()),
+            content_type: format.content_type(),
+            bytes,
+        })
+    }
+}
+
+fn to_csv(headers: &[String], rows: &[Vec<Cell>]) -> Result<Vec<u8>, AppError> {
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    wtr.write_record(headers.iter().map(|h| csv_safe(h)))
+        .map_err(|e| AppError::Internal(format!("csv header: {e}")))?;
+    for row in rows {
+        // Names and subjects are user-controlled; see `csv_safe`.
+        let record = row.iter().map(|cell| match cell {
+            Cell::Text(s) => csv_safe(s),
+            Cell::Number(n) => n.to_string(),
+            Cell::Empty => String::new(),
+        });
+        wtr.write_record(record)
+            .map_err(|e| AppError::Internal(format!("csv row: {e}")))?;
+    }
+    wtr.flush()
+        .map_err(|e| AppError::Internal(format!("csv flush: {e}")))?;
+    wtr.into_inner()
+        .map_err(|e| AppError::Internal(format!("csv finalize: {e}")))
+}
+
+const XLSX_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const XLSX_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const XLSX_WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Broadsheet" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+
+const XLSX_WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Style 1 is the bold header.
+const XLSX_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/></cellXfs></styleSheet>"#;
+
+/// A single-sheet workbook with a bold, frozen header row. Text is written
+/// as inline strings, which spreadsheets never evaluate as formulas.
+fn to_xlsx(headers: &[String], rows: &[Vec<Cell>]) -> Result<Vec<u8>, AppError> {
+    let mut sheet = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><sheetData>"#,
+    );
+    let header: Vec<Cell> = headers.iter().map(|h| Cell::Text(h.clone())).collect();
+    for (r, row) in std::iter::once(&header).chain(rows).enumerate() {
+        let style = if r == 0 { r#" s="1""# } else { "" };
+        sheet.push_str(&format!(r#"<row r="{}">"#, r + 1));
+        for (c, cell) in row.iter().enumerate() {
+            let at = format!("{}{}", column_name(c), r + 1);
+            match cell {
+                Cell::Text(s) => sheet.push_str(&format!(
+                    r#"<c r="{at}" t="inlineStr"{style}><is><t xml:space="preserve">{}</t></is></c>"#,
+                    xml_escape(s)
+                )),
+                Cell::Number(n) => sheet.push_str(&format!(r#"<c r="{at}"{style}><v>{n}</v></c>"#)),
+                Cell::Empty => {}
+            }
+        }
+        sheet.push_str("</row>");
+    }
+    sheet.push_str("</sheetData></worksheet>");
+
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for (name, content) in [
+        ("[Content_Types].xml", XLSX_CONTENT_TYPES),
+        ("_rels/.rels", XLSX_RELS),
+        ("xl/workbook.xml", XLSX_WORKBOOK),
+        ("xl/_rels/workbook.xml.rels", XLSX_WORKBOOK_RELS),
+        ("xl/styles.xml", XLSX_STYLES),
+        ("xl/worksheets/sheet1.xml", sheet.as_str()),
+    ] {
+        zip.start_file(name, options)
+            .and_then(|_| zip.write_all(content.as_bytes()).map_err(Into::into))
+            .map_err(|e| AppError::Internal(format!("broadsheet xlsx: {e}")))?;
+    }
+    Ok(zip
+        .finish()
+        .map_err(|e| AppError::Internal(format!("broadsheet xlsx: {e}")))?
+        .into_inner())
+}
+
+/// Spreadsheet column letters for a 0-based index: 0 → A, 26 → AA.
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Escape XML markup and drop control characters XML can't carry.
+fn xml_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for ch in s.chars() {
+        match ch {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(ch),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn column_names() {
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(51), "AZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+
+    #[test]
+    fn xml_escapes_markup_and_control_characters() {
+        assert_eq!(xml_escape("A&B <c> \"d\"\u{1}"), "A&amp;B &lt;c&gt; &quot;d&quot;");
+    }
+
+    #[test]
+    fn csv_guards_text_but_not_numbers() {
+        let bytes = to_csv(
+            &["Name".into(), "Total".into()],
+            &[vec![Cell::Text("=1+1".into()), Cell::Number(72.5)]],
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), "Name,Total\n'=1+1,72.5\n");
+    }
+}
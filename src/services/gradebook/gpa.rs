@@ -0,0 +1,166 @@
+use std::collections::{HashMap, HashSet};
+
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{GradebookScoreRow, SubjectResult};
+
+use super::publication::SheetStatus;
+use super::results::ResultEngine;
+use super::scale::round2;
+
+/// Mean `gpa_points` over the published subjects whose grade carries points;
+/// None when none do. Marks still in the workflow don't count yet.
+pub(crate) fn mean_gpa<'a>(subjects: impl IntoIterator<Item = &'a SubjectResult>) -> Option<f64> {
+    let (sum, n) = subjects
+        .into_iter()
+        .filter(|s| s.sheet_status == SheetStatus::Published.as_str())
+        .filter_map(|s| s.gpa_points)
+        .fold((0.0, 0), |(sum, n), p| (sum + p, n + 1));
+    (n > 0).then(|| round2(sum / n as f64))
+}
+
+#[derive(sqlx::FromRow)]
+struct GpaScoreRow {
+    #[sqlx(flatten)]
+    score: GradebookScoreRow,
+    max_scores: serde_json::Value,
+}
+
+#[derive(Default)]
+struct GpaTotals {
+    points: f64,
+    subjects: i32,
+    terms: HashSet<(String, String)>,
+}
+
+/// Rebuild the cached cumulative GPAs for the given students, or for the whole
+/// school when `student_ids` is None, from published sheets only. Runs inside
+/// the caller's transaction.
+///
+/// Leaves no rows when `gpa_enabled` is off or the grading section isn't
+/// usable yet: a half-configured scale means "no GPA", not a failed save.
+pub(crate) async fn refresh_gpas(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    student_ids: Option<&[Uuid]>,
+) -> Result<(), AppError> {
+    sqlx::query(
+        "DELETE FROM gpa_summaries WHERE org_id = $1 AND ($2::uuid[] IS NULL OR student_id = ANY($2))",
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .execute(&mut *conn)
+    .await?;
+
+    let engine = match ResultEngine::load(&mut *conn, org_id).await {
+        Ok(engine) => engine,
+        Err(AppError::BadRequest(_)) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if !engine.config.gpa_enabled {
+        return Ok(());
+    }
+
+    let rows: Vec<GpaScoreRow> = sqlx::query_as(
+        r#"
+        SELECT sc.*, sh.max_scores
+        FROM gradebook_scores sc
+        JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+        WHERE sc.org_id = $1 AND ($2::uuid[] IS NULL OR sc.student_id = ANY($2))
+          AND sh.status = 'published'
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let mut per_student: HashMap<Uuid, GpaTotals> = HashMap::new();
+    for row in rows {
+        let Some(points) = engine
+            .grade(&row.score, &row.max_scores)
+            .and_then(|g| g.gpa_points)
+        else {
+            continue;
+        };
+        let totals = per_student.entry(row.score.student_id).or_default();
+        totals.points += points;
+        totals.subjects += 1;
+        totals.terms.insert((row.score.academic_year, row.score.term));
+    }
+    if per_student.is_empty() {
+        return Ok(());
+    }
+
+    let len = per_student.len();
+    let (mut ids, mut gpas, mut terms, mut subjects) = (
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+        Vec::with_capacity(len),
+    );
+    for (id, t) in per_student {
+        ids.push(id);
+        gpas.push(round2(t.points / f64::from(t.subjects)));
+        terms.push(t.terms.len() as i32);
+        subjects.push(t.subjects);
+    }
+    sqlx::query(
+        r#"
+        INSERT INTO gpa_summaries (student_id, org_id, cumulative_gpa, terms, subjects)
+        SELECT id, $1, gpa, terms, subjects
+        FROM UNNEST($2::uuid[], $3::float8[], $4::int[], $5::int[]) AS t(id, gpa, terms, subjects)
+        "#,
+    )
+    .bind(org_id)
+    .bind(&ids)
+    .bind(&gpas)
+    .bind(&terms)
+    .bind(&subjects)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Cached cumulative GPA per student; students without one are absent.
+pub(crate) async fn gpas_for_students(
+    pool: &PgPool,
+    org_id: Uuid,
+    student_ids: &[Uuid],
+) -> Result<HashMap<Uuid, f64>, AppError> {
+    if student_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows: Vec<(Uuid, f64)> = sqlx::query_as(
+        r#"
+        SELECT student_id, cumulative_gpa FROM gpa_summaries
+        WHERE org_id = $1 AND student_id = ANY($2)
+        "#,
+    )
+    .bind(org_id)
+    .bind(student_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
+/// Mean cumulative GPA across the school's active students that have one.
+pub(crate) async fn school_average_gpa(
+    pool: &PgPool,
+    org_id: Uuid,
+) -> Result<Option<f64>, AppError> {
+    let mean: Option<f64> = sqlx::query_scalar(
+        r#"
+        SELECT AVG(g.cumulative_gpa)::float8
+        FROM gpa_summaries g
+        JOIN students s ON s.id = g.student_id
+        WHERE g.org_id = $1 AND s.status = 'active'
+        "#,
+    )
+    .bind(org_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(mean.map(round2))
+}
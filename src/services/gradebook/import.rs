@@ -0,0 +1,470 @@
+use std::collections::{BTreeMap, HashMap};
+
+use sqlx::Acquire;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    GradebookSheetRow, ImportedSheet, ScoreEntryInput, ScoreImportDefaults, ScoreImportResponse,
+};
+use crate::models::students::ImportRowError;
+
+use super::sheets::{sheet_components, unknown_component, write_sheet, SheetKey};
+use super::{
+    canonical_name, load_grading_config, GradebookService, GradingConfig, SetupList, COMPONENTS,
+};
+
+const MAX_IMPORT_ROWS: usize = 5000;
+
+/// Mapping targets besides the component keys.
+const ROW_FIELD_KEYS: &[&str] = &["admission_number", "academic_year", "term", "subject"];
+
+/// Per-row candidate after applying the mapping and defaults. Names are
+/// matched against school setup afterwards.
+struct Candidate {
+    row_num: usize,
+    admission_number: String,
+    academic_year: String,
+    term: String,
+    subject: String,
+    scores: Vec<(&'static str, f64)>,
+}
+
+/// A row matched to a student, ready to be written.
+struct Resolved {
+    row_num: usize,
+    student_id: Uuid,
+    scores: Vec<(&'static str, f64)>,
+}
+
+/// `(academic_year, term, subject, grade_level, section)`.
+type GroupKey = (String, String, String, String, Option<String>);
+
+#[derive(sqlx::FromRow)]
+struct StudentMatch {
+    id: Uuid,
+    admission_number: String,
+    grade_level: String,
+    section: Option<String>,
+}
+
+impl GradebookService {
+    /// Import scores from a CSV byte buffer + column mapping. `mapping` maps
+    /// CSV header names to `admission_number`, `academic_year`, `term`,
+    /// `subject` or a component key (e.g. "CA 1" → "ca"). Term, subject and
+    /// year can instead come from `defaults` for the whole file.
+    ///
+    /// Each student's row goes on their class's sheet for the subject and
+    /// term: the one for their section if it exists, else the whole-grade
+    /// sheet, else a new sheet for their section. Blank score cells leave the
+    /// current score alone.
+    pub async fn bulk_import(
+        &self,
+        org_id: Uuid,
+        csv_bytes: &[u8],
+        mapping: HashMap<String, String>,
+        defaults: ScoreImportDefaults,
+        skip_invalid: bool,
+        entered_by: Option<Uuid>,
+    ) -> Result<(ScoreImportResponse, bool), AppError> {
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        config.require_components()?;
+        validate_mapping(&mapping, &config, &defaults)?;
+
+        let mut rdr = csv::ReaderBuilder::new()
+            .has_headers(true)
+            .trim(csv::Trim::All)
+            .from_reader(csv_bytes);
+        let headers = rdr
+            .headers()
+            .map_err(|e| AppError::BadRequest(format!("Invalid CSV header: {e}")))?
+            .clone();
+        let header_to_field: Vec<Option<&str>> = headers
+            .iter()
+            .map(|h| mapping.get(h).map(|t| t.trim()).filter(|t| !t.is_empty()))
+            .collect();
+
+        let mut candidates: Vec<Candidate> = Vec::new();
+        let mut errors: Vec<ImportRowError> = Vec::new();
+        for (i, result) in rdr.records().enumerate() {
+            let row_num = i + 2; // 1 = header
+            if i >= MAX_IMPORT_ROWS {
+                errors.push(ImportRowError {
+                    row: row_num,
+                    field: None,
+                    message: format!("Row limit {MAX_IMPORT_ROWS} exceeded; remaining rows skipped"),
+                });
+                break;
+            }
+            let record = match result {
+                Ok(r) => r,
+                Err(e) => {
+                    errors.push(ImportRowError {
+                        row: row_num,
+                        field: None,
+                        message: format!("Failed to read row: {e}"),
+                    });
+                    continue;
+                }
+            };
+            match build_candidate(row_num, &record, &header_to_field, &defaults, &config) {
+                Ok(c) => candidates.push(c),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        // Match students, terms and subjects.
+        let numbers: Vec<&str> = candidates.iter().map(|c| c.admission_number.as_str()).collect();
+        let students: Vec<StudentMatch> = sqlx::query_as(
+            r#"
+            SELECT id, admission_number, grade_level, section FROM students
+            WHERE org_id = $1 AND admission_number = ANY($2)
+            "#,
+        )
+        .bind(org_id)
+        .bind(&numbers)
+        .fetch_all(&self.pool)
+        .await?;
+        let students: HashMap<&str, &StudentMatch> =
+            students.iter().map(|s| (s.admission_number.as_str(), s)).collect();
+        let mut names: HashMap<(bool, String), Result<String, String>> = HashMap::new();
+        let mut seen: HashMap<(Uuid, String, String, String), usize> = HashMap::new();
+        let mut groups: BTreeMap<GroupKey, Vec<Resolved>> = BTreeMap::new();
+        for c in candidates {
+            let Some(student) = students.get(c.admission_number.as_str()) else {
+                errors.push(row_err(
+                    c.row_num,
+                    Some("admission_number"),
+                    &format!("admission_number '{}' not found", c.admission_number),
+                ));
+                continue;
+            };
+            let term = match self.match_name(org_id, &mut names, SetupList::Terms, &c.term).await? {
+                Ok(t) => t,
+                Err(m) => {
+                    errors.push(row_err(c.row_num, Some("term"), &m));
+                    continue;
+                }
+            };
+            let subject =
+                match self.match_name(org_id, &mut names, SetupList::Subjects, &c.subject).await? {
+                    Ok(s) => s,
+                    Err(m) => {
+                        errors.push(row_err(c.row_num, Some("subject"), &m));
+                        continue;
+                    }
+                };
+            let once = (student.id, c.academic_year.clone(), term.clone(), subject.clone());
+            if let Some(first) = seen.insert(once, c.row_num) {
+                errors.push(row_err(
+                    c.row_num,
+                    None,
+                    &format!("duplicate of row {first} for the same student, subject and term"),
+                ));
+                continue;
+            }
+            groups
+                .entry((
+                    c.academic_year,
+                    term,
+                    subject,
+                    student.grade_level.clone(),
+                    student.section.clone(),
+                ))
+                .or_default()
+                .push(Resolved {
+                    row_num: c.row_num,
+                    student_id: student.id,
+                    scores: c.scores,
+                });
+        }
+
+        // Settle which sheet each group lands on, then check locks and max
+        // scores against it.
+        let mut targets: BTreeMap<GroupKey, (Option<GradebookSheetRow>, Vec<Resolved>)> =
+            BTreeMap::new();
+        for ((year, term, subject, grade, section), rows) in groups {
+            let sheet: Option<GradebookSheetRow> = sqlx::query_as(
+                r#"
+                SELECT * FROM gradebook_sheets
+                WHERE org_id = $1 AND academic_year = $2 AND term = $3 AND subject = $4
+                  AND grade_level = $5 AND (section IS NULL OR section = $6)
+                ORDER BY section NULLS LAST
+                LIMIT 1
+                "#,
+            )
+            .bind(org_id)
+            .bind(&year)
+            .bind(&term)
+            .bind(&subject)
+            .bind(&grade)
+            .bind(&section)
+            .fetch_optional(&self.pool)
+            .await?;
+            let section = match &sheet {
+                Some(s) => s.section.clone(),
+                None => section,
+            };
+            let target = targets
+                .entry((year, term, subject, grade, section))
+                .or_insert_with(|| (sheet, Vec::new()));
+            target.1.extend(rows);
+        }
+        for (sheet, rows) in targets.values_mut() {
+            if let Some(s) = sheet.as_ref().filter(|s| s.status != "draft") {
+                let message = format!(
+                    "{} {} scores for {} are {} and locked",
+                    s.subject, s.term, s.grade_level, s.status
+                );
+                errors.extend(rows.drain(..).map(|r| row_err(r.row_num, None, &message)));
+                continue;
+            }
+            let max_scores = sheet
+                .as_ref()
+                .map(|s| s.max_scores.clone())
+                .unwrap_or(serde_json::Value::Null);
+            let components = sheet_components(&config, &max_scores);
+            rows.retain(|r| {
+                let over = r.scores.iter().find_map(|(key, score)| {
+                    let max = components.iter().find(|c| c.key == *key)?.max_score;
+                    (*score > max).then_some((*key, *score, max))
+                });
+                match over {
+                    Some((key, score, max)) => {
+                        errors.push(row_err(
+                            r.row_num,
+                            Some(key),
+                            &format!("{key} score {score} must be between 0 and {max}"),
+                        ));
+                        false
+                    }
+                    None => true,
+                }
+            });
+        }
+        errors.sort_by_key(|e| e.row);
+
+        if !errors.is_empty() && !skip_invalid {
+            let response = ScoreImportResponse {
+                imported: 0,
+                skipped: errors.len(),
+                errors,
+                sheets: vec![],
+            };
+            return Ok((response, false));
+        }
+
+        // Write every sheet in one transaction. Each sheet gets a SAVEPOINT so
+        // a sheet that fails (e.g. a student's row is locked on another
+        // class's sheet) can be skipped without losing the others.
+        let mut tx = self.pool.begin().await?;
+        let mut sheets: Vec<ImportedSheet> = Vec::new();
+        let mut imported = 0;
+        for ((academic_year, term, subject, grade_level, section), (_, rows)) in targets {
+            if rows.is_empty() {
+                continue;
+            }
+            let key = SheetKey {
+                academic_year,
+                term,
+                subject,
+                grade_level,
+                section,
+            };
+            let entries: Vec<ScoreEntryInput> = rows
+                .iter()
+                .map(|r| ScoreEntryInput {
+                    student_id: r.student_id,
+                    scores: r.scores.iter().map(|(k, v)| (k.to_string(), Some(*v))).collect(),
+                })
+                .collect();
+
+            let mut sp = tx.begin().await?;
+            match write_sheet(&mut sp, org_id, &config, &key, None, &entries, entered_by).await {
+                Ok(sheet) => {
+                    sp.commit().await?;
+                    imported += rows.len();
+                    sheets.push(ImportedSheet {
+                        sheet_id: sheet.id,
+                        academic_year: key.academic_year,
+                        term: key.term,
+                        subject: key.subject,
+                        grade_level: key.grade_level,
+                        section: key.section,
+                        rows: rows.len(),
+                    });
+                }
+                Err(AppError::BadRequest(m) | AppError::Conflict(m) | AppError::NotFound(m)) => {
+                    sp.rollback().await?;
+                    let failed: Vec<ImportRowError> =
+                        rows.iter().map(|r| row_err(r.row_num, None, &m)).collect();
+                    if !skip_invalid {
+                        tx.rollback().await?;
+                        let response = ScoreImportResponse {
+                            imported: 0,
+                            skipped: failed.len(),
+                            errors: failed,
+                            sheets: vec![],
+                        };
+                        return Ok((response, false));
+                    }
+                    errors.extend(failed);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        tx.commit().await?;
+
+        errors.sort_by_key(|e| e.row);
+        let response = ScoreImportResponse {
+            imported,
+            skipped: errors.len(),
+            errors,
+            sheets,
+        };
+        Ok((response, true))
+    }
+
+    /// Configured spelling of a term or subject, cached per import. The inner
+    /// error is the row-level message.
+    async fn match_name(
+        &self,
+        org_id: Uuid,
+        cache: &mut HashMap<(bool, String), Result<String, String>>,
+        list: SetupList,
+        name: &str,
+    ) -> Result<Result<String, String>, AppError> {
+        let key = (matches!(list, SetupList::Terms), name.to_lowercase());
+        if let Some(found) = cache.get(&key) {
+            return Ok(found.clone());
+        }
+        let found = match canonical_name(&self.pool, org_id, list, name).await {
+            Ok(n) => Ok(n),
+            Err(AppError::BadRequest(m)) => Err(m),
+            Err(e) => return Err(e),
+        };
+        cache.insert(key, found.clone());
+        Ok(found)
+    }
+}
+
+fn build_candidate(
+    row_num: usize,
+    record: &csv::StringRecord,
+    header_to_field: &[Option<&str>],
+    defaults: &ScoreImportDefaults,
+    config: &GradingConfig,
+) -> Result<Candidate, ImportRowError> {
+    let mut fields: HashMap<&str, &str> = HashMap::new();
+    for (i, field) in header_to_field.iter().enumerate() {
+        let Some(field) = field else { continue };
+        let value = record.get(i).unwrap_or("").trim();
+        if !value.is_empty() {
+            fields.insert(field, value);
+        }
+    }
+    let or_default = |key: &str, default: &Option<String>| {
+        fields
+            .get(key)
+            .map(|v| v.to_string())
+            .or_else(|| default.as_deref().map(str::trim).filter(|d| !d.is_empty()).map(String::from))
+    };
+
+    let admission_number = fields
+        .get("admission_number")
+        .map(|v| v.to_string())
+        .ok_or_else(|| row_err(row_num, Some("admission_number"), "missing"))?;
+    let academic_year = config
+        .academic_year(or_default("academic_year", &defaults.academic_year).as_deref())
+        .map_err(|e| match e {
+            AppError::BadRequest(m) => row_err(row_num, Some("academic_year"), &m),
+            other => row_err(row_num, Some("academic_year"), &other.to_string()),
+        })?;
+    let term = or_default("term", &defaults.term)
+        .ok_or_else(|| row_err(row_num, Some("term"), "missing"))?;
+    let subject = or_default("subject", &defaults.subject)
+        .ok_or_else(|| row_err(row_num, Some("subject"), "missing"))?;
+
+    let mut scores = Vec::new();
+    for key in COMPONENTS {
+        let Some(raw) = fields.get(key) else { continue };
+        let score: f64 = raw
+            .parse()
+            .map_err(|_| row_err(row_num, Some(key), "must be a number"))?;
+        if !score.is_finite() || score < 0.0 {
+            return Err(row_err(row_num, Some(key), "must be 0 or more"));
+        }
+        scores.push((key, score));
+    }
+    if scores.is_empty() {
+        return Err(row_err(row_num, None, "no scores in this row"));
+    }
+
+    Ok(Candidate {
+        row_num,
+        admission_number,
+        academic_year,
+        term,
+        subject,
+        scores,
+    })
+}
+
+/// Validate that every mapping target is a row field or an enabled
+/// component, that no two columns map to the same target, and that
+/// admission number, term and subject each come from a column or a default.
+fn validate_mapping(
+    mapping: &HashMap<String, String>,
+    config: &GradingConfig,
+    defaults: &ScoreImportDefaults,
+) -> Result<(), AppError> {
+    let mut seen_targets: HashMap<&str, &str> = HashMap::new();
+    for (header, target) in mapping {
+        let t = target.trim();
+        if t.is_empty() {
+            continue;
+        }
+        if COMPONENTS.contains(&t) {
+            if config.weight(t).is_none() {
+                return Err(unknown_component(t, config));
+            }
+        } else if !ROW_FIELD_KEYS.contains(&t) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid mapping for header '{header}': '{t}' is not a recognized field"
+            )));
+        }
+        if let Some(prev) = seen_targets.insert(t, header.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Duplicate mapping target '{t}': both '{prev}' and '{header}' map to it"
+            )));
+        }
+    }
+
+    if !seen_targets.contains_key("admission_number") {
+        return Err(AppError::BadRequest(
+            "mapping must map a column to 'admission_number'".into(),
+        ));
+    }
+    if !COMPONENTS.iter().any(|c| seen_targets.contains_key(c)) {
+        return Err(AppError::BadRequest(
+            "mapping must map at least one column to a score component".into(),
+        ));
+    }
+    let given = |d: &Option<String>| d.as_deref().is_some_and(|d| !d.trim().is_empty());
+    for (field, default) in [("term", &defaults.term), ("subject", &defaults.subject)] {
+        if !seen_targets.contains_key(field) && !given(default) {
+            return Err(AppError::BadRequest(format!(
+                "{field} is required: map a column to '{field}' or send a '{field}' field"
+            )));
+        }
+    }
+    Ok(())
+}
+
+fn row_err(row: usize, field: Option<&str>, message: &str) -> ImportRowError {
+    ImportRowError {
+        row,
+        field: field.map(String::from),
+        message: message.into(),
+    }
+}
@@ -0,0 +1,184 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+pub(crate) mod broadsheet;
+pub(crate) mod gpa;
+pub(super) mod import;
+pub(crate) mod publication;
+pub(super) mod results;
+pub(crate) mod scale;
+pub(super) mod sheets;
+pub(crate) mod stats;
+
+/// Assessment components in display order, matching the `*_weight` columns
+/// of `school_configs` and the score columns of `gradebook_scores`.
+pub(crate) const COMPONENTS: [&str; 5] = ["ca", "exam", "assignment", "test", "project"];
+
+/// Column heading for a component: `CA`, `Exam`, …
+pub(crate) fn component_label(key: &str) -> String {
+    match key {
+        "ca" => "CA".into(),
+        other => {
+            let mut chars = other.chars();
+            chars
+                .next()
+                .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub struct GradebookService {
+    pub(super) pool: PgPool,
+}
+
+impl GradebookService {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+}
+
+/// Grading slice of `school_configs`, parsed once per request.
+pub(crate) struct GradingConfig {
+    /// Enabled components (weight > 0) with their weights, in [`COMPONENTS`] order.
+    pub weights: Vec<(&'static str, f64)>,
+    pub current_academic_year: Option<String>,
+    /// Raw `passmark`; see [`GradingConfig::passmark`].
+    pub passmark: Option<String>,
+    pub gpa_enabled: bool,
+}
+
+impl GradingConfig {
+    pub fn weight(&self, component: &str) -> Option<f64> {
+        self.weights
+            .iter()
+            .find(|(k, _)| *k == component)
+            .map(|(_, w)| *w)
+    }
+
+    /// Error unless at least one component has a weight.
+    pub fn require_components(&self) -> Result<(), AppError> {
+        if self.weights.is_empty() {
+            return Err(AppError::BadRequest(
+                "No assessment weights are configured; set ca_weight/exam_weight in the grading section first".into(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Pass mark out of 100. Required before results can be computed.
+    pub fn passmark(&self) -> Result<f64, AppError> {
+        match parse_number(self.passmark.as_deref()) {
+            Some(p) if (0.0..=100.0).contains(&p) => Ok(p),
+            Some(_) => Err(AppError::BadRequest("passmark must be between 0 and 100".into())),
+            None => Err(AppError::BadRequest(
+                "No passmark is configured; set it in the grading section first".into(),
+            )),
+        }
+    }
+
+    /// The request's academic year, or the school's current one.
+    pub fn academic_year(&self, requested: Option<&str>) -> Result<String, AppError> {
+        requested
+            .map(str::trim)
+            .filter(|y| !y.is_empty())
+            .or(self.current_academic_year.as_deref().map(str::trim).filter(|y| !y.is_empty()))
+            .map(String::from)
+            .ok_or_else(|| {
+                AppError::BadRequest(
+                    "academic_year is required when current_academic_year isn't set in school setup".into(),
+                )
+            })
+    }
+}
+
+#[derive(Default, sqlx::FromRow)]
+struct GradingRow {
+    ca_weight: Option<String>,
+    exam_weight: Option<String>,
+    assignment_weight: Option<String>,
+    test_weight: Option<String>,
+    project_weight: Option<String>,
+    current_academic_year: Option<String>,
+    passmark: Option<String>,
+    gpa_enabled: Option<bool>,
+}
+
+pub(crate) async fn load_grading_config(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+) -> Result<GradingConfig, AppError> {
+    let row: GradingRow = sqlx::query_as(
+        r#"
+        SELECT ca_weight, exam_weight, assignment_weight, test_weight, project_weight,
+               current_academic_year, passmark, gpa_enabled
+        FROM school_configs WHERE org_id = $1
+        "#,
+    )
+    .bind(org_id)
+    .fetch_optional(conn)
+    .await?
+    .unwrap_or_default();
+
+    let raw = [
+        row.ca_weight,
+        row.exam_weight,
+        row.assignment_weight,
+        row.test_weight,
+        row.project_weight,
+    ];
+    let weights = COMPONENTS
+        .iter()
+        .zip(raw)
+        .filter_map(|(k, w)| Some((*k, parse_number(w.as_deref())?)))
+        .filter(|(_, w)| *w > 0.0)
+        .collect();
+
+    Ok(GradingConfig {
+        weights,
+        current_academic_year: row.current_academic_year,
+        passmark: row.passmark,
+        gpa_enabled: row.gpa_enabled.unwrap_or(false),
+    })
+}
+
+/// Grading numbers are stored as TEXT (`"40"`, `"3.5"`); blank or junk means unset.
+pub(crate) fn parse_number(s: Option<&str>) -> Option<f64> {
+    s.and_then(|s| s.trim().trim_end_matches('%').parse::<f64>().ok())
+        .filter(|n| n.is_finite())
+}
+
+/// Match a name against a setup table's `name` column case-insensitively and
+/// return the configured spelling.
+pub(crate) async fn canonical_name(
+    pool: &PgPool,
+    org_id: Uuid,
+    table: SetupList,
+    name: &str,
+) -> Result<String, AppError> {
+    let (sql, label) = match table {
+        SetupList::Terms => (
+            "SELECT name FROM school_terms WHERE org_id = $1 AND LOWER(name) = LOWER($2) ORDER BY position LIMIT 1",
+            "term",
+        ),
+        SetupList::Subjects => (
+            "SELECT name FROM school_subjects WHERE org_id = $1 AND LOWER(name) = LOWER($2) ORDER BY position LIMIT 1",
+            "subject",
+        ),
+    };
+    let wanted = name.trim();
+    sqlx::query_scalar(sql)
+        .bind(org_id)
+        .bind(wanted)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| AppError::BadRequest(format!("{label} '{wanted}' is not configured for this school")))
+}
+
+#[derive(Clone, Copy)]
+pub(crate) enum SetupList {
+    Terms,
+    Subjects,
+}
@@ -0,0 +1,500 @@
+//! Result publication workflow. Score sheets move draft → submitted →
+//! approved → published; every move is logged in `result_status_history`,
+//! and publishing freezes each score's graded result.
+
+use std::collections::HashSet;
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    ClassResultStatusResponse, GradebookScoreRow, GradebookSheetRow, ResultSheetStatus,
+    ResultStatusChange, ResultStatusQuery, SetResultStatusRequest,
+};
+use crate::services::notifications::{self, GuardianNotice};
+
+use super::gpa::refresh_gpas;
+use super::results::{ClassKey, ResultEngine};
+use super::{canonical_name, load_grading_config, GradebookService, SetupList};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) enum SheetStatus {
+    Draft,
+    Submitted,
+    Approved,
+    Published,
+}
+
+impl SheetStatus {
+    pub const ALL: [SheetStatus; 4] = [
+        SheetStatus::Draft,
+        SheetStatus::Submitted,
+        SheetStatus::Approved,
+        SheetStatus::Published,
+    ];
+
+    pub fn parse(s: &str) -> Result<Self, AppError> {
+        Self::ALL
+            .into_iter()
+            .find(|st| st.as_str() == s.trim())
+            .ok_or_else(|| {
+                let names: Vec<&str> = Self::ALL.iter().map(|st| st.as_str()).collect();
+                AppError::BadRequest(format!("Invalid status '{s}'; must be one of {names:?}"))
+            })
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SheetStatus::Draft => "draft",
+            SheetStatus::Submitted => "submitted",
+            SheetStatus::Approved => "approved",
+            SheetStatus::Published => "published",
+        }
+    }
+
+    /// Whether the class workflow allows moving from `self` to `to`: one
+    /// step forward, or back to draft from anywhere.
+    fn can_move_to(self, to: SheetStatus) -> bool {
+        match to {
+            SheetStatus::Draft => self != SheetStatus::Draft,
+            SheetStatus::Submitted => self == SheetStatus::Draft,
+            SheetStatus::Approved => self == SheetStatus::Submitted,
+            SheetStatus::Published => self == SheetStatus::Approved,
+        }
+    }
+
+    /// `can_move_to` as a `BadRequest` naming the sheet's subject.
+    pub(super) fn ensure_can_move_to(self, subject: &str, to: SheetStatus) -> Result<(), AppError> {
+        if self.can_move_to(to) {
+            return Ok(());
+        }
+        Err(AppError::BadRequest(format!(
+            "{subject} is {}; only {} sheets can be {}",
+            self.as_str(),
+            SheetStatus::ALL
+                .into_iter()
+                .find(|s| s.can_move_to(to))
+                .map(|s| s.as_str())
+                .unwrap_or_default(),
+            to.as_str()
+        )))
+    }
+}
+
+impl GradebookService {
+    /// Where each of a class's score sheets is in the workflow, with the
+    /// class's status history.
+    pub async fn result_status(
+        &self,
+        org_id: Uuid,
+        q: ResultStatusQuery,
+    ) -> Result<ClassResultStatusResponse, AppError> {
+        let class = self
+            .resolve_status_class(
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        let mut conn = self.pool.acquire().await?;
+        let sheets = class_sheets(&mut conn, org_id, &class, None, false).await?;
+        class_status_response(&mut conn, org_id, class, sheets, None).await
+    }
+
+    /// Move every sheet in a class and term (or one subject's sheet) to
+    /// `req.status`. Sheets already there are left alone; any other sheet
+    /// that isn't one step behind fails the whole request.
+    pub async fn set_result_status(
+        &self,
+        org_id: Uuid,
+        req: SetResultStatusRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<ClassResultStatusResponse, AppError> {
+        let to = SheetStatus::parse(&req.status)?;
+        let class = self
+            .resolve_status_class(
+                org_id,
+                req.academic_year.as_deref(),
+                &req.term,
+                &req.grade_level,
+                req.section.as_deref(),
+            )
+            .await?;
+        let subject = match req.subject.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(s) => Some(canonical_name(&self.pool, org_id, SetupList::Subjects, s).await?),
+            None => None,
+        };
+
+        let mut tx = self.pool.begin().await?;
+        let sheets = class_sheets(&mut tx, org_id, &class, subject.as_deref(), true).await?;
+        if sheets.is_empty() {
+            return Err(AppError::NotFound(format!(
+                "No {}score sheets for {}{} in {} {}",
+                subject.as_deref().map(|s| format!("{s} ")).unwrap_or_default(),
+                class.grade_level,
+                class.section.as_deref().map(|s| format!(" {s}")).unwrap_or_default(),
+                class.term,
+                class.academic_year
+            )));
+        }
+        let mut moving = Vec::with_capacity(sheets.len());
+        for sheet in &sheets {
+            let from = SheetStatus::parse(&sheet.status)?;
+            if from == to {
+                continue;
+            }
+            from.ensure_can_move_to(&sheet.subject, to)?;
+            moving.push(sheet.clone());
+        }
+        let queued = apply_status(
+            &mut tx,
+            org_id,
+            &moving,
+            to,
+            req.reason.as_deref(),
+            changed_by,
+        )
+        .await?;
+        let sheets = class_sheets(&mut tx, org_id, &class, None, false).await?;
+        let response = class_status_response(&mut tx, org_id, class, sheets, queued).await?;
+        tx.commit().await?;
+        Ok(response)
+    }
+
+    /// Like [`GradebookService::resolve_class`], without requiring weights
+    /// or a grading scale.
+    async fn resolve_status_class(
+        &self,
+        org_id: Uuid,
+        academic_year: Option<&str>,
+        term: &str,
+        grade_level: &str,
+        section: Option<&str>,
+    ) -> Result<ClassKey, AppError> {
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        self.resolve_class(&config, org_id, academic_year, term, grade_level, section)
+            .await
+    }
+}
+
+/// Move `sheets` to `to`, logging each move. Publishing freezes graded
+/// results and, with `exam_result_notify` on, queues one notice per student
+/// for their guardian naming the subjects newly published; leaving published
+/// clears the snapshot. Returns how
+/// many notices were queued, or None when none were due.
+pub(super) async fn apply_status(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    sheets: &[GradebookSheetRow],
+    to: SheetStatus,
+    reason: Option<&str>,
+    changed_by: Option<Uuid>,
+) -> Result<Option<u64>, AppError> {
+    if sheets.is_empty() {
+        return Ok(None);
+    }
+    let reason = reason.map(str::trim).filter(|r| !r.is_empty());
+    let ids: Vec<Uuid> = sheets.iter().map(|s| s.id).collect();
+    // Fail before writing anything when results can't be graded.
+    let engine = match to {
+        SheetStatus::Published => Some(ResultEngine::load(&mut *conn, org_id).await?),
+        _ => None,
+    };
+
+    sqlx::query(
+        r#"
+        UPDATE gradebook_sheets SET
+            status = $3,
+            submitted_at = CASE WHEN $3 = 'draft' THEN NULL
+                WHEN $3 = 'submitted' THEN NOW() ELSE submitted_at END,
+            submitted_by_user_id = CASE WHEN $3 = 'draft' THEN NULL
+                WHEN $3 = 'submitted' THEN $4 ELSE submitted_by_user_id END,
+            approved_at = CASE WHEN $3 = 'approved' THEN NOW()
+                WHEN $3 = 'published' THEN approved_at END,
+            approved_by_user_id = CASE WHEN $3 = 'approved' THEN $4
+                WHEN $3 = 'published' THEN approved_by_user_id END,
+            published_at = CASE WHEN $3 = 'published' THEN COALESCE(published_at, NOW()) END,
+            published_by_user_id = CASE WHEN $3 = 'published'
+                THEN COALESCE(published_by_user_id, $4) END
+        WHERE id = ANY($1) AND org_id = $2
+        "#,
+    )
+    .bind(&ids)
+    .bind(org_id)
+    .bind(to.as_str())
+    .bind(changed_by)
+    .execute(&mut *conn)
+    .await?;
+
+    for sheet in sheets {
+        sqlx::query(
+            r#"
+            INSERT INTO result_status_history
+                (org_id, sheet_id, academic_year, term, subject, grade_level, section,
+                 from_status, to_status, reason, changed_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            "#,
+        )
+        .bind(org_id)
+        .bind(sheet.id)
+        .bind(&sheet.academic_year)
+        .bind(&sheet.term)
+        .bind(&sheet.subject)
+        .bind(&sheet.grade_level)
+        .bind(&sheet.section)
+        .bind(&sheet.status)
+        .bind(to.as_str())
+        .bind(reason)
+        .bind(changed_by)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    let was_published = sheets.iter().any(|s| s.status == SheetStatus::Published.as_str());
+    let Some(engine) = engine else {
+        if was_published {
+            let students: Vec<Uuid> = sqlx::query_scalar(
+                r#"
+                UPDATE gradebook_scores SET frozen_result = NULL
+                WHERE sheet_id = ANY($1) AND frozen_result IS NOT NULL
+                RETURNING student_id
+                "#,
+            )
+            .bind(&ids)
+            .fetch_all(&mut *conn)
+            .await?;
+            refresh_gpas(&mut *conn, org_id, Some(&students)).await?;
+        }
+        return Ok(None);
+    };
+
+    let scores: Vec<GradebookScoreRow> =
+        sqlx::query_as("SELECT * FROM gradebook_scores WHERE sheet_id = ANY($1)")
+            .bind(&ids)
+            .fetch_all(&mut *conn)
+            .await?;
+    let (mut score_ids, mut frozen) = (Vec::new(), Vec::new());
+    for score in &scores {
+        let Some(sheet) = sheets.iter().find(|s| s.id == score.sheet_id) else {
+            continue;
+        };
+        let Some(graded) = engine.grade_live(score, &sheet.max_scores) else {
+            continue;
+        };
+        score_ids.push(score.id);
+        frozen.push(serde_json::to_value(graded).map_err(|e| AppError::Internal(e.to_string()))?);
+    }
+    sqlx::query(
+        r#"
+        UPDATE gradebook_scores sc SET frozen_result = f.result
+        FROM UNNEST($1::uuid[], $2::jsonb[]) AS f(id, result)
+        WHERE sc.id = f.id
+        "#,
+    )
+    .bind(&score_ids)
+    .bind(&frozen)
+    .execute(&mut *conn)
+    .await?;
+
+    let mut students: Vec<Uuid> = Vec::new();
+    let mut seen = HashSet::new();
+    for score in &scores {
+        if seen.insert(score.student_id) {
+            students.push(score.student_id);
+        }
+    }
+    refresh_gpas(&mut *conn, org_id, Some(&students)).await?;
+
+    let notify: Option<bool> = sqlx::query_scalar(
+        "SELECT exam_result_notify FROM school_configs WHERE org_id = $1",
+    )
+    .bind(org_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .flatten();
+    if notify != Some(true) {
+        return Ok(None);
+    }
+    let (term, academic_year) = (&sheets[0].term, &sheets[0].academic_year);
+    // Guardians hear about each subject once, in sheet order; a publish with
+    // nothing new for a student queues nothing for them.
+    let graded: HashSet<Uuid> = score_ids.into_iter().collect();
+    let (mut notice_students, mut notice_subjects) = (Vec::new(), Vec::new());
+    for sheet in sheets {
+        for score in scores.iter().filter(|s| s.sheet_id == sheet.id && graded.contains(&s.id)) {
+            notice_students.push(score.student_id);
+            notice_subjects.push(score.subject.as_str());
+        }
+    }
+    let fresh: Vec<NoticeRow> = sqlx::query_as(
+        r#"
+        WITH input AS (
+            SELECT * FROM UNNEST($2::uuid[], $3::text[])
+                WITH ORDINALITY AS n(student_id, subject, ord)
+        ),
+        fresh AS (
+            INSERT INTO result_notices (org_id, student_id, academic_year, term, subject)
+            SELECT $1, student_id, $4, $5, subject FROM input
+            ON CONFLICT DO NOTHING
+            RETURNING id, student_id, subject
+        )
+        SELECT (ARRAY_AGG(f.id ORDER BY i.ord))[1] AS id, f.student_id,
+               ARRAY_AGG(f.subject ORDER BY i.ord) AS subjects,
+               s.first_name, s.last_name, o.name AS school
+        FROM fresh f
+        JOIN input i ON i.student_id = f.student_id AND i.subject = f.subject
+        JOIN students s ON s.id = f.student_id
+        JOIN organizations o ON o.id = s.org_id
+        GROUP BY f.student_id, s.first_name, s.last_name, o.name
+        ORDER BY s.last_name, s.first_name, f.student_id
+        "#,
+    )
+    .bind(org_id)
+    .bind(&notice_students)
+    .bind(&notice_subjects)
+    .bind(academic_year)
+    .bind(term)
+    .fetch_all(&mut *conn)
+    .await?;
+    let mut queued = 0;
+    for row in fresh {
+        let (first_name, last_name, school) = (&row.first_name, &row.last_name, &row.school);
+        let subject = format!("{term} {academic_year} results: {first_name} {last_name}");
+        let body = format!(
+            "{first_name} {last_name}'s {term} {academic_year} results for {} have been \
+             published by {school}.",
+            row.subjects.join(", ")
+        );
+        queued += notifications::queue_for_primary_guardian(
+            &mut *conn,
+            org_id,
+            &GuardianNotice {
+                student_id: row.student_id,
+                kind: "exam_results",
+                subject: &subject,
+                body: &body,
+                source_id: Some(row.id),
+            },
+        )
+        .await?;
+    }
+    Ok(Some(queued))
+}
+
+#[derive(sqlx::FromRow)]
+struct NoticeRow {
+    id: Uuid,
+    student_id: Uuid,
+    subjects: Vec<String>,
+    first_name: String,
+    last_name: String,
+    school: String,
+}
+
+/// A class's sheets in subject order. A section includes the grade's
+/// whole-class sheets.
+async fn class_sheets(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    class: &ClassKey,
+    subject: Option<&str>,
+    for_update: bool,
+) -> Result<Vec<GradebookSheetRow>, AppError> {
+    let sql = format!(
+        r#"
+        SELECT sh.* FROM gradebook_sheets sh
+        LEFT JOIN school_subjects sub ON sub.org_id = sh.org_id AND sub.name = sh.subject
+        WHERE sh.org_id = $1 AND sh.academic_year = $2 AND sh.term = $3 AND sh.grade_level = $4
+          AND ($5::text IS NULL OR COALESCE(sh.section, $5) = $5)
+          AND ($6::text IS NULL OR sh.subject = $6)
+        ORDER BY sub.position NULLS LAST, sh.subject, sh.section NULLS FIRST
+        {}
+        "#,
+        if for_update { "FOR UPDATE OF sh" } else { "" }
+    );
+    Ok(sqlx::query_as(&sql)
+        .bind(org_id)
+        .bind(&class.academic_year)
+        .bind(&class.term)
+        .bind(&class.grade_level)
+        .bind(&class.section)
+        .bind(subject)
+        .fetch_all(&mut *conn)
+        .await?)
+}
+
+async fn class_status_response(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    class: ClassKey,
+    sheets: Vec<GradebookSheetRow>,
+    notifications_queued: Option<u64>,
+) -> Result<ClassResultStatusResponse, AppError> {
+    let history: Vec<ResultStatusChange> = sqlx::query_as(
+        r#"
+        SELECT sheet_id, subject, section, from_status, to_status, reason,
+               changed_by_user_id, changed_at
+        FROM result_status_history
+        WHERE org_id = $1 AND academic_year = $2 AND term = $3 AND grade_level = $4
+          AND ($5::text IS NULL OR COALESCE(section, $5) = $5)
+        ORDER BY changed_at DESC, subject
+        "#,
+    )
+    .bind(org_id)
+    .bind(&class.academic_year)
+    .bind(&class.term)
+    .bind(&class.grade_level)
+    .bind(&class.section)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let status = sheets
+        .iter()
+        .filter_map(|s| SheetStatus::parse(&s.status).ok())
+        .min()
+        .map(|s| s.as_str().to_string());
+    Ok(ClassResultStatusResponse {
+        academic_year: class.academic_year,
+        term: class.term,
+        grade_level: class.grade_level,
+        section: class.section,
+        status,
+        sheets: sheets
+            .into_iter()
+            .map(|s| ResultSheetStatus {
+                sheet_id: s.id,
+                subject: s.subject,
+                section: s.section,
+                status: s.status,
+                submitted_at: s.submitted_at,
+                approved_at: s.approved_at,
+                published_at: s.published_at,
+            })
+            .collect(),
+        notifications_queued,
+        history,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_workflow_moves_one_step_or_back_to_draft() {
+        use SheetStatus::*;
+        assert!(Draft.can_move_to(Submitted));
+        assert!(Submitted.can_move_to(Approved));
+        assert!(Approved.can_move_to(Published));
+        assert!(Published.can_move_to(Draft));
+        assert!(Approved.can_move_to(Draft));
+        assert!(!Draft.can_move_to(Published));
+        assert!(!Submitted.can_move_to(Published));
+        assert!(!Published.can_move_to(Approved));
+        assert!(!Draft.can_move_to(Draft));
+        assert!(SheetStatus::parse("archived").is_err());
+    }
+}
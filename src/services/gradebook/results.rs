@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    ClassResultsQuery, ClassResultsResponse, GradebookScoreRow, StudentResultsResponse,
+    StudentScoresQuery, StudentTermResult, SubjectResult, TermResults,
+};
+use crate::services::students::validate_grade_level;
+
+use super::scale::{load_grading_scale, round2, weighted_total, GradingScale};
+use super::gpa::mean_gpa;
+use super::sheets::sheet_components;
+use super::{canonical_name, load_grading_config, GradebookService, GradingConfig, SetupList};
+
+/// Everything needed to turn component scores into graded results, loaded
+/// and validated once per request.
+pub(crate) struct ResultEngine {
+    pub config: GradingConfig,
+    pub scale: GradingScale,
+    pub passmark: f64,
+}
+
+impl ResultEngine {
+    /// Errors with `BadRequest` when weights, passmark or the grading scale
+    /// aren't usable.
+    pub async fn load(conn: &mut PgConnection, org_id: Uuid) -> Result<Self, AppError> {
+        let config = load_grading_config(&mut *conn, org_id).await?;
+        config.require_components()?;
+        let passmark = config.passmark()?;
+        let scale = load_grading_scale(conn, org_id).await?;
+        Ok(Self {
+            config,
+            scale,
+            passmark,
+        })
+    }
+
+    /// Graded result for one score row: the snapshot frozen at publication
+    /// when there is one, otherwise computed from the current weights, scale
+    /// and passmark. None when no component has a score.
+    pub fn grade(
+        &self,
+        score: &GradebookScoreRow,
+        max_scores: &serde_json::Value,
+    ) -> Option<GradedScore> {
+        if let Some(frozen) = score
+            .frozen_result
+            .as_ref()
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+        {
+            return Some(frozen);
+        }
+        self.grade_live(score, max_scores)
+    }
+
+    /// Graded result from the current configuration, ignoring any snapshot.
+    pub fn grade_live(
+        &self,
+        score: &GradebookScoreRow,
+        max_scores: &serde_json::Value,
+    ) -> Option<GradedScore> {
+        let components = sheet_components(&self.config, max_scores);
+        let weighted = weighted_total(&components, |c| score.component(c))?;
+        let band = self.scale.grade_for(weighted.total);
+        Some(GradedScore {
+            total: weighted.total,
+            complete: weighted.complete,
+            grade: band.grade.clone(),
+            descriptor: band.descriptor.clone(),
+            gpa_points: band.gpa_points,
+            passed: weighted.total >= self.passmark,
+        })
+    }
+
+    /// Graded result for one score row; None when no component has a score.
+    pub fn subject_result(
+        &self,
+        score: &GradebookScoreRow,
+        max_scores: &serde_json::Value,
+        sheet_status: &str,
+    ) -> Option<SubjectResult> {
+        let graded = self.grade(score, max_scores)?;
+        Some(SubjectResult {
+            subject: score.subject.clone(),
+            total: graded.total,
+            grade: graded.grade,
+            descriptor: graded.descriptor,
+            gpa_points: graded.gpa_points,
+            passed: graded.passed,
+            complete: graded.complete,
+            sheet_status: sheet_status.to_string(),
+            scores: self
+                .config
+                .weights
+                .iter()
+                .filter_map(|(key, _)| Some((key.to_string(), score.component(key)?)))
+                .collect(),
+        })
+    }
+}
+
+/// One subject's graded outcome; stored in `gradebook_scores.frozen_result`
+/// when a sheet is published.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct GradedScore {
+    pub total: f64,
+    pub complete: bool,
+    pub grade: String,
+    pub descriptor: Option<String>,
+    pub gpa_points: Option<f64>,
+    pub passed: bool,
+}
+
+/// Mean total and number of subjects passed.
+pub(crate) fn summarize(subjects: &[SubjectResult]) -> (f64, usize) {
+    let passed = subjects.iter().filter(|s| s.passed).count();
+    if subjects.is_empty() {
+        return (0.0, passed);
+    }
+    let sum: f64 = subjects.iter().map(|s| s.total).sum();
+    (round2(sum / subjects.len() as f64), passed)
+}
+
+#[derive(sqlx::FromRow)]
+pub(super) struct ResultRow {
+    #[sqlx(flatten)]
+    pub score: GradebookScoreRow,
+    pub max_scores: serde_json::Value,
+    pub sheet_status: String,
+    pub subject_position: Option<i16>,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+/// A class resolved against school setup.
+#[derive(Clone)]
+pub(super) struct ClassKey {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    pub section: Option<String>,
+}
+
+impl GradebookService {
+    /// Validate the grade level and match the term; the year defaults to the
+    /// current one.
+    pub(super) async fn resolve_class(
+        &self,
+        config: &GradingConfig,
+        org_id: Uuid,
+        academic_year: Option<&str>,
+        term: &str,
+        grade_level: &str,
+        section: Option<&str>,
+    ) -> Result<ClassKey, AppError> {
+        validate_grade_level(&self.pool, org_id, grade_level).await?;
+        Ok(ClassKey {
+            academic_year: config.academic_year(academic_year)?,
+            term: canonical_name(&self.pool, org_id, SetupList::Terms, term).await?,
+            grade_level: grade_level.to_string(),
+            section: section
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        })
+    }
+
+    /// Score rows for a class and term, in roster order then subject order.
+    pub(super) async fn class_rows(
+        &self,
+        org_id: Uuid,
+        class: &ClassKey,
+        subject: Option<&str>,
+    ) -> Result<Vec<ResultRow>, AppError> {
+        // Whole-grade sheets count towards a section through the student's
+        // current section.
+        let rows = sqlx::query_as(
+            r#"
+            SELECT sc.*, sh.max_scores, sh.status AS sheet_status, sub.position AS subject_position,
+                   s.admission_number, s.first_name, s.last_name
+            FROM gradebook_scores sc
+            JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+            JOIN students s ON s.id = sc.student_id
+            LEFT JOIN school_subjects sub ON sub.org_id = sc.org_id AND sub.name = sc.subject
+            WHERE sc.org_id = $1 AND sc.academic_year = $2 AND sc.term = $3
+              AND sh.grade_level = $4
+              AND ($5::text IS NULL OR COALESCE(sh.section, s.section) = $5)
+              AND ($6::text IS NULL OR sc.subject = $6)
+            ORDER BY s.last_name, s.first_name, s.id, sub.position NULLS LAST, sc.subject
+            "#,
+        )
+        .bind(org_id)
+        .bind(&class.academic_year)
+        .bind(&class.term)
+        .bind(&class.grade_level)
+        .bind(&class.section)
+        .bind(subject)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows)
+    }
+
+    /// Term results for every student with scores in a class, in roster order.
+    pub async fn class_results(
+        &self,
+        org_id: Uuid,
+        q: ClassResultsQuery,
+    ) -> Result<ClassResultsResponse, AppError> {
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        let class = self
+            .resolve_class(
+                &engine.config,
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        let subject = match q.subject.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            Some(s) => Some(canonical_name(&self.pool, org_id, SetupList::Subjects, s).await?),
+            None => None,
+        };
+        let rows = self.class_rows(org_id, &class, subject.as_deref()).await?;
+
+        let mut students: Vec<StudentTermResult> = Vec::new();
+        for row in rows {
+            let Some(result) = engine.subject_result(&row.score, &row.max_scores, &row.sheet_status)
+            else {
+                continue;
+            };
+            match students.last_mut() {
+                Some(s) if s.student_id == row.score.student_id => s.subjects.push(result),
+                _ => students.push(StudentTermResult {
+                    student_id: row.score.student_id,
+                    admission_number: row.admission_number,
+                    first_name: row.first_name,
+                    last_name: row.last_name,
+                    subjects: vec![result],
+                    average: 0.0,
+                    subjects_passed: 0,
+                    gpa: None,
+                }),
+            }
+        }
+        for s in &mut students {
+            (s.average, s.subjects_passed) = summarize(&s.subjects);
+            if engine.config.gpa_enabled {
+                s.gpa = mean_gpa(&s.subjects);
+            }
+        }
+
+        Ok(ClassResultsResponse {
+            academic_year: class.academic_year,
+            term: class.term,
+            grade_level: class.grade_level,
+            section: class.section,
+            passmark: engine.passmark,
+            students,
+        })
+    }
+
+    /// A student's results for each term they have scores in.
+    pub async fn student_results(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentScoresQuery,
+    ) -> Result<StudentResultsResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        let mut results = self.results_with(&engine, org_id, &[student_id], &q).await?;
+        Ok(results.remove(&student_id).unwrap_or(StudentResultsResponse {
+            student_id,
+            passmark: engine.passmark,
+            cumulative_gpa: None,
+            terms: Vec::new(),
+        }))
+    }
+
+    /// `student_results` for several students in one query, keyed by
+    /// student; students without scores are left out.
+    pub async fn results_for_students(
+        &self,
+        org_id: Uuid,
+        student_ids: &[Uuid],
+        q: StudentScoresQuery,
+    ) -> Result<HashMap<Uuid, StudentResultsResponse>, AppError> {
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        self.results_with(&engine, org_id, student_ids, &q).await
+    }
+
+    async fn results_with(
+        &self,
+        engine: &ResultEngine,
+        org_id: Uuid,
+        student_ids: &[Uuid],
+        q: &StudentScoresQuery,
+    ) -> Result<HashMap<Uuid, StudentResultsResponse>, AppError> {
+        let rows: Vec<ResultRow> = sqlx::query_as(
+            r#"
+            SELECT sc.*, sh.max_scores, sh.status AS sheet_status, sub.position AS subject_position,
+                   s.admission_number, s.first_name, s.last_name
+            FROM gradebook_scores sc
+            JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+            JOIN students s ON s.id = sc.student_id
+            LEFT JOIN school_terms t ON t.org_id = sc.org_id AND t.name = sc.term
+            LEFT JOIN school_subjects sub ON sub.org_id = sc.org_id AND sub.name = sc.subject
+            WHERE sc.org_id = $1 AND sc.student_id = ANY($2)
+            ORDER BY sc.student_id, sc.academic_year, t.position NULLS LAST, sc.term,
+                     sub.position NULLS LAST, sc.subject
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_ids)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut by_student: HashMap<Uuid, Vec<TermResults>> = HashMap::new();
+        for row in rows {
+            let Some(result) = engine.subject_result(&row.score, &row.max_scores, &row.sheet_status)
+            else {
+                continue;
+            };
+            let terms = by_student.entry(row.score.student_id).or_default();
+            match terms.last_mut() {
+                Some(t) if t.academic_year == row.score.academic_year && t.term == row.score.term => {
+                    t.subjects.push(result)
+                }
+                _ => terms.push(TermResults {
+                    academic_year: row.score.academic_year,
+                    term: row.score.term,
+                    subjects: vec![result],
+                    average: 0.0,
+                    subjects_passed: 0,
+                    gpa: None,
+                    cumulative_gpa: None,
+                }),
+            }
+        }
+
+        // Cumulative GPA runs over every term, so filters apply afterwards.
+        let gpa_enabled = engine.config.gpa_enabled;
+        let year = q.academic_year.as_deref().map(str::trim).filter(|y| !y.is_empty());
+        let term = q.term.as_deref().map(str::trim).filter(|t| !t.is_empty());
+        let mut results = HashMap::with_capacity(by_student.len());
+        for (student_id, mut terms) in by_student {
+            for i in 0..terms.len() {
+                let (average, passed) = summarize(&terms[i].subjects);
+                let cumulative =
+                    gpa_enabled.then(|| mean_gpa(terms[..=i].iter().flat_map(|t| &t.subjects)));
+                let t = &mut terms[i];
+                (t.average, t.subjects_passed) = (average, passed);
+                if gpa_enabled {
+                    t.gpa = mean_gpa(&t.subjects);
+                    t.cumulative_gpa = cumulative.flatten();
+                }
+            }
+            let cumulative_gpa = terms.last().and_then(|t| t.cumulative_gpa);
+            terms.retain(|t| {
+                year.is_none_or(|y| t.academic_year == y)
+                    && term.is_none_or(|n| t.term.eq_ignore_ascii_case(n))
+            });
+            results.insert(
+                student_id,
+                StudentResultsResponse {
+                    student_id,
+                    passmark: engine.passmark,
+                    cumulative_gpa,
+                    terms,
+                },
+            );
+        }
+        Ok(results)
+    }
+}
@@ -0,0 +1,258 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::ScoreComponent;
+use crate::models::school_setup::GradingScaleRow;
+
+use super::parse_number;
+
+/// One row of the grading scale with its bounds parsed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GradeBand {
+    pub grade: String,
+    pub min: f64,
+    pub max: f64,
+    pub descriptor: Option<String>,
+    pub gpa_points: Option<f64>,
+}
+
+/// The school's grading scale, validated to be contiguous over 0–100.
+#[derive(Debug, Clone)]
+pub(crate) struct GradingScale {
+    /// Sorted by `min`, lowest first.
+    bands: Vec<GradeBand>,
+}
+
+impl GradingScale {
+    /// Parse and validate the TEXT rows from `school_grading_scales`.
+    ///
+    /// Bands must not overlap, the lowest must start at 0 and the highest end
+    /// at 100. Neighbouring bands may step by up to one point (`0–39`, `40–49`)
+    /// since most scales are written in whole numbers.
+    pub fn parse(rows: &[GradingScaleRow]) -> Result<Self, AppError> {
+        if rows.is_empty() {
+            return Err(AppError::BadRequest(
+                "No grading scale is configured; add grades in the grading section first".into(),
+            ));
+        }
+        let mut bands = Vec::with_capacity(rows.len());
+        for row in rows {
+            let grade = row.grade.trim();
+            if grade.is_empty() {
+                return Err(AppError::BadRequest(format!(
+                    "Grading scale row {} has no grade",
+                    row.position + 1
+                )));
+            }
+            let bound = |field: &str, raw: &str| {
+                parse_number(Some(raw)).ok_or_else(|| {
+                    AppError::BadRequest(format!("Grade '{grade}': {field} '{raw}' is not a number"))
+                })
+            };
+            let min = bound("min_score", &row.min_score)?;
+            let max = bound("max_score", &row.max_score)?;
+            if !(0.0..=100.0).contains(&min) || !(0.0..=100.0).contains(&max) || min > max {
+                return Err(AppError::BadRequest(format!(
+                    "Grade '{grade}': scores must be within 0–100 with min_score ≤ max_score (got {min}–{max})"
+                )));
+            }
+            let gpa_points = match row.gpa_points.as_deref().map(str::trim) {
+                None | Some("") => None,
+                Some(raw) => Some(parse_number(Some(raw)).ok_or_else(|| {
+                    AppError::BadRequest(format!("Grade '{grade}': gpa_points '{raw}' is not a number"))
+                })?),
+            };
+            bands.push(GradeBand {
+                grade: grade.to_string(),
+                min,
+                max,
+                descriptor: row
+                    .descriptor
+                    .as_deref()
+                    .map(str::trim)
+                    .filter(|d| !d.is_empty())
+                    .map(String::from),
+                gpa_points,
+            });
+        }
+        bands.sort_by(|a, b| a.min.total_cmp(&b.min));
+
+        for pair in bands.windows(2) {
+            let (lower, upper) = (&pair[0], &pair[1]);
+            if upper.min <= lower.max {
+                return Err(AppError::BadRequest(format!(
+                    "Grades '{}' ({}–{}) and '{}' ({}–{}) overlap",
+                    lower.grade, lower.min, lower.max, upper.grade, upper.min, upper.max
+                )));
+            }
+            if upper.min - lower.max > 1.0 {
+                return Err(AppError::BadRequest(format!(
+                    "Grading scale has a gap between '{}' (ends at {}) and '{}' (starts at {})",
+                    lower.grade, lower.max, upper.grade, upper.min
+                )));
+            }
+        }
+        let (lowest, highest) = (&bands[0], &bands[bands.len() - 1]);
+        if lowest.min > 0.0 {
+            return Err(AppError::BadRequest(format!(
+                "Grading scale must start at 0; the lowest grade '{}' starts at {}",
+                lowest.grade, lowest.min
+            )));
+        }
+        if highest.max < 100.0 {
+            return Err(AppError::BadRequest(format!(
+                "Grading scale must reach 100; the highest grade '{}' ends at {}",
+                highest.grade, highest.max
+            )));
+        }
+        Ok(Self { bands })
+    }
+
+    /// Band for a total out of 100. A total that falls between two bands'
+    /// bounds (74.5 with `70–74` and `75–100`) takes the lower band.
+    pub fn grade_for(&self, total: f64) -> &GradeBand {
+        self.bands
+            .iter()
+            .rev()
+            .find(|b| b.min <= total)
+            .unwrap_or(&self.bands[0])
+    }
+
+    /// Bands from lowest to highest.
+    pub fn bands(&self) -> &[GradeBand] {
+        &self.bands
+    }
+}
+
+pub(crate) async fn load_grading_scale(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+) -> Result<GradingScale, AppError> {
+    let rows: Vec<GradingScaleRow> = sqlx::query_as(
+        r#"
+        SELECT id, org_id, grade, min_score, max_score, descriptor, gpa_points, position
+        FROM school_grading_scales WHERE org_id = $1 ORDER BY position
+        "#,
+    )
+    .bind(org_id)
+    .fetch_all(conn)
+    .await?;
+    GradingScale::parse(&rows)
+}
+
+/// A weighted term total out of 100.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct WeightedTotal {
+    pub total: f64,
+    /// False when some enabled component has no score; missing scores count as 0.
+    pub complete: bool,
+}
+
+/// Each component's score as a fraction of its max, times its weight, scaled
+/// so the weights sum to 100. None when no component has a score.
+pub(crate) fn weighted_total(
+    components: &[ScoreComponent],
+    score: impl Fn(&str) -> Option<f64>,
+) -> Option<WeightedTotal> {
+    let weight_sum: f64 = components.iter().map(|c| c.weight).sum();
+    if weight_sum <= 0.0 {
+        return None;
+    }
+    let mut earned = 0.0;
+    let mut entered = 0;
+    for c in components {
+        if let Some(s) = score(&c.key) {
+            earned += s.min(c.max_score) / c.max_score * c.weight;
+            entered += 1;
+        }
+    }
+    (entered > 0).then(|| WeightedTotal {
+        total: round2(earned / weight_sum * 100.0),
+        complete: entered == components.len(),
+    })
+}
+
+pub(crate) fn round2(n: f64) -> f64 {
+    (n * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(grade: &str, min: &str, max: &str, gpa: Option<&str>) -> GradingScaleRow {
+        GradingScaleRow {
+            id: Uuid::nil(),
+            org_id: Uuid::nil(),
+            grade: grade.into(),
+            min_score: min.into(),
+            max_score: max.into(),
+            descriptor: None,
+            gpa_points: gpa.map(String::from),
+            position: 0,
+        }
+    }
+
+    fn component(key: &str, weight: f64, max_score: f64) -> ScoreComponent {
+        ScoreComponent {
+            key: key.into(),
+            weight,
+            max_score,
+        }
+    }
+
+    #[test]
+    fn scale_lookup_uses_lower_bound() {
+        let scale = GradingScale::parse(&[
+            row("A", "70", "100", Some("4.0")),
+            row("F", "0", "49", Some("0")),
+            row("C", "50", "69", Some("2.0")),
+        ])
+        .unwrap();
+        assert_eq!(scale.grade_for(100.0).grade, "A");
+        assert_eq!(scale.grade_for(69.5).grade, "C");
+        assert_eq!(scale.grade_for(50.0).grade, "C");
+        assert_eq!(scale.grade_for(0.0).grade, "F");
+        assert_eq!(scale.grade_for(70.0).gpa_points, Some(4.0));
+    }
+
+    #[test]
+    fn scale_rejects_bad_rows() {
+        let bad = [
+            vec![],
+            vec![row("A", "0", "100", Some("four"))],
+            vec![row("A", "abc", "100", None)],
+            vec![row("A", "0", "120", None)],
+            vec![row("A", "60", "100", None), row("F", "0", "60", None)],
+            vec![row("A", "60", "100", None), row("F", "0", "49", None)],
+            vec![row("A", "10", "100", None)],
+            vec![row("A", "50", "90", None), row("F", "0", "49", None)],
+        ];
+        for rows in bad {
+            assert!(
+                matches!(GradingScale::parse(&rows), Err(AppError::BadRequest(_))),
+                "accepted {rows:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn weighted_total_scales_by_max_and_weight() {
+        let components = [component("ca", 40.0, 40.0), component("exam", 60.0, 100.0)];
+        let t = weighted_total(&components, |c| match c {
+            "ca" => Some(30.0),
+            "exam" => Some(50.0),
+            _ => None,
+        })
+        .unwrap();
+        assert_eq!(t, WeightedTotal { total: 60.0, complete: true });
+
+        // Weights that don't sum to 100 are normalised; missing counts as 0.
+        let components = [component("ca", 30.0, 30.0), component("exam", 30.0, 30.0)];
+        let t = weighted_total(&components, |c| (c == "ca").then_some(30.0)).unwrap();
+        assert_eq!(t, WeightedTotal { total: 50.0, complete: false });
+
+        assert_eq!(weighted_total(&components, |_| None), None);
+    }
+}
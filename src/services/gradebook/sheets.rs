@@ -0,0 +1,601 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    GradebookScoreRow, GradebookSheetRow, SaveScoreSheetRequest, ScoreComponent, ScoreEntryInput,
+    ScoreSheetEntry,
+    ScoreSheetQuery, ScoreSheetResponse, SheetStatusRequest, StudentScoresQuery,
+    StudentScoresResponse, StudentSubjectScores,
+};
+use crate::services::students::validate_grade_level;
+
+use super::publication::{apply_status, SheetStatus};
+use super::{
+    canonical_name, load_grading_config, GradebookService, GradingConfig, SetupList, COMPONENTS,
+};
+
+/// A sheet's identity with names resolved to their configured spelling.
+pub(super) struct SheetKey {
+    pub academic_year: String,
+    pub term: String,
+    pub subject: String,
+    pub grade_level: String,
+    pub section: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RosterRow {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    last_name: String,
+    ca: Option<f64>,
+    exam: Option<f64>,
+    assignment: Option<f64>,
+    test: Option<f64>,
+    project: Option<f64>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ScoreWithStatusRow {
+    #[sqlx(flatten)]
+    score: GradebookScoreRow,
+    sheet_status: String,
+}
+
+impl GradebookService {
+    /// Enter or update scores for a whole class in one subject and term.
+    /// Scores are upserted per student and per component, so a teacher can
+    /// enter CA and exam scores in separate passes. Only draft sheets can be
+    /// edited.
+    pub async fn save_sheet(
+        &self,
+        org_id: Uuid,
+        req: SaveScoreSheetRequest,
+        entered_by: Option<Uuid>,
+    ) -> Result<ScoreSheetResponse, AppError> {
+        if req.entries.is_empty() {
+            return Err(AppError::BadRequest("entries array is empty".into()));
+        }
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        config.require_components()?;
+        let key = self
+            .resolve_key(
+                org_id,
+                &config,
+                req.academic_year.as_deref(),
+                &req.term,
+                &req.subject,
+                &req.grade_level,
+                req.section.as_deref(),
+            )
+            .await?;
+
+        let mut tx = self.pool.begin().await?;
+        let sheet = write_sheet(
+            &mut tx,
+            org_id,
+            &config,
+            &key,
+            req.max_scores.as_ref(),
+            &req.entries,
+            entered_by,
+        )
+        .await?;
+        tx.commit().await?;
+
+        self.sheet_response(org_id, &config, key, Some(sheet)).await
+    }
+
+    /// Class roster for a subject and term with each student's scores.
+    /// Includes active students currently in the class plus anyone with
+    /// scores on this sheet.
+    pub async fn get_sheet(
+        &self,
+        org_id: Uuid,
+        q: ScoreSheetQuery,
+    ) -> Result<ScoreSheetResponse, AppError> {
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        let key = self
+            .resolve_key(
+                org_id,
+                &config,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.subject,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        let sheet: Option<GradebookSheetRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM gradebook_sheets
+            WHERE org_id = $1 AND academic_year = $2 AND term = $3 AND subject = $4
+              AND grade_level = $5 AND COALESCE(section, '') = COALESCE($6, '')
+            "#,
+        )
+        .bind(org_id)
+        .bind(&key.academic_year)
+        .bind(&key.term)
+        .bind(&key.subject)
+        .bind(&key.grade_level)
+        .bind(&key.section)
+        .fetch_optional(&self.pool)
+        .await?;
+        self.sheet_response(org_id, &config, key, sheet).await
+    }
+
+    /// Move one sheet a step through the workflow, or back to draft, under
+    /// the same rule as a class. The move is logged like any other.
+    pub async fn set_sheet_status(
+        &self,
+        org_id: Uuid,
+        id: Uuid,
+        req: SheetStatusRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<ScoreSheetResponse, AppError> {
+        let status = SheetStatus::parse(&req.status)?;
+        let mut tx = self.pool.begin().await?;
+        let sheet: GradebookSheetRow = sqlx::query_as(
+            "SELECT * FROM gradebook_sheets WHERE id = $1 AND org_id = $2 FOR UPDATE",
+        )
+        .bind(id)
+        .bind(org_id)
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Score sheet not found".into()))?;
+        if sheet.status != status.as_str() {
+            SheetStatus::parse(&sheet.status)?.ensure_can_move_to(&sheet.subject, status)?;
+            apply_status(
+                &mut tx,
+                org_id,
+                std::slice::from_ref(&sheet),
+                status,
+                req.reason.as_deref(),
+                changed_by,
+            )
+            .await?;
+        }
+        let sheet: GradebookSheetRow = sqlx::query_as("SELECT * FROM gradebook_sheets WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        let key = SheetKey {
+            academic_year: sheet.academic_year.clone(),
+            term: sheet.term.clone(),
+            subject: sheet.subject.clone(),
+            grade_level: sheet.grade_level.clone(),
+            section: sheet.section.clone(),
+        };
+        self.sheet_response(org_id, &config, key, Some(sheet)).await
+    }
+
+    /// Every subject's scores for one student, optionally for one year/term.
+    pub async fn student_scores(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentScoresQuery,
+    ) -> Result<StudentScoresResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+
+        let rows: Vec<ScoreWithStatusRow> = sqlx::query_as(
+            r#"
+            SELECT sc.*, sh.status AS sheet_status
+            FROM gradebook_scores sc
+            JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+            LEFT JOIN school_terms t ON t.org_id = sc.org_id AND t.name = sc.term
+            WHERE sc.org_id = $1 AND sc.student_id = $2
+              AND ($3::text IS NULL OR sc.academic_year = $3)
+              AND ($4::text IS NULL OR LOWER(sc.term) = LOWER($4))
+            ORDER BY sc.academic_year, t.position NULLS LAST, sc.term, sc.subject
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(q.academic_year.as_deref().map(str::trim).filter(|y| !y.is_empty()))
+        .bind(q.term.as_deref().map(str::trim).filter(|t| !t.is_empty()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(StudentScoresResponse {
+            student_id,
+            subjects: rows
+                .into_iter()
+                .map(|ScoreWithStatusRow { score, sheet_status }| StudentSubjectScores {
+                    scores: entered_scores(&config, |c| score.component(c)),
+                    academic_year: score.academic_year,
+                    term: score.term,
+                    subject: score.subject,
+                    sheet_id: score.sheet_id,
+                    status: sheet_status,
+                })
+                .collect(),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn resolve_key(
+        &self,
+        org_id: Uuid,
+        config: &GradingConfig,
+        academic_year: Option<&str>,
+        term: &str,
+        subject: &str,
+        grade_level: &str,
+        section: Option<&str>,
+    ) -> Result<SheetKey, AppError> {
+        validate_grade_level(&self.pool, org_id, grade_level).await?;
+        Ok(SheetKey {
+            academic_year: config.academic_year(academic_year)?,
+            term: canonical_name(&self.pool, org_id, SetupList::Terms, term).await?,
+            subject: canonical_name(&self.pool, org_id, SetupList::Subjects, subject).await?,
+            grade_level: grade_level.to_string(),
+            section: section
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        })
+    }
+
+    async fn sheet_response(
+        &self,
+        org_id: Uuid,
+        config: &GradingConfig,
+        key: SheetKey,
+        sheet: Option<GradebookSheetRow>,
+    ) -> Result<ScoreSheetResponse, AppError> {
+        let rows: Vec<RosterRow> = sqlx::query_as(
+            r#"
+            SELECT s.id, s.admission_number, s.first_name, s.last_name,
+                   sc.ca, sc.exam, sc.assignment, sc.test, sc.project
+            FROM students s
+            LEFT JOIN gradebook_scores sc ON sc.student_id = s.id AND sc.sheet_id = $4
+            WHERE s.org_id = $1
+              AND ((s.status = 'active' AND s.grade_level = $2
+                    AND ($3::text IS NULL OR s.section = $3))
+                   OR sc.id IS NOT NULL)
+            ORDER BY s.last_name, s.first_name
+            "#,
+        )
+        .bind(org_id)
+        .bind(&key.grade_level)
+        .bind(&key.section)
+        .bind(sheet.as_ref().map(|s| s.id))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let entries = rows
+            .into_iter()
+            .map(|r| {
+                let scores = entered_scores(config, |c| match c {
+                    "ca" => r.ca,
+                    "exam" => r.exam,
+                    "assignment" => r.assignment,
+                    "test" => r.test,
+                    "project" => r.project,
+                    _ => None,
+                });
+                ScoreSheetEntry {
+                    student_id: r.id,
+                    admission_number: r.admission_number,
+                    first_name: r.first_name,
+                    last_name: r.last_name,
+                    scores,
+                }
+            })
+            .collect();
+
+        let max_scores = sheet
+            .as_ref()
+            .map(|s| s.max_scores.clone())
+            .unwrap_or(serde_json::Value::Null);
+        Ok(ScoreSheetResponse {
+            id: sheet.as_ref().map(|s| s.id),
+            academic_year: key.academic_year,
+            term: key.term,
+            subject: key.subject,
+            grade_level: key.grade_level,
+            section: key.section,
+            status: sheet
+                .as_ref()
+                .map(|s| s.status.clone())
+                .unwrap_or_else(|| "draft".into()),
+            submitted_at: sheet.as_ref().and_then(|s| s.submitted_at),
+            approved_at: sheet.as_ref().and_then(|s| s.approved_at),
+            published_at: sheet.as_ref().and_then(|s| s.published_at),
+            components: sheet_components(config, &max_scores),
+            entries,
+        })
+    }
+}
+
+/// Upsert a sheet and its entries inside the caller's transaction and
+/// refresh the affected GPAs. Entries are validated before anything is
+/// written; errors leave the transaction to be rolled back.
+pub(super) async fn write_sheet(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    config: &GradingConfig,
+    key: &SheetKey,
+    max_scores_override: Option<&HashMap<String, f64>>,
+    entries: &[ScoreEntryInput],
+    entered_by: Option<Uuid>,
+) -> Result<GradebookSheetRow, AppError> {
+    let existing: Option<GradebookSheetRow> = sqlx::query_as(
+        r#"
+        SELECT * FROM gradebook_sheets
+        WHERE org_id = $1 AND academic_year = $2 AND term = $3 AND subject = $4
+          AND grade_level = $5 AND COALESCE(section, '') = COALESCE($6, '')
+        FOR UPDATE
+        "#,
+    )
+    .bind(org_id)
+    .bind(&key.academic_year)
+    .bind(&key.term)
+    .bind(&key.subject)
+    .bind(&key.grade_level)
+    .bind(&key.section)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(sheet) = &existing {
+        ensure_unlocked(sheet)?;
+    }
+
+    // Merge requested max scores over the sheet's current ones.
+    let mut max_scores: serde_json::Map<String, serde_json::Value> = existing
+        .as_ref()
+        .and_then(|s| s.max_scores.as_object().cloned())
+        .unwrap_or_default();
+    for (component, max) in max_scores_override.into_iter().flatten() {
+        if config.weight(component).is_none() {
+            return Err(unknown_component(component, config));
+        }
+        if !max.is_finite() || *max <= 0.0 {
+            return Err(AppError::BadRequest(format!(
+                "max_scores.{component} must be greater than 0"
+            )));
+        }
+        max_scores.insert(component.clone(), serde_json::json!(max));
+    }
+    let max_scores = serde_json::Value::Object(max_scores);
+    let components = sheet_components(config, &max_scores);
+    let max_for = |c: &str| components.iter().find(|s| s.key == c).map(|s| s.max_score);
+
+    // Validate every entry up front so a bad row doesn't leave a half-written sheet.
+    let mut seen: HashSet<Uuid> = HashSet::with_capacity(entries.len());
+    for e in entries {
+        if !seen.insert(e.student_id) {
+            return Err(AppError::BadRequest(format!(
+                "duplicate student_id {} in entries array",
+                e.student_id
+            )));
+        }
+        for (component, score) in &e.scores {
+            let max = max_for(component).ok_or_else(|| unknown_component(component, config))?;
+            if let Some(score) = score
+                && (!score.is_finite() || *score < 0.0 || *score > max)
+            {
+                return Err(AppError::BadRequest(format!(
+                    "{component} score {score} for student {} must be between 0 and {max}",
+                    e.student_id
+                )));
+            }
+        }
+    }
+
+    let ids: Vec<Uuid> = entries.iter().map(|e| e.student_id).collect();
+    let placements: Vec<(Uuid, String, Option<String>)> = sqlx::query_as(
+        "SELECT id, grade_level, section FROM students WHERE org_id = $1 AND id = ANY($2)",
+    )
+    .bind(org_id)
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+    let placements: HashMap<Uuid, (String, Option<String>)> = placements
+        .into_iter()
+        .map(|(id, g, s)| (id, (g, s)))
+        .collect();
+    for id in &ids {
+        let (grade, student_section) = placements
+            .get(id)
+            .ok_or_else(|| AppError::NotFound(format!("Student {id} not found")))?;
+        let in_class = grade == &key.grade_level
+            && key
+                .section
+                .as_deref()
+                .is_none_or(|s| student_section.as_deref() == Some(s));
+        if !in_class {
+            return Err(AppError::BadRequest(format!(
+                "Student {id} is not in {}{}",
+                key.grade_level,
+                key.section.as_deref().map(|s| format!(" {s}")).unwrap_or_default()
+            )));
+        }
+    }
+
+    // A student who changed class keeps one row per subject and term; it
+    // moves to this sheet unless the sheet it's on is past draft.
+    let locked_elsewhere: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT sc.student_id FROM gradebook_scores sc
+        JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+        WHERE sc.org_id = $1 AND sc.student_id = ANY($2)
+          AND sc.academic_year = $3 AND sc.term = $4 AND sc.subject = $5
+          AND sh.status <> 'draft'
+          AND ($6::uuid IS NULL OR sc.sheet_id <> $6)
+        LIMIT 1
+        "#,
+    )
+    .bind(org_id)
+    .bind(&ids)
+    .bind(&key.academic_year)
+    .bind(&key.term)
+    .bind(&key.subject)
+    .bind(existing.as_ref().map(|s| s.id))
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(id) = locked_elsewhere {
+        return Err(AppError::Conflict(format!(
+            "Student {id} already has locked {} scores for {} {}",
+            key.subject, key.term, key.academic_year
+        )));
+    }
+
+    let sheet: GradebookSheetRow = sqlx::query_as(
+        r#"
+        INSERT INTO gradebook_sheets
+            (org_id, academic_year, term, subject, grade_level, section, max_scores, created_by_user_id)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        ON CONFLICT (org_id, academic_year, term, subject, grade_level, COALESCE(section, ''))
+        DO UPDATE SET max_scores = EXCLUDED.max_scores
+        RETURNING *
+        "#,
+    )
+    .bind(org_id)
+    .bind(&key.academic_year)
+    .bind(&key.term)
+    .bind(&key.subject)
+    .bind(&key.grade_level)
+    .bind(&key.section)
+    .bind(&max_scores)
+    .bind(entered_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    for e in entries {
+        let given = |c: &str| e.scores.get(c);
+        sqlx::query(
+            r#"
+            INSERT INTO gradebook_scores
+                (sheet_id, org_id, student_id, academic_year, term, subject,
+                 ca, exam, assignment, test, project, entered_by_user_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (student_id, academic_year, term, subject) DO UPDATE SET
+                sheet_id = EXCLUDED.sheet_id,
+                ca = CASE WHEN $13 THEN EXCLUDED.ca ELSE gradebook_scores.ca END,
+                exam = CASE WHEN $14 THEN EXCLUDED.exam ELSE gradebook_scores.exam END,
+                assignment = CASE WHEN $15 THEN EXCLUDED.assignment ELSE gradebook_scores.assignment END,
+                test = CASE WHEN $16 THEN EXCLUDED.test ELSE gradebook_scores.test END,
+                project = CASE WHEN $17 THEN EXCLUDED.project ELSE gradebook_scores.project END,
+                entered_by_user_id = EXCLUDED.entered_by_user_id
+            "#,
+        )
+        .bind(sheet.id)
+        .bind(org_id)
+        .bind(e.student_id)
+        .bind(&key.academic_year)
+        .bind(&key.term)
+        .bind(&key.subject)
+        .bind(given("ca").copied().flatten())
+        .bind(given("exam").copied().flatten())
+        .bind(given("assignment").copied().flatten())
+        .bind(given("test").copied().flatten())
+        .bind(given("project").copied().flatten())
+        .bind(entered_by)
+        .bind(given("ca").is_some())
+        .bind(given("exam").is_some())
+        .bind(given("assignment").is_some())
+        .bind(given("test").is_some())
+        .bind(given("project").is_some())
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    // Lowering a max score mustn't strand scores already above it.
+    let over_max: Option<Uuid> = sqlx::query_scalar(
+        r#"
+        SELECT student_id FROM gradebook_scores
+        WHERE sheet_id = $1
+          AND (ca > $2 OR exam > $3 OR assignment > $4 OR test > $5 OR project > $6)
+        LIMIT 1
+        "#,
+    )
+    .bind(sheet.id)
+    .bind(max_for("ca"))
+    .bind(max_for("exam"))
+    .bind(max_for("assignment"))
+    .bind(max_for("test"))
+    .bind(max_for("project"))
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(id) = over_max {
+        return Err(AppError::BadRequest(format!(
+            "Student {id} has a score above the new max_scores; correct it first"
+        )));
+    }
+
+    Ok(sheet)
+}
+
+/// Enabled components with the sheet's max score for each (the weight when
+/// the sheet doesn't override it).
+pub(crate) fn sheet_components(
+    config: &GradingConfig,
+    max_scores: &serde_json::Value,
+) -> Vec<ScoreComponent> {
+    config
+        .weights
+        .iter()
+        .map(|(key, weight)| ScoreComponent {
+            key: key.to_string(),
+            weight: *weight,
+            max_score: max_scores
+                .get(*key)
+                .and_then(|v| v.as_f64())
+                .filter(|m| *m > 0.0)
+                .unwrap_or(*weight),
+        })
+        .collect()
+}
+
+/// Entered scores for the enabled components.
+fn entered_scores(
+    config: &GradingConfig,
+    score: impl Fn(&str) -> Option<f64>,
+) -> BTreeMap<String, f64> {
+    config
+        .weights
+        .iter()
+        .filter_map(|(key, _)| Some((key.to_string(), score(key)?)))
+        .collect()
+}
+
+fn ensure_unlocked(sheet: &GradebookSheetRow) -> Result<(), AppError> {
+    if sheet.status != SheetStatus::Draft.as_str() {
+        return Err(AppError::Conflict(format!(
+            "{} {} scores for {} are {} and locked",
+            sheet.subject, sheet.term, sheet.grade_level, sheet.status
+        )));
+    }
+    Ok(())
+}
+
+pub(super) fn unknown_component(component: &str, config: &GradingConfig) -> AppError {
+    let enabled: Vec<&str> = config.weights.iter().map(|(k, _)| *k).collect();
+    if COMPONENTS.contains(&component) {
+        AppError::BadRequest(format!(
+            "Component '{component}' has no weight in the grading section; enabled components are {enabled:?}"
+        ))
+    } else {
+        AppError::BadRequest(format!(
+            "Unknown component '{component}'; enabled components are {enabled:?}"
+        ))
+    }
+}
@@ -0,0 +1,404 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    ClassStatisticsQuery, ClassStatisticsResponse, StudentPosition, StudentStatisticsQuery,
+    StudentStatisticsResponse, StudentSubjectStatistics, SubjectPosition, SubjectStatistics,
+};
+
+use super::results::{ClassKey, ResultEngine};
+use super::scale::round2;
+use super::sheets::sheet_components;
+use super::{canonical_name, GradebookService, SetupList};
+
+/// How equal scores are positioned (`position_tie_break` in the report_card
+/// section).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum TieBreak {
+    /// Ties share a position and the next one is skipped: 1, 2, 2, 4.
+    #[default]
+    Shared,
+    /// Ties share a position and the next one isn't skipped: 1, 2, 2, 3.
+    Dense,
+    /// The higher exam score goes first; remaining ties are shared.
+    ExamScore,
+}
+
+impl TieBreak {
+    pub fn parse(s: &str) -> Result<Self, AppError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "shared" => Ok(Self::Shared),
+            "dense" => Ok(Self::Dense),
+            "exam_score" => Ok(Self::ExamScore),
+            other => Err(AppError::BadRequest(format!(
+                "Invalid position_tie_break '{other}'. Use shared, dense or exam_score"
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Shared => "shared",
+            Self::Dense => "dense",
+            Self::ExamScore => "exam_score",
+        }
+    }
+}
+
+/// 1-based positions for `(score, exam)` pairs, highest score first, in input
+/// order. `exam` only matters under [`TieBreak::ExamScore`]; a missing one
+/// ranks below any present one.
+pub(crate) fn positions(scores: &[(f64, Option<f64>)], rule: TieBreak) -> Vec<u32> {
+    let key = |i: usize| {
+        let (score, exam) = scores[i];
+        (score, if rule == TieBreak::ExamScore { exam } else { None })
+    };
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&a, &b| {
+        let (ka, kb) = (key(a), key(b));
+        kb.0.total_cmp(&ka.0)
+            .then_with(|| kb.1.partial_cmp(&ka.1).unwrap_or(Ordering::Equal))
+    });
+
+    let mut out = vec![0; scores.len()];
+    let (mut prev, mut position, mut distinct) = (None, 0, 0);
+    for (i, &idx) in order.iter().enumerate() {
+        let k = key(idx);
+        if prev != Some(k) {
+            distinct += 1;
+            position = match rule {
+                TieBreak::Dense => distinct,
+                _ => i as u32 + 1,
+            };
+            prev = Some(k);
+        }
+        out[idx] = position;
+    }
+    out
+}
+
+#[derive(Default, sqlx::FromRow)]
+struct DisplayRow {
+    show_position: Option<bool>,
+    show_class_average: Option<bool>,
+    show_highest_lowest: Option<bool>,
+    position_tie_break: Option<String>,
+}
+
+/// One graded subject, with the exam as a fraction of its max for
+/// tie-breaking.
+struct Graded {
+    subject: String,
+    total: f64,
+    exam: Option<f64>,
+}
+
+struct StudentGrades {
+    student_id: Uuid,
+    admission_number: String,
+    first_name: String,
+    last_name: String,
+    subjects: Vec<Graded>,
+}
+
+impl GradebookService {
+    /// Positions, averages and highest/lowest totals for a class and term.
+    pub async fn class_statistics(
+        &self,
+        org_id: Uuid,
+        q: ClassStatisticsQuery,
+    ) -> Result<ClassStatisticsResponse, AppError> {
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        let class = self
+            .resolve_class(
+                &engine.config,
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        self.compute_statistics(&engine, org_id, class).await
+    }
+
+    /// A student's positions and class figures for one term, in the class
+    /// their scores were entered for.
+    pub async fn student_statistics(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentStatisticsQuery,
+    ) -> Result<StudentStatisticsResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+        let engine = ResultEngine::load(&mut *self.pool.acquire().await?, org_id).await?;
+        let academic_year = engine.config.academic_year(q.academic_year.as_deref())?;
+        let term = canonical_name(&self.pool, org_id, SetupList::Terms, &q.term).await?;
+        let no_results =
+            || AppError::NotFound(format!("Student has no results for {term} {academic_year}"));
+
+        let class: Option<(String, Option<String>)> = sqlx::query_as(
+            r#"
+            SELECT sh.grade_level, COALESCE(sh.section, s.section)
+            FROM gradebook_scores sc
+            JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+            JOIN students s ON s.id = sc.student_id
+            WHERE sc.org_id = $1 AND sc.student_id = $2
+              AND sc.academic_year = $3 AND sc.term = $4
+            ORDER BY sc.updated_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(&academic_year)
+        .bind(&term)
+        .fetch_optional(&self.pool)
+        .await?;
+        let Some((grade_level, section)) = class else {
+            return Err(no_results());
+        };
+
+        let stats = self
+            .compute_statistics(
+                &engine,
+                org_id,
+                ClassKey {
+                    academic_year: academic_year.clone(),
+                    term: term.clone(),
+                    grade_level,
+                    section,
+                },
+            )
+            .await?;
+        let Some(student) = stats.students.iter().find(|s| s.student_id == student_id) else {
+            return Err(no_results());
+        };
+        let by_subject: HashMap<&str, &SubjectStatistics> =
+            stats.subjects.iter().map(|s| (s.subject.as_str(), s)).collect();
+        let subjects = student
+            .subjects
+            .iter()
+            .map(|p| {
+                let s = by_subject[p.subject.as_str()];
+                StudentSubjectStatistics {
+                    subject: p.subject.clone(),
+                    total: p.total,
+                    position: p.position,
+                    students: s.students,
+                    class_average: s.average,
+                    highest: s.highest,
+                    lowest: s.lowest,
+                }
+            })
+            .collect();
+
+        Ok(StudentStatisticsResponse {
+            student_id,
+            average: student.average,
+            position: student.position,
+            subjects,
+            academic_year: stats.academic_year,
+            term: stats.term,
+            grade_level: stats.grade_level,
+            section: stats.section,
+            tie_break: stats.tie_break,
+            show_position: stats.show_position,
+            show_class_average: stats.show_class_average,
+            show_highest_lowest: stats.show_highest_lowest,
+            class_size: stats.class_size,
+            class_average: stats.class_average,
+        })
+    }
+
+    pub(super) async fn compute_statistics(
+        &self,
+        engine: &ResultEngine,
+        org_id: Uuid,
+        class: ClassKey,
+    ) -> Result<ClassStatisticsResponse, AppError> {
+        let display: DisplayRow = sqlx::query_as(
+            r#"
+            SELECT show_position, show_class_average, show_highest_lowest, position_tie_break
+            FROM school_configs WHERE org_id = $1
+            "#,
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or_default();
+        // Validated when the report_card section is saved.
+        let rule = display
+            .position_tie_break
+            .as_deref()
+            .and_then(|t| TieBreak::parse(t).ok())
+            .unwrap_or_default();
+
+        let rows = self.class_rows(org_id, &class, None).await?;
+        let mut students: Vec<StudentGrades> = Vec::new();
+        let mut subject_order: Vec<(Option<i16>, String)> = Vec::new();
+        for row in rows {
+            let Some(result) = engine.grade(&row.score, &row.max_scores) else {
+                continue;
+            };
+            let exam = sheet_components(&engine.config, &row.max_scores)
+                .into_iter()
+                .find(|c| c.key == "exam")
+                .and_then(|c| Some(row.score.component("exam")? / c.max_score));
+            let key = (row.subject_position, row.score.subject.clone());
+            if !subject_order.contains(&key) {
+                subject_order.push(key);
+            }
+            let graded = Graded {
+                subject: row.score.subject,
+                total: result.total,
+                exam,
+            };
+            match students.last_mut() {
+                Some(s) if s.student_id == row.score.student_id => s.subjects.push(graded),
+                _ => students.push(StudentGrades {
+                    student_id: row.score.student_id,
+                    admission_number: row.admission_number,
+                    first_name: row.first_name,
+                    last_name: row.last_name,
+                    subjects: vec![graded],
+                }),
+            }
+        }
+        subject_order.sort_by(|a, b| match (a.0, b.0) {
+            (Some(x), Some(y)) => x.cmp(&y).then_with(|| a.1.cmp(&b.1)),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => a.1.cmp(&b.1),
+        });
+
+        // Subject positions and figures.
+        let mut subject_positions: HashMap<(Uuid, &str), u32> = HashMap::new();
+        let mut subjects = Vec::with_capacity(subject_order.len());
+        for (_, subject) in &subject_order {
+            let entries: Vec<(Uuid, f64, Option<f64>)> = students
+                .iter()
+                .flat_map(|s| {
+                    s.subjects
+                        .iter()
+                        .filter(|g| &g.subject == subject)
+                        .map(|g| (s.student_id, g.total, g.exam))
+                })
+                .collect();
+            let keys: Vec<(f64, Option<f64>)> = entries.iter().map(|e| (e.1, e.2)).collect();
+            for (e, position) in entries.iter().zip(positions(&keys, rule)) {
+                subject_positions.insert((e.0, subject.as_str()), position);
+            }
+            let totals = keys.iter().map(|k| k.0);
+            subjects.push(SubjectStatistics {
+                subject: subject.clone(),
+                students: entries.len(),
+                average: round2(totals.clone().sum::<f64>() / entries.len() as f64),
+                highest: totals.clone().fold(f64::MIN, f64::max),
+                lowest: totals.fold(f64::MAX, f64::min),
+            });
+        }
+
+        // Class positions on the mean total; the exam tie-break uses the
+        // mean exam fraction.
+        let keys: Vec<(f64, Option<f64>)> = students
+            .iter()
+            .map(|s| {
+                let average = mean(s.subjects.iter().map(|g| g.total)).unwrap_or(0.0);
+                (round2(average), mean(s.subjects.iter().filter_map(|g| g.exam)))
+            })
+            .collect();
+        let class_positions = positions(&keys, rule);
+        let mut ranked: Vec<StudentPosition> = students
+            .iter()
+            .zip(keys.iter().zip(class_positions))
+            .map(|(s, ((average, _), position))| StudentPosition {
+                student_id: s.student_id,
+                admission_number: s.admission_number.clone(),
+                first_name: s.first_name.clone(),
+                last_name: s.last_name.clone(),
+                average: *average,
+                position,
+                subjects: s
+                    .subjects
+                    .iter()
+                    .map(|g| SubjectPosition {
+                        subject: g.subject.clone(),
+                        total: g.total,
+                        position: subject_positions[&(s.student_id, g.subject.as_str())],
+                    })
+                    .collect(),
+            })
+            .collect();
+        ranked.sort_by_key(|s| s.position);
+
+        Ok(ClassStatisticsResponse {
+            academic_year: class.academic_year,
+            term: class.term,
+            grade_level: class.grade_level,
+            section: class.section,
+            tie_break: rule.as_str().to_string(),
+            show_position: display.show_position.unwrap_or(false),
+            show_class_average: display.show_class_average.unwrap_or(false),
+            show_highest_lowest: display.show_highest_lowest.unwrap_or(false),
+            class_size: ranked.len(),
+            class_average: mean(keys.iter().map(|k| k.0)).map(round2),
+            subjects,
+            students: ranked,
+        })
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, n) = values.fold((0.0, 0), |(sum, n), v| (sum + v, n + 1));
+    (n > 0).then(|| sum / n as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(scores: &[f64]) -> Vec<(f64, Option<f64>)> {
+        scores.iter().map(|s| (*s, None)).collect()
+    }
+
+    #[test]
+    fn shared_and_dense_positions() {
+        let scores = plain(&[70.0, 85.0, 70.0, 60.0]);
+        assert_eq!(positions(&scores, TieBreak::Shared), vec![2, 1, 2, 4]);
+        assert_eq!(positions(&scores, TieBreak::Dense), vec![2, 1, 2, 3]);
+        assert!(positions(&[], TieBreak::Shared).is_empty());
+    }
+
+    #[test]
+    fn exam_score_breaks_ties_then_shares() {
+        let scores = vec![
+            (70.0, Some(0.5)),
+            (70.0, Some(0.8)),
+            (70.0, None),
+            (70.0, Some(0.5)),
+            (90.0, Some(0.1)),
+        ];
+        assert_eq!(positions(&scores, TieBreak::ExamScore), vec![3, 2, 5, 3, 1]);
+        assert_eq!(positions(&scores, TieBreak::Shared), vec![2, 2, 2, 2, 1]);
+    }
+
+    #[test]
+    fn tie_break_parses_known_rules_only() {
+        assert_eq!(TieBreak::parse(" Dense ").unwrap(), TieBreak::Dense);
+        assert_eq!(TieBreak::parse("exam_score").unwrap().as_str(), "exam_score");
+        assert!(TieBreak::parse("random").is_err());
+    }
+}
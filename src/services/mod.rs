@@ -1,4 +1,10 @@
+pub mod attendance;
+pub mod calendar;
+pub mod fees;
+pub mod gradebook;
+pub mod notifications;
 pub mod organization;
+pub mod report_cards;
 pub mod school_setup;
 pub mod students;
 pub mod user;
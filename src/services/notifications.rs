@@ -0,0 +1,96 @@
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+
+/// Channels the outbox can deliver on; anything else in
+/// `notification_channels` is ignored.
+const SUPPORTED_CHANNELS: [&str; 2] = ["email", "sms"];
+
+/// A message about one student for their primary guardian.
+pub(crate) struct GuardianNotice<'a> {
+    pub student_id: Uuid,
+    pub kind: &'a str,
+    pub subject: &'a str,
+    pub body: &'a str,
+    /// Row that caused the notice (e.g. an attendance flag).
+    pub source_id: Option<Uuid>,
+}
+
+/// Queue `notice` for the student's primary guardian on every channel in the
+/// school's `notification_channels` the guardian has a contact for (email →
+/// email address, sms → phone). Falls back to email when no channel is
+/// configured. Returns how many rows were queued; 0 when the student has no
+/// guardian or no usable contact.
+pub(crate) async fn queue_for_primary_guardian(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    notice: &GuardianNotice<'_>,
+) -> Result<u64, AppError> {
+    let configured: Option<serde_json::Value> = sqlx::query_scalar(
+        "SELECT notification_channels FROM school_configs WHERE org_id = $1",
+    )
+    .bind(org_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let mut channels: Vec<&str> = Vec::new();
+    for c in configured.as_ref().and_then(|v| v.as_array()).into_iter().flatten() {
+        let Some(c) = c.as_str() else { continue };
+        if let Some(ch) = SUPPORTED_CHANNELS.iter().copied().find(|s| s.eq_ignore_ascii_case(c.trim()))
+            && !channels.contains(&ch)
+        {
+            channels.push(ch);
+        }
+    }
+    if channels.is_empty() {
+        channels.push("email");
+    }
+
+    // Primary guardian; the first-listed one if none is marked primary.
+    let guardian: Option<(Uuid, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT id, email, phone FROM student_guardians
+        WHERE student_id = $1 AND org_id = $2
+        ORDER BY is_primary DESC, position
+        LIMIT 1
+        "#,
+    )
+    .bind(notice.student_id)
+    .bind(org_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((guardian_id, email, phone)) = guardian else {
+        return Ok(0);
+    };
+
+    let mut queued = 0;
+    for channel in channels {
+        let recipient = match channel {
+            "email" => email.as_deref(),
+            _ => phone.as_deref(),
+        };
+        let Some(recipient) = recipient.map(str::trim).filter(|r| !r.is_empty()) else {
+            continue;
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO notifications
+                (org_id, student_id, guardian_id, kind, channel, recipient, subject, body, source_id)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(org_id)
+        .bind(notice.student_id)
+        .bind(guardian_id)
+        .bind(notice.kind)
+        .bind(channel)
+        .bind(recipient)
+        .bind(notice.subject)
+        .bind(notice.body)
+        .bind(notice.source_id)
+        .execute(&mut *conn)
+        .await?;
+        queued += 1;
+    }
+    Ok(queued)
+}
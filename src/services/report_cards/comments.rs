@@ -0,0 +1,719 @@
+//! Subject-teacher, class-teacher and principal comments per student per
+//! term, and the school's comment bank.
+
+use std::collections::{HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::{ClassResultsQuery, StudentScoresQuery};
+use crate::models::report_cards::{
+    CommentBankQuery, CommentBankResponse, CommentBankRow, CommentSheetEntry, CommentSheetQuery,
+    CommentSheetResponse, CreateCommentBankRequest, SaveCommentSheetRequest, StudentComment,
+    StudentCommentsResponse, UpdateCommentBankRequest,
+};
+use crate::services::gradebook::scale::load_grading_scale;
+use crate::services::gradebook::{canonical_name, SetupList};
+
+use super::roster::ClassTerm;
+use super::ReportCardService;
+
+/// Ceiling while `comment_char_limit` is unset.
+const MAX_COMMENT_CHARS: usize = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    SubjectTeacher,
+    ClassTeacher,
+    Principal,
+}
+
+impl CommentKind {
+    pub fn parse(s: &str) -> Result<Self, AppError> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "subject_teacher" => Ok(Self::SubjectTeacher),
+            "class_teacher" => Ok(Self::ClassTeacher),
+            "principal" => Ok(Self::Principal),
+            other => Err(AppError::BadRequest(format!(
+                "Invalid comment kind '{other}'. Use subject_teacher, class_teacher or principal"
+            ))),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::SubjectTeacher => "subject_teacher",
+            Self::ClassTeacher => "class_teacher",
+            Self::Principal => "principal",
+        }
+    }
+}
+
+/// Validate `comment_char_limit` from the report_card section: a positive
+/// whole number, or blank for none.
+pub(crate) fn parse_char_limit(raw: Option<&str>) -> Result<Option<usize>, AppError> {
+    match raw.map(str::trim).filter(|s| !s.is_empty()) {
+        None => Ok(None),
+        Some(s) => match s.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(Some(n)),
+            _ => Err(AppError::BadRequest(format!(
+                "comment_char_limit must be a positive whole number (got '{s}')"
+            ))),
+        },
+    }
+}
+
+/// Comments for one student's report card.
+#[derive(Debug, Default)]
+pub(super) struct CardComments {
+    /// Subject → subject teacher's comment.
+    pub subjects: HashMap<String, String>,
+    pub class_teacher: Option<String>,
+    pub principal: Option<String>,
+}
+
+#[derive(Default, sqlx::FromRow)]
+struct CommentConfigRow {
+    show_teacher_comments: Option<bool>,
+    show_class_teacher_comment: Option<bool>,
+    comment_char_limit: Option<String>,
+}
+
+struct CommentConfig {
+    show_teacher_comments: bool,
+    show_class_teacher_comment: bool,
+    char_limit: Option<usize>,
+}
+
+impl CommentConfig {
+    fn max_chars(&self) -> usize {
+        self.char_limit.unwrap_or(MAX_COMMENT_CHARS)
+    }
+
+    /// Error unless the report_card section turns this kind on. The
+    /// principal's remark has no toggle.
+    fn ensure_enabled(&self, kind: CommentKind) -> Result<(), AppError> {
+        let (enabled, setting) = match kind {
+            CommentKind::SubjectTeacher => (self.show_teacher_comments, "show_teacher_comments"),
+            CommentKind::ClassTeacher => {
+                (self.show_class_teacher_comment, "show_class_teacher_comment")
+            }
+            CommentKind::Principal => return Ok(()),
+        };
+        if enabled {
+            Ok(())
+        } else {
+            Err(AppError::BadRequest(format!(
+                "{} comments are turned off; enable {setting} in the report_card section",
+                kind.as_str()
+            )))
+        }
+    }
+
+    /// Trimmed comment, checked against the character limit.
+    fn check<'a>(&self, comment: &'a str) -> Result<&'a str, AppError> {
+        let comment = comment.trim();
+        let chars = comment.chars().count();
+        if chars > self.max_chars() {
+            return Err(AppError::BadRequest(format!(
+                "Comment is {chars} characters; the limit is {}",
+                self.max_chars()
+            )));
+        }
+        Ok(comment)
+    }
+
+    /// A comment bank entry: like [`Self::check`], and not blank.
+    fn check_bank<'a>(&self, comment: &'a str) -> Result<&'a str, AppError> {
+        let comment = self.check(comment)?;
+        if comment.is_empty() {
+            return Err(AppError::BadRequest("comment must not be empty".into()));
+        }
+        Ok(comment)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CommentRow {
+    student_id: Uuid,
+    academic_year: String,
+    term: String,
+    kind: String,
+    subject: String,
+    comment: String,
+    updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl ReportCardService {
+    /// Enter or update one kind of comment for a class in one term. A blank
+    /// or null comment removes the student's comment.
+    pub async fn save_comment_sheet(
+        &self,
+        org_id: Uuid,
+        req: SaveCommentSheetRequest,
+        author: Option<Uuid>,
+    ) -> Result<CommentSheetResponse, AppError> {
+        if req.entries.is_empty() {
+            return Err(AppError::BadRequest("entries array is empty".into()));
+        }
+        let kind = CommentKind::parse(&req.kind)?;
+        let config = self.load_comment_config(org_id).await?;
+        config.ensure_enabled(kind)?;
+        let subject = self.comment_subject(org_id, kind, req.subject.as_deref()).await?;
+        let class = self
+            .resolve_class_term(
+                org_id,
+                req.academic_year.as_deref(),
+                &req.term,
+                &req.grade_level,
+                req.section.as_deref(),
+            )
+            .await?;
+
+        // Validate every entry up front so a bad row doesn't leave a half-written sheet.
+        let mut seen: HashSet<Uuid> = HashSet::with_capacity(req.entries.len());
+        let mut changes: Vec<(Uuid, Option<&str>)> = Vec::with_capacity(req.entries.len());
+        for e in &req.entries {
+            if !seen.insert(e.student_id) {
+                return Err(AppError::BadRequest(format!(
+                    "duplicate student_id {} in entries array",
+                    e.student_id
+                )));
+            }
+            let comment = match e.comment.as_deref() {
+                Some(c) => Some(config.check(c).map_err(|err| match err {
+                    AppError::BadRequest(msg) => {
+                        AppError::BadRequest(format!("Student {}: {msg}", e.student_id))
+                    }
+                    other => other,
+                })?),
+                None => None,
+            };
+            changes.push((e.student_id, comment.filter(|c| !c.is_empty())));
+        }
+
+        let ids: Vec<Uuid> = req.entries.iter().map(|e| e.student_id).collect();
+        self.ensure_in_class(org_id, &class, &ids).await?;
+
+        let subject_key = subject.clone().unwrap_or_default();
+        let mut tx = self.pool.begin().await?;
+        for (student_id, comment) in &changes {
+            match comment {
+                Some(comment) => {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO report_card_comments
+                            (org_id, student_id, academic_year, term, kind, subject, comment, author_user_id)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                        ON CONFLICT (student_id, academic_year, term, kind, subject) DO UPDATE SET
+                            comment = EXCLUDED.comment,
+                            author_user_id = EXCLUDED.author_user_id
+                        "#,
+                    )
+                    .bind(org_id)
+                    .bind(student_id)
+                    .bind(&class.academic_year)
+                    .bind(&class.term)
+                    .bind(kind.as_str())
+                    .bind(&subject_key)
+                    .bind(comment)
+                    .bind(author)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query(
+                        r#"
+                        DELETE FROM report_card_comments
+                        WHERE org_id = $1 AND student_id = $2 AND academic_year = $3
+                          AND term = $4 AND kind = $5 AND subject = $6
+                        "#,
+                    )
+                    .bind(org_id)
+                    .bind(student_id)
+                    .bind(&class.academic_year)
+                    .bind(&class.term)
+                    .bind(kind.as_str())
+                    .bind(&subject_key)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        self.comment_sheet(org_id, config, class, kind, subject).await
+    }
+
+    /// Class roster for a term with each student's comment of one kind and
+    /// suggestions from the comment bank for their grade.
+    pub async fn get_comment_sheet(
+        &self,
+        org_id: Uuid,
+        q: CommentSheetQuery,
+    ) -> Result<CommentSheetResponse, AppError> {
+        let kind = CommentKind::parse(&q.kind)?;
+        let config = self.load_comment_config(org_id).await?;
+        let subject = self.comment_subject(org_id, kind, q.subject.as_deref()).await?;
+        let class = self
+            .resolve_class_term(
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        self.comment_sheet(org_id, config, class, kind, subject).await
+    }
+
+    /// Every comment for one student, optionally for one year/term.
+    pub async fn student_comments(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentScoresQuery,
+    ) -> Result<StudentCommentsResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+
+        let rows: Vec<CommentRow> = sqlx::query_as(
+            r#"
+            SELECT c.student_id, c.academic_year, c.term, c.kind, c.subject, c.comment, c.updated_at
+            FROM report_card_comments c
+            LEFT JOIN school_terms t ON t.org_id = c.org_id AND t.name = c.term
+            LEFT JOIN school_subjects sub ON sub.org_id = c.org_id AND sub.name = c.subject
+            WHERE c.org_id = $1 AND c.student_id = $2
+              AND ($3::text IS NULL OR c.academic_year = $3)
+              AND ($4::text IS NULL OR LOWER(c.term) = LOWER($4))
+            ORDER BY c.academic_year, t.position NULLS LAST, c.term,
+                     CASE c.kind WHEN 'subject_teacher' THEN 0 WHEN 'class_teacher' THEN 1 ELSE 2 END,
+                     sub.position NULLS LAST, c.subject
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(q.academic_year.as_deref().map(str::trim).filter(|y| !y.is_empty()))
+        .bind(q.term.as_deref().map(str::trim).filter(|t| !t.is_empty()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let comments = rows
+            .into_iter()
+            .map(|r| StudentComment {
+                academic_year: r.academic_year,
+                term: r.term,
+                kind: r.kind,
+                subject: Some(r.subject).filter(|s| !s.is_empty()),
+                comment: r.comment,
+                updated_at: r.updated_at,
+            })
+            .collect();
+        Ok(StudentCommentsResponse {
+            student_id,
+            comments,
+        })
+    }
+
+    /// Comments for a set of students in one term, for report cards.
+    /// Kinds turned off in the report_card section are left out.
+    pub(super) async fn comments_for_students(
+        &self,
+        org_id: Uuid,
+        student_ids: &[Uuid],
+        academic_year: &str,
+        term: &str,
+    ) -> Result<HashMap<Uuid, CardComments>, AppError> {
+        let config = self.load_comment_config(org_id).await?;
+        let rows: Vec<CommentRow> = sqlx::query_as(
+            r#"
+            SELECT student_id, academic_year, term, kind, subject, comment, updated_at
+            FROM report_card_comments
+            WHERE org_id = $1 AND student_id = ANY($2) AND academic_year = $3 AND term = $4
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_ids)
+        .bind(academic_year)
+        .bind(term)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut out: HashMap<Uuid, CardComments> = HashMap::new();
+        for row in rows {
+            let Ok(kind) = CommentKind::parse(&row.kind) else {
+                continue;
+            };
+            if config.ensure_enabled(kind).is_err() {
+                continue;
+            }
+            let card = out.entry(row.student_id).or_default();
+            match kind {
+                CommentKind::SubjectTeacher => {
+                    card.subjects.insert(row.subject, row.comment);
+                }
+                CommentKind::ClassTeacher => card.class_teacher = Some(row.comment),
+                CommentKind::Principal => card.principal = Some(row.comment),
+            }
+        }
+        Ok(out)
+    }
+
+    /// Comment bank entries, optionally those usable for a kind and grade.
+    pub async fn list_comment_bank(
+        &self,
+        org_id: Uuid,
+        q: CommentBankQuery,
+    ) -> Result<Vec<CommentBankResponse>, AppError> {
+        let kind = match q.kind.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            Some(k) => Some(CommentKind::parse(k)?),
+            None => None,
+        };
+        let rows = self
+            .bank_entries(
+                org_id,
+                kind,
+                q.grade.as_deref().map(str::trim).filter(|g| !g.is_empty()),
+            )
+            .await?;
+        Ok(rows.into_iter().map(CommentBankResponse::from).collect())
+    }
+
+    pub async fn create_comment_bank_entry(
+        &self,
+        org_id: Uuid,
+        req: CreateCommentBankRequest,
+        created_by: Option<Uuid>,
+    ) -> Result<CommentBankResponse, AppError> {
+        let config = self.load_comment_config(org_id).await?;
+        let comment = config.check_bank(&req.comment)?;
+        let kind = match req.kind.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            Some(k) => Some(CommentKind::parse(k)?.as_str()),
+            None => None,
+        };
+        let grade = match req.grade.as_deref().map(str::trim).filter(|g| !g.is_empty()) {
+            Some(g) => Some(self.canonical_grade(org_id, g).await?),
+            None => None,
+        };
+
+        let row: CommentBankRow = sqlx::query_as(
+            r#"
+            INSERT INTO comment_bank (org_id, kind, grade, comment, created_by_user_id)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(org_id)
+        .bind(kind)
+        .bind(grade)
+        .bind(comment)
+        .bind(created_by)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn update_comment_bank_entry(
+        &self,
+        org_id: Uuid,
+        id: Uuid,
+        req: UpdateCommentBankRequest,
+    ) -> Result<CommentBankResponse, AppError> {
+        let existing: CommentBankRow =
+            sqlx::query_as("SELECT * FROM comment_bank WHERE id = $1 AND org_id = $2")
+                .bind(id)
+                .bind(org_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .ok_or_else(|| AppError::NotFound("Comment bank entry not found".into()))?;
+
+        let kind = match req.kind.as_deref().map(str::trim) {
+            None => existing.kind,
+            Some("") => None,
+            Some(k) => Some(CommentKind::parse(k)?.as_str().to_string()),
+        };
+        let grade = match req.grade.as_deref().map(str::trim) {
+            None => existing.grade,
+            Some("") => None,
+            Some(g) => Some(self.canonical_grade(org_id, g).await?),
+        };
+        let comment = match req.comment.as_deref() {
+            None => existing.comment,
+            Some(c) => {
+                let config = self.load_comment_config(org_id).await?;
+                config.check_bank(c)?.to_string()
+            }
+        };
+
+        let row: CommentBankRow = sqlx::query_as(
+            r#"
+            UPDATE comment_bank SET kind = $3, grade = $4, comment = $5
+            WHERE id = $1 AND org_id = $2
+            RETURNING *
+            "#,
+        )
+        .bind(id)
+        .bind(org_id)
+        .bind(kind)
+        .bind(grade)
+        .bind(comment)
+        .fetch_one(&self.pool)
+        .await?;
+        Ok(row.into())
+    }
+
+    pub async fn delete_comment_bank_entry(&self, org_id: Uuid, id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM comment_bank WHERE id = $1 AND org_id = $2")
+            .bind(id)
+            .bind(org_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("Comment bank entry not found".into()));
+        }
+        Ok(())
+    }
+
+    async fn load_comment_config(&self, org_id: Uuid) -> Result<CommentConfig, AppError> {
+        let row: CommentConfigRow = sqlx::query_as(
+            r#"
+            SELECT show_teacher_comments, show_class_teacher_comment, comment_char_limit
+            FROM school_configs WHERE org_id = $1
+            "#,
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or_default();
+        Ok(CommentConfig {
+            show_teacher_comments: row.show_teacher_comments.unwrap_or(false),
+            show_class_teacher_comment: row.show_class_teacher_comment.unwrap_or(false),
+            // Checked on save; an older unparseable value counts as unset.
+            char_limit: parse_char_limit(row.comment_char_limit.as_deref()).ok().flatten(),
+        })
+    }
+
+    /// The configured subject for subject teacher comments; other kinds
+    /// take none.
+    async fn comment_subject(
+        &self,
+        org_id: Uuid,
+        kind: CommentKind,
+        subject: Option<&str>,
+    ) -> Result<Option<String>, AppError> {
+        let subject = subject.map(str::trim).filter(|s| !s.is_empty());
+        match (kind, subject) {
+            (CommentKind::SubjectTeacher, Some(s)) => {
+                Ok(Some(canonical_name(&self.pool, org_id, SetupList::Subjects, s).await?))
+            }
+            (CommentKind::SubjectTeacher, None) => Err(AppError::BadRequest(
+                "subject is required for subject_teacher comments".into(),
+            )),
+            (_, Some(_)) => Err(AppError::BadRequest(format!(
+                "subject only applies to subject_teacher comments, not {}",
+                kind.as_str()
+            ))),
+            (_, None) => Ok(None),
+        }
+    }
+
+    /// A grade's spelling in `school_grading_scales`, matched case-insensitively.
+    async fn canonical_grade(&self, org_id: Uuid, grade: &str) -> Result<String, AppError> {
+        let grades: Vec<String> =
+            sqlx::query_scalar("SELECT grade FROM school_grading_scales WHERE org_id = $1 ORDER BY position")
+                .bind(org_id)
+                .fetch_all(&self.pool)
+                .await?;
+        grades
+            .iter()
+            .map(|g| g.trim())
+            .find(|g| g.eq_ignore_ascii_case(grade))
+            .map(String::from)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Unknown grade '{grade}'; the grading scale has {grades:?}"
+                ))
+            })
+    }
+
+    /// Entries usable for `kind` and `grade`: those for that kind or any kind,
+    /// and that grade or any grade. Grade-specific entries come first.
+    async fn bank_entries(
+        &self,
+        org_id: Uuid,
+        kind: Option<CommentKind>,
+        grade: Option<&str>,
+    ) -> Result<Vec<CommentBankRow>, AppError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT * FROM comment_bank
+            WHERE org_id = $1
+              AND ($2::text IS NULL OR kind IS NULL OR kind = $2)
+              AND ($3::text IS NULL OR grade IS NULL OR LOWER(grade) = LOWER($3))
+            ORDER BY grade IS NULL, kind IS NULL, created_at, id
+            "#,
+        )
+        .bind(org_id)
+        .bind(kind.map(CommentKind::as_str))
+        .bind(grade)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+
+    /// Each student's grade for suggestions: their grade in the subject for
+    /// subject teacher comments, otherwise the grade of their average.
+    async fn sheet_grades(
+        &self,
+        org_id: Uuid,
+        class: &ClassTerm,
+        kind: CommentKind,
+        subject: Option<&str>,
+    ) -> Result<HashMap<Uuid, String>, AppError> {
+        let results = self
+            .gradebook
+            .class_results(
+                org_id,
+                ClassResultsQuery {
+                    academic_year: Some(class.academic_year.clone()),
+                    term: class.term.clone(),
+                    grade_level: class.grade_level.clone(),
+                    section: class.section.clone(),
+                    subject: subject.map(String::from),
+                },
+            )
+            .await;
+        // Without a usable grading section there are no grades to suggest for.
+        let results = match results {
+            Ok(r) => r,
+            Err(AppError::BadRequest(_)) => return Ok(HashMap::new()),
+            Err(e) => return Err(e),
+        };
+        if kind == CommentKind::SubjectTeacher {
+            return Ok(results
+                .students
+                .into_iter()
+                .filter_map(|s| Some((s.student_id, s.subjects.into_iter().next()?.grade)))
+                .collect());
+        }
+        let scale = load_grading_scale(&mut *self.pool.acquire().await?, org_id).await?;
+        Ok(results
+            .students
+            .into_iter()
+            .map(|s| (s.student_id, scale.grade_for(s.average).grade.clone()))
+            .collect())
+    }
+
+    async fn comment_sheet(
+        &self,
+        org_id: Uuid,
+        config: CommentConfig,
+        class: ClassTerm,
+        kind: CommentKind,
+        subject: Option<String>,
+    ) -> Result<CommentSheetResponse, AppError> {
+        let roster = self.class_roster(org_id, &class).await?;
+        let ids: Vec<Uuid> = roster.iter().map(|r| r.id).collect();
+        let mut comments: HashMap<Uuid, String> = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT student_id, comment FROM report_card_comments
+            WHERE org_id = $1 AND student_id = ANY($2) AND academic_year = $3
+              AND term = $4 AND kind = $5 AND subject = $6
+            "#,
+        )
+        .bind(org_id)
+        .bind(&ids)
+        .bind(&class.academic_year)
+        .bind(&class.term)
+        .bind(kind.as_str())
+        .bind(subject.as_deref().unwrap_or_default())
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+        let mut grades = self
+            .sheet_grades(org_id, &class, kind, subject.as_deref())
+            .await?;
+        let bank: Vec<CommentBankRow> = self
+            .bank_entries(org_id, Some(kind), None)
+            .await?
+            .into_iter()
+            .filter(|b| b.comment.chars().count() <= config.max_chars())
+            .collect();
+
+        let entries = roster
+            .into_iter()
+            .map(|r| {
+                let grade = grades.remove(&r.id);
+                let suggestions = suggestions(&bank, grade.as_deref());
+                CommentSheetEntry {
+                    student_id: r.id,
+                    admission_number: r.admission_number,
+                    first_name: r.first_name,
+                    last_name: r.last_name,
+                    grade,
+                    comment: comments.remove(&r.id),
+                    suggestions,
+                }
+            })
+            .collect();
+
+        Ok(CommentSheetResponse {
+            academic_year: class.academic_year,
+            term: class.term,
+            grade_level: class.grade_level,
+            section: class.section,
+            kind: kind.as_str().to_string(),
+            subject,
+            char_limit: config.char_limit,
+            entries,
+        })
+    }
+}
+
+/// Bank comments for a grade, those written for it first; a student without
+/// a grade only gets the ones for any grade.
+fn suggestions(bank: &[CommentBankRow], grade: Option<&str>) -> Vec<String> {
+    let matches = |b: &CommentBankRow| match (&b.grade, grade) {
+        (Some(g), Some(wanted)) => g.eq_ignore_ascii_case(wanted),
+        (Some(_), None) => false,
+        (None, _) => true,
+    };
+    bank.iter().filter(|b| matches(b)).map(|b| b.comment.clone()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_char_limits() {
+        assert_eq!(parse_char_limit(Some(" 200 ")).unwrap(), Some(200));
+        assert_eq!(parse_char_limit(Some("")).unwrap(), None);
+        assert_eq!(parse_char_limit(None).unwrap(), None);
+        assert!(parse_char_limit(Some("0")).is_err());
+        assert!(parse_char_limit(Some("two hundred")).is_err());
+        assert!(parse_char_limit(Some("150.5")).is_err());
+    }
+
+    #[test]
+    fn counts_characters_not_bytes() {
+        let config = CommentConfig {
+            show_teacher_comments: true,
+            show_class_teacher_comment: false,
+            char_limit: Some(5),
+        };
+        assert_eq!(config.check(" Ọlá é ").unwrap(), "Ọlá é");
+        assert!(config.check("Ọlá é!").is_err());
+        assert!(config.ensure_enabled(CommentKind::SubjectTeacher).is_ok());
+        assert!(config.ensure_enabled(CommentKind::ClassTeacher).is_err());
+        assert!(config.ensure_enabled(CommentKind::Principal).is_ok());
+    }
+}
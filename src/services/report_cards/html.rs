@@ -0,0 +1,201 @@
+use std::fmt::Write;
+
+use crate::models::report_cards::{ReportCard, ReportCardTrait};
+
+use super::layout::{
+    class_name, descriptive_lines, number, parse_color, remark_lines, student_name,
+    subject_table, summary_lines, trait_key,
+};
+
+const DEFAULT_COLOR: &str = "#1f3a5f";
+
+/// A self-contained HTML page (inline CSS, no scripts).
+pub(super) fn render(card: &ReportCard) -> String {
+    // Only a parseable hex colour reaches the stylesheet.
+    let color = card
+        .school
+        .primary_color
+        .as_deref()
+        .filter(|c| parse_color(Some(c)).is_some())
+        .unwrap_or(DEFAULT_COLOR);
+    let title = format!("{} — {} {}", student_name(card), card.term, card.academic_year);
+
+    let mut out = String::new();
+    let _ = write!(
+        out,
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{title}</title>
+<style>
+body {{ font-family: Helvetica, Arial, sans-serif; color: #222; margin: 24px; }}
+header {{ border-bottom: 4px solid {color}; padding-bottom: 8px; display: flex; gap: 16px; align-items: center; }}
+header img {{ max-height: 72px; }}
+h1 {{ color: {color}; margin: 0; }}
+h2 {{ color: {color}; font-size: 1.1em; margin-top: 24px; }}
+table {{ border-collapse: collapse; width: 100%; margin-top: 8px; }}
+th {{ background: {color}; color: #fff; text-align: left; }}
+th, td {{ border: 1px solid #ccc; padding: 4px 6px; }}
+.provisional {{ color: #b00; font-weight: bold; }}
+.signatures {{ display: flex; gap: 48px; margin-top: 48px; }}
+.signatures div {{ border-top: 1px solid #222; min-width: 200px; padding-top: 4px; }}
+</style>
+</head>
+<body>
+<header>
+"#,
+        title = escape(&title),
+    );
+    if let Some(logo) = card.school.logo_url.as_deref().filter(|u| is_image_url(u)) {
+        let _ = writeln!(out, r#"<img src="{}" alt="">"#, escape(logo));
+    }
+    let _ = writeln!(out, "<div><h1>{}</h1>", escape(&card.school.name));
+    if let Some(motto) = &card.school.motto {
+        let _ = writeln!(out, "<div><em>{}</em></div>", escape(motto));
+    }
+    let _ = writeln!(out, "</div>\n</header>");
+
+    let _ = writeln!(
+        out,
+        "<h2>Report Card — {} {}</h2>",
+        escape(&card.term),
+        escape(&card.academic_year)
+    );
+    if card.provisional {
+        let _ = writeln!(out, r#"<p class="provisional">Provisional: results not yet published</p>"#);
+    }
+    let _ = writeln!(out, "<table>");
+    for (label, value) in [
+        ("Name", student_name(card)),
+        ("Admission No", card.student.admission_number.clone()),
+        ("Class", class_name(card)),
+        ("Gender", card.student.gender.clone()),
+    ] {
+        let _ = writeln!(out, "<tr><th>{label}</th><td>{}</td></tr>", escape(&value));
+    }
+    let _ = writeln!(out, "</table>");
+
+    let table = subject_table(card);
+    let _ = writeln!(out, "<h2>Results</h2>\n<table>\n<tr>");
+    for h in &table.headings {
+        let _ = write!(out, "<th>{}</th>", escape(h));
+    }
+    let _ = writeln!(out, "</tr>");
+    for row in &table.rows {
+        let _ = write!(out, "<tr>");
+        for cell in row {
+            let _ = write!(out, "<td>{}</td>", escape(cell));
+        }
+        let _ = writeln!(out, "</tr>");
+    }
+    let _ = writeln!(out, "</table>");
+
+    let comments = descriptive_lines(card);
+    if !comments.is_empty() {
+        let _ = writeln!(out, "<h2>Comments</h2>");
+        for line in comments {
+            let _ = writeln!(out, "<p>{}</p>", escape(&line));
+        }
+    }
+
+    let _ = writeln!(out, "<h2>Summary</h2>\n<table>");
+    for (label, value) in summary_lines(card) {
+        let _ = writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(&label), escape(&value));
+    }
+    let _ = writeln!(out, "</table>");
+
+    let traits = [("Psychomotor Skills", &card.psychomotor), ("Affective Traits", &card.affective)];
+    for (heading, traits) in traits {
+        if let Some(traits) = traits {
+            write_traits(&mut out, heading, traits);
+        }
+    }
+    if let Some(key) = trait_key(card) {
+        let _ = writeln!(out, "<p><small>{}</small></p>", escape(&key));
+    }
+
+    let remarks = remark_lines(card);
+    if !remarks.is_empty() {
+        let _ = writeln!(out, "<h2>Remarks</h2>");
+        for (label, comment) in remarks {
+            let _ = writeln!(out, "<p><strong>{}:</strong> {}</p>", escape(&label), escape(&comment));
+        }
+    }
+
+    if let Some(legend) = &card.grading_legend {
+        let _ = writeln!(
+            out,
+            "<h2>Grading Key</h2>\n<table>\n<tr><th>Grade</th><th>Score</th><th>Meaning</th></tr>"
+        );
+        for band in legend {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{}–{}</td><td>{}</td></tr>",
+                escape(&band.grade),
+                number(band.min_score),
+                number(band.max_score),
+                escape(band.descriptor.as_deref().unwrap_or("")),
+            );
+        }
+        let _ = writeln!(out, "</table>");
+    }
+
+    if !card.signatures.is_empty() {
+        let _ = writeln!(out, r#"<div class="signatures">"#);
+        for s in &card.signatures {
+            let _ = writeln!(out, "<div>{}'s signature</div>", escape(s));
+        }
+        let _ = writeln!(out, "</div>");
+    }
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn write_traits(out: &mut String, heading: &str, traits: &[ReportCardTrait]) {
+    let _ = writeln!(out, "<h2>{heading}</h2>\n<table>\n<tr><th>Trait</th><th>Rating</th></tr>");
+    for t in traits {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            escape(&t.name),
+            escape(t.rating.as_deref().unwrap_or("")),
+        );
+    }
+    let _ = writeln!(out, "</table>");
+}
+
+/// Logos are only linked from http(s) URLs.
+fn is_image_url(url: &str) -> bool {
+    let url = url.trim().to_ascii_lowercase();
+    url.starts_with("https://") || url.starts_with("http://")
+}
+
+/// Escape text for HTML element content and double-quoted attributes.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::escape;
+
+    #[test]
+    fn escapes_markup() {
+        assert_eq!(
+            escape(r#"<script>"a" & 'b'</script>"#),
+            "&lt;script&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/script&gt;"
+        );
+    }
+}
@@ -0,0 +1,327 @@
+//! What goes on a report card, shared by the HTML and PDF renderers.
+
+use crate::models::report_cards::ReportCard;
+use crate::services::gradebook::{component_label, COMPONENTS};
+
+/// `report_template` from the report_card section.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum Template {
+    #[default]
+    Standard,
+    /// Always shows the assessment breakdown and grading legend.
+    Detailed,
+    /// Never shows the assessment breakdown or grading legend.
+    Minimal,
+    /// Headings in UK terms ("Attainment", "Mark").
+    UkStyle,
+    /// The grade descriptor is the mark; totals aren't printed.
+    StandardsBased,
+    /// Like standard, with the descriptor written out as a comment line.
+    Descriptive,
+}
+
+impl Template {
+    /// Unknown or unset templates fall back to standard.
+    pub fn parse(s: Option<&str>) -> Self {
+        match s.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            Some("detailed") => Self::Detailed,
+            Some("minimal") => Self::Minimal,
+            Some("uk_style") => Self::UkStyle,
+            Some("standards_based") => Self::StandardsBased,
+            Some("descriptive") => Self::Descriptive,
+            _ => Self::Standard,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Standard => "standard",
+            Self::Detailed => "detailed",
+            Self::Minimal => "minimal",
+            Self::UkStyle => "uk_style",
+            Self::StandardsBased => "standards_based",
+            Self::Descriptive => "descriptive",
+        }
+    }
+
+    pub fn breakdown(self, show_assessment_breakdown: bool) -> bool {
+        match self {
+            Self::Detailed => true,
+            Self::Minimal => false,
+            _ => show_assessment_breakdown,
+        }
+    }
+
+    pub fn legend(self, show_grading_legend: bool) -> bool {
+        match self {
+            Self::Detailed => true,
+            Self::Minimal => false,
+            _ => show_grading_legend,
+        }
+    }
+}
+
+/// Sort key putting components in [`COMPONENTS`] order.
+pub(super) fn component_order(key: &str) -> usize {
+    COMPONENTS.iter().position(|c| *c == key).unwrap_or(COMPONENTS.len())
+}
+
+/// The subject table: headings and one row of cells per subject.
+pub(super) struct SubjectTable {
+    pub headings: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+pub(super) fn subject_table(card: &ReportCard) -> SubjectTable {
+    let template = Template::parse(Some(&card.template));
+    let any = |f: &dyn Fn(&crate::models::report_cards::ReportCardSubject) -> bool| {
+        card.subjects.iter().any(f)
+    };
+    let show_total = template != Template::StandardsBased;
+    let show_position = any(&|s| s.position.is_some());
+    let show_average = any(&|s| s.class_average.is_some());
+    let show_high_low = any(&|s| s.highest.is_some());
+    let show_remark = template != Template::Minimal;
+
+    let mut headings = vec!["Subject".to_string()];
+    headings.extend(card.components.iter().map(|c| component_label(c)));
+    if show_total {
+        headings.push(if template == Template::UkStyle { "Mark (%)" } else { "Total" }.into());
+    }
+    headings.push(
+        match template {
+            Template::UkStyle => "Attainment",
+            Template::StandardsBased => "Level",
+            _ => "Grade",
+        }
+        .into(),
+    );
+    if show_position {
+        headings.push("Position".into());
+    }
+    if show_average {
+        headings.push("Class Avg".into());
+    }
+    if show_high_low {
+        headings.push("Highest".into());
+        headings.push("Lowest".into());
+    }
+    if show_remark {
+        headings.push(if template == Template::StandardsBased { "Standard" } else { "Remark" }.into());
+    }
+    if card.subject_teacher_signature {
+        headings.push("Teacher's Sign".into());
+    }
+
+    let rows = card
+        .subjects
+        .iter()
+        .map(|s| {
+            let mut row = vec![s.subject.clone()];
+            for c in &card.components {
+                row.push(
+                    s.scores
+                        .as_ref()
+                        .and_then(|m| m.get(c))
+                        .map(|v| number(*v))
+                        .unwrap_or_else(|| "-".into()),
+                );
+            }
+            if show_total {
+                row.push(number(s.total));
+            }
+            row.push(s.grade.clone());
+            if show_position {
+                row.push(s.position.map(ordinal).unwrap_or_default());
+            }
+            if show_average {
+                row.push(s.class_average.map(number).unwrap_or_default());
+            }
+            if show_high_low {
+                row.push(s.highest.map(number).unwrap_or_default());
+                row.push(s.lowest.map(number).unwrap_or_default());
+            }
+            if show_remark {
+                row.push(s.descriptor.clone().unwrap_or_default());
+            }
+            if card.subject_teacher_signature {
+                row.push(String::new());
+            }
+            row
+        })
+        .collect();
+    SubjectTable { headings, rows }
+}
+
+/// Label/value lines for the summary block.
+pub(super) fn summary_lines(card: &ReportCard) -> Vec<(String, String)> {
+    let s = &card.summary;
+    let mut lines = Vec::new();
+    if Template::parse(Some(&card.template)) != Template::StandardsBased {
+        lines.push(("Average".into(), number(s.average)));
+    }
+    lines.push((
+        "Subjects passed".into(),
+        format!("{} of {}", s.subjects_passed, s.subjects_taken),
+    ));
+    if let (Some(position), Some(size)) = (s.position, s.class_size) {
+        lines.push(("Position in class".into(), format!("{} of {size}", ordinal(position))));
+    }
+    if let Some(avg) = s.class_average {
+        lines.push(("Class average".into(), number(avg)));
+    }
+    if let Some(gpa) = s.gpa {
+        lines.push(("Term GPA".into(), format!("{gpa:.2}")));
+    }
+    if let Some(gpa) = s.cumulative_gpa {
+        lines.push(("Cumulative GPA".into(), format!("{gpa:.2}")));
+    }
+    if let Some(a) = &card.attendance {
+        lines.push(("Days school opened".into(), a.days_opened.to_string()));
+        lines.push(("Days present".into(), (a.counts.present + a.counts.late).to_string()));
+        lines.push(("Days absent".into(), a.counts.absent.to_string()));
+        if let Some(rate) = a.attendance_rate {
+            lines.push(("Attendance".into(), format!("{}%", number(rate))));
+        }
+    }
+    if let Some(t) = &card.next_term {
+        lines.push((
+            format!("{} begins", t.name),
+            t.start_date.format("%-d %B %Y").to_string(),
+        ));
+    }
+    lines
+}
+
+/// Descriptor comment lines for the descriptive template.
+pub(super) fn descriptive_lines(card: &ReportCard) -> Vec<String> {
+    if Template::parse(Some(&card.template)) != Template::Descriptive {
+        return Vec::new();
+    }
+    card.subjects
+        .iter()
+        .filter_map(|s| Some(format!("{}: {}", s.subject, s.descriptor.as_deref()?)))
+        .collect()
+}
+
+/// Heading/comment pairs for the remarks section: subject teachers' comments
+/// in subject order, then the class teacher's and the principal's.
+pub(super) fn remark_lines(card: &ReportCard) -> Vec<(String, String)> {
+    let mut lines: Vec<(String, String)> = card
+        .subjects
+        .iter()
+        .filter_map(|s| Some((s.subject.clone(), s.comment.clone()?)))
+        .collect();
+    if let Some(c) = &card.class_teacher_comment {
+        lines.push(("Class Teacher's Comment".into(), c.clone()));
+    }
+    if let Some(c) = &card.principal_comment {
+        lines.push(("Principal's Remark".into(), c.clone()));
+    }
+    lines
+}
+
+/// "Key: 5 Excellent, 4 Very Good, …" under the trait tables.
+pub(super) fn trait_key(card: &ReportCard) -> Option<String> {
+    let scale = card.trait_scale.as_ref().filter(|s| !s.is_empty())?;
+    let levels: Vec<String> = scale
+        .iter()
+        .map(|l| match &l.descriptor {
+            Some(d) => format!("{} {d}", l.rating),
+            None => l.rating.clone(),
+        })
+        .collect();
+    Some(format!("Key: {}", levels.join(", ")))
+}
+
+pub(super) fn student_name(card: &ReportCard) -> String {
+    let s = &card.student;
+    full_name(&s.last_name, &s.first_name, s.middle_name.as_deref())
+}
+
+/// Surname first, as printed on school documents.
+pub(super) fn full_name(last: &str, first: &str, middle: Option<&str>) -> String {
+    [Some(last), Some(first), middle]
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub(super) fn class_name(card: &ReportCard) -> String {
+    class_label(&card.student.grade_level, card.student.section.as_deref())
+}
+
+/// `JSS 1 A`, or just the grade level without a section.
+pub(super) fn class_label(grade_level: &str, section: Option<&str>) -> String {
+    match section {
+        Some(section) => format!("{grade_level} {section}"),
+        None => grade_level.to_string(),
+    }
+}
+
+/// `66`, `58.67`: up to two decimals without trailing zeros.
+pub(super) fn number(v: f64) -> String {
+    let s = format!("{v:.2}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+pub(super) fn ordinal(n: u32) -> String {
+    let suffix = match (n % 10, n % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+    format!("{n}{suffix}")
+}
+
+/// `#RRGGBB` or `#RGB` as 0–1 RGB; None for anything else.
+pub(super) fn parse_color(s: Option<&str>) -> Option<(f32, f32, f32)> {
+    let hex = s?.trim().strip_prefix('#')?;
+    // Checked before slicing by byte, so multi-byte characters can't split.
+    if !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    let hex = match hex.len() {
+        3 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 => hex.to_string(),
+        _ => return None,
+    };
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok().map(|v| v as f32 / 255.0);
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_numbers_and_ordinals() {
+        assert_eq!(number(66.0), "66");
+        assert_eq!(number(58.666), "58.67");
+        assert_eq!(number(70.5), "70.5");
+        let ords: Vec<String> = [1, 2, 3, 4, 11, 12, 13, 21, 22, 103].map(ordinal).into();
+        assert_eq!(ords, ["1st", "2nd", "3rd", "4th", "11th", "12th", "13th", "21st", "22nd", "103rd"]);
+    }
+
+    #[test]
+    fn parses_brand_colors() {
+        assert_eq!(parse_color(Some("#ff0000")), Some((1.0, 0.0, 0.0)));
+        assert_eq!(parse_color(Some("#fff")), Some((1.0, 1.0, 1.0)));
+        assert_eq!(parse_color(Some("red")), None);
+        assert_eq!(parse_color(None), None);
+        assert_eq!(parse_color(Some("#aébcd")), None);
+        assert_eq!(parse_color(Some("#éé")), None);
+    }
+
+    #[test]
+    fn templates_override_breakdown_and_legend() {
+        assert!(Template::parse(Some("detailed")).breakdown(false));
+        assert!(!Template::parse(Some("minimal")).legend(true));
+        assert!(Template::parse(Some("standard")).breakdown(true));
+        assert_eq!(Template::parse(Some("unknown")), Template::Standard);
+    }
+}
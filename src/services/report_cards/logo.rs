@@ -0,0 +1,525 @@
+//! The school logo for PDF output: fetched from `logo_url` with a size and
+//! time limit, and turned into image data a PDF can embed. Only JPEG and
+//! non-interlaced PNG are taken; anything else, or a failed fetch, means the
+//! PDF goes out without a logo.
+//!
+//! `logo_url` is set by the school, so the fetch is kept off the server's
+//! own network: redirects aren't followed, and hosts that are or resolve to
+//! loopback, private, link-local or other non-public addresses are refused.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Client, Url};
+use tokio::sync::RwLock;
+
+use crate::config::ReportCardsConfig;
+
+const MAX_BYTES: usize = 512 * 1024;
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Caps decoded PNGs, which can be far larger than the file.
+const MAX_PIXELS: usize = 4096 * 4096;
+/// How long a fetched logo, or a failed fetch, is reused.
+const CACHE_TTL: Duration = Duration::from_secs(600);
+/// Distinct logo URLs kept at once.
+const CACHE_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ColorSpace {
+    Gray,
+    Rgb,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum Encoding {
+    /// The JPEG file itself, embedded with DCTDecode.
+    Jpeg,
+    /// Zlib-compressed 8-bit samples, embedded with FlateDecode.
+    Flate,
+}
+
+/// A decoded logo, ready to embed.
+#[derive(Debug, Clone)]
+pub(super) struct Logo {
+    pub(super) width: u32,
+    pub(super) height: u32,
+    pub(super) color: ColorSpace,
+    pub(super) encoding: Encoding,
+    pub(super) data: Vec<u8>,
+    /// Zlib-compressed 8-bit alpha, for PNGs with transparency.
+    pub(super) alpha: Option<Vec<u8>>,
+}
+
+/// A fetched logo, or None for a failed fetch, and when it was fetched.
+type CachedLogo = (Option<Arc<Logo>>, Instant);
+
+/// Fetches and caches school logos. One client serves every render.
+pub(super) struct LogoFetcher {
+    client: Client,
+    allow_private_hosts: bool,
+    cache: RwLock<HashMap<String, CachedLogo>>,
+}
+
+impl LogoFetcher {
+    pub(super) fn new(config: &ReportCardsConfig) -> Self {
+        let mut builder = Client::builder()
+            .timeout(TIMEOUT)
+            .redirect(redirect::Policy::none())
+            .no_proxy();
+        if !config.allow_private_logo_hosts {
+            builder = builder.dns_resolver(Arc::new(PublicOnly));
+        }
+        Self {
+            client: builder.build().expect("Failed to build HTTP client"),
+            allow_private_hosts: config.allow_private_logo_hosts,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// The decoded logo at `url`, from the cache when fetched recently.
+    /// Failures are logged and give None.
+    pub(super) async fn fetch(&self, url: Option<&str>) -> Option<Arc<Logo>> {
+        let url = url.map(str::trim).filter(|u| !u.is_empty())?;
+        if let Some((logo, at)) = self.cache.read().await.get(url)
+            && at.elapsed() < CACHE_TTL
+        {
+            return logo.clone();
+        }
+
+        let logo = self.fetch_uncached(url).await.map(Arc::new);
+        let mut cache = self.cache.write().await;
+        if cache.len() >= CACHE_CAPACITY {
+            cache.retain(|_, (_, at)| at.elapsed() < CACHE_TTL);
+            if cache.len() >= CACHE_CAPACITY {
+                cache.clear();
+            }
+        }
+        cache.insert(url.to_string(), (logo.clone(), Instant::now()));
+        logo
+    }
+
+    async fn fetch_uncached(&self, url: &str) -> Option<Logo> {
+        let parsed = match self.check_url(url) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                tracing::warn!(url, error = %e, "report card logo refused");
+                return None;
+            }
+        };
+        match self.download(parsed).await {
+            Ok(bytes) => {
+                let logo = decode(&bytes);
+                if logo.is_none() {
+                    tracing::warn!(url, "report card logo is not a JPEG or PNG we can embed");
+                }
+                logo
+            }
+            Err(e) => {
+                tracing::warn!(url, error = %e, "report card logo fetch failed");
+                None
+            }
+        }
+    }
+
+    /// An http(s) URL whose host, if an IP address, is public. Host names
+    /// are checked when the client resolves them.
+    fn check_url(&self, url: &str) -> Result<Url, String> {
+        let parsed = Url::parse(url).map_err(|e| e.to_string())?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err("not an http(s) URL".into());
+        }
+        let ip = match parsed.host() {
+            Some(url::Host::Ipv4(ip)) => Some(IpAddr::V4(ip)),
+            Some(url::Host::Ipv6(ip)) => Some(IpAddr::V6(ip)),
+            Some(url::Host::Domain(_)) => None,
+            None => return Err("no host".into()),
+        };
+        if let Some(ip) = ip
+            && !self.allow_private_hosts
+            && !is_public(ip)
+        {
+            return Err(format!("{ip} is not a public address"));
+        }
+        Ok(parsed)
+    }
+
+    async fn download(&self, url: Url) -> Result<Vec<u8>, String> {
+        let mut response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        // Redirects aren't followed; the target could be anywhere.
+        if !response.status().is_success() {
+            return Err(format!("status {}", response.status()));
+        }
+        if response.content_length().is_some_and(|n| n > MAX_BYTES as u64) {
+            return Err("larger than the limit".into());
+        }
+        let mut bytes = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| e.to_string())? {
+            if bytes.len() + chunk.len() > MAX_BYTES {
+                return Err("larger than the limit".into());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Resolves host names through the system resolver, failing for any name
+/// with a non-public address so the check and the connection can't see
+/// different answers.
+struct PublicOnly;
+
+impl Resolve for PublicOnly {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|a| !is_public(a.ip())) {
+                return Err(format!("{} resolves to {}, not a public address", name.as_str(), addr.ip()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether `ip` is a publicly routable unicast address.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public_v4(v4),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Carrier-grade NAT, 100.64.0.0/10.
+        || (a == 100 && (64..128).contains(&b))
+        // Benchmarking and reserved ranges, 198.18.0.0/15 and 240.0.0.0/4.
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let first = ip.segments()[0];
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        || ip.is_unique_local()
+        || ip.is_unicast_link_local()
+        // Documentation, 2001:db8::/32.
+        || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+}
+
+/// A JPEG or PNG from its bytes, whatever the server called it.
+pub(super) fn decode(bytes: &[u8]) -> Option<Logo> {
+    if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        decode_jpeg(bytes)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        decode_png(bytes)
+    } else {
+        None
+    }
+}
+
+/// Reads the size and components from the frame header; the data itself is
+/// left to the PDF viewer. Grayscale and RGB only.
+fn decode_jpeg(bytes: &[u8]) -> Option<Logo> {
+    let mut i = 2;
+    while i + 4 <= bytes.len() {
+        if bytes[i] != 0xFF {
+            return None;
+        }
+        let marker = bytes[i + 1];
+        if marker == 0xFF {
+            i += 1;
+            continue;
+        }
+        let len = u16::from_be_bytes([bytes[i + 2], bytes[i + 3]]) as usize;
+        // Start-of-frame markers, except DHT (C4), JPG (C8) and DAC (CC).
+        if (0xC0..=0xCF).contains(&marker) && ![0xC4, 0xC8, 0xCC].contains(&marker) {
+            let frame = bytes.get(i + 4..i + 2 + len)?;
+            let height = u16::from_be_bytes([*frame.get(1)?, *frame.get(2)?]) as u32;
+            let width = u16::from_be_bytes([*frame.get(3)?, *frame.get(4)?]) as u32;
+            let color = match frame.get(5)? {
+                1 => ColorSpace::Gray,
+                3 => ColorSpace::Rgb,
+                _ => return None,
+            };
+            if width == 0 || height == 0 || frame[0] != 8 {
+                return None;
+            }
+            return Some(Logo {
+                width,
+                height,
+                color,
+                encoding: Encoding::Jpeg,
+                data: bytes.to_vec(),
+                alpha: None,
+            });
+        }
+        i += 2 + len;
+    }
+    None
+}
+
+/// Decodes a non-interlaced PNG to 8-bit samples, splitting out any alpha.
+fn decode_png(bytes: &[u8]) -> Option<Logo> {
+    let mut i = 8;
+    let (mut width, mut height, mut depth, mut color_type) = (0u32, 0u32, 0u8, 0u8);
+    let mut palette: &[u8] = &[];
+    let mut idat = Vec::new();
+    while i + 8 <= bytes.len() {
+        let len = u32::from_be_bytes(bytes[i..i + 4].try_into().ok()?) as usize;
+        let kind = &bytes[i + 4..i + 8];
+        let data = bytes.get(i + 8..i + 8 + len)?;
+        match kind {
+            b"IHDR" => {
+                width = u32::from_be_bytes(data.get(0..4)?.try_into().ok()?);
+                height = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?);
+                depth = *data.get(8)?;
+                color_type = *data.get(9)?;
+                if *data.get(12)? != 0 {
+                    return None; // interlaced
+                }
+            }
+            b"PLTE" => palette = data,
+            b"IDAT" => idat.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        i += 12 + len;
+    }
+    let (w, h) = (width as usize, height as usize);
+    if w == 0 || h == 0 || w.checked_mul(h)? > MAX_PIXELS {
+        return None;
+    }
+    let channels = match (color_type, depth) {
+        (0, 8 | 16) => 1,
+        (2, 8 | 16) => 3,
+        (3, 1 | 2 | 4 | 8) if !palette.is_empty() => 1,
+        (4, 8 | 16) => 2,
+        (6, 8 | 16) => 4,
+        _ => return None,
+    };
+    let bits_per_pixel = channels * depth as usize;
+    let stride = (w * bits_per_pixel).div_ceil(8);
+    let pixel_bytes = bits_per_pixel.div_ceil(8);
+
+    let mut raw = Vec::with_capacity((stride + 1) * h);
+    ZlibDecoder::new(&idat[..])
+        .take(((stride + 1) * h) as u64)
+        .read_to_end(&mut raw)
+        .ok()?;
+    if raw.len() != (stride + 1) * h {
+        return None;
+    }
+    let rows = unfilter(&raw, stride, pixel_bytes, h)?;
+
+    let (color, out_channels) = match color_type {
+        0 | 4 => (ColorSpace::Gray, 1),
+        _ => (ColorSpace::Rgb, 3),
+    };
+    let mut samples = Vec::with_capacity(w * h * out_channels);
+    let mut alpha = matches!(color_type, 4 | 6).then(|| Vec::with_capacity(w * h));
+    for row in rows.chunks(stride) {
+        for x in 0..w {
+            if color_type == 3 {
+                let per_byte = 8 / depth as usize;
+                let byte = row[x / per_byte];
+                let shift = 8 - depth as usize * (x % per_byte + 1);
+                let index = ((byte >> shift) & ((1u16 << depth) - 1) as u8) as usize;
+                samples.extend_from_slice(palette.get(index * 3..index * 3 + 3)?);
+                continue;
+            }
+            // 16-bit samples keep their high byte.
+            let step = depth as usize / 8;
+            let pixel = &row[x * channels * step..(x + 1) * channels * step];
+            for c in 0..channels {
+                let sample = pixel[c * step];
+                match &mut alpha {
+                    Some(a) if c == channels - 1 => a.push(sample),
+                    _ => samples.push(sample),
+                }
+            }
+        }
+    }
+
+    Some(Logo {
+        width,
+        height,
+        color,
+        encoding: Encoding::Flate,
+        data: compress(&samples)?,
+        alpha: match alpha {
+            Some(a) => Some(compress(&a)?),
+            None => None,
+        },
+    })
+}
+
+/// Undoes PNG's per-row filters, returning the rows without filter bytes.
+fn unfilter(raw: &[u8], stride: usize, bpp: usize, height: usize) -> Option<Vec<u8>> {
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let filter = raw[y * (stride + 1)];
+        let line = &raw[y * (stride + 1) + 1..(y + 1) * (stride + 1)];
+        let (done, rest) = out.split_at_mut(y * stride);
+        let prev = if y == 0 { None } else { Some(&done[(y - 1) * stride..]) };
+        let cur = &mut rest[..stride];
+        for x in 0..stride {
+            let a = if x >= bpp { cur[x - bpp] } else { 0 };
+            let b = prev.map_or(0, |p| p[x]);
+            let c = if x >= bpp { prev.map_or(0, |p| p[x - bpp]) } else { 0 };
+            let predicted = match filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            cur[x] = line[x].wrapping_add(predicted);
+        }
+    }
+    Some(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+fn compress(data: &[u8]) -> Option<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).ok()?;
+    encoder.finish().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(kind: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = (data.len() as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(data);
+        out.extend_from_slice(&[0; 4]); // CRC isn't checked
+        out
+    }
+
+    fn png(width: u32, height: u32, color_type: u8, rows: &[u8]) -> Vec<u8> {
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[8, color_type, 0, 0, 0]);
+        let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+        out.extend(chunk(b"IHDR", &ihdr));
+        out.extend(chunk(b"IDAT", &compress(rows).unwrap()));
+        out.extend(chunk(b"IEND", &[]));
+        out
+    }
+
+    fn inflate(data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        ZlibDecoder::new(data).read_to_end(&mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn decodes_rgba_png_with_filters() {
+        // 2×2 RGBA: row 0 unfiltered, row 1 with the Up filter (+1 on each byte).
+        let rows = [
+            0, 255, 0, 0, 255, 0, 255, 0, 128, //
+            2, 1, 1, 1, 0, 1, 1, 1, 0,
+        ];
+        let logo = decode(&png(2, 2, 6, &rows)).unwrap();
+        assert_eq!((logo.width, logo.height, logo.color), (2, 2, ColorSpace::Rgb));
+        assert_eq!(logo.encoding, Encoding::Flate);
+        assert_eq!(inflate(&logo.data), [255, 0, 0, 0, 255, 0, 0, 1, 1, 1, 0, 1]);
+        assert_eq!(inflate(&logo.alpha.unwrap()), [255, 128, 255, 128]);
+    }
+
+    #[test]
+    fn reads_jpeg_frame_header() {
+        let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x04, 0x00, 0x00];
+        jpeg.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 8, 0x00, 0x30, 0x00, 0x40, 3]);
+        jpeg.extend_from_slice(&[0; 9]);
+        let logo = decode(&jpeg).unwrap();
+        assert_eq!((logo.width, logo.height, logo.color), (64, 48, ColorSpace::Rgb));
+        assert_eq!(logo.encoding, Encoding::Jpeg);
+    }
+
+    #[test]
+    fn tells_public_addresses_from_private_ones() {
+        for ip in ["93.184.215.14", "2606:2800:21f:cb07:6820:80da:af6b:8b2c"] {
+            assert!(is_public(ip.parse().unwrap()), "{ip}");
+        }
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:169.254.169.254",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{ip}");
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_private_hosts() {
+        let fetcher = LogoFetcher::new(&ReportCardsConfig { allow_private_logo_hosts: false });
+        for url in [
+            "http://169.254.169.254/latest/meta-data/",
+            "http://[::1]/logo.png",
+            "file:///etc/passwd",
+        ] {
+            assert!(fetcher.fetch(Some(url)).await.is_none(), "{url}");
+        }
+        let localhost: Name = "localhost".parse().unwrap();
+        assert!(PublicOnly.resolve(localhost).await.is_err());
+        assert!(fetcher.check_url("http://127.0.0.1/logo.png").is_err());
+        assert!(fetcher.check_url("https://cdn.example.com/logo.png").is_ok());
+    }
+
+    #[test]
+    fn rejects_other_images() {
+        assert!(decode(b"GIF89a....").is_none());
+        assert!(decode(b"<svg xmlns=\"http://www.w3.org/2000/svg\"/>").is_none());
+        assert!(decode(&[0xFF, 0xD8, 0xFF]).is_none());
+        // Less image data than the header promises.
+        assert!(decode(&png(2, 2, 6, &[0, 1, 2])).is_none());
+    }
+}
@@ -0,0 +1,526 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+use sqlx::PgPool;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+
+use crate::config::ReportCardsConfig;
+use crate::errors::AppError;
+use crate::models::gradebook::{
+    ClassStatisticsQuery, ClassStatisticsResponse, StudentScoresQuery, StudentStatisticsQuery,
+};
+use crate::models::report_cards::{
+    ClassReportCardsQuery, ReportCard, ReportCardAttendance, ReportCardLegendBand,
+    ReportCardNextTerm, ReportCardQuery, ReportCardSchool, ReportCardStudent, ReportCardSubject,
+    ReportCardSummary,
+};
+use crate::services::attendance::stats::counts_for_students;
+use crate::services::calendar::SchoolCalendar;
+use crate::services::gradebook::scale::load_grading_scale;
+use crate::services::gradebook::GradebookService;
+
+pub(crate) mod comments;
+mod html;
+mod layout;
+mod logo;
+mod pdf;
+mod roster;
+pub(crate) mod traits;
+mod transcript;
+
+use layout::Template;
+use traits::{card_traits, effective_scale, trait_names, Category};
+
+/// Output formats for a report card.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Pdf,
+    Html,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn parse(s: Option<&str>) -> Result<Self, AppError> {
+        match s.map(|s| s.trim().to_ascii_lowercase()).as_deref() {
+            None | Some("") | Some("pdf") => Ok(Self::Pdf),
+            Some("html") => Ok(Self::Html),
+            Some("json") => Ok(Self::Json),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Invalid format '{other}'. Use pdf, html or json"
+            ))),
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Pdf => "application/pdf",
+            Self::Html => "text/html; charset=utf-8",
+            Self::Json => "application/json",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// A rendered file ready to send.
+pub struct ReportFile {
+    pub filename: String,
+    pub content_type: &'static str,
+    pub bytes: Vec<u8>,
+}
+
+pub struct ReportCardService {
+    pool: PgPool,
+    gradebook: GradebookService,
+    logos: logo::LogoFetcher,
+}
+
+impl ReportCardService {
+    pub fn new(pool: PgPool, config: &ReportCardsConfig) -> Self {
+        Self {
+            gradebook: GradebookService::new(pool.clone()),
+            logos: logo::LogoFetcher::new(config),
+            pool,
+        }
+    }
+
+    /// One student's report card for a term, in the class their scores were
+    /// entered for.
+    pub async fn student_report_card(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: &ReportCardQuery,
+    ) -> Result<ReportCard, AppError> {
+        let placement = self
+            .gradebook
+            .student_statistics(
+                org_id,
+                student_id,
+                StudentStatisticsQuery {
+                    academic_year: q.academic_year.clone(),
+                    term: q.term.clone(),
+                },
+            )
+            .await?;
+        let stats = self
+            .gradebook
+            .class_statistics(
+                org_id,
+                ClassStatisticsQuery {
+                    academic_year: Some(placement.academic_year),
+                    term: placement.term,
+                    grade_level: placement.grade_level,
+                    section: placement.section,
+                },
+            )
+            .await?;
+        let mut cards = self.build_cards(org_id, &stats, Some(student_id)).await?;
+        cards
+            .pop()
+            .ok_or_else(|| AppError::NotFound("Student has no results for that term".into()))
+    }
+
+    /// Render one card as PDF or HTML.
+    pub async fn render(
+        &self,
+        card: &ReportCard,
+        format: ReportFormat,
+    ) -> Result<ReportFile, AppError> {
+        let logo = match format {
+            ReportFormat::Pdf => self.logos.fetch(card.school.logo_url.as_deref()).await,
+            _ => None,
+        };
+        render_card(card, format, logo.as_deref())
+    }
+
+    /// Report cards for every student with results in a class, as a ZIP of
+    /// one PDF or HTML file each.
+    pub async fn class_report_cards(
+        &self,
+        org_id: Uuid,
+        q: ClassReportCardsQuery,
+    ) -> Result<ReportFile, AppError> {
+        let format = ReportFormat::parse(q.format.as_deref())?;
+        if format == ReportFormat::Json {
+            return Err(AppError::BadRequest(
+                "Class report cards are available as pdf or html".into(),
+            ));
+        }
+        let stats = self
+            .gradebook
+            .class_statistics(
+                org_id,
+                ClassStatisticsQuery {
+                    academic_year: q.academic_year,
+                    term: q.term,
+                    grade_level: q.grade_level,
+                    section: q.section,
+                },
+            )
+            .await?;
+        if stats.students.is_empty() {
+            return Err(AppError::NotFound("No results for this class and term".into()));
+        }
+        let cards = self.build_cards(org_id, &stats, None).await?;
+        let logo = match (format, cards.first()) {
+            (ReportFormat::Pdf, Some(card)) => self.logos.fetch(card.school.logo_url.as_deref()).await,
+            _ => None,
+        };
+
+        let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        for card in &cards {
+            let file = render_card(card, format, logo.as_deref())?;
+            zip.start_file(file.filename, options)
+                .and_then(|_| zip.write_all(&file.bytes).map_err(Into::into))
+                .map_err(|e| AppError::Internal(format!("report card zip: {e}")))?;
+        }
+        let bytes = zip
+            .finish()
+            .map_err(|e| AppError::Internal(format!("report card zip: {e}")))?
+            .into_inner();
+
+        let mut stem = vec![stats.grade_level.as_str()];
+        stem.extend(stats.section.as_deref());
+        stem.extend([stats.term.as_str(), stats.academic_year.as_str()]);
+        Ok(ReportFile {
+            filename: format!("report_cards_{}.zip", file_stem(&stem)),
+            content_type: "application/zip",
+            bytes,
+        })
+    }
+
+    /// Assemble cards for the class's students (or just `only`), in position
+    /// order.
+    async fn build_cards(
+        &self,
+        org_id: Uuid,
+        stats: &ClassStatisticsResponse,
+        only: Option<Uuid>,
+    ) -> Result<Vec<ReportCard>, AppError> {
+        let settings: SettingsRow = sqlx::query_as(
+            r#"
+            SELECT o.name, c.motto, c.logo_url, c.primary_color, c.current_academic_year,
+                   c.report_template, c.show_assessment_breakdown, c.show_grading_legend,
+                   c.show_gpa, c.show_psychomotor, c.psychomotor_traits,
+                   c.show_affective, c.affective_traits, c.trait_rating_scale,
+                   c.show_principal_signature,
+                   c.show_subject_teacher_signature, c.show_attendance_summary,
+                   c.show_next_term_dates
+            FROM organizations o
+            LEFT JOIN school_configs c ON c.org_id = o.id
+            WHERE o.id = $1
+            "#,
+        )
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        let template = Template::parse(settings.report_template.as_deref());
+        let breakdown = template.breakdown(settings.show_assessment_breakdown.unwrap_or(false));
+        let school = ReportCardSchool {
+            name: settings.name.clone(),
+            motto: settings.motto.clone(),
+            logo_url: settings.logo_url.clone(),
+            primary_color: settings.primary_color.clone(),
+        };
+
+        let ids: Vec<Uuid> = stats
+            .students
+            .iter()
+            .map(|s| s.student_id)
+            .filter(|id| only.is_none_or(|o| o == *id))
+            .collect();
+        let students: Vec<StudentInfoRow> = sqlx::query_as(
+            r#"
+            SELECT id, admission_number, first_name, middle_name, last_name, gender
+            FROM students WHERE org_id = $1 AND id = ANY($2)
+            "#,
+        )
+        .bind(org_id)
+        .bind(&ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let mut students: HashMap<Uuid, StudentInfoRow> =
+            students.into_iter().map(|s| (s.id, s)).collect();
+
+        let grading_legend = if template.legend(settings.show_grading_legend.unwrap_or(false)) {
+            let scale = load_grading_scale(&mut *self.pool.acquire().await?, org_id).await?;
+            Some(
+                scale
+                    .bands()
+                    .iter()
+                    .rev()
+                    .map(|b| ReportCardLegendBand {
+                        grade: b.grade.clone(),
+                        min_score: b.min,
+                        max_score: b.max,
+                        descriptor: b.descriptor.clone(),
+                    })
+                    .collect::<Vec<_>>(),
+            )
+        } else {
+            None
+        };
+
+        // Term dates in school setup belong to the current academic year, so
+        // attendance and next-term dates only appear on current-year cards.
+        let current_year = settings.current_academic_year.as_deref().map(str::trim)
+            == Some(stats.academic_year.as_str());
+        let terms: Vec<(String, Option<chrono::NaiveDate>, Option<chrono::NaiveDate>)> =
+            if current_year {
+                sqlx::query_as(
+                    r#"
+                    SELECT name, start_date, end_date FROM school_terms
+                    WHERE org_id = $1 ORDER BY position
+                    "#,
+                )
+                .bind(org_id)
+                .fetch_all(&self.pool)
+                .await?
+            } else {
+                Vec::new()
+            };
+        let this_term = terms.iter().position(|t| t.0 == stats.term);
+        let next_term = this_term
+            .and_then(|i| terms.get(i + 1))
+            .filter(|_| settings.show_next_term_dates.unwrap_or(false))
+            .and_then(|(name, start, _)| {
+                Some(ReportCardNextTerm {
+                    name: name.clone(),
+                    start_date: (*start)?,
+                })
+            });
+        let term_dates = this_term
+            .and_then(|i| Some((terms[i].1?, terms[i].2?)))
+            .filter(|_| settings.show_attendance_summary.unwrap_or(false));
+        let (days_opened, mut attendance) = match term_dates {
+            Some((from, to)) => {
+                let calendar = SchoolCalendar::load(&mut *self.pool.acquire().await?, org_id, Some((from, to))).await?;
+                let counts = counts_for_students(&self.pool, org_id, &ids, from, to).await?;
+                (calendar.instructional_days(from, to), counts)
+            }
+            None => (0, HashMap::new()),
+        };
+
+        let show_psychomotor = settings.show_psychomotor.unwrap_or(false);
+        let show_affective = settings.show_affective.unwrap_or(false);
+        let psychomotor_traits = trait_names(settings.psychomotor_traits.as_ref());
+        let affective_traits = trait_names(settings.affective_traits.as_ref());
+        let trait_scale = (show_psychomotor || show_affective)
+            .then(|| effective_scale(settings.trait_rating_scale.as_ref()));
+        let mut ratings = if trait_scale.is_some() {
+            self.ratings_for_students(org_id, &ids, &stats.academic_year, &stats.term)
+                .await?
+        } else {
+            HashMap::new()
+        };
+        let mut comments = self
+            .comments_for_students(org_id, &ids, &stats.academic_year, &stats.term)
+            .await?;
+        let mut signatures = Vec::new();
+        if settings.show_principal_signature.unwrap_or(false) {
+            signatures.push("Principal".to_string());
+        }
+
+        let mut results = self
+            .gradebook
+            .results_for_students(
+                org_id,
+                &ids,
+                StudentScoresQuery {
+                    academic_year: Some(stats.academic_year.clone()),
+                    term: Some(stats.term.clone()),
+                },
+            )
+            .await?;
+        let subject_stats: HashMap<&str, _> =
+            stats.subjects.iter().map(|s| (s.subject.as_str(), s)).collect();
+        let mut cards = Vec::with_capacity(ids.len());
+        for standing in stats.students.iter().filter(|s| ids.contains(&s.student_id)) {
+            let Some(info) = students.remove(&standing.student_id) else {
+                continue;
+            };
+            let Some(results) = results.remove(&standing.student_id) else {
+                continue;
+            };
+            let Some(term) = results.terms.into_iter().next() else {
+                continue;
+            };
+            let positions: HashMap<&str, u32> = standing
+                .subjects
+                .iter()
+                .map(|s| (s.subject.as_str(), s.position))
+                .collect();
+
+            let mut remarks = comments.remove(&info.id).unwrap_or_default();
+            let mut components: Vec<String> = Vec::new();
+            let subjects = term
+                .subjects
+                .iter()
+                .map(|r| {
+                    let figures = subject_stats.get(r.subject.as_str());
+                    if breakdown {
+                        for key in r.scores.keys() {
+                            if !components.contains(key) {
+                                components.push(key.clone());
+                            }
+                        }
+                    }
+                    ReportCardSubject {
+                        subject: r.subject.clone(),
+                        scores: breakdown.then(|| r.scores.clone()),
+                        total: r.total,
+                        grade: r.grade.clone(),
+                        descriptor: r.descriptor.clone(),
+                        passed: r.passed,
+                        position: positions
+                            .get(r.subject.as_str())
+                            .copied()
+                            .filter(|_| stats.show_position),
+                        class_average: figures
+                            .map(|f| f.average)
+                            .filter(|_| stats.show_class_average),
+                        highest: figures.map(|f| f.highest).filter(|_| stats.show_highest_lowest),
+                        lowest: figures.map(|f| f.lowest).filter(|_| stats.show_highest_lowest),
+                        comment: remarks.subjects.remove(&r.subject),
+                    }
+                })
+                .collect();
+            components.sort_by_key(|c| layout::component_order(c));
+
+            let show_gpa = settings.show_gpa.unwrap_or(false);
+            let rated = ratings.remove(&info.id);
+            cards.push(ReportCard {
+                school: school.clone(),
+                template: template.as_str().to_string(),
+                academic_year: stats.academic_year.clone(),
+                term: stats.term.clone(),
+                student: ReportCardStudent {
+                    student_id: info.id,
+                    admission_number: info.admission_number,
+                    first_name: info.first_name,
+                    middle_name: info.middle_name,
+                    last_name: info.last_name,
+                    gender: info.gender,
+                    grade_level: stats.grade_level.clone(),
+                    section: stats.section.clone(),
+                },
+                provisional: term.subjects.iter().any(|s| s.sheet_status != "published"),
+                passmark: results.passmark,
+                components,
+                subjects,
+                summary: ReportCardSummary {
+                    average: term.average,
+                    subjects_taken: term.subjects.len(),
+                    subjects_passed: term.subjects_passed,
+                    position: stats.show_position.then_some(standing.position),
+                    class_size: stats.show_position.then_some(stats.class_size),
+                    class_average: stats.class_average.filter(|_| stats.show_class_average),
+                    gpa: term.gpa.filter(|_| show_gpa),
+                    cumulative_gpa: term.cumulative_gpa.filter(|_| show_gpa),
+                },
+                grading_legend: grading_legend.clone(),
+                attendance: term_dates.map(|_| {
+                    let counts = attendance.remove(&info.id).unwrap_or_default();
+                    ReportCardAttendance {
+                        days_opened,
+                        attendance_rate: counts.rate(),
+                        counts,
+                    }
+                }),
+                next_term: next_term.clone(),
+                psychomotor: show_psychomotor.then(|| {
+                    card_traits(Category::Psychomotor, &psychomotor_traits, rated.as_ref())
+                }),
+                affective: show_affective
+                    .then(|| card_traits(Category::Affective, &affective_traits, rated.as_ref())),
+                trait_scale: trait_scale.clone(),
+                class_teacher_comment: remarks.class_teacher,
+                principal_comment: remarks.principal,
+                subject_teacher_signature: settings
+                    .show_subject_teacher_signature
+                    .unwrap_or(false),
+                signatures: signatures.clone(),
+            });
+        }
+        Ok(cards)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SettingsRow {
+    name: String,
+    motto: Option<String>,
+    logo_url: Option<String>,
+    primary_color: Option<String>,
+    current_academic_year: Option<String>,
+    report_template: Option<String>,
+    show_assessment_breakdown: Option<bool>,
+    show_grading_legend: Option<bool>,
+    show_gpa: Option<bool>,
+    show_psychomotor: Option<bool>,
+    psychomotor_traits: Option<serde_json::Value>,
+    show_affective: Option<bool>,
+    affective_traits: Option<serde_json::Value>,
+    trait_rating_scale: Option<serde_json::Value>,
+    show_principal_signature: Option<bool>,
+    show_subject_teacher_signature: Option<bool>,
+    show_attendance_summary: Option<bool>,
+    show_next_term_dates: Option<bool>,
+}
+
+#[derive(sqlx::FromRow)]
+struct StudentInfoRow {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    middle_name: Option<String>,
+    last_name: String,
+    gender: String,
+}
+
+/// One card as PDF or HTML, with the logo already fetched for PDF.
+fn render_card(
+    card: &ReportCard,
+    format: ReportFormat,
+    logo: Option<&logo::Logo>,
+) -> Result<ReportFile, AppError> {
+    let bytes = match format {
+        ReportFormat::Pdf => pdf::render(card, logo),
+        ReportFormat::Html => html::render(card).into_bytes(),
+        ReportFormat::Json => serde_json::to_vec(card)
+            .map_err(|e| AppError::Internal(format!("report card json: {e}")))?,
+    };
+    Ok(ReportFile {
+        filename: format!(
+            "{}.{}",
+            file_stem(&[&card.student.admission_number, &card.term, &card.academic_year]),
+            format.extension()
+        ),
+        content_type: format.content_type(),
+        bytes,
+    })
+}
+
+/// Join parts into a filename-safe stem: `SCH/2025/001`, `First Term` →
+/// `SCH_2025_001_First_Term`.
+pub(crate) fn file_stem(parts: &[&str]) -> String {
+    parts
+        .iter()
+        .map(|p| {
+            p.trim()
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("_")
+}
@@ -0,0 +1,574 @@
+use pdf_writer::{Content, Filter, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+use crate::models::report_cards::{ReportCard, ReportCardSchool, Transcript};
+
+use super::layout::{
+    class_label, class_name, descriptive_lines, full_name, number, parse_color, remark_lines,
+    student_name, subject_table, summary_lines, trait_key,
+};
+use super::logo::{ColorSpace, Encoding, Logo};
+
+// A4 in points.
+const PAGE_W: f32 = 595.0;
+const PAGE_H: f32 = 842.0;
+const MARGIN: f32 = 40.0;
+const CONTENT_W: f32 = PAGE_W - 2.0 * MARGIN;
+const TABLE_SIZE: f32 = 9.0;
+const ROW_H: f32 = 16.0;
+const CELL_PAD: f32 = 4.0;
+
+const REGULAR: Name = Name(b"F1");
+const BOLD: Name = Name(b"F2");
+const LOGO: Name = Name(b"Im1");
+const LOGO_MAX_W: f32 = 120.0;
+
+const DEFAULT_COLOR: (f32, f32, f32) = (0.12, 0.23, 0.37);
+const GRID: (f32, f32, f32) = (0.8, 0.8, 0.8);
+
+/// A4 PDF using the standard Helvetica fonts, so only the logo is
+/// embedded.
+pub(super) fn render(card: &ReportCard, logo: Option<&Logo>) -> Vec<u8> {
+    let mut c = Canvas::with_header(
+        &card.school,
+        logo,
+        &format!("REPORT CARD — {} {}", card.term, card.academic_year),
+    );
+    if card.provisional {
+        c.line(10.0, true, (0.7, 0.0, 0.0), "Provisional: results not yet published");
+    }
+    c.y -= 4.0;
+
+    let details = vec![
+        vec!["Name".to_string(), student_name(card)],
+        vec!["Admission No".to_string(), card.student.admission_number.clone()],
+        vec!["Class".to_string(), class_name(card)],
+        vec!["Gender".to_string(), card.student.gender.clone()],
+    ];
+    c.table(None, &details);
+
+    let table = subject_table(card);
+    c.heading("Results");
+    c.table(Some(&table.headings), &table.rows);
+
+    let comments = descriptive_lines(card);
+    if !comments.is_empty() {
+        c.heading("Comments");
+        for line in comments {
+            c.line(TABLE_SIZE + 1.0, false, (0.13, 0.13, 0.13), &line);
+        }
+    }
+
+    let summary: Vec<Vec<String>> = summary_lines(card)
+        .into_iter()
+        .map(|(label, value)| vec![label, value])
+        .collect();
+    c.heading("Summary");
+    c.table(None, &summary);
+
+    let traits = [("Psychomotor Skills", &card.psychomotor), ("Affective Traits", &card.affective)];
+    for (heading, traits) in traits {
+        if let Some(traits) = traits {
+            let rows: Vec<Vec<String>> = traits
+                .iter()
+                .map(|t| vec![t.name.clone(), t.rating.clone().unwrap_or_default()])
+                .collect();
+            c.heading(heading);
+            c.table(Some(&["Trait".to_string(), "Rating".to_string()]), &rows);
+        }
+    }
+    if let Some(key) = trait_key(card) {
+        c.line(TABLE_SIZE, false, (0.3, 0.3, 0.3), &key);
+    }
+
+    let remarks = remark_lines(card);
+    if !remarks.is_empty() {
+        c.heading("Remarks");
+        for (label, comment) in remarks {
+            c.line(TABLE_SIZE + 1.0, true, (0.13, 0.13, 0.13), &label);
+            c.paragraph(TABLE_SIZE + 1.0, (0.13, 0.13, 0.13), &comment);
+        }
+    }
+
+    if let Some(legend) = &card.grading_legend {
+        let rows: Vec<Vec<String>> = legend
+            .iter()
+            .map(|b| {
+                vec![
+                    b.grade.clone(),
+                    format!("{}–{}", number(b.min_score), number(b.max_score)),
+                    b.descriptor.clone().unwrap_or_default(),
+                ]
+            })
+            .collect();
+        c.heading("Grading Key");
+        c.table(
+            Some(&["Grade".to_string(), "Score".to_string(), "Meaning".to_string()]),
+            &rows,
+        );
+    }
+
+    c.signatures(&card.signatures);
+
+    let title = format!("{} — {} {}", student_name(card), card.term, card.academic_year);
+    c.finish(&title)
+}
+
+/// A4 transcript: the student's details, then one results table per term,
+/// oldest first, under a heading naming the class they were in.
+pub(super) fn render_transcript(t: &Transcript, logo: Option<&Logo>) -> Vec<u8> {
+    let mut c = Canvas::with_header(&t.school, logo, "ACADEMIC TRANSCRIPT");
+    let s = &t.student;
+    let name = full_name(&s.last_name, &s.first_name, s.middle_name.as_deref());
+
+    let mut details = vec![
+        vec!["Name".to_string(), name.clone()],
+        vec!["Admission No".to_string(), s.admission_number.clone()],
+        vec!["Gender".to_string(), s.gender.clone()],
+        vec!["Date of birth".to_string(), s.date_of_birth.format("%-d %B %Y").to_string()],
+        vec!["Enrolled".to_string(), s.enrollment_date.format("%-d %B %Y").to_string()],
+        vec!["Status".to_string(), s.status.clone()],
+    ];
+    if let Some(left) = s.left_on {
+        details.push(vec!["Left".to_string(), left.format("%-d %B %Y").to_string()]);
+    }
+    c.table(None, &details);
+
+    if t.terms.is_empty() {
+        c.heading("Results");
+        c.line(TABLE_SIZE + 1.0, false, (0.13, 0.13, 0.13), "No results recorded.");
+    }
+    let show_points = t.terms.iter().flat_map(|t| &t.subjects).any(|s| s.gpa_points.is_some());
+    let mut headings = vec!["Subject".to_string(), "Total".to_string(), "Grade".to_string()];
+    if show_points {
+        headings.push("Points".to_string());
+    }
+    headings.push("Result".to_string());
+    for term in &t.terms {
+        let mut heading = format!(
+            "{} {} — {}",
+            term.term,
+            term.academic_year,
+            class_label(&term.grade_level, term.section.as_deref())
+        );
+        if term.provisional {
+            heading.push_str(" (provisional)");
+        }
+        c.heading(&heading);
+        let rows: Vec<Vec<String>> = term
+            .subjects
+            .iter()
+            .map(|s| {
+                let mut row = vec![s.subject.clone(), number(s.total), s.grade.clone()];
+                if show_points {
+                    row.push(s.gpa_points.map(number).unwrap_or_default());
+                }
+                row.push(if s.passed { "Pass" } else { "Fail" }.to_string());
+                row
+            })
+            .collect();
+        c.table(Some(&headings), &rows);
+
+        let mut summary = vec![
+            format!("Average {}", number(term.average)),
+            format!("Passed {} of {}", term.subjects_passed, term.subjects.len()),
+        ];
+        if let Some(gpa) = term.gpa {
+            summary.push(format!("Term GPA {gpa:.2}"));
+        }
+        if let Some(gpa) = term.cumulative_gpa {
+            summary.push(format!("Cumulative GPA {gpa:.2}"));
+        }
+        c.line(TABLE_SIZE, false, (0.3, 0.3, 0.3), &summary.join("  •  "));
+    }
+
+    let mut summary = vec![
+        vec!["Terms".to_string(), t.terms.len().to_string()],
+        vec!["Pass mark".to_string(), number(t.passmark)],
+    ];
+    if let Some(gpa) = t.cumulative_gpa {
+        summary.push(vec!["Cumulative GPA".to_string(), format!("{gpa:.2}")]);
+    }
+    summary.push(vec!["Issued".to_string(), t.issued_on.format("%-d %B %Y").to_string()]);
+    c.heading("Summary");
+    c.table(None, &summary);
+    if t.terms.iter().any(|t| t.provisional) {
+        c.line(TABLE_SIZE, true, (0.7, 0.0, 0.0), "Provisional terms include results not yet published.");
+    }
+
+    c.signatures(&["Principal".to_string()]);
+
+    c.finish(&format!("{name} — Transcript"))
+}
+
+/// Pages of content, laid out top to bottom with `y` as the cursor.
+struct Canvas<'a> {
+    pages: Vec<Content>,
+    y: f32,
+    color: (f32, f32, f32),
+    logo: Option<&'a Logo>,
+}
+
+impl<'a> Canvas<'a> {
+    fn new(color: (f32, f32, f32)) -> Self {
+        Self {
+            pages: vec![Content::new()],
+            y: PAGE_H - MARGIN,
+            color,
+            logo: None,
+        }
+    }
+
+    /// A canvas with the school's header band, its logo at the right, and
+    /// the document title.
+    fn with_header(school: &ReportCardSchool, logo: Option<&'a Logo>, title: &str) -> Self {
+        let color = parse_color(school.primary_color.as_deref()).unwrap_or(DEFAULT_COLOR);
+        let mut c = Self::new(color);
+        // Header band in the school colour.
+        let band = if school.motto.is_some() { 62.0 } else { 48.0 };
+        c.fill_rect(0.0, PAGE_H - band, PAGE_W, band, color);
+        if let Some(logo) = logo {
+            // Fit within the band, less a margin, keeping the aspect ratio.
+            let max_h = band - 12.0;
+            let scale = (max_h / logo.height as f32).min(LOGO_MAX_W / logo.width as f32);
+            let (w, h) = (logo.width as f32 * scale, logo.height as f32 * scale);
+            let (x, y) = (PAGE_W - MARGIN - w, PAGE_H - band + (band - h) / 2.0);
+            let page = c.page();
+            page.save_state();
+            page.transform([w, 0.0, 0.0, h, x, y]);
+            page.x_object(LOGO);
+            page.restore_state();
+            c.logo = Some(logo);
+        }
+        c.text(MARGIN, PAGE_H - 32.0, 18.0, true, (1.0, 1.0, 1.0), &school.name);
+        if let Some(motto) = &school.motto {
+            c.text(MARGIN, PAGE_H - 50.0, 10.0, false, (1.0, 1.0, 1.0), motto);
+        }
+        c.y = PAGE_H - band - 28.0;
+        c.line(16.0, true, color, title);
+        c
+    }
+
+    fn page(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("canvas always has a page")
+    }
+
+    /// Start a new page unless `height` still fits above the bottom margin.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height < MARGIN {
+            self.pages.push(Content::new());
+            self.y = PAGE_H - MARGIN;
+        }
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, rgb: (f32, f32, f32), s: &str) {
+        let bytes = win_ansi(s);
+        let page = self.page();
+        page.set_fill_rgb(rgb.0, rgb.1, rgb.2);
+        page.begin_text();
+        page.set_font(if bold { BOLD } else { REGULAR }, size);
+        page.next_line(x, y);
+        page.show(Str(&bytes));
+        page.end_text();
+    }
+
+    /// A line of text at the cursor, moving it down.
+    fn line(&mut self, size: f32, bold: bool, rgb: (f32, f32, f32), s: &str) {
+        self.ensure(size + 6.0);
+        self.y -= size + 4.0;
+        let s = truncate(s, CONTENT_W, size, bold);
+        self.text(MARGIN, self.y, size, bold, rgb, &s);
+        self.y -= 4.0;
+    }
+
+    /// Text wrapped to the content width, over as many lines as it takes.
+    fn paragraph(&mut self, size: f32, rgb: (f32, f32, f32), s: &str) {
+        for line in wrap(s, CONTENT_W, size, false) {
+            self.line(size, false, rgb, &line);
+        }
+    }
+
+    fn heading(&mut self, s: &str) {
+        // Keep a heading with at least two table rows.
+        self.ensure(18.0 + 3.0 * ROW_H);
+        self.y -= 10.0;
+        self.line(11.0, true, self.color, s);
+    }
+
+    /// Signature lines side by side, e.g. "Principal's signature".
+    fn signatures(&mut self, names: &[String]) {
+        if names.is_empty() {
+            return;
+        }
+        self.ensure(60.0);
+        self.y -= 44.0;
+        let width = 180.0;
+        for (i, s) in names.iter().enumerate() {
+            let x = MARGIN + i as f32 * (width + 40.0);
+            self.stroke_line(x, self.y, x + width, self.y, (0.13, 0.13, 0.13));
+            let label = format!("{s}'s signature");
+            self.text(x, self.y - 12.0, TABLE_SIZE, false, (0.13, 0.13, 0.13), &label);
+        }
+    }
+
+    fn fill_rect(&mut self, x: f32, y: f32, w: f32, h: f32, rgb: (f32, f32, f32)) {
+        let page = self.page();
+        page.set_fill_rgb(rgb.0, rgb.1, rgb.2);
+        page.rect(x, y, w, h);
+        page.fill_nonzero();
+    }
+
+    fn stroke_line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, rgb: (f32, f32, f32)) {
+        let page = self.page();
+        page.set_stroke_rgb(rgb.0, rgb.1, rgb.2);
+        page.set_line_width(0.5);
+        page.move_to(x1, y1);
+        page.line_to(x2, y2);
+        page.stroke();
+    }
+
+    /// A grid with an optional coloured heading row, repeated on each page.
+    /// Spare width goes to the first column of a headed table and the last
+    /// column of a label/value one; cells that don't fit are cut.
+    fn table(&mut self, headings: Option<&[String]>, rows: &[Vec<String>]) {
+        let columns = headings
+            .map(|h| h.len())
+            .or_else(|| rows.first().map(Vec::len))
+            .unwrap_or(0);
+        if columns == 0 {
+            return;
+        }
+        let mut widths = vec![0.0f32; columns];
+        for (i, w) in widths.iter_mut().enumerate() {
+            let head = headings.map_or(0.0, |h| text_width(&h[i], TABLE_SIZE, true));
+            let body = rows
+                .iter()
+                .map(|r| r.get(i).map_or(0.0, |s| text_width(s, TABLE_SIZE, false)))
+                .fold(0.0, f32::max);
+            *w = head.max(body) + 2.0 * CELL_PAD;
+        }
+        let total: f32 = widths.iter().sum();
+        if total > CONTENT_W {
+            widths.iter_mut().for_each(|w| *w *= CONTENT_W / total);
+        } else if headings.is_some() {
+            widths[0] += CONTENT_W - total;
+        } else {
+            widths[columns - 1] += CONTENT_W - total;
+        }
+
+        let draw_headings = |c: &mut Canvas| {
+            if let Some(headings) = headings {
+                c.y -= ROW_H;
+                c.fill_rect(MARGIN, c.y, CONTENT_W, ROW_H, c.color);
+                c.cells(headings, &widths, true, (1.0, 1.0, 1.0));
+            }
+        };
+        self.ensure(2.0 * ROW_H);
+        draw_headings(self);
+        for row in rows {
+            if self.y - ROW_H < MARGIN {
+                self.ensure(PAGE_H);
+                draw_headings(self);
+            }
+            self.y -= ROW_H;
+            self.cells(row, &widths, false, (0.13, 0.13, 0.13));
+            self.stroke_line(MARGIN, self.y, MARGIN + CONTENT_W, self.y, GRID);
+        }
+    }
+
+    fn cells(&mut self, cells: &[String], widths: &[f32], bold: bool, rgb: (f32, f32, f32)) {
+        let mut x = MARGIN;
+        for (cell, width) in cells.iter().zip(widths) {
+            let s = truncate(cell, width - 2.0 * CELL_PAD, TABLE_SIZE, bold);
+            self.text(x + CELL_PAD, self.y + 5.0, TABLE_SIZE, bold, rgb, &s);
+            x += width;
+        }
+    }
+
+    fn finish(self, title: &str) -> Vec<u8> {
+        let mut pdf = Pdf::new();
+        let catalog_id = Ref::new(1);
+        let tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let page_ids: Vec<Ref> = (0..self.pages.len() as i32).map(|i| Ref::new(6 + 2 * i)).collect();
+        let logo_id = Ref::new(6 + 2 * page_ids.len() as i32);
+        let alpha_id = Ref::new(logo_id.get() + 1);
+
+        pdf.catalog(catalog_id).pages(tree_id);
+        pdf.pages(tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        for (content, page_id) in self.pages.into_iter().zip(&page_ids) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(*page_id);
+            page.media_box(Rect::new(0.0, 0.0, PAGE_W, PAGE_H));
+            page.parent(tree_id);
+            page.contents(content_id);
+            let mut resources = page.resources();
+            resources.fonts().pair(REGULAR, regular_id).pair(BOLD, bold_id);
+            if self.logo.is_some() {
+                resources.x_objects().pair(LOGO, logo_id);
+            }
+            resources.finish();
+            page.finish();
+            pdf.stream(content_id, &content.finish());
+        }
+        for (id, name) in [(regular_id, "Helvetica"), (bold_id, "Helvetica-Bold")] {
+            pdf.type1_font(id)
+                .base_font(Name(name.as_bytes()))
+                .encoding_predefined(Name(b"WinAnsiEncoding"));
+        }
+        if let Some(logo) = self.logo {
+            let mut image = pdf.image_xobject(logo_id, &logo.data);
+            image.filter(match logo.encoding {
+                Encoding::Jpeg => Filter::DctDecode,
+                Encoding::Flate => Filter::FlateDecode,
+            });
+            image.width(logo.width as i32);
+            image.height(logo.height as i32);
+            match logo.color {
+                ColorSpace::Gray => image.color_space().device_gray(),
+                ColorSpace::Rgb => image.color_space().device_rgb(),
+            }
+            image.bits_per_component(8);
+            if logo.alpha.is_some() {
+                image.s_mask(alpha_id);
+            }
+            image.finish();
+            if let Some(alpha) = &logo.alpha {
+                let mut mask = pdf.image_xobject(alpha_id, alpha);
+                mask.filter(Filter::FlateDecode);
+                mask.width(logo.width as i32);
+                mask.height(logo.height as i32);
+                mask.color_space().device_gray();
+                mask.bits_per_component(8);
+            }
+        }
+        pdf.document_info(info_id).title(TextStr(title));
+        pdf.finish()
+    }
+}
+
+/// Helvetica advance widths (1/1000 em) for ASCII 32..=126.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278, // ' '../
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556, // 0..?
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778, // @..O
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556, // P.._
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556, // `..o
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584, // p..~
+];
+
+/// Approximate rendered width; bold runs about 5% wider.
+fn text_width(s: &str, size: f32, bold: bool) -> f32 {
+    let units: u32 = s
+        .chars()
+        .map(|c| match c as u32 {
+            n @ 32..=126 => u32::from(HELVETICA_WIDTHS[(n - 32) as usize]),
+            _ => 556,
+        })
+        .sum();
+    let width = units as f32 * size / 1000.0;
+    if bold { width * 1.05 } else { width }
+}
+
+/// Break `s` into lines that fit in `max` points, at spaces where possible.
+fn wrap(s: &str, max: f32, size: f32, bold: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    for word in s.split_whitespace() {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{current} {word}")
+        };
+        if text_width(&candidate, size, bold) <= max {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        // A word wider than the line is split wherever it overflows.
+        for c in word.chars() {
+            current.push(c);
+            if text_width(&current, size, bold) > max && current.chars().count() > 1 {
+                current.pop();
+                lines.push(std::mem::replace(&mut current, c.to_string()));
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Cut `s` with an ellipsis so it fits in `max` points.
+fn truncate(s: &str, max: f32, size: f32, bold: bool) -> String {
+    if text_width(s, size, bold) <= max {
+        return s.to_string();
+    }
+    let mut out = String::new();
+    for c in s.chars() {
+        out.push(c);
+        if text_width(&out, size, bold) + text_width("…", size, bold) > max {
+            out.pop();
+            break;
+        }
+    }
+    out.push('…');
+    out
+}
+
+/// Encode for the standard fonts' WinAnsiEncoding; anything outside it
+/// becomes `?`.
+fn win_ansi(s: &str) -> Vec<u8> {
+    s.chars()
+        .map(|c| match c {
+            ' '..='~' | '\u{a0}'..='\u{ff}' => c as u8,
+            '€' => 0x80,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_win_ansi() {
+        assert_eq!(win_ansi("Ọlá — €5"), b"?l\xe1 \x97 \x805".to_vec());
+    }
+
+    #[test]
+    fn truncates_to_width() {
+        assert_eq!(truncate("Maths", 100.0, 9.0, false), "Maths");
+        let cut = truncate("Further Mathematics and Statistics", 60.0, 9.0, false);
+        assert!(cut.ends_with('…'));
+        assert!(text_width(&cut, 9.0, false) <= 60.0);
+    }
+
+    #[test]
+    fn wraps_to_width() {
+        let text = "A diligent student who participates well in class discussions";
+        let lines = wrap(text, 120.0, 9.0, false);
+        assert!(lines.len() > 1);
+        assert!(lines.iter().all(|l| text_width(l, 9.0, false) <= 120.0));
+        assert_eq!(lines.join(" "), text);
+        let long = wrap("Supercalifragilisticexpialidocious", 40.0, 9.0, false);
+        assert!(long.len() > 1);
+        assert_eq!(long.concat(), "Supercalifragilisticexpialidocious");
+        assert!(wrap("  ", 40.0, 9.0, false).is_empty());
+    }
+}
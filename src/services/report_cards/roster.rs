@@ -0,0 +1,110 @@
+//! Class lookups shared by trait ratings and comments.
+
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::services::gradebook::{canonical_name, load_grading_config, SetupList};
+use crate::services::students::validate_grade_level;
+
+use super::ReportCardService;
+
+/// A class and term with names resolved to their configured spelling.
+pub(super) struct ClassTerm {
+    pub academic_year: String,
+    pub term: String,
+    pub grade_level: String,
+    pub section: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+pub(super) struct RosterRow {
+    pub id: Uuid,
+    pub admission_number: String,
+    pub first_name: String,
+    pub last_name: String,
+}
+
+impl ReportCardService {
+    pub(super) async fn resolve_class_term(
+        &self,
+        org_id: Uuid,
+        academic_year: Option<&str>,
+        term: &str,
+        grade_level: &str,
+        section: Option<&str>,
+    ) -> Result<ClassTerm, AppError> {
+        validate_grade_level(&self.pool, org_id, grade_level).await?;
+        let grading = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        Ok(ClassTerm {
+            academic_year: grading.academic_year(academic_year)?,
+            term: canonical_name(&self.pool, org_id, SetupList::Terms, term).await?,
+            grade_level: grade_level.to_string(),
+            section: section
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(String::from),
+        })
+    }
+
+    /// Error unless every student is in this school and currently in the class.
+    pub(super) async fn ensure_in_class(
+        &self,
+        org_id: Uuid,
+        class: &ClassTerm,
+        ids: &[Uuid],
+    ) -> Result<(), AppError> {
+        let placements: Vec<(Uuid, String, Option<String>)> = sqlx::query_as(
+            "SELECT id, grade_level, section FROM students WHERE org_id = $1 AND id = ANY($2)",
+        )
+        .bind(org_id)
+        .bind(ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let placements: HashMap<Uuid, (String, Option<String>)> = placements
+            .into_iter()
+            .map(|(id, g, s)| (id, (g, s)))
+            .collect();
+        for id in ids {
+            let (grade, student_section) = placements
+                .get(id)
+                .ok_or_else(|| AppError::NotFound(format!("Student {id} not found")))?;
+            let in_class = grade == &class.grade_level
+                && class
+                    .section
+                    .as_deref()
+                    .is_none_or(|s| student_section.as_deref() == Some(s));
+            if !in_class {
+                return Err(AppError::BadRequest(format!(
+                    "Student {id} is not in {}{}",
+                    class.grade_level,
+                    class.section.as_deref().map(|s| format!(" {s}")).unwrap_or_default()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Active students currently in the class.
+    pub(super) async fn class_roster(
+        &self,
+        org_id: Uuid,
+        class: &ClassTerm,
+    ) -> Result<Vec<RosterRow>, AppError> {
+        Ok(sqlx::query_as(
+            r#"
+            SELECT id, admission_number, first_name, last_name
+            FROM students
+            WHERE org_id = $1 AND status = 'active' AND grade_level = $2
+              AND ($3::text IS NULL OR section = $3)
+            ORDER BY last_name, first_name
+            "#,
+        )
+        .bind(org_id)
+        .bind(&class.grade_level)
+        .bind(&class.section)
+        .fetch_all(&self.pool)
+        .await?)
+    }
+}
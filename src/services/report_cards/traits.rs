@@ -0,0 +1,516 @@
+//! Psychomotor and affective trait ratings per student per term.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::StudentScoresQuery;
+use crate::models::report_cards::{
+    ReportCardTrait, SaveTraitSheetRequest, StudentTraitRatingsResponse, StudentTraitTerm,
+    TraitRatingLevel, TraitSheetEntry, TraitSheetQuery, TraitSheetResponse,
+};
+use super::roster::ClassTerm;
+use super::ReportCardService;
+
+/// Used while `trait_rating_scale` is empty.
+const DEFAULT_SCALE: [(&str, &str); 5] = [
+    ("5", "Excellent"),
+    ("4", "Very Good"),
+    ("3", "Good"),
+    ("2", "Fair"),
+    ("1", "Poor"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Category {
+    Psychomotor,
+    Affective,
+}
+
+impl Category {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Psychomotor => "psychomotor",
+            Self::Affective => "affective",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "psychomotor" => Some(Self::Psychomotor),
+            "affective" => Some(Self::Affective),
+            _ => None,
+        }
+    }
+}
+
+/// Validate a `trait_rating_scale` from the report_card section. Items are
+/// ratings (`"5"`, `5`) or `{"rating": "5", "descriptor": "Excellent"}`.
+pub(crate) fn parse_trait_scale(v: &serde_json::Value) -> Result<Vec<TraitRatingLevel>, AppError> {
+    let items = v
+        .as_array()
+        .ok_or_else(|| AppError::BadRequest("trait_rating_scale must be an array".into()))?;
+    let mut seen = HashSet::new();
+    let mut levels = Vec::with_capacity(items.len());
+    for item in items {
+        let (rating, descriptor) = match item {
+            serde_json::Value::Object(o) => (o.get("rating"), o.get("descriptor")),
+            other => (Some(other), None),
+        };
+        let rating = match rating {
+            Some(serde_json::Value::String(s)) => s.trim().to_string(),
+            Some(serde_json::Value::Number(n)) => n.to_string(),
+            _ => String::new(),
+        };
+        if rating.is_empty() {
+            return Err(AppError::BadRequest(
+                "Every trait_rating_scale entry needs a rating".into(),
+            ));
+        }
+        if !seen.insert(rating.to_lowercase()) {
+            return Err(AppError::BadRequest(format!(
+                "Rating '{rating}' appears more than once in trait_rating_scale"
+            )));
+        }
+        let descriptor = descriptor
+            .and_then(|d| d.as_str())
+            .map(str::trim)
+            .filter(|d| !d.is_empty())
+            .map(String::from);
+        levels.push(TraitRatingLevel { rating, descriptor });
+    }
+    Ok(levels)
+}
+
+/// Normalised form stored in `school_configs.trait_rating_scale`.
+pub(crate) fn trait_scale_json(levels: &[TraitRatingLevel]) -> serde_json::Value {
+    serde_json::to_value(levels).unwrap_or_else(|_| serde_json::json!([]))
+}
+
+/// The configured scale, or the default one while none is set.
+pub(super) fn effective_scale(v: Option<&serde_json::Value>) -> Vec<TraitRatingLevel> {
+    let configured = v.and_then(|v| parse_trait_scale(v).ok()).unwrap_or_default();
+    if !configured.is_empty() {
+        return configured;
+    }
+    DEFAULT_SCALE
+        .iter()
+        .map(|(rating, descriptor)| TraitRatingLevel {
+            rating: rating.to_string(),
+            descriptor: Some(descriptor.to_string()),
+        })
+        .collect()
+}
+
+/// Trait names from `psychomotor_traits` / `affective_traits`.
+pub(super) fn trait_names(v: Option<&serde_json::Value>) -> Vec<String> {
+    v.and_then(|v| v.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter_map(|t| t.as_str())
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// One student's ratings for a term: (category, lowercased trait) → rating.
+pub(super) type Ratings = HashMap<(Category, String), String>;
+
+#[derive(Default, sqlx::FromRow)]
+struct TraitConfigRow {
+    psychomotor_traits: Option<serde_json::Value>,
+    affective_traits: Option<serde_json::Value>,
+    trait_rating_scale: Option<serde_json::Value>,
+}
+
+struct TraitConfig {
+    psychomotor: Vec<String>,
+    affective: Vec<String>,
+    scale: Vec<TraitRatingLevel>,
+}
+
+impl TraitConfig {
+    fn traits(&self, category: Category) -> &[String] {
+        match category {
+            Category::Psychomotor => &self.psychomotor,
+            Category::Affective => &self.affective,
+        }
+    }
+
+    /// Configured spelling of a trait, matched case-insensitively.
+    fn canonical_trait(&self, category: Category, name: &str) -> Result<&str, AppError> {
+        let list = self.traits(category);
+        if list.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "No {0} traits are configured; set {0}_traits in the report_card section first",
+                category.as_str()
+            )));
+        }
+        let wanted = name.trim();
+        list.iter()
+            .find(|t| t.eq_ignore_ascii_case(wanted))
+            .map(String::as_str)
+            .ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Unknown {} trait '{wanted}'; configured traits are {list:?}",
+                    category.as_str()
+                ))
+            })
+    }
+
+    fn canonical_rating(&self, trait_name: &str, rating: &str) -> Result<&str, AppError> {
+        let wanted = rating.trim();
+        self.scale
+            .iter()
+            .find(|l| l.rating.eq_ignore_ascii_case(wanted))
+            .map(|l| l.rating.as_str())
+            .ok_or_else(|| {
+                let allowed: Vec<&str> = self.scale.iter().map(|l| l.rating.as_str()).collect();
+                AppError::BadRequest(format!(
+                    "Rating '{wanted}' for {trait_name} is not on the trait rating scale; use one of {allowed:?}"
+                ))
+            })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RatingRow {
+    student_id: Uuid,
+    academic_year: String,
+    term: String,
+    category: String,
+    trait_name: String,
+    rating: String,
+}
+
+impl ReportCardService {
+    /// Enter or update trait ratings for a class in one term. Ratings are
+    /// upserted per student and trait, so classes can be rated in passes.
+    pub async fn save_trait_sheet(
+        &self,
+        org_id: Uuid,
+        req: SaveTraitSheetRequest,
+        rated_by: Option<Uuid>,
+    ) -> Result<TraitSheetResponse, AppError> {
+        if req.entries.is_empty() {
+            return Err(AppError::BadRequest("entries array is empty".into()));
+        }
+        let config = self.load_trait_config(org_id).await?;
+        let class = self
+            .resolve_class_term(
+                org_id,
+                req.academic_year.as_deref(),
+                &req.term,
+                &req.grade_level,
+                req.section.as_deref(),
+            )
+            .await?;
+
+        // Validate every entry up front so a bad row doesn't leave a half-written sheet.
+        let mut seen: HashSet<Uuid> = HashSet::with_capacity(req.entries.len());
+        let mut changes: Vec<(Uuid, Category, String, Option<String>)> = Vec::new();
+        for e in &req.entries {
+            if !seen.insert(e.student_id) {
+                return Err(AppError::BadRequest(format!(
+                    "duplicate student_id {} in entries array",
+                    e.student_id
+                )));
+            }
+            for (category, ratings) in [
+                (Category::Psychomotor, &e.psychomotor),
+                (Category::Affective, &e.affective),
+            ] {
+                for (name, rating) in ratings {
+                    let name = config.canonical_trait(category, name)?;
+                    let rating = match rating.as_deref().map(str::trim).filter(|r| !r.is_empty()) {
+                        Some(r) => Some(config.canonical_rating(name, r)?.to_string()),
+                        None => None,
+                    };
+                    changes.push((e.student_id, category, name.to_string(), rating));
+                }
+            }
+        }
+
+        let ids: Vec<Uuid> = req.entries.iter().map(|e| e.student_id).collect();
+        self.ensure_in_class(org_id, &class, &ids).await?;
+
+        let mut tx = self.pool.begin().await?;
+        for (student_id, category, name, rating) in &changes {
+            match rating {
+                Some(rating) => {
+                    sqlx::query(
+                        r#"
+                        INSERT INTO student_trait_ratings
+                            (org_id, student_id, academic_year, term, category, trait, rating, rated_by_user_id)
+                        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                        ON CONFLICT (student_id, academic_year, term, category, trait) DO UPDATE SET
+                            rating = EXCLUDED.rating,
+                            rated_by_user_id = EXCLUDED.rated_by_user_id
+                        "#,
+                    )
+                    .bind(org_id)
+                    .bind(student_id)
+                    .bind(&class.academic_year)
+                    .bind(&class.term)
+                    .bind(category.as_str())
+                    .bind(name)
+                    .bind(rating)
+                    .bind(rated_by)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                None => {
+                    sqlx::query(
+                        r#"
+                        DELETE FROM student_trait_ratings
+                        WHERE org_id = $1 AND student_id = $2 AND academic_year = $3
+                          AND term = $4 AND category = $5 AND trait = $6
+                        "#,
+                    )
+                    .bind(org_id)
+                    .bind(student_id)
+                    .bind(&class.academic_year)
+                    .bind(&class.term)
+                    .bind(category.as_str())
+                    .bind(name)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+            }
+        }
+        tx.commit().await?;
+
+        self.trait_sheet(org_id, config, class).await
+    }
+
+    /// Class roster for a term with each student's trait ratings.
+    pub async fn get_trait_sheet(
+        &self,
+        org_id: Uuid,
+        q: TraitSheetQuery,
+    ) -> Result<TraitSheetResponse, AppError> {
+        let config = self.load_trait_config(org_id).await?;
+        let class = self
+            .resolve_class_term(
+                org_id,
+                q.academic_year.as_deref(),
+                &q.term,
+                &q.grade_level,
+                q.section.as_deref(),
+            )
+            .await?;
+        self.trait_sheet(org_id, config, class).await
+    }
+
+    /// Every term's trait ratings for one student, optionally for one year/term.
+    pub async fn student_trait_ratings(
+        &self,
+        org_id: Uuid,
+        student_id: Uuid,
+        q: StudentScoresQuery,
+    ) -> Result<StudentTraitRatingsResponse, AppError> {
+        let exists: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM students WHERE id = $1 AND org_id = $2)",
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_one(&self.pool)
+        .await?;
+        if !exists {
+            return Err(AppError::NotFound("Student not found".into()));
+        }
+
+        let rows: Vec<RatingRow> = sqlx::query_as(
+            r#"
+            SELECT r.student_id, r.academic_year, r.term, r.category, r.trait AS trait_name, r.rating
+            FROM student_trait_ratings r
+            LEFT JOIN school_terms t ON t.org_id = r.org_id AND t.name = r.term
+            WHERE r.org_id = $1 AND r.student_id = $2
+              AND ($3::text IS NULL OR r.academic_year = $3)
+              AND ($4::text IS NULL OR LOWER(r.term) = LOWER($4))
+            ORDER BY r.academic_year, t.position NULLS LAST, r.term
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .bind(q.academic_year.as_deref().map(str::trim).filter(|y| !y.is_empty()))
+        .bind(q.term.as_deref().map(str::trim).filter(|t| !t.is_empty()))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut terms: Vec<StudentTraitTerm> = Vec::new();
+        for row in rows {
+            let same = terms
+                .last()
+                .is_some_and(|t| t.academic_year == row.academic_year && t.term == row.term);
+            if !same {
+                terms.push(StudentTraitTerm {
+                    academic_year: row.academic_year.clone(),
+                    term: row.term.clone(),
+                    psychomotor: BTreeMap::new(),
+                    affective: BTreeMap::new(),
+                });
+            }
+            if let Some(t) = terms.last_mut() {
+                match Category::parse(&row.category) {
+                    Some(Category::Psychomotor) => t.psychomotor.insert(row.trait_name, row.rating),
+                    Some(Category::Affective) => t.affective.insert(row.trait_name, row.rating),
+                    None => None,
+                };
+            }
+        }
+        Ok(StudentTraitRatingsResponse { student_id, terms })
+    }
+
+    /// Ratings for a set of students in one term, for report cards.
+    pub(super) async fn ratings_for_students(
+        &self,
+        org_id: Uuid,
+        student_ids: &[Uuid],
+        academic_year: &str,
+        term: &str,
+    ) -> Result<HashMap<Uuid, Ratings>, AppError> {
+        let rows: Vec<RatingRow> = sqlx::query_as(
+            r#"
+            SELECT student_id, academic_year, term, category, trait AS trait_name, rating
+            FROM student_trait_ratings
+            WHERE org_id = $1 AND student_id = ANY($2) AND academic_year = $3 AND term = $4
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_ids)
+        .bind(academic_year)
+        .bind(term)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut out: HashMap<Uuid, Ratings> = HashMap::new();
+        for row in rows {
+            if let Some(category) = Category::parse(&row.category) {
+                out.entry(row.student_id)
+                    .or_default()
+                    .insert((category, row.trait_name.to_lowercase()), row.rating);
+            }
+        }
+        Ok(out)
+    }
+
+    async fn load_trait_config(&self, org_id: Uuid) -> Result<TraitConfig, AppError> {
+        let row: TraitConfigRow = sqlx::query_as(
+            r#"
+            SELECT psychomotor_traits, affective_traits, trait_rating_scale
+            FROM school_configs WHERE org_id = $1
+            "#,
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or_default();
+        Ok(TraitConfig {
+            psychomotor: trait_names(row.psychomotor_traits.as_ref()),
+            affective: trait_names(row.affective_traits.as_ref()),
+            scale: effective_scale(row.trait_rating_scale.as_ref()),
+        })
+    }
+
+    async fn trait_sheet(
+        &self,
+        org_id: Uuid,
+        config: TraitConfig,
+        class: ClassTerm,
+    ) -> Result<TraitSheetResponse, AppError> {
+        let roster = self.class_roster(org_id, &class).await?;
+        let ids: Vec<Uuid> = roster.iter().map(|r| r.id).collect();
+        let mut ratings = self
+            .ratings_for_students(org_id, &ids, &class.academic_year, &class.term)
+            .await?;
+
+        let entries = roster
+            .into_iter()
+            .map(|r| {
+                let rated = ratings.remove(&r.id).unwrap_or_default();
+                let pick = |category: Category| -> BTreeMap<String, String> {
+                    config
+                        .traits(category)
+                        .iter()
+                        .filter_map(|t| {
+                            let rating = rated.get(&(category, t.to_lowercase()))?;
+                            Some((t.clone(), rating.clone()))
+                        })
+                        .collect()
+                };
+                TraitSheetEntry {
+                    student_id: r.id,
+                    admission_number: r.admission_number,
+                    first_name: r.first_name,
+                    last_name: r.last_name,
+                    psychomotor: pick(Category::Psychomotor),
+                    affective: pick(Category::Affective),
+                }
+            })
+            .collect();
+
+        Ok(TraitSheetResponse {
+            academic_year: class.academic_year,
+            term: class.term,
+            grade_level: class.grade_level,
+            section: class.section,
+            scale: config.scale,
+            psychomotor_traits: config.psychomotor,
+            affective_traits: config.affective,
+            entries,
+        })
+    }
+}
+
+/// Configured traits with the student's ratings, for a report card.
+pub(super) fn card_traits(
+    category: Category,
+    names: &[String],
+    ratings: Option<&Ratings>,
+) -> Vec<ReportCardTrait> {
+    names
+        .iter()
+        .map(|name| ReportCardTrait {
+            name: name.clone(),
+            rating: ratings.and_then(|r| r.get(&(category, name.to_lowercase())).cloned()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn parses_trait_scales() {
+        let scale = parse_trait_scale(&json!([
+            { "rating": "A", "descriptor": " Outstanding " },
+            "B",
+            3
+        ]))
+        .unwrap();
+        let ratings: Vec<&str> = scale.iter().map(|l| l.rating.as_str()).collect();
+        assert_eq!(ratings, ["A", "B", "3"]);
+        assert_eq!(scale[0].descriptor.as_deref(), Some("Outstanding"));
+        assert!(scale[1].descriptor.is_none());
+
+        assert!(parse_trait_scale(&json!("A")).is_err());
+        assert!(parse_trait_scale(&json!([{ "descriptor": "No rating" }])).is_err());
+        assert!(parse_trait_scale(&json!(["a", "A"])).is_err());
+    }
+
+    #[test]
+    fn empty_scale_falls_back_to_default() {
+        let scale = effective_scale(Some(&json!([])));
+        assert_eq!(scale.len(), 5);
+        assert_eq!(scale[0].rating, "5");
+        assert_eq!(scale[0].descriptor.as_deref(), Some("Excellent"));
+        assert_eq!(effective_scale(None).len(), 5);
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::StudentScoresQuery;
+use crate::models::report_cards::{
+    ReportCardSchool, Transcript, TranscriptStudent, TranscriptSubject, TranscriptTerm,
+};
+use crate::services::calendar::SchoolCalendar;
+
+use super::{file_stem, pdf, ReportCardService, ReportFile, ReportFormat};
+
+#[derive(sqlx::FromRow)]
+struct TranscriptStudentRow {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    middle_name: Option<String>,
+    last_name: String,
+    gender: String,
+    date_of_birth: NaiveDate,
+    enrollment_date: NaiveDate,
+    status: String,
+    left_on: Option<NaiveDate>,
+    school_name: String,
+    motto: Option<String>,
+    logo_url: Option<String>,
+    primary_color: Option<String>,
+}
+
+/// The sheets a student's scores for one term were entered on.
+#[derive(sqlx::FromRow)]
+struct TermSheetRow {
+    academic_year: String,
+    term: String,
+    /// When the first score of the term was entered.
+    entered_on: NaiveDate,
+    grade_level: String,
+    section: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct ClassChange {
+    effective_date: NaiveDate,
+    /// The academic year a promotion closed; None for manual class changes
+    /// and for batches run before it was recorded.
+    academic_year: Option<String>,
+    from_grade_level: Option<String>,
+    from_section: Option<String>,
+    to_grade_level: Option<String>,
+    to_section: Option<String>,
+}
+
+impl ReportCardService {
+    /// A student's results over every term they have scores in, whatever
+    /// their status, so graduated and transferred students keep theirs.
+    ///
+    /// Each term shows the class the student spent its academic year in,
+    /// from `student_class_history`; without history it is the class of the
+    /// sheets the scores are on.
+    pub async fn transcript(&self, org_id: Uuid, student_id: Uuid) -> Result<Transcript, AppError> {
+        let row: TranscriptStudentRow = sqlx::query_as(
+            r#"
+            SELECT s.id, s.admission_number, s.first_name, s.middle_name, s.last_name, s.gender,
+                   s.date_of_birth, s.enrollment_date, s.status,
+                   CASE WHEN s.status IN ('graduated', 'transferred', 'withdrawn') THEN COALESCE(
+                       (SELECT h.effective_date FROM student_status_history h
+                        WHERE h.student_id = s.id AND h.to_status = s.status
+                        ORDER BY h.changed_at DESC LIMIT 1),
+                       s.graduation_date,
+                       s.withdrawn_at::date
+                   ) END AS left_on,
+                   o.name AS school_name, c.motto, c.logo_url, c.primary_color
+            FROM students s
+            JOIN organizations o ON o.id = s.org_id
+            LEFT JOIN school_configs c ON c.org_id = s.org_id
+            WHERE s.id = $1 AND s.org_id = $2
+            "#,
+        )
+        .bind(student_id)
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Student not found".into()))?;
+
+        let results = self
+            .gradebook
+            .student_results(
+                org_id,
+                student_id,
+                StudentScoresQuery {
+                    academic_year: None,
+                    term: None,
+                },
+            )
+            .await?;
+
+        let sheets: Vec<TermSheetRow> = sqlx::query_as(
+            r#"
+            SELECT sc.academic_year, sc.term, MIN(sc.created_at)::date AS entered_on,
+                   MODE() WITHIN GROUP (ORDER BY sh.grade_level) AS grade_level,
+                   MODE() WITHIN GROUP (ORDER BY sh.section) AS section
+            FROM gradebook_scores sc
+            JOIN gradebook_sheets sh ON sh.id = sc.sheet_id
+            WHERE sc.org_id = $1 AND sc.student_id = $2
+            GROUP BY sc.academic_year, sc.term
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let sheets: HashMap<(String, String), TermSheetRow> = sheets
+            .into_iter()
+            .map(|s| ((s.academic_year.clone(), s.term.clone()), s))
+            .collect();
+        let history: Vec<ClassChange> = sqlx::query_as(
+            r#"
+            SELECT effective_date, academic_year,
+                   from_grade_level, from_section, to_grade_level, to_section
+            FROM student_class_history
+            WHERE org_id = $1 AND student_id = $2
+            ORDER BY effective_date, changed_at
+            "#,
+        )
+        .bind(org_id)
+        .bind(student_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut terms = Vec::with_capacity(results.terms.len());
+        for t in results.terms {
+            let sheet = sheets.get(&(t.academic_year.clone(), t.term.clone()));
+            let (grade_level, section) = sheet
+                .and_then(|s| class_for_term(&history, &t.academic_year, s.entered_on))
+                .or_else(|| sheet.map(|s| (s.grade_level.clone(), s.section.clone())))
+                .unwrap_or_default();
+            terms.push(TranscriptTerm {
+                provisional: t.subjects.iter().any(|s| s.sheet_status != "published"),
+                subjects: t
+                    .subjects
+                    .into_iter()
+                    .map(|s| TranscriptSubject {
+                        subject: s.subject,
+                        total: s.total,
+                        grade: s.grade,
+                        gpa_points: s.gpa_points,
+                        passed: s.passed,
+                    })
+                    .collect(),
+                academic_year: t.academic_year,
+                term: t.term,
+                grade_level,
+                section,
+                average: t.average,
+                subjects_passed: t.subjects_passed,
+                gpa: t.gpa,
+                cumulative_gpa: t.cumulative_gpa,
+            });
+        }
+
+        let calendar = SchoolCalendar::load(&mut *self.pool.acquire().await?, org_id, None).await?;
+        Ok(Transcript {
+            school: ReportCardSchool {
+                name: row.school_name,
+                motto: row.motto,
+                logo_url: row.logo_url,
+                primary_color: row.primary_color,
+            },
+            student: TranscriptStudent {
+                student_id: row.id,
+                admission_number: row.admission_number,
+                first_name: row.first_name,
+                middle_name: row.middle_name,
+                last_name: row.last_name,
+                gender: row.gender,
+                date_of_birth: row.date_of_birth,
+                enrollment_date: row.enrollment_date,
+                status: row.status,
+                left_on: row.left_on,
+            },
+            passmark: results.passmark,
+            cumulative_gpa: results.cumulative_gpa,
+            terms,
+            issued_on: calendar.today(),
+        })
+    }
+
+    /// Render a transcript as PDF or JSON.
+    pub async fn render_transcript(
+        &self,
+        transcript: &Transcript,
+        format: ReportFormat,
+    ) -> Result<ReportFile, AppError> {
+        let bytes = match format {
+            ReportFormat::Pdf => {
+                let logo = self.logos.fetch(transcript.school.logo_url.as_deref()).await;
+                pdf::render_transcript(transcript, logo.as_deref())
+            }
+            ReportFormat::Json => serde_json::to_vec(transcript)
+                .map_err(|e| AppError::Internal(format!("transcript json: {e}")))?,
+            ReportFormat::Html => {
+                return Err(AppError::BadRequest(
+                    "Transcripts are available as pdf or json".into(),
+                ))
+            }
+        };
+        Ok(ReportFile {
+            filename: format!(
+                "{}.{}",
+                file_stem(&["transcript", &transcript.student.admission_number]),
+                format.extension()
+            ),
+            content_type: format.content_type(),
+            bytes,
+        })
+    }
+}
+
+type Class = (String, Option<String>);
+
+/// The class a student spent `academic_year` in, by their class history,
+/// oldest change first. A promotion that closed that year says where they
+/// came from, however late the term's scores were entered. Otherwise the
+/// last promotion closing an earlier year says where they went, unless a
+/// change without a year, dated after it, places them by `entered_on`.
+/// Academic years compare as text, so "2024/2025" sorts before "2025/2026".
+fn class_for_term(history: &[ClassChange], academic_year: &str, entered_on: NaiveDate) -> Option<Class> {
+    if let Some(class) = history
+        .iter()
+        .find(|c| c.academic_year.as_deref() == Some(academic_year))
+        .and_then(from_class)
+    {
+        return Some(class);
+    }
+    let earlier = history
+        .iter()
+        .rev()
+        .find(|c| c.academic_year.as_deref().is_some_and(|y| y < academic_year));
+    let undated: Vec<&ClassChange> = history
+        .iter()
+        .filter(|c| c.academic_year.is_none())
+        .filter(|c| earlier.is_none_or(|e| c.effective_date > e.effective_date))
+        .collect();
+    class_on(&undated, entered_on).or_else(|| earlier.and_then(|c| to_class(c).or_else(|| from_class(c))))
+}
+
+/// The class a student was in on `date` by their class history, oldest
+/// change first: where the last change on or before that date moved them,
+/// else where the first later change moved them from. A change that took
+/// them out of class (graduation) counts from its origin. None without a
+/// change that says.
+fn class_on(history: &[&ClassChange], date: NaiveDate) -> Option<Class> {
+    let split = history.partition_point(|c| c.effective_date <= date);
+    let before = split.checked_sub(1).map(|i| history[i]);
+    let after = history.get(split).copied();
+    before
+        .and_then(to_class)
+        .or_else(|| before.and_then(from_class))
+        .or_else(|| after.and_then(from_class))
+}
+
+fn from_class(c: &ClassChange) -> Option<Class> {
+    Some((c.from_grade_level.clone()?, c.from_section.clone()))
+}
+
+fn to_class(c: &ClassChange) -> Option<Class> {
+    Some((c.to_grade_level.clone()?, c.to_section.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(date: &str, from: Option<&str>, to: Option<&str>) -> ClassChange {
+        ClassChange {
+            effective_date: date.parse().unwrap(),
+            academic_year: None,
+            from_grade_level: from.map(String::from),
+            from_section: from.map(|_| "A".to_string()),
+            to_grade_level: to.map(String::from),
+            to_section: to.map(|_| "B".to_string()),
+        }
+    }
+
+    fn promotion(date: &str, year: &str, from: &str, to: Option<&str>) -> ClassChange {
+        ClassChange {
+            academic_year: Some(year.into()),
+            ..change(date, Some(from), to)
+        }
+    }
+
+    fn on(history: &[ClassChange], date: &str) -> Option<String> {
+        let history: Vec<&ClassChange> = history.iter().collect();
+        class_on(&history, date.parse().unwrap()).map(|(g, _)| g)
+    }
+
+    fn in_year(history: &[ClassChange], year: &str, entered_on: &str) -> Option<String> {
+        class_for_term(history, year, entered_on.parse().unwrap()).map(|(g, _)| g)
+    }
+
+    #[test]
+    fn finds_class_on_a_date() {
+        let history = [
+            change("2024-07-20", Some("JSS 1"), Some("JSS 2")),
+            change("2025-07-20", Some("JSS 2"), Some("JSS 3")),
+            change("2026-07-20", Some("JSS 3"), None),
+        ];
+        assert_eq!(on(&history, "2023-10-01").as_deref(), Some("JSS 1"));
+        assert_eq!(on(&history, "2024-07-20").as_deref(), Some("JSS 2"));
+        assert_eq!(on(&history, "2025-01-15").as_deref(), Some("JSS 2"));
+        assert_eq!(on(&history, "2025-10-01").as_deref(), Some("JSS 3"));
+        // Scores entered after graduation belong to the final class.
+        assert_eq!(on(&history, "2026-08-01").as_deref(), Some("JSS 3"));
+        assert_eq!(on(&[], "2025-01-15"), None);
+        assert_eq!(
+            class_for_term(&history, "2024/2025", "2025-01-15".parse().unwrap())
+                .and_then(|(_, s)| s)
+                .as_deref(),
+            Some("B")
+        );
+    }
+
+    #[test]
+    fn matches_terms_to_promotions_by_academic_year() {
+        let history = [
+            promotion("2024-07-20", "2023/2024", "JSS 1", Some("JSS 2")),
+            promotion("2025-07-20", "2024/2025", "JSS 2", Some("JSS 3")),
+            // A manual move to another section mid-year.
+            change("2025-11-03", Some("JSS 3"), Some("JSS 3")),
+        ];
+        // Third-term scores typed in after the year-end promotion.
+        assert_eq!(in_year(&history, "2024/2025", "2025-08-10").as_deref(), Some("JSS 2"));
+        assert_eq!(in_year(&history, "2023/2024", "2024-09-01").as_deref(), Some("JSS 1"));
+        // The year after the last promotion: where it moved them, then
+        // dated changes without a year.
+        assert_eq!(in_year(&history, "2025/2026", "2025-10-01").as_deref(), Some("JSS 3"));
+        let sections = |entered_on: &str| {
+            class_for_term(&history, "2025/2026", entered_on.parse().unwrap()).and_then(|(_, s)| s)
+        };
+        assert_eq!(sections("2025-10-01").as_deref(), Some("A"));
+        assert_eq!(sections("2025-12-01").as_deref(), Some("B"));
+    }
+}
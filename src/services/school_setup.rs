@@ -2,6 +2,13 @@ use sqlx::PgPool;
 use uuid::Uuid;
 
 use crate::errors::AppError;
+use crate::services::attendance::alerts as attendance_alerts;
+use crate::services::attendance::stats as attendance_stats;
+use crate::services::calendar;
+use crate::services::gradebook::gpa;
+use crate::services::gradebook::stats::TieBreak;
+use crate::services::report_cards::comments::parse_char_limit;
+use crate::services::report_cards::traits::{parse_trait_scale, trait_scale_json};
 use crate::models::school_setup::{
     FeeCategoryRow, FeeDiscountRow, GradeLevelRow, GradingScaleRow, PublicBrandingResponse,
     ScheduleGroupRow, SchedulePeriodRow, SchoolConfigRow, SchoolSetupData, SectionStatus,
@@ -100,6 +107,20 @@ impl SchoolSetupService {
             }
         }
 
+        // Tardy-to-absence conversion and the school's open days are baked
+        // into the attendance summaries, so a policy or calendar save has to
+        // rebuild them. Turning alerts off closes the open flags.
+        if obj.contains_key("policies") || obj.contains_key("academic_calendar") {
+            attendance_stats::refresh_summaries(&mut tx, org_id, None).await?;
+        }
+        if obj.contains_key("policies") {
+            attendance_alerts::resolve_if_disabled(&mut tx, org_id).await?;
+        }
+        // Cached GPAs depend on the weights, passmark and scale.
+        if obj.contains_key("grading") {
+            gpa::refresh_gpas(&mut tx, org_id, None).await?;
+        }
+
         // Touch updated_at on the config row so it reflects child table changes
         sqlx::query("UPDATE school_configs SET updated_at = NOW() WHERE org_id = $1")
             .bind(org_id)
@@ -119,6 +140,7 @@ impl SchoolSetupService {
         &self,
         slug: &str,
     ) -> Result<Option<PublicBrandingResponse>, AppError> {
+        #[allow(clippy::type_complexity)]
         let row: Option<(String, String, Option<String>, Option<String>, Option<String>, Option<String>)> =
             sqlx::query_as(
                 r#"
@@ -348,6 +370,23 @@ async fn upsert_report_card(
     org_id: Uuid,
     v: &serde_json::Value,
 ) -> Result<(), AppError> {
+    let tie_break = str_val(v, "position_tie_break")
+        .map(|s| s.trim().to_ascii_lowercase())
+        .filter(|s| !s.is_empty());
+    if let Some(t) = &tie_break {
+        TieBreak::parse(t)?;
+    }
+    let trait_scale = match v.get("trait_rating_scale") {
+        Some(scale) if !scale.is_null() => trait_scale_json(&parse_trait_scale(scale)?),
+        _ => serde_json::json!([]),
+    };
+    let char_limit = match v.get("comment_char_limit") {
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        Some(serde_json::Value::String(s)) => Some(s.trim().to_string()).filter(|s| !s.is_empty()),
+        _ => None,
+    };
+    parse_char_limit(char_limit.as_deref())?;
+
     sqlx::query(
         r#"UPDATE school_configs SET
             report_template = $2,
@@ -360,7 +399,8 @@ async fn upsert_report_card(
             show_teacher_comments = $15, show_class_teacher_comment = $16,
             show_principal_signature = $17, show_subject_teacher_signature = $18,
             comment_char_limit = $19, show_attendance_summary = $20,
-            show_next_term_dates = $21, show_co_curricular = $22
+            show_next_term_dates = $21, show_co_curricular = $22,
+            position_tie_break = $23, trait_rating_scale = $24
            WHERE org_id = $1"#,
     )
     .bind(org_id)
@@ -381,10 +421,12 @@ async fn upsert_report_card(
     .bind(bool_val(v, "show_class_teacher_comment"))
     .bind(bool_val(v, "show_principal_signature"))
     .bind(bool_val(v, "show_subject_teacher_signature"))
-    .bind(str_val(v, "comment_char_limit"))
+    .bind(char_limit)
     .bind(bool_val(v, "show_attendance_summary"))
     .bind(bool_val(v, "show_next_term_dates"))
     .bind(bool_val(v, "show_co_curricular"))
+    .bind(tie_break)
+    .bind(trait_scale)
     .execute(&mut *tx)
     .await?;
     Ok(())
@@ -442,34 +484,86 @@ async fn upsert_academic_calendar(
     org_id: Uuid,
     v: &serde_json::Value,
 ) -> Result<(), AppError> {
+    // Stored in canonical form ("monday"), so "Mon" and "MONDAY" both work.
+    let mut school_days = Vec::new();
+    match v.get("school_days") {
+        None | Some(serde_json::Value::Null) => {}
+        Some(serde_json::Value::Array(days)) => {
+            for d in days {
+                let day = d.as_str().and_then(calendar::parse_weekday).ok_or_else(|| {
+                    AppError::BadRequest(format!(
+                        "Invalid school day {d}. Use weekday names such as \"monday\""
+                    ))
+                })?;
+                let name = calendar::weekday_name(day);
+                if !school_days.contains(&name) {
+                    school_days.push(name);
+                }
+            }
+        }
+        Some(_) => {
+            return Err(AppError::BadRequest(
+                "school_days must be an array of weekday names".into(),
+            ));
+        }
+    }
+
     // Scalar columns
     sqlx::query(
         r#"UPDATE school_configs SET
-            calendar_type = $2, current_academic_year = $3
+            calendar_type = $2, current_academic_year = $3, school_days = $4
            WHERE org_id = $1"#,
     )
     .bind(org_id)
     .bind(str_val(v, "calendar_type"))
     .bind(str_val(v, "current_academic_year"))
+    .bind(serde_json::json!(school_days))
     .execute(&mut *tx)
     .await?;
 
     // Child rows: terms
     if let Some(terms) = v.get("terms").and_then(|t| t.as_array()) {
+        // Pre-DATE values the calendar migration couldn't convert stay with
+        // their term until it's given a date.
+        let legacy: Vec<(String, Option<String>, Option<String>)> = sqlx::query_as(
+            r#"SELECT LOWER(name), legacy_start_date, legacy_end_date FROM school_terms
+               WHERE org_id = $1 AND (legacy_start_date IS NOT NULL OR legacy_end_date IS NOT NULL)"#,
+        )
+        .bind(org_id)
+        .fetch_all(&mut *tx)
+        .await?;
         sqlx::query("DELETE FROM school_terms WHERE org_id = $1")
             .bind(org_id)
             .execute(&mut *tx)
             .await?;
         for (i, t) in terms.iter().enumerate() {
+            let name = str_val(t, "name").unwrap_or_default();
+            let start_date = date_val(t, "start_date", &name)?;
+            let end_date = date_val(t, "end_date", &name)?;
+            if let (Some(start), Some(end)) = (start_date, end_date)
+                && end < start
+            {
+                return Err(AppError::BadRequest(format!(
+                    "Term '{name}' ends before it starts"
+                )));
+            }
+            let (legacy_start, legacy_end) = legacy
+                .iter()
+                .find(|(n, _, _)| *n == name.to_lowercase())
+                .map(|(_, s, e)| (s.clone(), e.clone()))
+                .unwrap_or_default();
             sqlx::query(
-                r#"INSERT INTO school_terms (org_id, name, start_date, end_date, position)
-                   VALUES ($1, $2, $3, $4, $5)"#,
+                r#"INSERT INTO school_terms
+                   (org_id, name, start_date, end_date, position, legacy_start_date, legacy_end_date)
+                   VALUES ($1, $2, $3, $4, $5, $6, $7)"#,
             )
             .bind(org_id)
-            .bind(str_val(t, "name").unwrap_or_default())
-            .bind(str_val(t, "start_date"))
-            .bind(str_val(t, "end_date"))
+            .bind(&name)
+            .bind(start_date)
+            .bind(end_date)
             .bind(i as i16)
+            .bind(legacy_start.filter(|_| start_date.is_none()))
+            .bind(legacy_end.filter(|_| end_date.is_none()))
             .execute(&mut *tx)
             .await?;
         }
@@ -675,14 +769,15 @@ async fn upsert_fees(
         for (i, d) in discs.iter().enumerate() {
             sqlx::query(
                 r#"INSERT INTO school_fee_discounts
-                   (org_id, name, percentage, applies_to, position)
-                   VALUES ($1, $2, $3, $4, $5)"#,
+                   (org_id, name, percentage, applies_to, position, sibling_position)
+                   VALUES ($1, $2, $3, $4, $5, $6)"#,
             )
             .bind(org_id)
             .bind(str_val(d, "name").unwrap_or_default())
             .bind(str_val(d, "percentage"))
             .bind(str_val(d, "applies_to"))
             .bind(i as i16)
+            .bind(sibling_position(d)?)
             .execute(&mut *tx)
             .await?;
         }
@@ -710,6 +805,7 @@ async fn upsert_schedule(
     // Schedules can be an object (keyed by group name) or an array of group objects
     if let Some(obj) = schedules_val.as_object() {
         let mut pos: i16 = 0;
+        #[allow(clippy::explicit_counter_loop)]
         for (group_name, group_val) in obj {
             let group_id = insert_schedule_group(
                 &mut *tx, org_id, group_name, group_val, pos,
@@ -788,6 +884,37 @@ fn bool_val(v: &serde_json::Value, key: &str) -> Option<bool> {
     v.get(key).and_then(|v| v.as_bool())
 }
 
+/// Optional YYYY-MM-DD field of a term; blank counts as unset.
+fn date_val(
+    v: &serde_json::Value,
+    key: &str,
+    term: &str,
+) -> Result<Option<chrono::NaiveDate>, AppError> {
+    match str_val(v, key).as_deref().map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(s) => chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| AppError::BadRequest(format!("Term '{term}' {key} must be YYYY-MM-DD"))),
+    }
+}
+
+/// A discount type's `sibling_position`: blank for none, else 2 or more.
+fn sibling_position(d: &serde_json::Value) -> Result<Option<i16>, AppError> {
+    let value = match d.get("sibling_position") {
+        None | Some(serde_json::Value::Null) => return Ok(None),
+        Some(serde_json::Value::String(s)) if s.trim().is_empty() => return Ok(None),
+        Some(serde_json::Value::String(s)) => s.trim().parse::<i16>().ok(),
+        Some(v) => v.as_i64().and_then(|n| i16::try_from(n).ok()),
+    };
+    match value {
+        Some(n) if n >= 2 => Ok(Some(n)),
+        _ => Err(AppError::BadRequest(format!(
+            "Discount '{}' sibling_position must be a whole number of 2 or more",
+            str_val(d, "name").unwrap_or_default()
+        ))),
+    }
+}
+
 fn json_val(v: &serde_json::Value, key: &str) -> serde_json::Value {
     v.get(key).cloned().unwrap_or(serde_json::Value::Null)
 }
@@ -988,6 +1115,7 @@ mod tests {
             language: None,
             calendar_type: None,
             current_academic_year: None,
+            school_days: serde_json::json!([]),
             grade_level_structure_id: None,
             group_sections: serde_json::json!({}),
             custom_group_levels: serde_json::json!({}),
@@ -1010,6 +1138,7 @@ mod tests {
             show_highest_lowest: None,
             show_grading_legend: None,
             show_position: None,
+            position_tie_break: None,
             show_gpa: None,
             show_effort_grades: None,
             show_behavior_rating: None,
@@ -1017,6 +1146,7 @@ mod tests {
             psychomotor_traits: serde_json::json!([]),
             show_affective: None,
             affective_traits: serde_json::json!([]),
+            trait_rating_scale: serde_json::json!([]),
             show_teacher_comments: None,
             show_class_teacher_comment: None,
             show_principal_signature: None,
@@ -0,0 +1,191 @@
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::students::{
+    PaginationInfo, PromotionBatch, PromotionBatchDecision, PromotionBatchDetail,
+    PromotionBatchListQuery, PromotionBatchListResponse,
+};
+
+use super::crud::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use super::export::csv_safe;
+use super::StudentsService;
+
+/// One row per promotion batch in `$1`'s org, optionally limited to batch
+/// `$2`. The reversal's rows share the batch id, so the batch's own figures
+/// come from the other rows.
+const BATCHES_CTE: &str = r#"
+    WITH batches AS (
+        SELECT h.promotion_batch_id AS batch_id,
+               MAX(h.academic_year) AS academic_year,
+               COALESCE(MIN(h.effective_date) FILTER (WHERE h.change_kind <> 'revert'),
+                        MIN(h.effective_date)) AS effective_date,
+               COALESCE(MIN(h.changed_at) FILTER (WHERE h.change_kind <> 'revert'),
+                        MIN(h.changed_at)) AS run_at,
+               (ARRAY_AGG(h.changed_by_user_id) FILTER (WHERE h.change_kind <> 'revert'))[1] AS run_by,
+               COUNT(*) FILTER (WHERE h.change_kind = 'promote') AS promoted,
+               COUNT(*) FILTER (WHERE h.change_kind = 'retain') AS retained,
+               COUNT(*) FILTER (WHERE h.change_kind = 'graduate') AS graduated,
+               BOOL_OR(h.change_kind = 'revert') AS reverted,
+               MIN(h.changed_at) FILTER (WHERE h.change_kind = 'revert') AS reverted_at
+        FROM student_class_history h
+        WHERE h.org_id = $1 AND h.promotion_batch_id IS NOT NULL
+          AND ($2::uuid IS NULL OR h.promotion_batch_id = $2)
+        GROUP BY h.promotion_batch_id
+    )
+"#;
+
+const CSV_HEADERS: &[&str] = &[
+    "Admission No",
+    "First Name",
+    "Last Name",
+    "Action",
+    "From Grade",
+    "From Section",
+    "From Stream",
+    "To Grade",
+    "To Section",
+    "To Stream",
+    "Reason",
+];
+
+impl StudentsService {
+    /// Past promotion batches, most recent first.
+    pub async fn list_promotion_batches(
+        &self,
+        org_id: Uuid,
+        q: PromotionBatchListQuery,
+    ) -> Result<PromotionBatchListResponse, AppError> {
+        let page = q.page.unwrap_or(1).max(1);
+        let page_size = q
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let offset = page.saturating_sub(1).saturating_mul(page_size);
+        let academic_year = q
+            .academic_year
+            .as_deref()
+            .map(str::trim)
+            .filter(|y| !y.is_empty());
+
+        let data: Vec<PromotionBatch> = sqlx::query_as(&format!(
+            r#"{BATCHES_CTE}
+            SELECT b.*, NULLIF(TRIM(CONCAT_WS(' ', u.first_name, u.last_name)), '') AS run_by_name
+            FROM batches b
+            LEFT JOIN users u ON u.id = b.run_by
+            WHERE $3::text IS NULL OR b.academic_year = $3
+            ORDER BY b.run_at DESC, b.batch_id
+            LIMIT $4 OFFSET $5
+            "#
+        ))
+        .bind(org_id)
+        .bind(None::<Uuid>)
+        .bind(academic_year)
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let total: i64 = sqlx::query_scalar(&format!(
+            r#"{BATCHES_CTE}
+            SELECT COUNT(*) FROM batches b
+            WHERE $3::text IS NULL OR b.academic_year = $3
+            "#
+        ))
+        .bind(org_id)
+        .bind(None::<Uuid>)
+        .bind(academic_year)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let total_pages = if total == 0 {
+            0
+        } else {
+            ((total as f64) / (page_size as f64)).ceil() as i64
+        };
+        Ok(PromotionBatchListResponse {
+            data,
+            pagination: PaginationInfo {
+                page,
+                page_size,
+                total,
+                total_pages,
+            },
+        })
+    }
+
+    /// One promotion batch with each student's decision.
+    pub async fn promotion_batch(
+        &self,
+        org_id: Uuid,
+        batch_id: Uuid,
+    ) -> Result<PromotionBatchDetail, AppError> {
+        let batch: PromotionBatch = sqlx::query_as(&format!(
+            r#"{BATCHES_CTE}
+            SELECT b.*, NULLIF(TRIM(CONCAT_WS(' ', u.first_name, u.last_name)), '') AS run_by_name
+            FROM batches b
+            LEFT JOIN users u ON u.id = b.run_by
+            "#
+        ))
+        .bind(org_id)
+        .bind(batch_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Promotion batch not found".into()))?;
+
+        let decisions: Vec<PromotionBatchDecision> = sqlx::query_as(
+            r#"
+            SELECT h.student_id, s.admission_number, s.first_name, s.last_name,
+                   h.change_kind AS action,
+                   h.from_grade_level AS from_grade, h.from_section, h.from_stream,
+                   h.to_grade_level AS to_grade, h.to_section, h.to_stream,
+                   h.reason
+            FROM student_class_history h
+            JOIN students s ON s.id = h.student_id
+            WHERE h.org_id = $1 AND h.promotion_batch_id = $2 AND h.change_kind <> 'revert'
+            ORDER BY h.from_grade_level, h.from_section, s.last_name, s.first_name, s.id
+            "#,
+        )
+        .bind(org_id)
+        .bind(batch_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(PromotionBatchDetail { batch, decisions })
+    }
+
+    /// A promotion batch's decisions as CSV bytes.
+    pub async fn promotion_batch_csv(
+        &self,
+        org_id: Uuid,
+        batch_id: Uuid,
+    ) -> Result<Vec<u8>, AppError> {
+        let detail = self.promotion_batch(org_id, batch_id).await?;
+
+        let mut wtr = csv::Writer::from_writer(vec![]);
+        wtr.write_record(CSV_HEADERS)
+            .map_err(|e| AppError::Internal(format!("csv header: {e}")))?;
+        for d in &detail.decisions {
+            let cell = |v: &Option<String>| csv_safe(v.as_deref().unwrap_or(""));
+            let row = [
+                csv_safe(&d.admission_number),
+                csv_safe(&d.first_name),
+                csv_safe(&d.last_name),
+                csv_safe(&d.action),
+                cell(&d.from_grade),
+                cell(&d.from_section),
+                cell(&d.from_stream),
+                cell(&d.to_grade),
+                cell(&d.to_section),
+                cell(&d.to_stream),
+                cell(&d.reason),
+            ];
+            wtr.write_record(&row)
+                .map_err(|e| AppError::Internal(format!("csv row: {e}")))?;
+        }
+
+        wtr.flush()
+            .map_err(|e| AppError::Internal(format!("csv flush: {e}")))?;
+        wtr.into_inner()
+            .map_err(|e| AppError::Internal(format!("csv finalize: {e}")))
+    }
+}
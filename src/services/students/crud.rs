@@ -12,6 +12,10 @@ use crate::models::students::{
     StudentResponse, StudentRow, StudentStatusHistoryRow, StudentSummary, UpdateStudentRequest,
 };
 
+use crate::services::attendance::stats::{self as attendance_stats, RECENT_ATTENDANCE_LIMIT};
+use crate::services::fees::ledger::{self, RECENT_PAYMENTS_LIMIT};
+use crate::services::gradebook::gpa;
+
 use super::admission;
 use super::StudentsService;
 
@@ -27,8 +31,8 @@ const ALLOWED_STATUSES: &[&str] = &[
 const ALLOWED_BOARDING: &[&str] = &["day", "boarding", "weekly_boarding"];
 
 const MAX_GUARDIANS: usize = 3;
-const DEFAULT_PAGE_SIZE: i64 = 25;
-const MAX_PAGE_SIZE: i64 = 100;
+pub(crate) const DEFAULT_PAGE_SIZE: i64 = 25;
+pub(crate) const MAX_PAGE_SIZE: i64 = 100;
 
 impl StudentsService {
     /// Create a single student with optional guardians.
@@ -134,13 +138,36 @@ impl StudentsService {
         let mut guardians_map = fetch_guardians_for_students(&self.pool, &[student.id]).await?;
         let guardians = guardians_map.remove(&student.id).unwrap_or_default();
 
+        let ids = [student.id];
         let mut response = StudentResponse::from_row(student, guardians);
+        response.attendance_rate = attendance_stats::rates_for_students(&self.pool, org_id, &ids)
+            .await?
+            .remove(&response.id);
+        response.gpa = gpa::gpas_for_students(&self.pool, org_id, &ids)
+            .await?
+            .remove(&response.id);
+        if let Some(status) = ledger::statuses_for_students(&self.pool, org_id, &ids)
+            .await?
+            .remove(&response.id)
+        {
+            response.fee_status = status;
+        }
 
         if include_contains(include, "recent_payments") {
-            response.recent_payments = Some(vec![]);
+            let mut recent =
+                ledger::recent_for_students(&self.pool, org_id, &ids, RECENT_PAYMENTS_LIMIT)
+                    .await?;
+            response.recent_payments = Some(recent.remove(&response.id).unwrap_or_default());
         }
         if include_contains(include, "recent_attendance") {
-            response.recent_attendance = Some(vec![]);
+            let mut recent = attendance_stats::recent_for_students(
+                &self.pool,
+                org_id,
+                &ids,
+                RECENT_ATTENDANCE_LIMIT,
+            )
+            .await?;
+            response.recent_attendance = Some(recent.remove(&response.id).unwrap_or_default());
         }
 
         Ok(response)
@@ -161,17 +188,12 @@ impl StudentsService {
         // or wrap silently in release; an empty page is the right answer past the end.
         let offset = page.saturating_sub(1).saturating_mul(page_size);
 
-        let sort_col = match q.sort.as_deref() {
-            Some("first_name") => "first_name",
-            Some("admission_number") => "admission_number",
-            Some("enrollment_date") => "enrollment_date",
-            Some("created_at") => "created_at",
-            _ => "last_name",
-        };
-        let order = match q.order.as_deref() {
-            Some("desc") | Some("DESC") => "DESC",
-            _ => "ASC",
-        };
+        if let (Some(min), Some(max)) = (q.min_gpa, q.max_gpa)
+            && min > max
+        {
+            return Err(AppError::BadRequest("min_gpa must not be greater than max_gpa".into()));
+        }
+        let (sort_col, order) = sort_clause(&q);
 
         let (page_data, total, summary) = tokio::try_join!(
             fetch_page(&self.pool, org_id, &q, sort_col, order, page_size, offset),
@@ -187,22 +209,50 @@ impl StudentsService {
 
         let student_ids: Vec<Uuid> = page_data.iter().map(|s| s.id).collect();
         let mut guardians_map = fetch_guardians_for_students(&self.pool, &student_ids).await?;
+        let mut rates =
+            attendance_stats::rates_for_students(&self.pool, org_id, &student_ids).await?;
+        let mut gpas = gpa::gpas_for_students(&self.pool, org_id, &student_ids).await?;
+        let mut fee_statuses =
+            ledger::statuses_for_students(&self.pool, org_id, &student_ids).await?;
 
         let include = q.include.as_deref().unwrap_or("");
         let want_payments = include_contains(include, "recent_payments");
+        let mut recent_payments = if want_payments {
+            ledger::recent_for_students(&self.pool, org_id, &student_ids, RECENT_PAYMENTS_LIMIT)
+                .await?
+        } else {
+            HashMap::new()
+        };
         let want_attendance = include_contains(include, "recent_attendance");
+        let mut recent_attendance = if want_attendance {
+            attendance_stats::recent_for_students(
+                &self.pool,
+                org_id,
+                &student_ids,
+                RECENT_ATTENDANCE_LIMIT,
+            )
+            .await?
+        } else {
+            HashMap::new()
+        };
 
         let data: Vec<StudentResponse> = page_data
             .into_iter()
             .map(|s| {
                 let g = guardians_map.remove(&s.id).unwrap_or_default();
                 let mut resp = StudentResponse::from_row(s, g);
-                // Empty arrays for now; populated when fees/attendance modules ship.
+                resp.attendance_rate = rates.remove(&resp.id);
+                resp.gpa = gpas.remove(&resp.id);
+                if let Some(status) = fee_statuses.remove(&resp.id) {
+                    resp.fee_status = status;
+                }
                 if want_payments {
-                    resp.recent_payments = Some(vec![]);
+                    resp.recent_payments =
+                        Some(recent_payments.remove(&resp.id).unwrap_or_default());
                 }
                 if want_attendance {
-                    resp.recent_attendance = Some(vec![]);
+                    resp.recent_attendance =
+                        Some(recent_attendance.remove(&resp.id).unwrap_or_default());
                 }
                 resp
             })
@@ -558,7 +608,7 @@ pub(super) fn validate_boarding(b: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-pub(super) async fn validate_grade_level(
+pub(crate) async fn validate_grade_level(
     pool: &sqlx::PgPool,
     org_id: Uuid,
     grade_level: &str,
@@ -680,7 +730,7 @@ async fn fetch_page(
     let mut qb = QueryBuilder::<sqlx::Postgres>::new("SELECT * FROM students WHERE org_id = ");
     qb.push_bind(org_id);
     push_filters(&mut qb, q);
-    qb.push(format!(" ORDER BY {sort_col} {order} LIMIT "));
+    qb.push(format!(" ORDER BY {sort_col} {order} NULLS LAST, id LIMIT "));
     qb.push_bind(page_size);
     qb.push(" OFFSET ");
     qb.push_bind(offset);
@@ -715,12 +765,14 @@ async fn fetch_summary(pool: &sqlx::PgPool, org_id: Uuid) -> Result<StudentSumma
     .bind(org_id)
     .fetch_one(pool)
     .await?;
+    let average_attendance = attendance_stats::school_average_rate(pool, org_id).await?;
+    let average_gpa = gpa::school_average_gpa(pool, org_id).await?;
 
     Ok(StudentSummary {
         total_students: row.0,
         active: row.1,
-        average_gpa: None,
-        average_attendance: None,
+        average_gpa,
+        average_attendance,
     })
 }
 
@@ -753,6 +805,19 @@ fn push_filters(qb: &mut QueryBuilder<'_, sqlx::Postgres>, q: &StudentListQuery)
         qb.push_bind(b.to_string());
     }
 
+    if q.min_gpa.is_some() || q.max_gpa.is_some() {
+        qb.push(" AND EXISTS (SELECT 1 FROM gpa_summaries g WHERE g.student_id = students.id");
+        if let Some(min) = q.min_gpa {
+            qb.push(" AND g.cumulative_gpa >= ");
+            qb.push_bind(min);
+        }
+        if let Some(max) = q.max_gpa {
+            qb.push(" AND g.cumulative_gpa <= ");
+            qb.push_bind(max);
+        }
+        qb.push(")");
+    }
+
     if let Some(search) = q.search.as_deref().filter(|s| !s.is_empty()) {
         let pattern = format!("%{search}%");
         qb.push(" AND (first_name ILIKE ");
@@ -781,25 +846,35 @@ pub(super) async fn fetch_filtered(
     org_id: Uuid,
     q: &StudentListQuery,
 ) -> Result<Vec<StudentRow>, AppError> {
+    let (sort_col, order) = sort_clause(q);
+
+    let mut qb = QueryBuilder::<sqlx::Postgres>::new("SELECT * FROM students WHERE org_id = ");
+    qb.push_bind(org_id);
+    push_filters(&mut qb, q);
+    qb.push(format!(" ORDER BY {sort_col} {order} NULLS LAST, id"));
+
+    let rows = qb.build_query_as::<StudentRow>().fetch_all(pool).await?;
+    Ok(rows)
+}
+
+/// ORDER BY column and direction for the list and export. Students without a
+/// GPA sort last either way.
+fn sort_clause(q: &StudentListQuery) -> (&'static str, &'static str) {
     let sort_col = match q.sort.as_deref() {
         Some("first_name") => "first_name",
         Some("admission_number") => "admission_number",
         Some("enrollment_date") => "enrollment_date",
         Some("created_at") => "created_at",
+        Some("gpa") => {
+            "(SELECT g.cumulative_gpa FROM gpa_summaries g WHERE g.student_id = students.id)"
+        }
         _ => "last_name",
     };
     let order = match q.order.as_deref() {
         Some("desc") | Some("DESC") => "DESC",
         _ => "ASC",
     };
-
-    let mut qb = QueryBuilder::<sqlx::Postgres>::new("SELECT * FROM students WHERE org_id = ");
-    qb.push_bind(org_id);
-    push_filters(&mut qb, q);
-    qb.push(format!(" ORDER BY {sort_col} {order}"));
-
-    let rows = qb.build_query_as::<StudentRow>().fetch_all(pool).await?;
-    Ok(rows)
+    (sort_col, order)
 }
 
 pub(super) fn today() -> NaiveDate {
@@ -4,6 +4,7 @@ use uuid::Uuid;
 
 use crate::errors::AppError;
 use crate::models::students::{StudentGuardianRow, StudentListQuery};
+use crate::services::fees::ledger;
 
 use super::crud::{fetch_filtered, fetch_guardians_for_students};
 use super::StudentsService;
@@ -36,6 +37,7 @@ impl StudentsService {
         let students = fetch_filtered(&self.pool, org_id, &q).await?;
         let ids: Vec<Uuid> = students.iter().map(|s| s.id).collect();
         let guardians_map = fetch_guardians_for_students(&self.pool, &ids).await?;
+        let fee_statuses = ledger::statuses_for_students(&self.pool, org_id, &ids).await?;
 
         let mut wtr = csv::Writer::from_writer(vec![]);
         wtr.write_record(CSV_HEADERS)
@@ -47,6 +49,7 @@ impl StudentsService {
             let g_name = primary.as_ref().map(|g| guardian_name(g)).unwrap_or_default();
             let g_phone = primary.as_ref().and_then(|g| g.phone.as_deref()).unwrap_or("");
             let g_email = primary.as_ref().and_then(|g| g.email.as_deref()).unwrap_or("");
+            let fee_status = fee_statuses.get(&s.id).map_or("unknown", String::as_str);
 
             // Every user-controlled cell goes through CSV-injection sanitization
             // so a name/phone/email starting with =, +, -, @ or tab can't be
@@ -62,7 +65,7 @@ impl StudentsService {
                 csv_safe(&dob),
                 csv_safe(&s.status),
                 csv_safe(s.boarding_status.as_deref().unwrap_or("")),
-                csv_safe("unknown"),
+                csv_safe(fee_status),
                 csv_safe(&g_name),
                 csv_safe(g_phone),
                 csv_safe(g_email),
@@ -93,7 +96,7 @@ fn guardian_name(g: &StudentGuardianRow) -> String {
 /// Neutralize CSV-formula characters at the start of a cell.
 /// If the value begins with `=`, `+`, `-`, `@`, tab, or carriage return,
 /// prefix with a single quote so spreadsheet apps treat it as text.
-fn csv_safe(value: &str) -> String {
+pub(crate) fn csv_safe(value: &str) -> String {
     if matches!(value.chars().next(), Some('=' | '+' | '-' | '@' | '\t' | '\r')) {
         format!("'{value}")
     } else {
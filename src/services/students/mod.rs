@@ -1,10 +1,17 @@
 use sqlx::PgPool;
 
 pub(super) mod admission;
+pub(super) mod batches;
 pub(super) mod crud;
 pub(super) mod export;
 pub(super) mod import;
 pub(super) mod promote;
+pub(super) mod revert;
+pub(super) mod rollover;
+pub(super) mod suggest;
+
+pub(crate) use crud::{validate_grade_level, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+pub(crate) use export::csv_safe;
 
 pub struct StudentsService {
     pub(super) pool: PgPool,
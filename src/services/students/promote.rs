@@ -1,18 +1,39 @@
-use std::collections::HashSet;
+use std::collections::HashMap;
 
+use chrono::NaiveDate;
+use sqlx::{Acquire, PgConnection};
 use uuid::Uuid;
 
 use crate::errors::AppError;
-use crate::models::students::{PromoteRequest, PromoteSummary, StudentRow};
+use crate::models::students::{
+    PromoteChange, PromoteDecision, PromoteError, PromoteRequest, PromoteSummary, StudentRow,
+};
 
 use super::crud::{today, validate_grade_level};
 use super::StudentsService;
 
 const ALLOWED_ACTIONS: &[&str] = &["promote", "retain", "graduate"];
 
+/// What every decision in one batch shares.
+pub(super) struct Batch<'a> {
+    pub(super) org_id: Uuid,
+    pub(super) batch_id: Uuid,
+    pub(super) effective_date: NaiveDate,
+    pub(super) academic_year: Option<&'a str>,
+    pub(super) changed_by: Option<Uuid>,
+}
+
 impl StudentsService {
     /// Bulk promote / retain / graduate students. Single transaction; all-or-nothing.
     /// Each decision writes one `student_class_history` row sharing a `promotion_batch_id`.
+    ///
+    /// With `skip_invalid` each decision runs in its own savepoint instead:
+    /// a decision that fails validation or can't be applied is left out and
+    /// reported in `errors`, and the rest commit under the same batch id.
+    ///
+    /// With `dry_run` the same checks run against the current rows, but
+    /// nothing is written: the summary lists each student's before and after
+    /// and has no batch id.
     pub async fn promote(
         &self,
         org_id: Uuid,
@@ -25,159 +46,293 @@ impl StudentsService {
 
         // Pre-validate all action enums, dedup student_ids, and check target
         // grade levels before opening the tx.
-        let mut seen: HashSet<Uuid> = HashSet::with_capacity(req.decisions.len());
+        let mut occurrences: HashMap<Uuid, usize> = HashMap::with_capacity(req.decisions.len());
         for d in &req.decisions {
-            if !seen.insert(d.student_id) {
-                // Two decisions for the same student would produce contradictory
-                // updates (e.g. promote+graduate) and inflate PromoteSummary counts.
-                return Err(AppError::BadRequest(format!(
-                    "duplicate student_id {} in decisions array",
-                    d.student_id
-                )));
+            *occurrences.entry(d.student_id).or_default() += 1;
+        }
+        let mut errors = Vec::new();
+        let mut valid = Vec::with_capacity(req.decisions.len());
+        for d in &req.decisions {
+            match self.validate_decision(org_id, d, occurrences[&d.student_id]).await {
+                Ok(()) => valid.push(d),
+                Err(AppError::BadRequest(message)) if req.skip_invalid => {
+                    errors.push(decision_error(d, message));
+                }
+                Err(e) => return Err(e),
             }
-            if !ALLOWED_ACTIONS.contains(&d.action.as_str()) {
-                return Err(AppError::BadRequest(format!(
-                    "Invalid action '{}'; must be promote/retain/graduate",
-                    d.action
-                )));
+        }
+
+        let academic_year = match req.academic_year.as_deref().map(str::trim) {
+            Some(year) if !year.is_empty() => Some(year.to_string()),
+            _ => sqlx::query_scalar::<_, Option<String>>(
+                "SELECT NULLIF(TRIM(current_academic_year), '') FROM school_configs WHERE org_id = $1",
+            )
+            .bind(org_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .flatten(),
+        };
+        let batch = Batch {
+            org_id,
+            batch_id: Uuid::new_v4(),
+            effective_date: req.effective_date.unwrap_or_else(today),
+            academic_year: academic_year.as_deref(),
+            changed_by,
+        };
+
+        let mut changes = Vec::new();
+        if req.dry_run {
+            // Reads only, so a preview never locks or touches live rows.
+            let mut conn = self.pool.acquire().await?;
+            for d in valid {
+                match plan_decision(&mut conn, org_id, d).await {
+                    Ok((_, change)) => changes.push(change),
+                    Err(e) if req.skip_invalid => errors.push(decision_error(d, error_message(e))),
+                    Err(e) => return Err(e),
+                }
             }
-            if d.action == "promote" {
-                let target = d.to_grade.as_deref().ok_or_else(|| {
-                    AppError::BadRequest(format!(
-                        "decision for student {} is missing to_grade",
-                        d.student_id
-                    ))
-                })?;
-                validate_grade_level(&self.pool, org_id, target).await?;
+        } else {
+            let mut tx = self.pool.begin().await?;
+            for d in valid {
+                let change = if req.skip_invalid {
+                    // A failed statement aborts the whole transaction, so each
+                    // decision gets a savepoint to roll back to.
+                    let mut sp = tx.begin().await?;
+                    match apply_decision(&mut sp, &batch, d).await {
+                        Ok(change) => {
+                            sp.commit().await?;
+                            change
+                        }
+                        Err(e) => {
+                            sp.rollback().await?;
+                            errors.push(decision_error(d, error_message(e)));
+                            continue;
+                        }
+                    }
+                } else {
+                    apply_decision(&mut tx, &batch, d).await?
+                };
+                changes.push(change);
             }
+            tx.commit().await?;
         }
 
-        let batch_id = Uuid::new_v4();
-        let effective_date = req.effective_date.unwrap_or_else(today);
+        let count = |action: &str| changes.iter().filter(|c| c.action == action).count() as i64;
+        Ok(PromoteSummary {
+            promoted: count("promote"),
+            retained: count("retain"),
+            graduated: count("graduate"),
+            batch_id: (!req.dry_run).then_some(batch.batch_id),
+            errors,
+            dry_run: req.dry_run,
+            changes: req.dry_run.then_some(changes),
+        })
+    }
+
+    /// Checks that need no student row. `occurrences` is how many decisions
+    /// the request has for this student.
+    pub(super) async fn validate_decision(
+        &self,
+        org_id: Uuid,
+        d: &PromoteDecision,
+        occurrences: usize,
+    ) -> Result<(), AppError> {
+        if occurrences > 1 {
+            // Two decisions for the same student would produce contradictory
+            // updates (e.g. promote+graduate) and inflate PromoteSummary counts.
+            return Err(AppError::BadRequest(format!(
+                "duplicate student_id {} in decisions array",
+                d.student_id
+            )));
+        }
+        if !ALLOWED_ACTIONS.contains(&d.action.as_str()) {
+            return Err(AppError::BadRequest(format!(
+                "Invalid action '{}'; must be promote/retain/graduate",
+                d.action
+            )));
+        }
+        if d.action == "promote" {
+            let target = d.to_grade.as_deref().ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "decision for student {} is missing to_grade",
+                    d.student_id
+                ))
+            })?;
+            validate_grade_level(&self.pool, org_id, target).await?;
+        }
+        Ok(())
+    }
+}
 
-        let mut tx = self.pool.begin().await?;
-        let mut promoted = 0i64;
-        let mut retained = 0i64;
-        let mut graduated = 0i64;
+/// Load a decision's student and the change it would make, without writing.
+pub(super) async fn plan_decision(
+    conn: &mut PgConnection,
+    org_id: Uuid,
+    d: &PromoteDecision,
+) -> Result<(StudentRow, PromoteChange), AppError> {
+    let current: StudentRow = sqlx::query_as(
+        "SELECT * FROM students WHERE id = $1 AND org_id = $2",
+    )
+    .bind(d.student_id)
+    .bind(org_id)
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| {
+        AppError::NotFound(format!("Student {} not found", d.student_id))
+    })?;
 
-        for d in &req.decisions {
-            let current: StudentRow = sqlx::query_as(
-                "SELECT * FROM students WHERE id = $1 AND org_id = $2",
+    // A graduate keeps their final class. A promotion without a section
+    // keeps the current one.
+    let (to_grade, to_section) = match d.action.as_str() {
+        "promote" => (
+            d.to_grade.clone().unwrap(),
+            d.to_section.clone().or_else(|| current.section.clone()),
+        ),
+        _ => (current.grade_level.clone(), current.section.clone()),
+    };
+    let to_status = if d.action == "graduate" {
+        "graduated".into()
+    } else {
+        current.status.clone()
+    };
+    let change = PromoteChange {
+        student_id: d.student_id,
+        action: d.action.clone(),
+        to_grade,
+        to_section,
+        to_status,
+        admission_number: current.admission_number.clone(),
+        first_name: current.first_name.clone(),
+        last_name: current.last_name.clone(),
+        from_grade: current.grade_level.clone(),
+        from_section: current.section.clone(),
+        from_status: current.status.clone(),
+    };
+    Ok((current, change))
+}
+
+/// Apply one validated decision and write its history.
+pub(super) async fn apply_decision(
+    conn: &mut PgConnection,
+    batch: &Batch<'_>,
+    d: &PromoteDecision,
+) -> Result<PromoteChange, AppError> {
+    let org_id = batch.org_id;
+    let (current, change) = plan_decision(&mut *conn, org_id, d).await?;
+
+    let (to_grade, to_section, to_stream, change_kind) = match d.action.as_str() {
+        "promote" => {
+            sqlx::query(
+                "UPDATE students SET grade_level = $3, section = COALESCE($4, section)
+                 WHERE id = $1 AND org_id = $2",
             )
             .bind(d.student_id)
             .bind(org_id)
-            .fetch_optional(&mut *tx)
-            .await?
-            .ok_or_else(|| {
-                AppError::NotFound(format!("Student {} not found", d.student_id))
-            })?;
-
-            let (to_grade, to_section, to_stream, change_kind) = match d.action.as_str() {
-                "promote" => {
-                    let to_g = d.to_grade.clone().unwrap();
-                    // If the caller didn't specify a section, the UPDATE keeps
-                    // the existing section via COALESCE. The audit row must
-                    // record the *effective* section, not the raw `None` from
-                    // the request, otherwise the history diverges from the row.
-                    let effective_section =
-                        d.to_section.clone().or_else(|| current.section.clone());
-                    sqlx::query(
-                        "UPDATE students SET grade_level = $3, section = COALESCE($4, section)
-                         WHERE id = $1 AND org_id = $2",
-                    )
-                    .bind(d.student_id)
-                    .bind(org_id)
-                    .bind(&to_g)
-                    .bind(&d.to_section)
-                    .execute(&mut *tx)
-                    .await?;
-                    promoted += 1;
-                    // Stream isn't changed by promote; record current.stream so
-                    // the audit row reflects the post-promotion state.
-                    (Some(to_g), effective_section, current.stream.clone(), "promote")
-                }
-                "retain" => {
-                    retained += 1;
-                    (
-                        Some(current.grade_level.clone()),
-                        current.section.clone(),
-                        current.stream.clone(),
-                        "retain",
-                    )
-                }
-                "graduate" => {
-                    // Bidirectional CHECK requires withdrawn_at NULL when status='graduated'.
-                    sqlx::query(
-                        r#"
-                        UPDATE students SET
-                            status = 'graduated',
-                            graduation_date = COALESCE(graduation_date, $3),
-                            withdrawn_at = NULL
-                        WHERE id = $1 AND org_id = $2
-                        "#,
-                    )
-                    .bind(d.student_id)
-                    .bind(org_id)
-                    .bind(effective_date)
-                    .execute(&mut *tx)
-                    .await?;
-                    sqlx::query(
-                        r#"
-                        INSERT INTO student_status_history
-                            (student_id, org_id, from_status, to_status, reason,
-                             effective_date, changed_by_user_id)
-                        VALUES ($1, $2, $3, 'graduated', $4, $5, $6)
-                        "#,
-                    )
-                    .bind(d.student_id)
-                    .bind(org_id)
-                    .bind(&current.status)
-                    .bind(&d.reason)
-                    .bind(effective_date)
-                    .bind(changed_by)
-                    .execute(&mut *tx)
-                    .await?;
-                    graduated += 1;
-                    (None, None, None, "graduate")
-                }
-                _ => unreachable!("validated above"),
-            };
-
+            .bind(&change.to_grade)
+            .bind(&d.to_section)
+            .execute(&mut *conn)
+            .await?;
+            // The audit row records the *effective* section and, since
+            // promote doesn't change it, the current stream, so the history
+            // matches the row.
+            (
+                Some(change.to_grade.clone()),
+                change.to_section.clone(),
+                current.stream.clone(),
+                "promote",
+            )
+        }
+        "retain" => (
+            Some(current.grade_level.clone()),
+            current.section.clone(),
+            current.stream.clone(),
+            "retain",
+        ),
+        "graduate" => {
+            // Bidirectional CHECK requires withdrawn_at NULL when status='graduated'.
+            sqlx::query(
+                r#"
+                UPDATE students SET
+                    status = 'graduated',
+                    graduation_date = COALESCE(graduation_date, $3),
+                    withdrawn_at = NULL
+                WHERE id = $1 AND org_id = $2
+                "#,
+            )
+            .bind(d.student_id)
+            .bind(org_id)
+            .bind(batch.effective_date)
+            .execute(&mut *conn)
+            .await?;
             sqlx::query(
                 r#"
-                INSERT INTO student_class_history
-                    (student_id, org_id,
-                     from_grade_level, from_section, from_stream,
-                     to_grade_level, to_section, to_stream,
-                     change_kind, reason, effective_date, changed_by_user_id, promotion_batch_id)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                INSERT INTO student_status_history
+                    (student_id, org_id, from_status, to_status, reason,
+                     effective_date, changed_by_user_id, promotion_batch_id)
+                VALUES ($1, $2, $3, 'graduated', $4, $5, $6, $7)
                 "#,
             )
             .bind(d.student_id)
             .bind(org_id)
-            .bind(&current.grade_level)
-            .bind(&current.section)
-            .bind(&current.stream)
-            .bind(&to_grade)
-            .bind(&to_section)
-            .bind(&to_stream)
-            .bind(change_kind)
+            .bind(&current.status)
             .bind(&d.reason)
-            .bind(effective_date)
-            .bind(changed_by)
-            .bind(batch_id)
-            .execute(&mut *tx)
+            .bind(batch.effective_date)
+            .bind(batch.changed_by)
+            .bind(batch.batch_id)
+            .execute(&mut *conn)
             .await?;
+            (None, None, None, "graduate")
         }
+        _ => unreachable!("validated above"),
+    };
 
-        tx.commit().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO student_class_history
+            (student_id, org_id,
+             from_grade_level, from_section, from_stream,
+             to_grade_level, to_section, to_stream,
+             change_kind, reason, effective_date, changed_by_user_id, promotion_batch_id,
+             academic_year)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+        "#,
+    )
+    .bind(d.student_id)
+    .bind(org_id)
+    .bind(&current.grade_level)
+    .bind(&current.section)
+    .bind(&current.stream)
+    .bind(&to_grade)
+    .bind(&to_section)
+    .bind(&to_stream)
+    .bind(change_kind)
+    .bind(&d.reason)
+    .bind(batch.effective_date)
+    .bind(batch.changed_by)
+    .bind(batch.batch_id)
+    .bind(batch.academic_year)
+    .execute(&mut *conn)
+    .await?;
 
-        Ok(PromoteSummary {
-            promoted,
-            retained,
-            graduated,
-            batch_id,
-            errors: vec![],
-        })
+    Ok(change)
+}
+
+fn decision_error(d: &PromoteDecision, message: String) -> PromoteError {
+    PromoteError {
+        student_id: d.student_id,
+        action: d.action.clone(),
+        message,
+    }
+}
+
+/// The message for a decision that couldn't be applied. Database errors are
+/// logged rather than echoed, as `AppError` does for whole requests.
+fn error_message(e: AppError) -> String {
+    match e {
+        AppError::BadRequest(m) | AppError::NotFound(m) | AppError::Conflict(m) => m,
+        other => {
+            tracing::error!(error = %other, "Promotion decision failed");
+            "Could not apply this decision".into()
+        }
     }
 }
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::students::{
+    RevertPromotionRequest, RevertPromotionSummary, StudentClassHistoryRow, StudentRow,
+};
+
+use super::crud::today;
+use super::StudentsService;
+
+impl StudentsService {
+    /// Undo a promotion batch: every student goes back to the grade level,
+    /// section, stream and status they had before it, and graduates lose
+    /// their `graduation_date`. Each student gets a `revert` class history
+    /// row carrying the batch id; graduates also get a status history row.
+    ///
+    /// All-or-nothing, and refused with 409 if the batch was already
+    /// reverted, belongs to an academic year rollover, or any of its
+    /// students has changed since.
+    pub async fn revert_promotion(
+        &self,
+        org_id: Uuid,
+        batch_id: Uuid,
+        req: RevertPromotionRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<RevertPromotionSummary, AppError> {
+        let effective_date = req.effective_date.unwrap_or_else(today);
+        let mut tx = self.pool.begin().await?;
+
+        // Lock the batch's rows before reading them. A concurrent revert of
+        // the same batch waits here, and the read below then sees its revert
+        // rows; without this, a retain-only batch could be reverted twice.
+        sqlx::query(
+            "SELECT 1 FROM student_class_history WHERE org_id = $1 AND promotion_batch_id = $2 FOR UPDATE",
+        )
+        .bind(org_id)
+        .bind(batch_id)
+        .execute(&mut *tx)
+        .await?;
+        let batch: Vec<StudentClassHistoryRow> = sqlx::query_as(
+            r#"
+            SELECT * FROM student_class_history
+            WHERE org_id = $1 AND promotion_batch_id = $2
+            ORDER BY changed_at, id
+            "#,
+        )
+        .bind(org_id)
+        .bind(batch_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        if batch.is_empty() {
+            return Err(AppError::NotFound("Promotion batch not found".into()));
+        }
+        if batch.iter().any(|r| r.change_kind == "revert") {
+            return Err(AppError::Conflict(
+                "Promotion batch has already been reverted".into(),
+            ));
+        }
+        let rollover: Option<String> = sqlx::query_scalar(
+            "SELECT academic_year FROM academic_year_archives WHERE org_id = $1 AND promotion_batch_id = $2",
+        )
+        .bind(org_id)
+        .bind(batch_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(year) = rollover {
+            return Err(AppError::Conflict(format!(
+                "Promotion batch is part of the {year} academic year rollover and can't be reverted on its own"
+            )));
+        }
+        let batch_at = batch.iter().map(|r| r.changed_at).max().unwrap_or_default();
+        let ids: Vec<Uuid> = batch.iter().map(|r| r.student_id).collect();
+
+        // Lock the students so nothing changes them between the check and
+        // the restore.
+        let students: HashMap<Uuid, StudentRow> = sqlx::query_as::<_, StudentRow>(
+            "SELECT * FROM students WHERE org_id = $1 AND id = ANY($2) FOR UPDATE",
+        )
+        .bind(org_id)
+        .bind(&ids)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|s| (s.id, s))
+        .collect();
+
+        let changed_later: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            SELECT student_id FROM student_class_history
+            WHERE org_id = $1 AND student_id = ANY($2) AND changed_at > $3
+              AND promotion_batch_id IS DISTINCT FROM $4
+            UNION
+            SELECT student_id FROM student_status_history
+            WHERE org_id = $1 AND student_id = ANY($2) AND changed_at > $3
+              AND promotion_batch_id IS DISTINCT FROM $4
+            "#,
+        )
+        .bind(org_id)
+        .bind(&ids)
+        .bind(batch_at)
+        .bind(batch_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // Status before graduation, from the batch's own status rows.
+        let prior_status: HashMap<Uuid, String> = sqlx::query_as::<_, (Uuid, String)>(
+            r#"
+            SELECT student_id, from_status FROM student_status_history
+            WHERE org_id = $1 AND promotion_batch_id = $2
+            "#,
+        )
+        .bind(org_id)
+        .bind(batch_id)
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .collect();
+
+        // A student also counts as changed when their row no longer matches
+        // what the batch left, e.g. after an edit that writes no history.
+        let mut changed = Vec::new();
+        for row in &batch {
+            let unchanged = students.get(&row.student_id).is_some_and(|s| {
+                if row.change_kind == "graduate" {
+                    s.status == "graduated"
+                        && Some(&s.grade_level) == row.from_grade_level.as_ref()
+                        && s.section == row.from_section
+                        && s.stream == row.from_stream
+                } else {
+                    s.status != "graduated"
+                        && Some(&s.grade_level) == row.to_grade_level.as_ref()
+                        && s.section == row.to_section
+                        && s.stream == row.to_stream
+                }
+            });
+            if !unchanged || changed_later.contains(&row.student_id) {
+                changed.push(
+                    students
+                        .get(&row.student_id)
+                        .map(|s| s.admission_number.clone())
+                        .unwrap_or_else(|| row.student_id.to_string()),
+                );
+            }
+        }
+        if !changed.is_empty() {
+            return Err(AppError::Conflict(format!(
+                "Students changed after the promotion batch: {}",
+                changed.join(", ")
+            )));
+        }
+
+        let (mut promoted, mut retained, mut graduated) = (0i64, 0i64, 0i64);
+        for row in &batch {
+            let current = &students[&row.student_id];
+            let status = match row.change_kind.as_str() {
+                "graduate" => prior_status
+                    .get(&row.student_id)
+                    .map(String::as_str)
+                    .unwrap_or("active"),
+                _ => current.status.as_str(),
+            };
+            // Same terminal-date rules as change_status. The batch cleared any
+            // withdrawn_at, so a student graduated from withdrawn gets the
+            // time of their last withdrawal back.
+            sqlx::query(
+                r#"
+                UPDATE students SET
+                    grade_level = COALESCE($3, grade_level),
+                    section = $4,
+                    stream = $5,
+                    status = $6,
+                    graduation_date = CASE WHEN $6 = 'graduated' THEN graduation_date ELSE NULL END,
+                    withdrawn_at = CASE WHEN $6 = 'withdrawn' THEN COALESCE(withdrawn_at,
+                        (SELECT MAX(h.changed_at) FROM student_status_history h
+                         WHERE h.student_id = $1 AND h.to_status = 'withdrawn'),
+                        NOW()) ELSE NULL END
+                WHERE id = $1 AND org_id = $2
+                "#,
+            )
+            .bind(row.student_id)
+            .bind(org_id)
+            .bind(&row.from_grade_level)
+            .bind(&row.from_section)
+            .bind(&row.from_stream)
+            .bind(status)
+            .execute(&mut *tx)
+            .await?;
+
+            if status != current.status {
+                sqlx::query(
+                    r#"
+                    INSERT INTO student_status_history
+                        (student_id, org_id, from_status, to_status, reason,
+                         effective_date, changed_by_user_id, promotion_batch_id)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                    "#,
+                )
+                .bind(row.student_id)
+                .bind(org_id)
+                .bind(&current.status)
+                .bind(status)
+                .bind(&req.reason)
+                .bind(effective_date)
+                .bind(changed_by)
+                .bind(batch_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sqlx::query(
+                r#"
+                INSERT INTO student_class_history
+                    (student_id, org_id,
+                     from_grade_level, from_section, from_stream,
+                     to_grade_level, to_section, to_stream,
+                     change_kind, reason, effective_date, changed_by_user_id, promotion_batch_id,
+                     academic_year)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, 'revert', $9, $10, $11, $12, $13)
+                "#,
+            )
+            .bind(row.student_id)
+            .bind(org_id)
+            .bind(&current.grade_level)
+            .bind(&current.section)
+            .bind(&current.stream)
+            .bind(&row.from_grade_level)
+            .bind(&row.from_section)
+            .bind(&row.from_stream)
+            .bind(&req.reason)
+            .bind(effective_date)
+            .bind(changed_by)
+            .bind(batch_id)
+            .bind(&row.academic_year)
+            .execute(&mut *tx)
+            .await?;
+
+            match row.change_kind.as_str() {
+                "promote" => promoted += 1,
+                "retain" => retained += 1,
+                _ => graduated += 1,
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(RevertPromotionSummary {
+            batch_id,
+            promoted,
+            retained,
+            graduated,
+        })
+    }
+}
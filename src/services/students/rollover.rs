@@ -0,0 +1,437 @@
+use std::collections::HashMap;
+
+use chrono::Months;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::students::{
+    AcademicYearArchive, ClassRollEntry, ClassRollQuery, ClassRollResponse, PromoteDecision,
+    RolloverRequest, RolloverSummary, RolloverTerm,
+};
+
+use super::crud::today;
+use super::promote::{apply_decision, Batch};
+use super::StudentsService;
+
+/// School setup child tables copied into an academic year archive, by key.
+const ARCHIVED_TABLES: &[(&str, &str)] = &[
+    ("terms", "school_terms"),
+    ("grade_levels", "school_grade_levels"),
+    ("grading_scales", "school_grading_scales"),
+    ("subjects", "school_subjects"),
+    ("fee_categories", "school_fee_categories"),
+    ("fee_discounts", "school_fee_discounts"),
+];
+
+impl StudentsService {
+    /// Close the current academic year and open the next, in one transaction:
+    ///
+    /// 1. snapshot every active student's class into the closed year's class rolls;
+    /// 2. archive the school's configuration as it stood;
+    /// 3. promote every active student to the next grade level by
+    ///    `school_grade_levels.position`, keeping their section, and graduate
+    ///    the last level — except where `decisions` says otherwise;
+    /// 4. advance `current_academic_year` and replace the terms;
+    /// 5. pin the admission number year to the new academic year's start year
+    ///    and restart its sequence after any numbers already issued for it.
+    ///
+    /// The decisions form one promotion batch, like `POST /students/promote`.
+    pub async fn rollover(
+        &self,
+        org_id: Uuid,
+        req: RolloverRequest,
+        changed_by: Option<Uuid>,
+    ) -> Result<RolloverSummary, AppError> {
+        let closing: String = sqlx::query_scalar::<_, Option<String>>(
+            "SELECT NULLIF(TRIM(current_academic_year), '') FROM school_configs WHERE org_id = $1",
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten()
+        .ok_or_else(|| {
+            AppError::BadRequest("current_academic_year isn't set in school setup".into())
+        })?;
+
+        let new_year = match req.new_academic_year.as_deref().map(str::trim) {
+            Some(year) if !year.is_empty() => year.to_string(),
+            _ => next_academic_year(&closing).ok_or_else(|| {
+                AppError::BadRequest(format!(
+                    "Can't tell the year after '{closing}'; give new_academic_year"
+                ))
+            })?,
+        };
+        if new_year == closing {
+            return Err(AppError::BadRequest(format!(
+                "new_academic_year must differ from the current academic year '{closing}'"
+            )));
+        }
+        let admission_year = start_year(&new_year).ok_or_else(|| {
+            AppError::BadRequest(format!(
+                "new_academic_year '{new_year}' must start with a year, e.g. 2026/2027"
+            ))
+        })?;
+
+        let terms = match req.terms {
+            Some(terms) => terms,
+            None => {
+                let current: Vec<RolloverTerm> = sqlx::query_as(
+                    "SELECT name, start_date, end_date FROM school_terms WHERE org_id = $1 ORDER BY position",
+                )
+                .bind(org_id)
+                .fetch_all(&self.pool)
+                .await?;
+                current.into_iter().map(next_year_term).collect()
+            }
+        };
+        for t in &terms {
+            if t.name.trim().is_empty() {
+                return Err(AppError::BadRequest("Every term needs a name".into()));
+            }
+            if let (Some(start), Some(end)) = (t.start_date, t.end_date)
+                && end < start
+            {
+                return Err(AppError::BadRequest(format!(
+                    "Term '{}' ends before it starts",
+                    t.name
+                )));
+            }
+        }
+
+        let ladder: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM school_grade_levels WHERE org_id = $1 ORDER BY position, name",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+        if ladder.is_empty() {
+            return Err(AppError::BadRequest(
+                "No grade levels are configured in school setup".into(),
+            ));
+        }
+
+        let mut occurrences: HashMap<Uuid, usize> = HashMap::with_capacity(req.decisions.len());
+        for d in &req.decisions {
+            *occurrences.entry(d.student_id).or_default() += 1;
+        }
+        for d in &req.decisions {
+            self.validate_decision(org_id, d, occurrences[&d.student_id]).await?;
+        }
+
+        let batch = Batch {
+            org_id,
+            batch_id: Uuid::new_v4(),
+            effective_date: req.effective_date.unwrap_or_else(today),
+            academic_year: Some(&closing),
+            changed_by,
+        };
+
+        let mut tx = self.pool.begin().await?;
+
+        // Serialise rollovers for the school, then check again under the lock.
+        sqlx::query("SELECT 1 FROM school_configs WHERE org_id = $1 FOR UPDATE")
+            .bind(org_id)
+            .execute(&mut *tx)
+            .await?;
+        let archived: bool = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM academic_year_archives WHERE org_id = $1 AND academic_year = $2)",
+        )
+        .bind(org_id)
+        .bind(&closing)
+        .fetch_one(&mut *tx)
+        .await?;
+        if archived {
+            return Err(AppError::Conflict(format!(
+                "Academic year {closing} has already been rolled over"
+            )));
+        }
+
+        // Read the roster under the lock, and lock it, so the decisions
+        // cover exactly the students archived below.
+        let roster: Vec<(Uuid, String, String)> = sqlx::query_as(
+            r#"
+            SELECT id, admission_number, grade_level FROM students
+            WHERE org_id = $1 AND status = 'active'
+            ORDER BY grade_level, last_name, first_name, id
+            FOR UPDATE
+            "#,
+        )
+        .bind(org_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        for d in &req.decisions {
+            if !roster.iter().any(|(id, _, _)| *id == d.student_id) {
+                return Err(AppError::BadRequest(format!(
+                    "Student {} is not an active student of this school",
+                    d.student_id
+                )));
+            }
+        }
+
+        let reason = format!("Academic year rollover to {new_year}");
+        let mut generated = Vec::with_capacity(roster.len());
+        let mut unplaced = Vec::new();
+        for (student_id, admission_number, grade_level) in &roster {
+            if occurrences.contains_key(student_id) {
+                continue;
+            }
+            let Some(i) = ladder.iter().position(|g| g == grade_level) else {
+                unplaced.push(admission_number.as_str());
+                continue;
+            };
+            generated.push(PromoteDecision {
+                student_id: *student_id,
+                action: if i + 1 == ladder.len() { "graduate" } else { "promote" }.into(),
+                to_grade: ladder.get(i + 1).cloned(),
+                to_section: None,
+                reason: Some(reason.clone()),
+            });
+        }
+        if !unplaced.is_empty() {
+            return Err(AppError::BadRequest(format!(
+                "Students in grade levels not in school setup need a decision: {}",
+                unplaced.join(", ")
+            )));
+        }
+
+        let class_roll_size = sqlx::query(
+            r#"
+            INSERT INTO academic_year_class_rolls
+                (org_id, academic_year, student_id, admission_number, first_name, last_name,
+                 grade_level, section, stream)
+            SELECT org_id, $2, id, admission_number, first_name, last_name,
+                   grade_level, section, stream
+            FROM students
+            WHERE org_id = $1 AND status = 'active'
+            "#,
+        )
+        .bind(org_id)
+        .bind(&closing)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected() as i64;
+
+        let children = ARCHIVED_TABLES
+            .iter()
+            .map(|(key, table)| {
+                format!(
+                    "'{key}', (SELECT COALESCE(jsonb_agg(to_jsonb(t) - 'id' - 'org_id' ORDER BY t.position), '[]')
+                               FROM {table} t WHERE t.org_id = $1)"
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",\n");
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO academic_year_archives
+                (org_id, academic_year, next_academic_year, configuration,
+                 promotion_batch_id, archived_by_user_id)
+            SELECT $1, $2, $3,
+                   jsonb_build_object('config', to_jsonb(c) - 'id' - 'org_id', {children}),
+                   $4, $5
+            FROM school_configs c
+            WHERE c.org_id = $1
+            "#
+        ))
+        .bind(org_id)
+        .bind(&closing)
+        .bind(&new_year)
+        .bind(batch.batch_id)
+        .bind(changed_by)
+        .execute(&mut *tx)
+        .await?;
+
+        let (mut promoted, mut retained, mut graduated) = (0i64, 0i64, 0i64);
+        for d in req.decisions.iter().chain(&generated) {
+            apply_decision(&mut tx, &batch, d).await?;
+            match d.action.as_str() {
+                "promote" => promoted += 1,
+                "retain" => retained += 1,
+                _ => graduated += 1,
+            }
+        }
+
+        // Numbers already issued for the new year keep theirs; the sequence
+        // carries on after the highest.
+        sqlx::query(
+            r#"
+            UPDATE school_configs SET
+                current_academic_year = $2,
+                admission_number_year = $3,
+                admission_number_next_seq = GREATEST(
+                    CASE WHEN admission_number_seq_year = $3 THEN admission_number_next_seq ELSE 1 END,
+                    (SELECT COALESCE(MAX(SUBSTRING(s.admission_number FROM '/' || $3::text || '/([0-9]+)$')::int), 0) + 1
+                     FROM students s WHERE s.org_id = $1)),
+                admission_number_seq_year = $3
+            WHERE org_id = $1
+            "#,
+        )
+        .bind(org_id)
+        .bind(&new_year)
+        .bind(admission_year)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM school_terms WHERE org_id = $1")
+            .bind(org_id)
+            .execute(&mut *tx)
+            .await?;
+        for (i, t) in terms.iter().enumerate() {
+            sqlx::query(
+                r#"INSERT INTO school_terms (org_id, name, start_date, end_date, position)
+                   VALUES ($1, $2, $3, $4, $5)"#,
+            )
+            .bind(org_id)
+            .bind(t.name.trim())
+            .bind(t.start_date)
+            .bind(t.end_date)
+            .bind(i as i16)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        let batch_id = batch.batch_id;
+        Ok(RolloverSummary {
+            closed_academic_year: closing,
+            academic_year: new_year,
+            batch_id,
+            promoted,
+            retained,
+            graduated,
+            class_roll_size,
+            terms,
+            admission_number_year: admission_year,
+        })
+    }
+
+    /// Archived configurations of closed academic years, most recent first.
+    pub async fn academic_year_archives(
+        &self,
+        org_id: Uuid,
+    ) -> Result<Vec<AcademicYearArchive>, AppError> {
+        let archives = sqlx::query_as(
+            r#"
+            SELECT academic_year, next_academic_year, configuration, promotion_batch_id,
+                   archived_by_user_id AS archived_by, archived_at
+            FROM academic_year_archives
+            WHERE org_id = $1
+            ORDER BY archived_at DESC
+            "#,
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(archives)
+    }
+
+    /// Class rolls of a closed academic year.
+    pub async fn class_rolls(
+        &self,
+        org_id: Uuid,
+        q: ClassRollQuery,
+    ) -> Result<ClassRollResponse, AppError> {
+        let academic_year = q.academic_year.trim().to_string();
+        let filter = |v: &Option<String>| {
+            v.as_deref()
+                .map(str::trim)
+                .filter(|s| !s.is_empty())
+                .map(str::to_string)
+        };
+        let data: Vec<ClassRollEntry> = sqlx::query_as(
+            r#"
+            SELECT student_id, admission_number, first_name, last_name,
+                   grade_level, section, stream
+            FROM academic_year_class_rolls
+            WHERE org_id = $1 AND academic_year = $2
+              AND ($3::text IS NULL OR grade_level = $3)
+              AND ($4::text IS NULL OR section = $4)
+            ORDER BY grade_level, section NULLS FIRST, last_name, first_name, student_id
+            "#,
+        )
+        .bind(org_id)
+        .bind(&academic_year)
+        .bind(filter(&q.grade_level))
+        .bind(filter(&q.section))
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(ClassRollResponse {
+            academic_year,
+            data,
+        })
+    }
+}
+
+/// Every number in the year moved on by one, keeping its width:
+/// `2025/2026` → `2026/2027`, `2025-26` → `2026-27`.
+fn next_academic_year(year: &str) -> Option<String> {
+    let mut out = String::with_capacity(year.len());
+    let mut digits = String::new();
+    let mut found = false;
+    for c in year.chars().chain(std::iter::once('\0')) {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if !digits.is_empty() {
+            let n: u64 = digits.parse().ok()?;
+            let width = digits.len();
+            let wrap = 10u64.checked_pow(width as u32)?;
+            out.push_str(&format!("{:0width$}", (n + 1) % wrap));
+            digits.clear();
+            found = true;
+        }
+        if c != '\0' {
+            out.push(c);
+        }
+    }
+    found.then_some(out)
+}
+
+/// The first four-digit number in an academic year, e.g. 2026 for `2026/2027`.
+fn start_year(year: &str) -> Option<i16> {
+    year.split(|c: char| !c.is_ascii_digit())
+        .find(|part| part.len() == 4)
+        .and_then(|part| part.parse().ok())
+}
+
+fn next_year_term(t: RolloverTerm) -> RolloverTerm {
+    let shift = |d: Option<chrono::NaiveDate>| d.and_then(|d| d.checked_add_months(Months::new(12)));
+    RolloverTerm {
+        name: t.name,
+        start_date: shift(t.start_date),
+        end_date: shift(t.end_date),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn derives_the_next_academic_year() {
+        assert_eq!(next_academic_year("2025/2026").as_deref(), Some("2026/2027"));
+        assert_eq!(next_academic_year("2025-26").as_deref(), Some("2026-27"));
+        assert_eq!(next_academic_year("2099-99").as_deref(), Some("2100-00"));
+        assert_eq!(next_academic_year("Session 2026").as_deref(), Some("Session 2027"));
+        assert_eq!(next_academic_year("Current"), None);
+        assert_eq!(start_year("2026/2027"), Some(2026));
+        assert_eq!(start_year("26/27"), None);
+    }
+
+    #[test]
+    fn shifts_terms_by_a_year() {
+        let d = |y, m, d| NaiveDate::from_ymd_opt(y, m, d);
+        let term = next_year_term(RolloverTerm {
+            name: "Second Term".into(),
+            start_date: d(2024, 2, 29),
+            end_date: None,
+        });
+        assert_eq!(term.start_date, d(2025, 2, 28));
+        assert_eq!(term.end_date, None);
+    }
+}
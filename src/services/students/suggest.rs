@@ -0,0 +1,480 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::errors::AppError;
+use crate::models::gradebook::ClassResultsQuery;
+use crate::models::students::{
+    PromotionSuggestionsQuery, PromotionSuggestionsResponse, SuggestedDecision,
+};
+use crate::services::attendance::grade_group;
+use crate::services::attendance::stats::{counts_for_students, rates_for_students};
+use crate::services::gradebook::scale::round2;
+use crate::services::gradebook::{load_grading_config, parse_number, GradebookService};
+
+use super::crud::validate_grade_level;
+use super::StudentsService;
+
+/// `promotion_rules` from the policies section, parsed for one grade level.
+/// Unset rules don't apply.
+#[derive(Debug, Default)]
+struct PromotionRules {
+    /// `overall_pass_percentage`: minimum annual average.
+    pass_percentage: Option<f64>,
+    /// `min_subjects_to_pass`, for the grade level's group when set per group.
+    min_subjects: Option<usize>,
+    /// `required_subjects`: core subjects that must each be passed.
+    required_subjects: Vec<String>,
+    /// `min_attendance_percentage`: minimum attendance rate for the year.
+    min_attendance: Option<f64>,
+    /// `conditional_promotion` with `max_subjects_for_supplementary`.
+    max_supplementary: Option<usize>,
+    /// `max_repeats`: times a student can repeat this grade before they are
+    /// moved up regardless.
+    max_repeats: Option<u32>,
+}
+
+impl PromotionRules {
+    fn parse(rules: &Value, group: Option<&str>) -> Self {
+        let number = |key: &str| rule_number(rules.get(key)?);
+        let count = |v: Option<f64>| v.filter(|n| *n >= 0.0).map(|n| n as usize);
+        let min_subjects = match rules.get("min_subjects_to_pass") {
+            Some(Value::Object(by_group)) => group.and_then(|g| by_group.get(g)).and_then(rule_number),
+            Some(v) => rule_number(v),
+            None => None,
+        };
+        let conditional = rules
+            .get("conditional_promotion")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        Self {
+            pass_percentage: number("overall_pass_percentage"),
+            min_subjects: count(min_subjects),
+            required_subjects: rules
+                .get("required_subjects")
+                .and_then(Value::as_array)
+                .map(|arr| {
+                    arr.iter()
+                        .filter_map(Value::as_str)
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(String::from)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            min_attendance: number("min_attendance_percentage"),
+            max_supplementary: count(number("max_subjects_for_supplementary"))
+                .filter(|_| conditional),
+            max_repeats: count(number("max_repeats")).map(|n| n as u32),
+        }
+    }
+}
+
+/// Rules are stored as strings (`"40"`) but numbers are accepted too.
+fn rule_number(v: &Value) -> Option<f64> {
+    match v {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => parse_number(Some(s)),
+        _ => None,
+    }
+}
+
+/// What a student achieved over the year.
+#[derive(Debug, Default)]
+struct YearRecord {
+    /// Subject → annual total (mean of the term totals).
+    subjects: BTreeMap<String, f64>,
+    attendance_rate: Option<f64>,
+    /// Times already retained in this grade level.
+    repeats: u32,
+}
+
+#[derive(Debug, PartialEq)]
+struct Outcome {
+    promote: bool,
+    conditional: bool,
+    reasons: Vec<String>,
+}
+
+/// Apply the rules to one student's year.
+fn evaluate(rules: &PromotionRules, record: &YearRecord, passmark: f64) -> Outcome {
+    if record.subjects.is_empty() {
+        return Outcome {
+            promote: false,
+            conditional: false,
+            reasons: vec!["No results for the year".into()],
+        };
+    }
+    let average = round2(record.subjects.values().sum::<f64>() / record.subjects.len() as f64);
+    let failed: Vec<&str> = record
+        .subjects
+        .iter()
+        .filter(|(_, total)| **total < passmark)
+        .map(|(subject, _)| subject.as_str())
+        .collect();
+    let passed = record.subjects.len() - failed.len();
+
+    // Shortfalls a supplementary exam can make up, and ones it can't.
+    let mut subject_reasons = Vec::new();
+    let mut other_reasons = Vec::new();
+    if let Some(min) = rules.pass_percentage
+        && average < min
+    {
+        other_reasons.push(format!("Average {average} is below {min}"));
+    }
+    if let Some(min) = rules.min_subjects
+        && passed < min
+    {
+        subject_reasons.push(format!("Passed {passed} subjects; {min} required"));
+    }
+    for required in &rules.required_subjects {
+        match record
+            .subjects
+            .iter()
+            .find(|(s, _)| s.eq_ignore_ascii_case(required))
+        {
+            Some((s, total)) if *total < passmark => {
+                subject_reasons.push(format!("Did not pass {s}"));
+            }
+            Some(_) => {}
+            None => other_reasons.push(format!("No result in {required}")),
+        }
+    }
+    if let (Some(min), Some(rate)) = (rules.min_attendance, record.attendance_rate)
+        && rate < min
+    {
+        other_reasons.push(format!("Attendance {rate}% is below {min}%"));
+    }
+
+    if subject_reasons.is_empty() && other_reasons.is_empty() {
+        return Outcome {
+            promote: true,
+            conditional: false,
+            reasons: vec![format!(
+                "Average {average}, passed {passed} of {}",
+                record.subjects.len()
+            )],
+        };
+    }
+    let only_subjects = other_reasons.is_empty();
+    let mut reasons = other_reasons;
+    reasons.append(&mut subject_reasons);
+    if only_subjects && rules.max_supplementary.is_some_and(|max| failed.len() <= max) {
+        reasons.push(format!("Conditional: supplementary exams in {}", failed.join(", ")));
+        return Outcome {
+            promote: true,
+            conditional: true,
+            reasons,
+        };
+    }
+    if let Some(max) = rules.max_repeats
+        && record.repeats >= max
+    {
+        reasons.push(format!(
+            "Already repeated this class {} time(s); max_repeats is {max}",
+            record.repeats
+        ));
+        return Outcome {
+            promote: true,
+            conditional: false,
+            reasons,
+        };
+    }
+    Outcome {
+        promote: false,
+        conditional: false,
+        reasons,
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RosterRow {
+    id: Uuid,
+    admission_number: String,
+    first_name: String,
+    last_name: String,
+    section: Option<String>,
+    repeats: i64,
+}
+
+impl StudentsService {
+    /// Evaluate every active student in a grade level (or one section of it)
+    /// against `promotion_rules` and propose a decision for each.
+    ///
+    /// A subject's annual total is the mean of its term totals for the year;
+    /// it is passed at the grading passmark. Attendance covers the year's
+    /// term dates for the current year, otherwise the student's overall rate.
+    /// Nothing is written.
+    pub async fn suggest_promotions(
+        &self,
+        org_id: Uuid,
+        q: PromotionSuggestionsQuery,
+    ) -> Result<PromotionSuggestionsResponse, AppError> {
+        validate_grade_level(&self.pool, org_id, &q.grade_level).await?;
+        let section = q
+            .section
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(String::from);
+        let config = load_grading_config(&mut *self.pool.acquire().await?, org_id).await?;
+        let academic_year = config.academic_year(q.academic_year.as_deref())?;
+
+        let (criteria, rules): (Option<String>, Option<Value>) = sqlx::query_as(
+            "SELECT promotion_criteria, promotion_rules FROM school_configs WHERE org_id = $1",
+        )
+        .bind(org_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .unwrap_or((None, None));
+        let group = grade_group(&self.pool, org_id, &q.grade_level).await?;
+        let rules = PromotionRules::parse(&rules.unwrap_or(Value::Null), group.as_deref());
+
+        let next_grade: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT name FROM school_grade_levels
+            WHERE org_id = $1
+              AND position > (SELECT position FROM school_grade_levels
+                              WHERE org_id = $1 AND name = $2 ORDER BY position LIMIT 1)
+            ORDER BY position
+            LIMIT 1
+            "#,
+        )
+        .bind(org_id)
+        .bind(&q.grade_level)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let roster: Vec<RosterRow> = sqlx::query_as(
+            r#"
+            SELECT s.id, s.admission_number, s.first_name, s.last_name, s.section,
+                   (SELECT COUNT(*) FROM student_class_history h
+                    WHERE h.student_id = s.id AND h.change_kind = 'retain'
+                      AND h.from_grade_level = s.grade_level
+                      AND NOT EXISTS (SELECT 1 FROM student_class_history r
+                                      WHERE r.promotion_batch_id = h.promotion_batch_id
+                                        AND r.change_kind = 'revert')) AS repeats
+            FROM students s
+            WHERE s.org_id = $1 AND s.grade_level = $2 AND s.status = 'active'
+              AND ($3::text IS NULL OR s.section = $3)
+            ORDER BY s.last_name, s.first_name, s.id
+            "#,
+        )
+        .bind(org_id)
+        .bind(&q.grade_level)
+        .bind(&section)
+        .fetch_all(&self.pool)
+        .await?;
+        let ids: Vec<Uuid> = roster.iter().map(|s| s.id).collect();
+
+        // Term totals per student and subject, from each term's results in
+        // this grade level.
+        let terms: Vec<(String, Option<NaiveDate>, Option<NaiveDate>)> = sqlx::query_as(
+            "SELECT name, start_date, end_date FROM school_terms WHERE org_id = $1 ORDER BY position",
+        )
+        .bind(org_id)
+        .fetch_all(&self.pool)
+        .await?;
+        let gradebook = GradebookService::new(self.pool.clone());
+        let mut passmark = 0.0;
+        let mut totals: HashMap<Uuid, BTreeMap<String, Vec<f64>>> = HashMap::new();
+        for (term, _, _) in &terms {
+            let results = gradebook
+                .class_results(
+                    org_id,
+                    ClassResultsQuery {
+                        academic_year: Some(academic_year.clone()),
+                        term: term.clone(),
+                        grade_level: q.grade_level.clone(),
+                        section: None,
+                        subject: None,
+                    },
+                )
+                .await?;
+            passmark = results.passmark;
+            for student in results.students {
+                let by_subject = totals.entry(student.student_id).or_default();
+                for s in student.subjects {
+                    by_subject.entry(s.subject).or_default().push(s.total);
+                }
+            }
+        }
+
+        // Term dates in school setup belong to the current academic year.
+        let current_year =
+            config.current_academic_year.as_deref().map(str::trim) == Some(academic_year.as_str());
+        let window = terms
+            .iter()
+            .filter_map(|(_, start, end)| Some(((*start)?, (*end)?)))
+            .reduce(|(a, b), (c, d)| (a.min(c), b.max(d)))
+            .filter(|_| current_year);
+        let mut attendance: HashMap<Uuid, f64> = match window {
+            Some((from, to)) => counts_for_students(&self.pool, org_id, &ids, from, to)
+                .await?
+                .into_iter()
+                .filter_map(|(id, counts)| Some((id, counts.rate()?)))
+                .collect(),
+            None => rates_for_students(&self.pool, org_id, &ids).await?,
+        };
+
+        let mut decisions = Vec::with_capacity(roster.len());
+        let (mut promote, mut retain, mut graduate) = (0, 0, 0);
+        for student in roster {
+            let record = YearRecord {
+                subjects: totals
+                    .remove(&student.id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(subject, t)| (subject, round2(t.iter().sum::<f64>() / t.len() as f64)))
+                    .collect(),
+                attendance_rate: attendance.remove(&student.id),
+                repeats: student.repeats.max(0) as u32,
+            };
+            let outcome = evaluate(&rules, &record, passmark);
+            let (action, to_grade, to_section) = match (&next_grade, outcome.promote) {
+                (Some(next), true) => {
+                    promote += 1;
+                    ("promote", Some(next.clone()), student.section.clone())
+                }
+                (None, true) => {
+                    graduate += 1;
+                    ("graduate", None, None)
+                }
+                (_, false) => {
+                    retain += 1;
+                    ("retain", None, None)
+                }
+            };
+            let subjects_passed = record.subjects.values().filter(|t| **t >= passmark).count();
+            decisions.push(SuggestedDecision {
+                student_id: student.id,
+                admission_number: student.admission_number,
+                first_name: student.first_name,
+                last_name: student.last_name,
+                action: action.into(),
+                to_grade,
+                to_section,
+                reason: outcome.reasons.join("; "),
+                reasons: outcome.reasons,
+                conditional: outcome.conditional,
+                average: (!record.subjects.is_empty()).then(|| {
+                    round2(record.subjects.values().sum::<f64>() / record.subjects.len() as f64)
+                }),
+                subjects_taken: record.subjects.len(),
+                subjects_passed,
+                attendance_rate: record.attendance_rate,
+            });
+        }
+
+        Ok(PromotionSuggestionsResponse {
+            academic_year,
+            grade_level: q.grade_level,
+            section,
+            criteria,
+            next_grade,
+            promote,
+            retain,
+            graduate,
+            decisions,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(scores: &[(&str, f64)]) -> YearRecord {
+        YearRecord {
+            subjects: scores.iter().map(|(s, t)| (s.to_string(), *t)).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_rules_per_group() {
+        let rules = serde_json::json!({
+            "min_subjects_to_pass": { "Primary": "5", "Secondary": "6" },
+            "overall_pass_percentage": "40",
+            "required_subjects": ["Mathematics", " "],
+            "conditional_promotion": false,
+            "max_subjects_for_supplementary": "2",
+            "max_repeats": 1,
+        });
+        let parsed = PromotionRules::parse(&rules, Some("Secondary"));
+        assert_eq!(parsed.min_subjects, Some(6));
+        assert_eq!(parsed.pass_percentage, Some(40.0));
+        assert_eq!(parsed.required_subjects, ["Mathematics"]);
+        assert_eq!(parsed.max_supplementary, None);
+        assert_eq!(parsed.max_repeats, Some(1));
+        assert_eq!(PromotionRules::parse(&rules, None).min_subjects, None);
+        let flat = serde_json::json!({ "min_subjects_to_pass": 3 });
+        assert_eq!(PromotionRules::parse(&flat, None).min_subjects, Some(3));
+    }
+
+    #[test]
+    fn promotes_when_every_rule_is_met() {
+        let rules = PromotionRules {
+            pass_percentage: Some(50.0),
+            min_subjects: Some(2),
+            required_subjects: vec!["mathematics".into()],
+            ..Default::default()
+        };
+        let outcome = evaluate(&rules, &record(&[("Mathematics", 70.0), ("English", 60.0)]), 50.0);
+        assert!(outcome.promote && !outcome.conditional);
+        assert_eq!(outcome.reasons, ["Average 65, passed 2 of 2"]);
+    }
+
+    #[test]
+    fn retains_with_every_shortfall() {
+        let rules = PromotionRules {
+            pass_percentage: Some(50.0),
+            min_subjects: Some(2),
+            required_subjects: vec!["Mathematics".into(), "Civics".into()],
+            min_attendance: Some(75.0),
+            ..Default::default()
+        };
+        let mut r = record(&[("Mathematics", 40.0), ("English", 55.0)]);
+        r.attendance_rate = Some(60.0);
+        let outcome = evaluate(&rules, &r, 50.0);
+        assert!(!outcome.promote);
+        assert_eq!(
+            outcome.reasons,
+            [
+                "Average 47.5 is below 50",
+                "No result in Civics",
+                "Attendance 60% is below 75%",
+                "Passed 1 subjects; 2 required",
+                "Did not pass Mathematics",
+            ]
+        );
+        assert_eq!(evaluate(&rules, &record(&[]), 50.0).reasons, ["No results for the year"]);
+    }
+
+    #[test]
+    fn conditional_promotion_and_max_repeats() {
+        let mut rules = PromotionRules {
+            min_subjects: Some(3),
+            max_supplementary: Some(1),
+            ..Default::default()
+        };
+        let r = record(&[("Mathematics", 40.0), ("English", 60.0), ("Science", 70.0)]);
+        let outcome = evaluate(&rules, &r, 50.0);
+        assert!(outcome.promote && outcome.conditional);
+        assert_eq!(
+            outcome.reasons.last().unwrap(),
+            "Conditional: supplementary exams in Mathematics"
+        );
+
+        // Too many failures for supplementary exams, but repeated enough.
+        rules.max_supplementary = Some(0);
+        rules.max_repeats = Some(1);
+        let mut r = r;
+        assert!(!evaluate(&rules, &r, 50.0).promote);
+        r.repeats = 1;
+        let outcome = evaluate(&rules, &r, 50.0);
+        assert!(outcome.promote && !outcome.conditional);
+    }
+}
@@ -2,7 +2,12 @@ use sqlx::PgPool;
 use std::sync::Arc;
 
 use crate::config::AppConfig;
+use crate::services::attendance::AttendanceService;
+use crate::services::calendar::CalendarService;
+use crate::services::fees::FeesService;
+use crate::services::gradebook::GradebookService;
 use crate::services::organization::OrganizationService;
+use crate::services::report_cards::ReportCardService;
 use crate::services::school_setup::SchoolSetupService;
 use crate::services::students::StudentsService;
 use crate::services::user::UserService;
@@ -17,6 +22,11 @@ pub struct AppState {
     pub organization_service: Arc<OrganizationService>,
     pub school_setup_service: Arc<SchoolSetupService>,
     pub students_service: Arc<StudentsService>,
+    pub attendance_service: Arc<AttendanceService>,
+    pub calendar_service: Arc<CalendarService>,
+    pub gradebook_service: Arc<GradebookService>,
+    pub report_card_service: Arc<ReportCardService>,
+    pub fees_service: Arc<FeesService>,
 }
 
 impl AppState {
@@ -26,6 +36,11 @@ impl AppState {
         let organization_service = Arc::new(OrganizationService::new(db_pool.clone()));
         let school_setup_service = Arc::new(SchoolSetupService::new(db_pool.clone()));
         let students_service = Arc::new(StudentsService::new(db_pool.clone()));
+        let attendance_service = Arc::new(AttendanceService::new(db_pool.clone()));
+        let calendar_service = Arc::new(CalendarService::new(db_pool.clone()));
+        let gradebook_service = Arc::new(GradebookService::new(db_pool.clone()));
+        let report_card_service = Arc::new(ReportCardService::new(db_pool.clone(), &config.report_cards));
+        let fees_service = Arc::new(FeesService::new(db_pool.clone()));
 
         Self {
             config: Arc::new(config),
@@ -35,6 +50,11 @@ impl AppState {
             organization_service,
             school_setup_service,
             students_service,
+            attendance_service,
+            calendar_service,
+            gradebook_service,
+            report_card_service,
+            fees_service,
         }
     }
 }
@@ -12,4 +12,10 @@ mod integration_test {
     mod auth_oauth;
     mod school_setup;
     mod students;
+    mod attendance;
+    mod attendance_alerts;
+    mod calendar;
+    mod gradebook;
+    mod report_cards;
+    mod fees;
 }
@@ -0,0 +1,708 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
+use serial_test::serial;
+use uuid::Uuid;
+use wiremock::MockServer;
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+/// Primary classes taking daily registers, open every day of the week so
+/// registers can be taken whichever weekday the test runs.
+fn register_setup() -> Value {
+    json!({
+        "academic_calendar": { "school_days": EVERY_DAY },
+        "grade_levels": { "grade_levels": ["Primary 1", "Primary 2"] },
+        "policies": { "absence_categories": ["Sick", "Family", "Unexplained"] }
+    })
+}
+
+fn days_ago(n: i64) -> String {
+    (Utc::now().date_naive() - Duration::days(n)).to_string()
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_mark_register_and_read_back() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+    let b = create_student(&state, &school.token, "Primary 1", "Emeka").await;
+    let date = days_ago(1);
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": date,
+            "grade_level": "Primary 1",
+            "records": [
+                { "student_id": a, "status": "present" },
+                { "student_id": b, "status": "absent", "absence_reason": "sick" },
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(body["id"].is_string());
+    assert_eq!(body["counts"]["present"], 1);
+    assert_eq!(body["counts"]["absent"], 1);
+    let absent = body["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["student_id"] == b.to_string())
+        .unwrap();
+    // Reason is stored in the configured spelling.
+    assert_eq!(absent["absence_reason"], "Sick");
+
+    // Re-marking the same day updates in place rather than duplicating.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": date,
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": b, "status": "late" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["counts"]["present"], 1);
+    assert_eq!(body["counts"]["late"], 1);
+    assert_eq!(body["counts"]["absent"], 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_mark_register_rejects_reason_outside_categories() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": a, "status": "absent", "absence_reason": "Holiday" }]
+        }),
+        &school.token,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let msg = body["error"]["message"].as_str().unwrap_or("");
+    assert!(msg.contains("Holiday"), "got {msg}");
+
+    // A reason on a present mark is rejected too.
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": a, "status": "present", "absence_reason": "Sick" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_mark_register_rejects_future_date_and_wrong_class() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    let p1 = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+    let p2 = create_student(&state, &school.token, "Primary 2", "Emeka").await;
+
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(-3),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": p1, "status": "present" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": p2, "status": "present" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let msg = body["error"]["message"].as_str().unwrap_or("");
+    assert!(msg.contains("not in Primary 1"), "got {msg}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_attendance_feeds_student_rate_and_summary() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    // 2 present + 1 late attended, 1 absent, 1 excused (not counted) → 3 / 4 = 75%.
+    let marks = [
+        (5, json!({ "student_id": a, "status": "present" })),
+        (4, json!({ "student_id": a, "status": "present" })),
+        (3, json!({ "student_id": a, "status": "late" })),
+        (2, json!({ "student_id": a, "status": "absent", "absence_reason": "Family" })),
+        (1, json!({ "student_id": a, "status": "excused", "absence_reason": "Sick" })),
+    ];
+    for (ago, record) in marks {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/attendance/registers",
+            json!({ "date": days_ago(ago), "grade_level": "Primary 1", "records": [record] }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}?include=recent_attendance"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["attendance_rate"], 75.0);
+    let recent = body["recent_attendance"].as_array().unwrap();
+    assert_eq!(recent.len(), 5);
+    assert_eq!(recent[0]["status"], "excused");
+    assert_eq!(recent[0]["date"], days_ago(1));
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["summary"]["average_attendance"], 75.0);
+    assert_eq!(body["data"][0]["attendance_rate"], 75.0);
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/attendance?from={}", days_ago(2)),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["counts"]["absent"], 1);
+    assert_eq!(body["counts"]["excused"], 1);
+    assert_eq!(body["attendance_rate"], 0.0);
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_closed_days_take_no_register_and_leave_the_rate() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    for (ago, status) in [(3, "present"), (2, "absent")] {
+        let (code, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/attendance/registers",
+            json!({
+                "date": days_ago(ago),
+                "grade_level": "Primary 1",
+                "records": [{ "student_id": a, "status": status }]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(code, StatusCode::OK, "body: {body}");
+    }
+
+    // Declaring the absent day a holiday afterwards drops it from the rate.
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({ "kind": "public_holiday", "name": "Founders' Day", "start_date": days_ago(2) }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["attendance_rate"], 100.0);
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/attendance"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["counts"]["absent"], 0);
+    assert_eq!(body["records"].as_array().unwrap().len(), 2);
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(2),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": a, "status": "present" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let msg = body["error"]["message"].as_str().unwrap_or("");
+    assert!(msg.contains("public_holiday"), "got {msg}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_non_admin_staff_can_read_register() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "user", register_setup()).await;
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/attendance/registers?date={}&grade_level=Primary%202", days_ago(0)),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["entries"].as_array().unwrap().len(), 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_register_lists_unmarked_roster() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", register_setup()).await;
+    create_student(&state, &school.token, "Primary 1", "Chidi").await;
+    create_student(&state, &school.token, "Primary 2", "Emeka").await;
+
+    let date = days_ago(1);
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/attendance/registers?date={date}&grade_level=Primary%201"),
+        &school.token,
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(body["id"].is_null());
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0]["first_name"], "Chidi");
+    assert!(entries[0]["status"].is_null());
+}
+
+// ── Period-level attendance ─────────────────────────────────────────
+
+/// School where Primary takes daily registers and Secondary takes them
+/// per lesson period (`secondary_mode` overrides the latter).
+fn period_setup(secondary_mode: &str) -> Value {
+    let periods = json!([
+        { "label": "Period 1", "start_time": "08:00", "end_time": "08:40", "is_break": false },
+        { "label": "Short Break", "start_time": "08:40", "end_time": "08:55", "is_break": true },
+        { "label": "Period 2", "start_time": "08:55", "end_time": "09:35", "is_break": false }
+    ]);
+    json!({
+        "academic_calendar": { "school_days": EVERY_DAY },
+        "grade_levels": {
+            "grade_levels": ["Primary 1", "JSS 1"],
+            "custom_group_levels": { "Primary": ["Primary 1"], "Secondary": ["JSS 1"] }
+        },
+        "schedule": {
+            "schedules": {
+                "Primary": { "periods": periods },
+                "Secondary": { "periods": periods }
+            }
+        },
+        "policies": {
+            "attendance_tracking_methods": { "Primary": "daily", "Secondary": secondary_mode },
+            "absence_categories": ["Sick", "Family"]
+        }
+    })
+}
+
+async fn lesson_period_ids(state: &AppState, token: &str, grade: &str) -> (String, Vec<String>) {
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/attendance/periods?grade_level={}", grade.replace(' ', "%20")),
+        token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let ids = body["periods"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|p| p["id"].as_str().unwrap().to_string())
+        .collect();
+    (body["tracking_mode"].as_str().unwrap().to_string(), ids)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_period_marks_roll_up_to_daily_status() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", period_setup("per_subject")).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Chidi").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Emeka").await;
+    let date = days_ago(1);
+
+    let (mode, periods) = lesson_period_ids(&state, &school.token, "JSS 1").await;
+    assert_eq!(mode, "per_period");
+    assert_eq!(periods.len(), 2, "break period must not be listed");
+
+    // A per-period class can't take a plain daily register.
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({ "date": date, "grade_level": "JSS 1", "records": [{ "student_id": a, "status": "present" }] }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Period 1: a absent, b absent (sick). Period 2: a present, b absent.
+    for (period, a_status, b_status) in [(&periods[0], "absent", "absent"), (&periods[1], "present", "absent")] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/attendance/registers",
+            json!({
+                "date": date,
+                "grade_level": "JSS 1",
+                "period_id": period,
+                "records": [
+                    { "student_id": a, "status": a_status },
+                    { "student_id": b, "status": b_status, "absence_reason": "Sick" },
+                ]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+        assert!(body["period_label"].is_string());
+    }
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/attendance/registers?date={date}&grade_level=JSS%201"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(body.get("period_label").is_none());
+    assert_eq!(body["counts"]["late"], 1);
+    assert_eq!(body["counts"]["absent"], 1);
+    let entries = body["entries"].as_array().unwrap();
+    assert!(entries.iter().all(|e| e["is_derived"] == true));
+    let b_entry = entries.iter().find(|e| e["student_id"] == b.to_string()).unwrap();
+    assert_eq!(b_entry["absence_reason"], "Sick");
+
+    // Only the daily mark counts towards the rate: one late day → 100%.
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/attendance"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["attendance_rate"], 100.0);
+    assert_eq!(body["records"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_period_register_rejects_breaks_and_daily_classes() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", period_setup("per_subject")).await;
+    let p1 = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+    let j1 = create_student(&state, &school.token, "JSS 1", "Emeka").await;
+
+    let break_id: Uuid = sqlx::query_scalar(
+        r#"
+        SELECT p.id FROM school_schedule_periods p
+        JOIN school_schedule_groups g ON g.id = p.group_id
+        WHERE g.org_id = $1 AND g.group_name = 'Secondary' AND p.is_break
+        "#,
+    )
+    .bind(school.org_id)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "JSS 1",
+            "period_id": break_id,
+            "records": [{ "student_id": j1, "status": "present" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    assert!(body["error"]["message"].as_str().unwrap_or("").contains("break"));
+
+    let (mode, periods) = lesson_period_ids(&state, &school.token, "Primary 1").await;
+    assert_eq!(mode, "daily");
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "period_id": periods[0],
+            "records": [{ "student_id": p1, "status": "present" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_both_mode_keeps_explicit_daily_mark() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", period_setup("both")).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Chidi").await;
+    let date = days_ago(1);
+    let (mode, periods) = lesson_period_ids(&state, &school.token, "JSS 1").await;
+    assert_eq!(mode, "both");
+
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({ "date": date, "grade_level": "JSS 1", "records": [{ "student_id": a, "status": "present" }] }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": date,
+            "grade_level": "JSS 1",
+            "period_id": periods[1],
+            "records": [{ "student_id": a, "status": "absent" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/attendance/registers?date={date}&grade_level=JSS%201"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["entries"][0]["status"], "present");
+    assert_eq!(body["entries"][0]["is_derived"], false);
+}
+
+// ── Lateness policy ─────────────────────────────────────────────────
+
+fn lateness_setup(tardies_to_absence: &str) -> Value {
+    json!({
+        "academic_calendar": { "school_days": EVERY_DAY },
+        "grade_levels": { "grade_levels": ["Primary 1"] },
+        "schedule": {
+            "schedules": {
+                "Primary": {
+                    "start_time": "08:00",
+                    "periods": [
+                        { "label": "Period 1", "start_time": "08:00", "end_time": "08:40", "is_break": false }
+                    ]
+                }
+            }
+        },
+        "policies": {
+            "late_grace_period": "10",
+            "tardies_to_absence": tardies_to_absence,
+            "absence_categories": ["Sick"]
+        }
+    })
+}
+
+#[tokio::test]
+#[serial]
+async fn test_arrival_time_classified_with_grace_period() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", lateness_setup("3")).await;
+    let on_time = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+    let in_grace = create_student(&state, &school.token, "Primary 1", "Emeka").await;
+    let late = create_student(&state, &school.token, "Primary 1", "Ngozi").await;
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "records": [
+                { "student_id": on_time, "arrival_time": "07:55" },
+                { "student_id": in_grace, "status": "late", "arrival_time": "08:10" },
+                { "student_id": late, "arrival_time": "08:11" },
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let status_of = |id: Uuid| {
+        body["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["student_id"] == id.to_string())
+            .unwrap()["status"]
+            .clone()
+    };
+    assert_eq!(status_of(on_time), "present");
+    assert_eq!(status_of(in_grace), "present");
+    assert_eq!(status_of(late), "late");
+    assert_eq!(body["counts"]["late"], 1);
+
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({
+            "date": days_ago(1),
+            "grade_level": "Primary 1",
+            "records": [{ "student_id": late, "status": "absent", "arrival_time": "09:00" }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_tardies_convert_to_absences_and_follow_policy_changes() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", lateness_setup("2")).await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    // 4 lates with tardies_to_absence = 2 → 2 converted → 2 / 4 attended.
+    for ago in 1..=4 {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/attendance/registers",
+            json!({
+                "date": days_ago(ago),
+                "grade_level": "Primary 1",
+                "records": [{ "student_id": a, "status": "late" }]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["attendance_rate"], 50.0);
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/attendance"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["counts"]["converted_lates"], 2);
+    assert_eq!(body["attendance_rate"], 50.0);
+
+    // Turning the conversion off through setup recomputes the stored rate.
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "policies": { "late_grace_period": "10", "tardies_to_absence": "" } }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students",
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["data"][0]["attendance_rate"], 100.0);
+    assert_eq!(body["summary"]["average_attendance"], 100.0);
+
+    let stored: i32 = sqlx::query_scalar(
+        "SELECT converted_lates FROM attendance_summaries WHERE org_id = $1 AND student_id = $2",
+    )
+    .bind(school.org_id)
+    .bind(a)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(stored, 0);
+}
@@ -0,0 +1,303 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
+use serial_test::serial;
+use wiremock::MockServer;
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+/// A Primary 1 school, open every day of the week, with the given policies
+/// section.
+fn alert_setup(policies: Value) -> Value {
+    json!({
+        "academic_calendar": { "school_days": EVERY_DAY },
+        "grade_levels": { "grade_levels": ["Primary 1"] },
+        "policies": policies,
+    })
+}
+
+async fn mark_day(state: &AppState, token: &str, days_ago: i64, records: Value) {
+    let date = Utc::now().date_naive() - Duration::days(days_ago);
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/registers",
+        json!({ "date": date, "grade_level": "Primary 1", "records": records }),
+        token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+}
+
+async fn run_alerts(state: &AppState, token: &str) -> Value {
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/alerts/run",
+        json!({}),
+        token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    body
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_consecutive_absences_flag_and_notify_primary_guardian() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(
+        &state,
+        &mock_server,
+        "admin",
+        alert_setup(json!({
+            "attendance_alerts": true,
+            "consecutive_absence_alert": "3",
+            "notification_channels": ["email", "sms", "push"],
+        })),
+    )
+    .await;
+    let a = create_student_with(
+        &state,
+        &school.token,
+        json!({
+            "first_name": "Chidi",
+            "grade_level": "Primary 1",
+            "guardians": [
+                { "first_name": "Uncle", "last_name": "Okafor", "email": "uncle@example.com", "is_primary": false },
+                { "first_name": "Ada", "last_name": "Okafor", "email": "ada@example.com", "phone": "+2348000000001", "is_primary": true },
+            ],
+        }),
+    )
+    .await;
+    let b = create_student(&state, &school.token, "Primary 1", "Emeka").await;
+
+    // a: three absences in a row. b: absences broken by an excused day.
+    for (days_ago, b_status) in [(3, "absent"), (2, "excused"), (1, "absent")] {
+        mark_day(
+            &state,
+            &school.token,
+            days_ago,
+            json!([
+                { "student_id": a, "status": "absent" },
+                { "student_id": b, "status": b_status },
+            ]),
+        )
+        .await;
+    }
+
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 1);
+    assert_eq!(run["notifications_queued"], 2);
+
+    let (status, flags) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {flags}");
+    let flags = flags.as_array().unwrap();
+    assert_eq!(flags.len(), 1);
+    assert_eq!(flags[0]["student_id"], a.to_string());
+    assert_eq!(flags[0]["kind"], "consecutive_absence");
+    assert_eq!(flags[0]["value"], 3.0);
+    assert_eq!(flags[0]["threshold"], 3.0);
+
+    // Notices go to the primary guardian only, one per supported channel.
+    let recipients: Vec<(String, String)> = sqlx::query_as(
+        "SELECT channel, recipient FROM notifications WHERE org_id = $1 ORDER BY channel",
+    )
+    .bind(school.org_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        recipients,
+        vec![
+            ("email".to_string(), "ada@example.com".to_string()),
+            ("sms".to_string(), "+2348000000001".to_string()),
+        ]
+    );
+
+    // A flag that is still open isn't re-notified.
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 0);
+    assert_eq!(run["notifications_queued"], 0);
+
+    // Coming back to school resolves the flag.
+    mark_day(&state, &school.token, 0, json!([{ "student_id": a, "status": "present" }])).await;
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["resolved"], 1);
+
+    let (_, open) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags",
+        &school.token,
+    )
+    .await;
+    assert!(open.as_array().unwrap().is_empty());
+    let (_, resolved) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags?status=resolved",
+        &school.token,
+    )
+    .await;
+    assert_eq!(resolved.as_array().unwrap().len(), 1);
+    assert!(resolved[0]["resolved_at"].is_string());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_closed_days_drop_out_of_absence_runs() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(
+        &state,
+        &mock_server,
+        "admin",
+        alert_setup(json!({ "attendance_alerts": true, "consecutive_absence_alert": "3" })),
+    )
+    .await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    for (days_ago, status) in [(4, "absent"), (3, "present"), (2, "absent"), (1, "absent")] {
+        mark_day(&state, &school.token, days_ago, json!([{ "student_id": a, "status": status }])).await;
+    }
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 0);
+
+    // The present day turns out to have been a closure, so it no longer
+    // breaks the run.
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({
+            "kind": "closure",
+            "name": "Flooding",
+            "start_date": Utc::now().date_naive() - Duration::days(3),
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_below_threshold_flags_after_minimum_days() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(
+        &state,
+        &mock_server,
+        "admin",
+        alert_setup(json!({ "attendance_alerts": true, "attendance_threshold": "75" })),
+    )
+    .await;
+    let a = create_student(&state, &school.token, "Primary 1", "Chidi").await;
+
+    for (days_ago, status) in [(5, "absent"), (4, "present"), (3, "absent"), (2, "present")] {
+        mark_day(&state, &school.token, days_ago, json!([{ "student_id": a, "status": status }])).await;
+    }
+    // 50% over four days: too few days to judge yet.
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 0);
+
+    mark_day(&state, &school.token, 1, json!([{ "student_id": a, "status": "present" }])).await;
+    let run = run_alerts(&state, &school.token).await;
+    assert_eq!(run["flagged"], 1);
+    // No guardian on file, so nothing to send.
+    assert_eq!(run["notifications_queued"], 0);
+
+    let (status, flags) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags?kind=below_threshold&grade_level=Primary%201",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {flags}");
+    assert_eq!(flags[0]["value"], 60.0);
+    assert_eq!(flags[0]["threshold"], 75.0);
+
+    // Turning alerts off resolves the open flags.
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "policies": { "attendance_alerts": false, "attendance_threshold": "75" } }),
+    )
+    .await;
+    let (_, open) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags",
+        &school.token,
+    )
+    .await;
+    assert!(open.as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_alert_run_requires_admin_and_enabled_toggle() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+
+    let disabled = setup_school(
+        &state,
+        &mock_server,
+        "admin",
+        alert_setup(json!({ "attendance_alerts": false, "consecutive_absence_alert": "3" })),
+    )
+    .await;
+    let (status, _) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/alerts/run",
+        json!({}),
+        &disabled.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags?status=stale",
+        &disabled.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let teacher = setup_school(
+        &state,
+        &mock_server,
+        "teacher",
+        alert_setup(json!({ "attendance_alerts": true, "consecutive_absence_alert": "3" })),
+    )
+    .await;
+    let (status, _) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/attendance/alerts/run",
+        json!({}),
+        &teacher.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Reading flags is open to any staff member.
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/attendance-flags",
+        &teacher.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -0,0 +1,309 @@
+use axum::http::StatusCode;
+use chrono::{Duration, NaiveDate, Utc};
+use serde_json::{json, Value};
+use serial_test::serial;
+use wiremock::MockServer;
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+fn today_plus(n: i64) -> NaiveDate {
+    Utc::now().date_naive() + Duration::days(n)
+}
+
+/// Two terms around today, open every day of the week so counts don't
+/// depend on which weekday the test runs.
+fn seven_day_terms() -> Value {
+    json!({
+        "academic_calendar": {
+            "calendar_type": "trimester",
+            "current_academic_year": "2025/2026",
+            "school_days": ["mon", "tue", "wed", "thu", "fri", "sat", "sun"],
+            "terms": [
+                { "name": "First Term", "start_date": today_plus(-30), "end_date": today_plus(30) },
+                { "name": "Second Term", "start_date": today_plus(60), "end_date": today_plus(120) },
+            ]
+        }
+    })
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_current_term_counts_instructional_days() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let token = setup_school(&state, &mock_server, "admin", seven_day_terms()).await.token;
+
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({
+            "kind": "public_holiday",
+            "name": "Independence Day",
+            "start_date": today_plus(5),
+            "end_date": today_plus(6),
+        }),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/current-term",
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["today"], json!(today_plus(0)));
+    assert_eq!(body["term"]["name"], "First Term");
+    assert_eq!(body["next_term"]["name"], "Second Term");
+    // 61 term days less the two-day holiday, 31 of them up to today.
+    assert_eq!(body["instructional_days"]["total"], 59);
+    assert_eq!(body["instructional_days"]["elapsed"], 31);
+    assert_eq!(body["instructional_days"]["remaining"], 28);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_day_reports_reason_when_closed() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let token = setup_school(&state, &mock_server, "admin", seven_day_terms()).await.token;
+
+    let (_, event) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({
+            "kind": "mid_term_break", "name": "Mid-term", "start_date": today_plus(10),
+            "end_date": today_plus(14), "notes": "Half term"
+        }),
+        &token,
+    )
+    .await;
+
+    let day = |n: i64| {
+        let state = state.clone();
+        let token = token.clone();
+        async move {
+            let (status, body) = get_auth(
+                test_router(state),
+                &format!("/api/v1/calendar/day?date={}", today_plus(n)),
+                &token,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "body: {body}");
+            body
+        }
+    };
+
+    let open = day(1).await;
+    assert_eq!(open["instructional"], true);
+    assert!(open.get("reason").is_none());
+    assert_eq!(open["term"]["name"], "First Term");
+
+    let on_break = day(12).await;
+    assert_eq!(on_break["instructional"], false);
+    assert_eq!(on_break["reason"], "mid_term_break");
+    assert_eq!(on_break["events"][0]["name"], "Mid-term");
+
+    let between_terms = day(45).await;
+    assert_eq!(between_terms["reason"], "outside_term");
+    assert!(between_terms.get("term").is_none());
+
+    // Shortening the break reopens the later days.
+    let id = event["id"].as_str().unwrap();
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/calendar/events/{id}"),
+        json!({ "end_date": today_plus(11) }),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["notes"], "Half term");
+    assert_eq!(day(12).await["instructional"], true);
+
+    // Notes are cleared by null or an empty string.
+    for notes in [json!("Staff training"), json!(null), json!("Staff training"), json!("")] {
+        let (status, body) = patch_json_auth(
+            test_router(state.clone()),
+            &format!("/api/v1/calendar/events/{id}"),
+            json!({ "notes": notes }),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+        let expected = if notes == "" { Value::Null } else { notes };
+        assert_eq!(body.get("notes").cloned().unwrap_or(Value::Null), expected);
+    }
+
+    let (status, _) = delete_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/calendar/events/{id}"),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(day(10).await["instructional"], true);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_calendar_event_validation_and_permissions() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let token = setup_school(&state, &mock_server, "admin", seven_day_terms()).await.token;
+
+    for bad in [
+        json!({ "kind": "festival", "name": "Fête", "start_date": today_plus(1) }),
+        json!({ "kind": "closure", "name": " ", "start_date": today_plus(1) }),
+        json!({ "kind": "closure", "name": "Flood", "start_date": today_plus(3), "end_date": today_plus(1) }),
+    ] {
+        let (status, body) = post_json_auth(
+            test_router(state.clone()),
+            "/api/v1/calendar/events",
+            bad,
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+
+    // Single-day events default end_date to start_date; listing filters by window.
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({ "kind": "closure", "name": "Flood", "start_date": today_plus(2) }),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    assert_eq!(body["end_date"], json!(today_plus(2)));
+    let (_, listed) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/calendar/events?from={}&to={}", today_plus(2), today_plus(9)),
+        &token,
+    )
+    .await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    let (_, listed) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/calendar/events?from={}", today_plus(3)),
+        &token,
+    )
+    .await;
+    assert!(listed.as_array().unwrap().is_empty());
+
+    let teacher = setup_school(&state, &mock_server, "teacher", seven_day_terms()).await.token;
+    let (status, _) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/events",
+        json!({ "kind": "closure", "name": "Flood", "start_date": today_plus(2) }),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/calendar/current-term",
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_setup_validates_term_dates_and_school_days() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let token = setup_school(&state, &mock_server, "admin", json!({})).await.token;
+
+    for bad in [
+        json!({ "terms": [{ "name": "First Term", "start_date": "01/09/2025", "end_date": "2025-12-15" }] }),
+        json!({ "terms": [{ "name": "First Term", "start_date": "2025-12-15", "end_date": "2025-09-01" }] }),
+        json!({ "school_days": ["monday", "funday"] }),
+    ] {
+        let (status, body) = patch_json_auth(
+            test_router(state.clone()),
+            "/api/v1/schools/setup",
+            json!({ "academic_calendar": bad }),
+            &token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "academic_calendar": {
+            "school_days": ["Sun", "MONDAY", "tue", "wed", "thu"],
+            "terms": [{ "name": "First Term", "start_date": "2025-09-01", "end_date": "2025-12-15" }]
+        }}),
+        &token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let cal = &body["data"]["academic_calendar"];
+    assert_eq!(cal["school_days"], json!(["sunday", "monday", "tuesday", "wednesday", "thursday"]));
+    assert_eq!(cal["terms"][0]["start_date"], "2025-09-01");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_setup_returns_unconverted_legacy_term_dates() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(
+        &state,
+        &mock_server,
+        "admin",
+        json!({ "academic_calendar": { "terms": [{ "name": "First Term" }] } }),
+    )
+    .await;
+
+    // What the calendar migration leaves behind for text it couldn't parse.
+    sqlx::query(
+        "UPDATE school_terms SET legacy_start_date = 'Sept 1st', legacy_end_date = 'mid Dec' WHERE org_id = $1",
+    )
+    .bind(school.org_id)
+    .execute(&state.db_pool)
+    .await
+    .unwrap();
+
+    let (status, body) =
+        get_auth(test_router(state.clone()), "/api/v1/schools/setup", &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let term = &body["data"]["academic_calendar"]["terms"][0];
+    assert_eq!(term["legacy_start_date"], "Sept 1st");
+    assert_eq!(term["legacy_end_date"], "mid Dec");
+
+    // Giving the term a start date retires only that legacy value, and the
+    // legacy fields can't be written through the API.
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "academic_calendar": { "terms": [{
+            "name": "First Term",
+            "start_date": "2025-09-01",
+            "legacy_end_date": "overwritten",
+        }] } }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let term = &body["data"]["academic_calendar"]["terms"][0];
+    assert_eq!(term["start_date"], "2025-09-01");
+    assert!(term.get("legacy_start_date").is_none());
+    assert_eq!(term["legacy_end_date"], "mid Dec");
+}
@@ -1,5 +1,6 @@
 use schoolnify_api::config::{
-    AppConfig, AuthConfig, CorsConfig, DatabaseConfig, ServerConfig, WorkOsConfig,
+    AppConfig, AuthConfig, CorsConfig, DatabaseConfig, JobsConfig, ReportCardsConfig, ServerConfig,
+    WorkOsConfig,
 };
 
 /// Build a test AppConfig with the wiremock server URL as the WorkOS API base.
@@ -42,5 +43,12 @@ pub fn test_config(workos_base_url: &str) -> AppConfig {
             allowed_origins: vec!["http://localhost:3000".into()],
             base_domain: "localhost".into(),
         },
+        jobs: JobsConfig {
+            attendance_alerts_interval_secs: 0,
+        },
+        // Logos are served by the wiremock server on 127.0.0.1.
+        report_cards: ReportCardsConfig {
+            allow_private_logo_hosts: true,
+        },
     }
 }
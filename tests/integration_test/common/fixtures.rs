@@ -1,6 +1,13 @@
+use axum::http::StatusCode;
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
 use sha2::{Digest, Sha256};
 use sqlx::PgPool;
 use uuid::Uuid;
+use wiremock::MockServer;
+
+use super::jwt::sign_test_jwt;
+use super::state::{post_json_auth, test_router};
 
 /// Generate a unique test email that won't collide.
 pub fn unique_email() -> String {
@@ -28,6 +35,7 @@ pub fn unique_slug(prefix: &str) -> String {
 }
 
 /// Seed a user directly in the test database. Returns the user's internal UUID.
+#[allow(clippy::let_and_return)]
 pub async fn seed_user(pool: &PgPool, workos_user_id: &str, email: &str) -> Uuid {
     let row = sqlx::query_scalar::<_, Uuid>(
         r#"
@@ -114,6 +122,67 @@ pub async fn seed_school_setup(pool: &PgPool, org_id: Uuid, data: serde_json::Va
         .expect("Failed to seed school setup");
 }
 
+/// `school_days` for a school open all week, so tests that take registers
+/// "yesterday" pass whichever weekday they run on.
+pub const EVERY_DAY: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+/// A seeded school, "Test School", and a token for its user.
+pub struct TestSchool {
+    pub org_id: Uuid,
+    pub token: String,
+}
+
+/// Seed a school whose user has `role`, save `setup` through school setup,
+/// and sign a token for the user.
+pub async fn setup_school(
+    state: &AppState,
+    mock_server: &MockServer,
+    role: &str,
+    setup: Value,
+) -> TestSchool {
+    let workos_id = unique_workos_id();
+    let (_user_id, org_id) = seed_user_with_org(
+        &state.db_pool,
+        &workos_id,
+        &unique_email(),
+        "Test School",
+        &unique_slug("school"),
+        &unique_workos_org_id(),
+        role,
+    )
+    .await;
+    seed_school_setup(&state.db_pool, org_id, setup).await;
+
+    TestSchool {
+        org_id,
+        token: sign_test_jwt(&workos_id, None, &mock_server.uri()),
+    }
+}
+
+/// Create a student in `grade` through the API. Returns their id.
+pub async fn create_student(state: &AppState, token: &str, grade: &str, first_name: &str) -> Uuid {
+    create_student_with(state, token, json!({ "grade_level": grade, "first_name": first_name })).await
+}
+
+/// Create a student through the API from `fields` laid over a default
+/// student (Ada Okafor, JSS 1). Returns their id.
+pub async fn create_student_with(state: &AppState, token: &str, fields: Value) -> Uuid {
+    let mut student = json!({
+        "first_name": "Ada",
+        "last_name": "Okafor",
+        "date_of_birth": "2013-05-10",
+        "gender": "female",
+        "grade_level": "JSS 1",
+    });
+    for (key, value) in fields.as_object().expect("student fields must be an object") {
+        student[key] = value.clone();
+    }
+    let (status, body) =
+        post_json_auth(test_router(state.clone()), "/api/v1/students", student, token).await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    body["id"].as_str().unwrap().parse().unwrap()
+}
+
 fn sha2_hash(input: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(input.as_bytes());
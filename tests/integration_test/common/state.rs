@@ -9,6 +9,8 @@ use wiremock::MockServer;
 use super::config::test_config;
 use super::db::setup_test_db;
 
+const TEST_BOUNDARY: &str = "----schoolnifyTestBoundary";
+
 /// Build a test AppState using the wiremock server as WorkOS backend.
 pub async fn test_app_state(mock_server: &MockServer) -> AppState {
     let pool = setup_test_db().await;
@@ -93,6 +95,17 @@ pub async fn patch_json_auth(
     send(app, Method::PATCH, uri, Some(body), vec![("authorization", &auth)]).await
 }
 
+/// Convenience: PUT with JSON body and Bearer token.
+pub async fn put_json_auth(
+    app: Router,
+    uri: &str,
+    body: serde_json::Value,
+    token: &str,
+) -> (StatusCode, serde_json::Value) {
+    let auth = format!("Bearer {token}");
+    send(app, Method::PUT, uri, Some(body), vec![("authorization", &auth)]).await
+}
+
 /// Convenience: GET with Bearer token.
 pub async fn get_auth(
     app: Router,
@@ -123,3 +136,50 @@ pub async fn post_with_cookie(
     let cookie = format!("{cookie_name}={cookie_value}");
     send(app, Method::POST, uri, None, vec![("cookie", &cookie)]).await
 }
+
+/// POST a multipart form with Bearer token. Parts are `(name, filename, bytes)`;
+/// parts with a filename are sent as `text/csv`.
+pub async fn multipart_post(
+    app: Router,
+    uri: &str,
+    parts: Vec<(&str, Option<&str>, Vec<u8>)>,
+    token: &str,
+) -> (StatusCode, serde_json::Value) {
+    let mut body: Vec<u8> = Vec::new();
+    for (name, filename, data) in parts {
+        body.extend_from_slice(format!("--{TEST_BOUNDARY}\r\n").as_bytes());
+        let disposition = match filename {
+            Some(f) => format!(
+                "Content-Disposition: form-data; name=\"{name}\"; filename=\"{f}\"\r\n\
+                 Content-Type: text/csv\r\n\r\n"
+            ),
+            None => format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"),
+        };
+        body.extend_from_slice(disposition.as_bytes());
+        body.extend_from_slice(&data);
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{TEST_BOUNDARY}--\r\n").as_bytes());
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header(
+            "content-type",
+            format!("multipart/form-data; boundary={TEST_BOUNDARY}"),
+        )
+        .header("authorization", format!("Bearer {token}"))
+        .body(Body::from(body))
+        .unwrap();
+
+    let response = app.oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let json: serde_json::Value = if bytes.is_empty() {
+        serde_json::Value::Null
+    } else {
+        serde_json::from_slice(&bytes)
+            .unwrap_or_else(|_| serde_json::json!({ "raw": String::from_utf8_lossy(&bytes).to_string() }))
+    };
+    (status, json)
+}
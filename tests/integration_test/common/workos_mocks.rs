@@ -136,6 +136,7 @@ pub fn mock_refresh_token_success(
 }
 
 /// Mock: POST /user_management/authenticate (authorization_code grant) → 200
+#[allow(dead_code)]
 pub fn mock_authenticate_code_success(
     workos_user_id: &str,
     email: &str,
@@ -0,0 +1,750 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use http_body_util::BodyExt;
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
+use serial_test::serial;
+use tower::ServiceExt;
+use uuid::Uuid;
+use wiremock::MockServer;
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+/// A school billing in NGN, with fee categories and discount types.
+fn fees_setup() -> Value {
+    json!({
+        "localization": { "currency": "NGN" },
+        "academic_calendar": {
+            "current_academic_year": "2025/2026",
+            "terms": [
+                { "name": "First Term", "start_date": "2025-09-01", "end_date": "2025-12-15" },
+                { "name": "Second Term", "start_date": "2026-01-10", "end_date": "2026-04-05" }
+            ]
+        },
+        "grade_levels": { "grade_levels": ["JSS 1", "JSS 2"] },
+        "fees": {
+            "fee_categories": [
+                { "name": "Tuition", "mandatory": true, "frequency": "per_term", "fee_type": "tuition",
+                  "applies_to": "all", "amounts": { "_flat": "50000", "JSS 1": "60000" } },
+                { "name": "Boarding", "mandatory": true, "frequency": "per_term", "fee_type": "boarding",
+                  "applies_to": "all", "amounts": { "_flat": "80000" } },
+                { "name": "Lunch", "mandatory": true, "frequency": "monthly", "fee_type": "facility",
+                  "applies_to": "day", "amounts": { "_flat": "5000" } },
+                { "name": "Development Levy", "mandatory": true, "frequency": "annual", "fee_type": "facility",
+                  "applies_to": "all", "amounts": { "_flat": 10000 } },
+                { "name": "Transport", "mandatory": false, "frequency": "per_term", "fee_type": "transport",
+                  "applies_to": "specific", "grade_levels": ["JSS 1"], "amounts": { "_flat": "15000" } }
+            ],
+            "fee_payment_due_day": "5",
+            "discount_types": [
+                { "name": "Sibling", "percentage": "10", "applies_to": "tuition", "sibling_position": 2 },
+                { "name": "Staff Child", "percentage": "25", "applies_to": "all" }
+            ]
+        }
+    })
+}
+
+fn lines(invoice: &Value) -> Vec<(String, f64)> {
+    invoice["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| (l["category"].as_str().unwrap().to_string(), l["amount"].as_f64().unwrap()))
+        .collect()
+}
+
+fn line(category: &str, amount: f64) -> (String, f64) {
+    (category.to_string(), amount)
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_generate_invoices_prices_each_student() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    let day_jss1 = create_student_with(
+        &state,
+        &school.token,
+        json!({ "last_name": "Adeyemi", "grade_level": "JSS 1", "boarding_status": "day" }),
+    )
+    .await
+    .to_string();
+    let boarder = create_student_with(
+        &state,
+        &school.token,
+        json!({ "last_name": "Bello", "grade_level": "JSS 2", "boarding_status": "boarding" }),
+    )
+    .await
+    .to_string();
+    let day_jss2 = create_student_with(
+        &state,
+        &school.token,
+        json!({ "last_name": "Chukwu", "grade_level": "JSS 2", "boarding_status": "day" }),
+    )
+    .await
+    .to_string();
+
+    let request = json!({
+        "term": "first term",
+        "optional_categories": ["Transport"],
+        "issue_date": "2025-08-20",
+    });
+
+    let app = test_router(state.clone());
+    let (status, body) =
+        post_json_auth(app, "/api/v1/fees/invoices/generate", request.clone(), &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["academic_year"], "2025/2026");
+    assert_eq!(body["term"], "First Term");
+    assert_eq!(body["generated"], 3);
+    assert_eq!(body["total_amount"], 315000.0);
+    let invoices = body["invoices"].as_array().unwrap();
+    // Ordered by grade level, then name; numbered in that order.
+    assert_eq!(invoices[0]["student_id"], day_jss1.to_string());
+    assert_eq!(invoices[0]["invoice_number"], "INV-000001");
+    assert_eq!(invoices[0]["currency"], "NGN");
+    assert_eq!(invoices[0]["due_date"], "2025-09-05");
+    assert_eq!(
+        lines(&invoices[0]),
+        [
+            line("Tuition", 60000.0),
+            line("Lunch", 15000.0),
+            line("Development Levy", 10000.0),
+            line("Transport", 15000.0),
+        ]
+    );
+    assert_eq!(invoices[0]["lines"][1]["quantity"], 3.0);
+    assert_eq!(invoices[1]["student_id"], boarder.to_string());
+    assert_eq!(
+        lines(&invoices[1]),
+        [line("Tuition", 50000.0), line("Boarding", 80000.0), line("Development Levy", 10000.0)]
+    );
+    assert_eq!(invoices[2]["student_id"], day_jss2.to_string());
+    assert_eq!(invoices[2]["invoice_number"], "INV-000003");
+    assert_eq!(invoices[2]["total_amount"], 75000.0);
+
+    // A term is invoiced once per student.
+    let app = test_router(state.clone());
+    let (_, body) =
+        post_json_auth(app, "/api/v1/fees/invoices/generate", request, &school.token).await;
+    assert_eq!(body["generated"], 0);
+    assert_eq!(body["skipped"].as_array().unwrap().len(), 3);
+    assert!(body["skipped"][0]["reason"].as_str().unwrap().contains("INV-000001"));
+
+    // Annual fees aren't billed again in the same year.
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "Second Term", "grade_level": "JSS 1", "issue_date": "2026-01-02" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["generated"], 1);
+    assert_eq!(
+        lines(&body["invoices"][0]),
+        [line("Tuition", 60000.0), line("Lunch", 15000.0)]
+    );
+    assert_eq!(body["invoices"][0]["invoice_number"], "INV-000004");
+    assert_eq!(body["invoices"][0]["due_date"], "2026-02-05");
+
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(
+        app,
+        &format!("/api/v1/fees/invoices?student_id={day_jss1}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["pagination"]["total"], 2);
+    assert_eq!(body["data"][0]["term"], "Second Term");
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "Third Term" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_due_date_moves_off_closed_days() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    create_student(&state, &school.token, "JSS 2", "Bola").await;
+
+    // The 5th is a Friday holiday; the weekend follows.
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/calendar/events",
+        json!({ "kind": "public_holiday", "name": "Eid", "start_date": "2025-09-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term", "issue_date": "2025-08-20" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["invoices"][0]["due_date"], "2025-09-08");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_concurrent_generate_invoices_each_student_once() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    for last_name in ["Adeyemi", "Bello", "Chukwu"] {
+        let student = json!({ "last_name": last_name, "grade_level": "JSS 1" });
+        create_student_with(&state, &school.token, student).await;
+    }
+
+    let request = json!({ "term": "First Term", "due_date": "2099-09-05" });
+    let ((first, a), (second, b)) = tokio::join!(
+        post_json_auth(
+            test_router(state.clone()),
+            "/api/v1/fees/invoices/generate",
+            request.clone(),
+            &school.token,
+        ),
+        post_json_auth(
+            test_router(state.clone()),
+            "/api/v1/fees/invoices/generate",
+            request.clone(),
+            &school.token,
+        ),
+    );
+    assert_eq!(first, StatusCode::OK, "body: {a}");
+    assert_eq!(second, StatusCode::OK, "body: {b}");
+    // Whichever ran second found everyone already invoiced.
+    let mut generated = [a["generated"].as_i64().unwrap(), b["generated"].as_i64().unwrap()];
+    generated.sort();
+    assert_eq!(generated, [0, 3]);
+    let later = if a["generated"] == 0 { &a } else { &b };
+    assert_eq!(later["skipped"].as_array().unwrap().len(), 3);
+
+    let per_student: Vec<i64> = sqlx::query_scalar(
+        r#"SELECT COUNT(*) FROM fee_invoices WHERE org_id = $1 AND status = 'issued'
+           GROUP BY student_id"#,
+    )
+    .bind(school.org_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(per_student, [1, 1, 1]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_regenerate_and_void_invoice() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    let boarder = create_student_with(
+        &state,
+        &school.token,
+        json!({ "last_name": "Bello", "grade_level": "JSS 2", "boarding_status": "boarding" }),
+    )
+    .await
+    .to_string();
+
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term", "student_ids": [boarder, Uuid::new_v4()] }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["generated"], 1);
+    assert_eq!(body["skipped"][0]["reason"], "Not an active student of this school");
+    let original = body["invoices"][0]["id"].as_str().unwrap().to_string();
+    assert_eq!(body["invoices"][0]["total_amount"], 140000.0);
+
+    // The student becomes a day student; the replacement reprices them.
+    let app = test_router(state.clone());
+    let (status, _) = patch_json_auth(
+        app,
+        &format!("/api/v1/students/{boarder}"),
+        json!({ "boarding_status": "day" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/fees/invoices/{original}/regenerate");
+    let (status, body) =
+        post_json_auth(app, &uri, json!({ "due_date": "2020-01-01" }), &school.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["invoice_number"], "INV-000002");
+    assert_eq!(body["replaces_invoice_id"], original.as_str());
+    assert_eq!(body["boarding_status"], "day");
+    assert_eq!(
+        lines(&body),
+        [line("Tuition", 50000.0), line("Lunch", 15000.0), line("Development Levy", 10000.0)]
+    );
+    let replacement = body["id"].as_str().unwrap().to_string();
+
+    let app = test_router(state.clone());
+    let (_, body) =
+        get_auth(app, &format!("/api/v1/fees/invoices/{original}"), &school.token).await;
+    assert_eq!(body["status"], "void");
+    assert_eq!(body["void_reason"], "Regenerated as INV-000002");
+    assert_eq!(body["replaced_by_invoice_id"], replacement.as_str());
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/fees/invoices/{replacement}/void");
+    let (status, body) =
+        post_json_auth(app, &uri, json!({ "reason": "Withdrawn" }), &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "void");
+    assert!(body["voided_at"].is_string());
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(app, "/api/v1/fees/invoices?status=void", &school.token).await;
+    assert_eq!(body["pagination"]["total"], 2);
+
+    // Once voided, the term can be invoiced afresh.
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["generated"], 1);
+    assert_eq!(body["invoices"][0]["invoice_number"], "INV-000003");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_payments_settle_invoices_and_drive_fee_status() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    let student = create_student_with(
+        &state,
+        &school.token,
+        json!({ "last_name": "Adeyemi", "grade_level": "JSS 1", "boarding_status": "day" }),
+    )
+    .await
+    .to_string();
+    let fees_uri = format!("/api/v1/students/{student}/fees");
+    let payments_uri = format!("{fees_uri}/payments");
+    let fee_status = |state: AppState| {
+        let uri = format!("/api/v1/students/{student}");
+        let token = school.token.clone();
+        async move {
+            let (_, body) = get_auth(test_router(state), &uri, &token).await;
+            body["fee_status"].as_str().unwrap().to_string()
+        }
+    };
+    assert_eq!(fee_status(state.clone()).await, "unknown");
+
+    // Tuition 60000 + Lunch 15000 + Development Levy 10000.
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term", "due_date": "2099-09-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["invoices"][0]["total_amount"], 85000.0);
+    let first = body["invoices"][0]["id"].as_str().unwrap().to_string();
+    assert_eq!(fee_status(state.clone()).await, "owing");
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        &payments_uri,
+        json!({ "amount": 50000, "method": "cash", "paid_on": "2025-09-02" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    assert_eq!(body["receipt_number"], "RCT-000001");
+    assert_eq!(body["currency"], "NGN");
+    assert_eq!(body["allocations"][0]["invoice_number"], "INV-000001");
+    assert_eq!(body["allocations"][0]["amount"], 50000.0);
+    assert_eq!(body["unallocated"], 0.0);
+    assert_eq!(fee_status(state.clone()).await, "partial");
+
+    // Overpaying leaves the rest as credit.
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        &payments_uri,
+        json!({
+            "amount": 40000.004,
+            "method": "bank_transfer",
+            "reference": "TRF-8812",
+            "invoice_id": first,
+            "paid_on": "2025-09-10",
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    assert_eq!(body["amount"], 40000.0);
+    assert_eq!(body["allocations"][0]["amount"], 35000.0);
+    assert_eq!(body["unallocated"], 5000.0);
+    assert_eq!(fee_status(state.clone()).await, "paid");
+    let app = test_router(state.clone());
+    let (_, body) =
+        get_auth(app, &format!("/api/v1/fees/invoices/{first}"), &school.token).await;
+    assert_eq!(body["amount_paid"], 85000.0);
+    assert_eq!(body["balance"], 0.0);
+
+    // Credit settles part of the next invoice.
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "Second Term", "due_date": "2099-02-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["invoices"][0]["total_amount"], 75000.0);
+    assert_eq!(body["invoices"][0]["amount_paid"], 5000.0);
+    assert_eq!(body["invoices"][0]["balance"], 70000.0);
+    let second = body["invoices"][0]["id"].as_str().unwrap().to_string();
+
+    let app = test_router(state.clone());
+    let (status, body) = get_auth(app, &fees_uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["fee_status"], "partial");
+    assert_eq!(body["total_invoiced"], 160000.0);
+    assert_eq!(body["total_paid"], 90000.0);
+    assert_eq!(body["balance"], 70000.0);
+    assert_eq!(body["credit"], 0.0);
+    assert_eq!(body["payments"][0]["receipt_number"], "RCT-000002");
+    assert_eq!(body["payments"][0]["allocations"].as_array().unwrap().len(), 2);
+
+    // Voiding returns what was paid on it to credit.
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/fees/invoices/{second}/void");
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(app, &fees_uri, &school.token).await;
+    assert_eq!(body["fee_status"], "paid");
+    assert_eq!(body["balance"], 0.0);
+    assert_eq!(body["credit"], 5000.0);
+
+    // Unpaid past its due date.
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "Second Term", "issue_date": "2026-01-02", "due_date": "2026-02-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["invoices"][0]["balance"], 70000.0);
+    assert_eq!(fee_status(state.clone()).await, "overdue");
+
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(
+        app,
+        &format!("/api/v1/students/{student}?include=recent_payments"),
+        &school.token,
+    )
+    .await;
+    let recent = body["recent_payments"].as_array().unwrap();
+    assert_eq!(recent.len(), 2);
+    assert_eq!(recent[0]["receipt_number"], "RCT-000002");
+    assert_eq!(recent[0]["method"], "bank_transfer");
+
+    let app = test_router(state.clone());
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/api/v1/students/export")
+        .header("authorization", format!("Bearer {}", school.token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    assert!(text.lines().nth(1).unwrap().contains(",day,overdue,"), "got: {text}");
+
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(app, "/api/v1/fees/payments?method=cash", &school.token).await;
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["total_amount"], 50000.0);
+
+    for (request, expected) in [
+        (json!({ "amount": 100, "method": "cheque" }), StatusCode::BAD_REQUEST),
+        (json!({ "amount": 0, "method": "cash" }), StatusCode::BAD_REQUEST),
+        (json!({ "amount": 1e12, "method": "cash" }), StatusCode::BAD_REQUEST),
+        (json!({ "amount": 100, "method": "cash", "invoice_id": second }), StatusCode::BAD_REQUEST),
+        (json!({ "amount": 100, "method": "cash", "paid_on": "2999-01-01" }), StatusCode::BAD_REQUEST),
+    ] {
+        let app = test_router(state.clone());
+        let (status, body) = post_json_auth(app, &payments_uri, request, &school.token).await;
+        assert_eq!(status, expected, "body: {body}");
+    }
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(
+        app,
+        &format!("/api/v1/students/{}/fees/payments", Uuid::new_v4()),
+        json!({ "amount": 100, "method": "cash" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_discounts_and_scholarships_reduce_invoice_lines() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+
+    // Two children of the same parent, recorded with differently written
+    // phone numbers; the elder is in JSS 2.
+    let mut ids = Vec::new();
+    for (last_name, grade, born, phone) in [
+        ("Okafor", "JSS 2", "2012-03-01", "0803 555-0101"),
+        ("Okafor", "JSS 1", "2013-06-15", "08035550101"),
+    ] {
+        let student = json!({
+            "first_name": "Chidi",
+            "last_name": last_name,
+            "date_of_birth": born,
+            "gender": "male",
+            "grade_level": grade,
+            "boarding_status": "day",
+            "guardians": [{ "first_name": "Ngozi", "last_name": "Okafor", "phone": phone }],
+        });
+        ids.push(create_student_with(&state, &school.token, student).await.to_string());
+    }
+    let (elder, younger) = (&ids[0], &ids[1]);
+    let discounts_uri = format!("/api/v1/students/{younger}/fees/discounts");
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        &discounts_uri,
+        json!({
+            "name": "Levy Bursary",
+            "kind": "scholarship",
+            "percentage": 50,
+            "applies_to": "development levy",
+            "academic_year": "2025/2026",
+            "reason": "Academic merit",
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    let bursary = body["id"].as_str().unwrap().to_string();
+
+    for (request, expected) in [
+        (json!({ "name": "Levy Bursary", "percentage": 50, "academic_year": "2025/2026" }), StatusCode::CONFLICT),
+        (json!({ "name": "Early Bird" }), StatusCode::BAD_REQUEST),
+        (json!({ "name": "Staff Child", "applies_to": "tuition" }), StatusCode::BAD_REQUEST),
+        (json!({ "name": "Merit", "percentage": 120 }), StatusCode::BAD_REQUEST),
+        (json!({ "name": "Merit", "percentage": 20, "applies_to": "lunch money" }), StatusCode::BAD_REQUEST),
+        (json!({ "name": "Merit", "kind": "waiver", "percentage": 20 }), StatusCode::BAD_REQUEST),
+    ] {
+        let app = test_router(state.clone());
+        let (status, body) = post_json_auth(app, &discounts_uri, request, &school.token).await;
+        assert_eq!(status, expected, "body: {body}");
+    }
+
+    let app = test_router(state.clone());
+    let (status, body) = get_auth(app, &discounts_uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["assigned"][0]["kind"], "scholarship");
+    assert_eq!(body["sibling"]["name"], "Sibling");
+    assert_eq!(body["sibling"]["position"], 2);
+    assert_eq!(body["sibling"]["family_size"], 2);
+    assert_eq!(body["sibling"]["sibling_ids"], json!([elder]));
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/{elder}/fees/discounts");
+    let (_, body) = get_auth(app, &uri, &school.token).await;
+    assert!(body["sibling"].is_null());
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term", "due_date": "2099-09-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let invoice_of = |student: &str| {
+        body["invoices"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|i| i["student_id"] == student)
+            .unwrap()
+            .clone()
+    };
+    // Tuition 50000 + Lunch 15000 + Development Levy 10000, undiscounted.
+    assert_eq!(invoice_of(elder)["total_amount"], 75000.0);
+    assert_eq!(invoice_of(elder)["discount_amount"], 0.0);
+    // 10% off Tuition 60000 as the second child, half the levy by scholarship.
+    let invoice = invoice_of(younger);
+    assert_eq!(invoice["total_amount"], 74000.0);
+    assert_eq!(invoice["discount_amount"], 11000.0);
+    let id = invoice["id"].as_str().unwrap().to_string();
+
+    let app = test_router(state.clone());
+    let (_, invoice) = get_auth(app, &format!("/api/v1/fees/invoices/{id}"), &school.token).await;
+    assert_eq!(
+        lines(&invoice),
+        [line("Tuition", 60000.0), line("Lunch", 15000.0), line("Development Levy", 10000.0)]
+    );
+    let tuition = &invoice["lines"][0];
+    assert_eq!(tuition["discount_amount"], 6000.0);
+    assert_eq!(tuition["discounts"][0]["name"], "Sibling");
+    assert_eq!(tuition["discounts"][0]["source"], "sibling");
+    assert_eq!(tuition["discounts"][0]["detail"], "2nd of 2 children sharing a guardian");
+    assert_eq!(invoice["lines"][1]["discounts"], json!([]));
+    let levy = &invoice["lines"][2]["discounts"][0];
+    assert_eq!(levy["name"], "Levy Bursary");
+    assert_eq!(levy["kind"], "scholarship");
+    assert_eq!(levy["source"], "assigned");
+    assert_eq!(levy["amount"], 5000.0);
+    assert_eq!(levy["student_discount_id"], bursary.as_str());
+
+    // A discount type assigned by hand, and the scholarship taken away,
+    // show up once the invoice is regenerated.
+    let app = test_router(state.clone());
+    let (status, body) =
+        post_json_auth(app, &discounts_uri, json!({ "name": "staff child" }), &school.token).await;
+    assert_eq!(status, StatusCode::CREATED, "body: {body}");
+    assert_eq!(body["name"], "Staff Child");
+    assert!(body["percentage"].is_null());
+    let app = test_router(state.clone());
+    let uri = format!("{discounts_uri}/{bursary}");
+    let (status, _) = delete_auth(app, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let app = test_router(state.clone());
+    let (status, _) = delete_auth(app, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let app = test_router(state.clone());
+    let (status, invoice) = post_json_auth(
+        app,
+        &format!("/api/v1/fees/invoices/{id}/regenerate"),
+        json!({}),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {invoice}");
+    // Staff Child 25% on everything, plus Sibling 10% on tuition.
+    assert_eq!(invoice["discount_amount"], 27250.0);
+    assert_eq!(invoice["total_amount"], 57750.0);
+    let tuition = &invoice["lines"][0]["discounts"];
+    assert_eq!(tuition[0]["name"], "Staff Child");
+    assert_eq!(tuition[1]["name"], "Sibling");
+    assert_eq!(invoice["lines"][2]["discount_amount"], 2500.0);
+
+    // Once Staff Child leaves school setup, only the student it's still
+    // assigned to is held back.
+    sqlx::query("DELETE FROM school_fee_discounts WHERE org_id = $1 AND name = 'Staff Child'")
+        .bind(school.org_id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "Second Term", "due_date": "2099-09-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["generated"], 1);
+    assert_eq!(body["invoices"][0]["student_id"], elder.as_str());
+    assert_eq!(body["skipped"][0]["student_id"], younger.as_str());
+    let reason = body["skipped"][0]["reason"].as_str().unwrap();
+    assert!(reason.contains("no longer in school setup"), "{reason}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_broken_discount_type_fails_the_run() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", fees_setup()).await;
+    for (grade, born) in [("JSS 2", "2012-03-01"), ("JSS 1", "2013-06-15")] {
+        let student = json!({
+            "last_name": "Okafor",
+            "date_of_birth": born,
+            "grade_level": grade,
+            "guardians": [{ "first_name": "Ngozi", "last_name": "Okafor", "phone": "08035550101" }],
+        });
+        create_student_with(&state, &school.token, student).await;
+    }
+
+    // The sibling discount still targets a fee category that's been renamed.
+    sqlx::query(
+        "UPDATE school_fee_discounts SET applies_to = 'School Bus' WHERE org_id = $1 AND name = 'Sibling'",
+    )
+    .bind(school.org_id)
+    .execute(&state.db_pool)
+    .await
+    .unwrap();
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/fees/invoices/generate",
+        json!({ "term": "First Term", "due_date": "2099-09-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let message = body["error"]["message"].as_str().unwrap();
+    assert!(message.contains("Sibling"), "{message}");
+
+    let issued: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM fee_invoices WHERE org_id = $1")
+        .bind(school.org_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(issued, 0);
+}
@@ -0,0 +1,1243 @@
+use std::io::{Cursor, Read};
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use http_body_util::BodyExt;
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
+use serial_test::serial;
+use tower::ServiceExt;
+use uuid::Uuid;
+use wiremock::MockServer;
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+/// Two JSS classes taking Mathematics and English, graded CA 40 / exam 60.
+fn gradebook_setup() -> Value {
+    json!({
+        "academic_calendar": {
+            "current_academic_year": "2025/2026",
+            "terms": [{ "name": "First Term" }, { "name": "Second Term" }]
+        },
+        "grade_levels": { "grade_levels": ["JSS 1", "JSS 2"] },
+        "subjects": { "subjects": ["Mathematics", "English Language"] },
+        "grading": grading(false),
+    })
+}
+
+fn grading(gpa_enabled: bool) -> Value {
+    json!({
+        "ca_weight": "40",
+        "exam_weight": "60",
+        "passmark": "50",
+        "gpa_enabled": gpa_enabled,
+        "grading_scale": [
+            { "grade": "A", "min_score": "70", "max_score": "100", "descriptor": "Excellent", "gpa_points": "4.0" },
+            { "grade": "C", "min_score": "50", "max_score": "69", "descriptor": "Credit", "gpa_points": "2.0" },
+            { "grade": "F", "min_score": "0", "max_score": "49", "descriptor": "Fail", "gpa_points": "0" }
+        ]
+    })
+}
+
+fn with_scale(scale: Value) -> Value {
+    let mut g = grading(false);
+    g["grading_scale"] = scale;
+    g
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_enter_scores_in_passes_and_read_back() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+
+    // CA first, with names matched case-insensitively.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        json!({
+            "term": "first term",
+            "subject": "mathematics",
+            "grade_level": "JSS 1",
+            "max_scores": { "exam": 100 },
+            "entries": [
+                { "student_id": a, "scores": { "ca": 35 } },
+                { "student_id": b, "scores": { "ca": 28.5 } },
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["academic_year"], "2025/2026");
+    assert_eq!(body["term"], "First Term");
+    assert_eq!(body["subject"], "Mathematics");
+    assert_eq!(body["status"], "draft");
+    assert_eq!(
+        body["components"],
+        json!([
+            { "key": "ca", "weight": 40.0, "max_score": 40.0 },
+            { "key": "exam", "weight": 60.0, "max_score": 100.0 },
+        ])
+    );
+
+    // Exam scores later; CA is kept.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        json!({
+            "term": "First Term",
+            "subject": "Mathematics",
+            "grade_level": "JSS 1",
+            "entries": [{ "student_id": a, "scores": { "exam": 88 } }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let entry = |id: Uuid| {
+        body["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["student_id"] == json!(id))
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(entry(a)["scores"], json!({ "ca": 35.0, "exam": 88.0 }));
+    assert_eq!(entry(b)["scores"], json!({ "ca": 28.5 }));
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/scores?term=First%20Term"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let subjects = body["subjects"].as_array().unwrap();
+    assert_eq!(subjects.len(), 1);
+    assert_eq!(subjects[0]["subject"], "Mathematics");
+    assert_eq!(subjects[0]["scores"]["exam"], 88.0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_score_sheet_validation() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let other_class = create_student(&state, &school.token, "JSS 2", "Chinedu").await;
+
+    let sheet = |subject: &str, entries: serde_json::Value| {
+        json!({ "term": "First Term", "subject": subject, "grade_level": "JSS 1", "entries": entries })
+    };
+    for bad in [
+        sheet("Mathematics", json!([{ "student_id": a, "scores": { "ca": 41 } }])),
+        sheet("Mathematics", json!([{ "student_id": a, "scores": { "ca": -1 } }])),
+        sheet("Mathematics", json!([{ "student_id": a, "scores": { "project": 5 } }])),
+        sheet("Mathematics", json!([{ "student_id": a, "scores": { "quiz": 5 } }])),
+        sheet("Mathematics", json!([{ "student_id": other_class, "scores": { "ca": 5 } }])),
+        sheet("Physics", json!([{ "student_id": a, "scores": { "ca": 5 } }])),
+        sheet("Mathematics", json!([])),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            bad,
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+
+    // Lowering a max below an existing score is refused.
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        sheet("Mathematics", json!([{ "student_id": a, "scores": { "ca": 30 } }])),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let mut lower = sheet("Mathematics", json!([{ "student_id": a, "scores": { "exam": 10 } }]));
+    lower["max_scores"] = json!({ "ca": 20 });
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        lower,
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_published_sheet_is_locked() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+
+    let entry = json!({
+        "term": "First Term",
+        "subject": "English Language",
+        "grade_level": "JSS 1",
+        "entries": [{ "student_id": a, "scores": { "ca": 30, "exam": 50 } }]
+    });
+    let (_, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        entry.clone(),
+        &school.token,
+    )
+    .await;
+    let id = body["id"].as_str().unwrap().to_string();
+
+    // A single sheet follows the same one-step workflow as a class.
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/gradebook/sheets/{id}/status"),
+        json!({ "status": "published" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    assert!(body.to_string().contains("only approved sheets"), "body: {body}");
+
+    for to in ["submitted", "approved", "published"] {
+        let (status, body) = patch_json_auth(
+            test_router(state.clone()),
+            &format!("/api/v1/gradebook/sheets/{id}/status"),
+            json!({ "status": to }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+        assert_eq!(body["status"], to);
+        assert_eq!(body["published_at"].is_string(), to == "published");
+    }
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        entry.clone(),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "body: {body}");
+
+    // Back to draft reopens it.
+    let (status, _) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/gradebook/sheets/{id}/status"),
+        json!({ "status": "draft" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        entry,
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Teachers enter scores but can't publish.
+    let teacher = setup_school(&state, &mock_server, "teacher", gradebook_setup()).await;
+    let (status, _) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/gradebook/sheets/{id}/status"),
+        json!({ "status": "published" }),
+        &teacher.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_results_weighted_graded_and_pass_marked() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+
+    for (subject, entries) in [
+        (
+            "Mathematics",
+            json!([
+                { "student_id": a, "scores": { "ca": 30, "exam": 50 } },
+                { "student_id": b, "scores": { "ca": 10 } },
+            ]),
+        ),
+        ("English Language", json!([{ "student_id": a, "scores": { "ca": 40, "exam": 100 } }])),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({
+                "term": "First Term",
+                "subject": subject,
+                "grade_level": "JSS 1",
+                "max_scores": { "exam": 100 },
+                "entries": entries,
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/results?term=First%20Term&grade_level=JSS%201",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["passmark"], 50.0);
+    let student = |id: Uuid| {
+        body["students"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|s| s["student_id"] == json!(id))
+            .unwrap()
+            .clone()
+    };
+
+    // 30/40 of 40 plus 50/100 of 60.
+    let amaka = student(a);
+    let maths = &amaka["subjects"][0];
+    assert_eq!(maths["subject"], "Mathematics");
+    assert_eq!(maths["total"], 60.0);
+    assert_eq!(maths["grade"], "C");
+    assert_eq!(maths["descriptor"], "Credit");
+    assert_eq!(maths["passed"], true);
+    assert_eq!(maths["complete"], true);
+    assert_eq!(amaka["subjects"][1]["total"], 100.0);
+    assert_eq!(amaka["subjects"][1]["grade"], "A");
+    assert_eq!(amaka["average"], 80.0);
+    assert_eq!(amaka["subjects_passed"], 2);
+
+    // A missing exam counts as zero.
+    let bola = &student(b)["subjects"][0];
+    assert_eq!(bola["total"], 10.0);
+    assert_eq!(bola["grade"], "F");
+    assert_eq!(bola["passed"], false);
+    assert_eq!(bola["complete"], false);
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/results"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["terms"][0]["term"], "First Term");
+    assert_eq!(body["terms"][0]["average"], 80.0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_results_reject_unusable_grading_scale() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+
+    for (scale, message) in [
+        (json!([{ "grade": "A", "min_score": "fifty", "max_score": "100" }]), "not a number"),
+        (
+            json!([
+                { "grade": "A", "min_score": "50", "max_score": "100" },
+                { "grade": "F", "min_score": "0", "max_score": "60" },
+            ]),
+            "overlap",
+        ),
+        (
+            json!([
+                { "grade": "A", "min_score": "70", "max_score": "100" },
+                { "grade": "F", "min_score": "0", "max_score": "49" },
+            ]),
+            "gap",
+        ),
+        (json!([{ "grade": "A", "min_score": "0", "max_score": "90" }]), "must reach 100"),
+    ] {
+        let (status, body) = patch_json_auth(
+            test_router(state.clone()),
+            "/api/v1/schools/setup",
+            json!({ "grading": with_scale(scale) }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+
+        let (status, body) = get_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/results?term=First%20Term&grade_level=JSS%201",
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+        assert!(body.to_string().contains(message), "body: {body}");
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_gpa_on_students_and_list_sort_filter() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    let c = create_student(&state, &school.token, "JSS 1", "Chidi").await;
+
+    let save = |term: &str, subject: &str, entries: Value| {
+        let state = state.clone();
+        let token = school.token.clone();
+        let body = json!({
+            "term": term,
+            "subject": subject,
+            "grade_level": "JSS 1",
+            "max_scores": { "exam": 100 },
+            "entries": entries,
+        });
+        async move {
+            let (status, body) =
+                put_json_auth(test_router(state), "/api/v1/gradebook/sheets", body, &token).await;
+            assert_eq!(status, StatusCode::OK, "body: {body}");
+        }
+    };
+    // Amaka: A then C → 4.0, then cumulative 3.0. Bola: C → 2.0. Chidi: none.
+    save(
+        "First Term",
+        "Mathematics",
+        json!([
+            { "student_id": a, "scores": { "ca": 40, "exam": 80 } },
+            { "student_id": b, "scores": { "ca": 20, "exam": 50 } },
+        ]),
+    )
+    .await;
+    save(
+        "Second Term",
+        "Mathematics",
+        json!([{ "student_id": a, "scores": { "ca": 20, "exam": 50 } }]),
+    )
+    .await;
+
+    let publish = |term: &str, subject: &str| {
+        let state = state.clone();
+        let token = school.token.clone();
+        let (term, subject) = (term.to_string(), subject.to_string());
+        async move {
+            for to in ["submitted", "approved", "published"] {
+                let (status, body) = patch_json_auth(
+                    test_router(state.clone()),
+                    "/api/v1/gradebook/results/status",
+                    json!({ "term": term, "grade_level": "JSS 1", "subject": subject, "status": to }),
+                    &token,
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK, "body: {body}");
+            }
+        }
+    };
+
+    // GPA stays off until the school enables it.
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}"),
+        &school.token,
+    )
+    .await;
+    assert!(body["gpa"].is_null(), "body: {body}");
+
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "grading": grading(true) }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    // Only published results count.
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}"),
+        &school.token,
+    )
+    .await;
+    assert!(body["gpa"].is_null(), "body: {body}");
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/results"),
+        &school.token,
+    )
+    .await;
+    assert!(body["cumulative_gpa"].is_null(), "body: {body}");
+    assert!(body["terms"][0]["gpa"].is_null(), "body: {body}");
+
+    publish("First Term", "Mathematics").await;
+    publish("Second Term", "Mathematics").await;
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["gpa"], 3.0);
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/results?term=Second%20Term"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["cumulative_gpa"], 3.0);
+    assert_eq!(body["terms"].as_array().unwrap().len(), 1);
+    assert_eq!(body["terms"][0]["gpa"], 2.0);
+    assert_eq!(body["terms"][0]["cumulative_gpa"], 3.0);
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students?sort=gpa&order=desc",
+        &school.token,
+    )
+    .await;
+    let ids: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|s| s["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, [a.to_string(), b.to_string(), c.to_string()]);
+    assert_eq!(body["summary"]["average_gpa"], 2.5);
+
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students?min_gpa=2.5&max_gpa=4",
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["id"], json!(a));
+
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students?min_gpa=3&max_gpa=2",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // New scores count once their sheet is published.
+    save(
+        "Second Term",
+        "English Language",
+        json!([{ "student_id": b, "scores": { "ca": 40, "exam": 100 } }]),
+    )
+    .await;
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{b}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["gpa"], 2.0);
+    publish("Second Term", "English Language").await;
+    let (_, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{b}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(body["gpa"], 3.0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_class_statistics_positions_and_tie_breaks() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    let c = create_student(&state, &school.token, "JSS 1", "Chidi").await;
+
+    for (subject, entries) in [
+        (
+            "Mathematics",
+            json!([
+                { "student_id": a, "scores": { "ca": 30, "exam": 60 } },
+                { "student_id": b, "scores": { "ca": 36, "exam": 50 } },
+                { "student_id": c, "scores": { "ca": 20, "exam": 40 } },
+            ]),
+        ),
+        (
+            "English Language",
+            json!([
+                { "student_id": a, "scores": { "ca": 40, "exam": 100 } },
+                { "student_id": b, "scores": { "ca": 40, "exam": 100 } },
+                { "student_id": c, "scores": { "ca": 20, "exam": 50 } },
+            ]),
+        ),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({
+                "term": "First Term",
+                "subject": subject,
+                "grade_level": "JSS 1",
+                "max_scores": { "exam": 100 },
+                "entries": entries,
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    let url = "/api/v1/gradebook/statistics?term=First%20Term&grade_level=JSS%201";
+    let positions = |body: &Value| -> Vec<(String, u64)> {
+        body["students"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| {
+                let name = s["first_name"].as_str().unwrap().to_string();
+                (name, s["position"].as_u64().unwrap())
+            })
+            .collect()
+    };
+
+    // Shared by default: A and B both average 83.
+    let (status, body) = get_auth(test_router(state.clone()), url, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["tie_break"], "shared");
+    assert_eq!(body["class_size"], 3);
+    assert_eq!(body["class_average"], 71.0);
+    assert_eq!(
+        positions(&body),
+        [("Amaka".into(), 1), ("Bola".into(), 1), ("Chidi".into(), 3)]
+    );
+    assert_eq!(
+        body["subjects"],
+        json!([
+            {
+                "subject": "Mathematics", "students": 3,
+                "average": 58.67, "highest": 66.0, "lowest": 44.0,
+            },
+            {
+                "subject": "English Language", "students": 3,
+                "average": 83.33, "highest": 100.0, "lowest": 50.0,
+            },
+        ])
+    );
+
+    let report_card = |tie_break: &str| {
+        json!({ "report_card": {
+            "report_template": "standard",
+            "show_position": true,
+            "show_class_average": true,
+            "show_highest_lowest": false,
+            "position_tie_break": tie_break,
+        }})
+    };
+    let (status, _) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        report_card("random"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        report_card("dense"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let (_, body) = get_auth(test_router(state.clone()), url, &school.token).await;
+    assert_eq!(body["tie_break"], "dense");
+    assert_eq!(body["show_position"], true);
+    assert_eq!(body["show_highest_lowest"], false);
+    assert_eq!(
+        positions(&body),
+        [("Amaka".into(), 1), ("Bola".into(), 1), ("Chidi".into(), 2)]
+    );
+
+    // Amaka's higher exam scores put her ahead of Bola.
+    patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        report_card("exam_score"),
+        &school.token,
+    )
+    .await;
+    let (_, body) = get_auth(test_router(state.clone()), url, &school.token).await;
+    assert_eq!(
+        positions(&body),
+        [("Amaka".into(), 1), ("Bola".into(), 2), ("Chidi".into(), 3)]
+    );
+    assert_eq!(
+        body["students"][1]["subjects"],
+        json!([
+            { "subject": "Mathematics", "total": 66.0, "position": 2 },
+            { "subject": "English Language", "total": 100.0, "position": 1 },
+        ])
+    );
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{c}/statistics?term=first%20term"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["grade_level"], "JSS 1");
+    assert_eq!(body["position"], 3);
+    assert_eq!(body["average"], 47.0);
+    assert_eq!(body["class_size"], 3);
+    assert_eq!(
+        body["subjects"][0],
+        json!({
+            "subject": "Mathematics", "total": 44.0, "position": 3, "students": 3,
+            "class_average": 58.67, "highest": 66.0, "lowest": 44.0,
+        })
+    );
+
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{c}/statistics?term=Second%20Term"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn set_result_status(
+    state: &AppState,
+    token: &str,
+    status: &str,
+    reason: Option<&str>,
+) -> (StatusCode, Value) {
+    patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/results/status",
+        json!({ "term": "First Term", "grade_level": "JSS 1", "status": status, "reason": reason }),
+        token,
+    )
+    .await
+}
+
+async fn maths_grade(state: &AppState, token: &str, student_id: Uuid) -> (Value, Value) {
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/results?term=First%20Term&grade_level=JSS%201&subject=Mathematics",
+        token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let student = body["students"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["student_id"] == student_id.to_string())
+        .unwrap();
+    let maths = &student["subjects"][0];
+    (maths["grade"].clone(), maths["sheet_status"].clone())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_result_workflow_freezes_and_notifies() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "policies": { "exam_result_notify": true, "notification_channels": ["email"] } }),
+    )
+    .await;
+
+    // A teacher in the same school.
+    let teacher_workos_id = unique_workos_id();
+    sqlx::query(
+        r#"
+        INSERT INTO users (workos_user_id, email, email_verified, org_id, role)
+        VALUES ($1, $2, true, $3, 'teacher')
+        "#,
+    )
+    .bind(&teacher_workos_id)
+    .bind(unique_email())
+    .bind(school.org_id)
+    .execute(&state.db_pool)
+    .await
+    .unwrap();
+    let teacher = sign_test_jwt(&teacher_workos_id, None, &mock_server.uri());
+
+    let a = create_student_with(
+        &state,
+        &school.token,
+        json!({
+            "first_name": "Amaka",
+            "guardians": [{ "first_name": "Ngozi", "last_name": "Okafor", "email": "ngozi@example.com", "is_primary": true }],
+        }),
+    )
+    .await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+
+    for (subject, scores_a, scores_b) in [
+        ("Mathematics", json!({ "ca": 30, "exam": 45 }), json!({ "ca": 20, "exam": 30 })),
+        ("English Language", json!({ "ca": 35, "exam": 55 }), json!({ "ca": 20, "exam": 20 })),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({
+                "term": "First Term",
+                "subject": subject,
+                "grade_level": "JSS 1",
+                "entries": [
+                    { "student_id": a, "scores": scores_a },
+                    { "student_id": b, "scores": scores_b },
+                ]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    // Teachers submit; approving needs an admin.
+    let (status, _) = set_result_status(&state, &teacher, "approved", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = set_result_status(&state, &teacher, "submitted", None).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "submitted");
+    assert_eq!(body["sheets"].as_array().unwrap().len(), 2);
+    assert!(body["sheets"][0]["submitted_at"].is_string());
+    assert!(body.get("notifications_queued").is_none());
+
+    // Submitted sheets are locked against edits.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        json!({
+            "term": "First Term",
+            "subject": "Mathematics",
+            "grade_level": "JSS 1",
+            "entries": [{ "student_id": a, "scores": { "ca": 40 } }]
+        }),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "body: {body}");
+
+    // Steps can't be skipped.
+    let (status, body) = set_result_status(&state, &school.token, "published", None).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+
+    let (status, body) = set_result_status(&state, &school.token, "approved", None).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "approved");
+    let (status, body) = set_result_status(&state, &school.token, "published", None).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "published");
+    // One notice per student with a guardian contact.
+    assert_eq!(body["notifications_queued"], 1);
+    let kinds: Vec<(Uuid, String)> =
+        sqlx::query_as("SELECT student_id, kind FROM notifications WHERE org_id = $1")
+            .bind(school.org_id)
+            .fetch_all(&state.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(kinds, vec![(a, "exam_results".to_string())]);
+
+    // Published results keep their grades when the scale changes.
+    assert_eq!(maths_grade(&state, &school.token, a).await, (json!("A"), json!("published")));
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "grading": with_scale(json!([
+            { "grade": "A", "min_score": "80", "max_score": "100", "gpa_points": "4.0" },
+            { "grade": "C", "min_score": "50", "max_score": "79", "gpa_points": "2.0" },
+            { "grade": "F", "min_score": "0", "max_score": "49", "gpa_points": "0" }
+        ])) }),
+    )
+    .await;
+    assert_eq!(maths_grade(&state, &school.token, a).await, (json!("A"), json!("published")));
+
+    // Unpublishing is admin only and logged with its reason.
+    let (status, _) = set_result_status(&state, &teacher, "draft", None).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) =
+        set_result_status(&state, &school.token, "draft", Some("Mathematics exam remarked")).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "draft");
+    assert!(body["sheets"][0].get("published_at").is_none());
+    let history = body["history"].as_array().unwrap();
+    assert_eq!(history.len(), 8);
+    assert_eq!(history[0]["from_status"], "published");
+    assert_eq!(history[0]["to_status"], "draft");
+    assert_eq!(history[0]["reason"], "Mathematics exam remarked");
+    assert_eq!(history[7]["to_status"], "submitted");
+
+    // Back in draft, results follow the current scale again.
+    assert_eq!(maths_grade(&state, &school.token, a).await, (json!("C"), json!("draft")));
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/results/status?term=First%20Term&grade_level=JSS%201",
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["status"], "draft");
+    assert_eq!(body["history"].as_array().unwrap().len(), 8);
+
+    // Republishing the term doesn't notify guardians again.
+    for to in ["submitted", "approved", "published"] {
+        let (status, body) = set_result_status(&state, &school.token, to, None).await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+    let (_, body) = set_result_status(&state, &school.token, "draft", Some("Recheck")).await;
+    assert_eq!(body["status"], "draft");
+    for to in ["submitted", "approved", "published"] {
+        let (status, body) = set_result_status(&state, &school.token, to, None).await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+        if to == "published" {
+            assert_eq!(body["notifications_queued"], 0);
+        }
+    }
+    let sent: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE org_id = $1")
+        .bind(school.org_id)
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(sent, 1);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_result_notices_follow_each_published_subject() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "policies": { "exam_result_notify": true, "notification_channels": ["email"] } }),
+    )
+    .await;
+    let a = create_student_with(
+        &state,
+        &school.token,
+        json!({
+            "first_name": "Amaka",
+            "guardians": [{ "first_name": "Ngozi", "last_name": "Okafor", "email": "ngozi@example.com", "is_primary": true }],
+        }),
+    )
+    .await;
+    for subject in ["Mathematics", "English Language"] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({
+                "term": "First Term",
+                "subject": subject,
+                "grade_level": "JSS 1",
+                "entries": [{ "student_id": a, "scores": { "ca": 30, "exam": 45 } }]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    // Moves one subject's sheet through `steps`; returns the last response.
+    let move_subject = |subject: &'static str, steps: &'static [&'static str]| {
+        let state = state.clone();
+        let token = school.token.clone();
+        async move {
+            let mut last = Value::Null;
+            for to in steps {
+                let (status, body) = patch_json_auth(
+                    test_router(state.clone()),
+                    "/api/v1/gradebook/results/status",
+                    json!({ "term": "First Term", "grade_level": "JSS 1", "subject": subject, "status": to }),
+                    &token,
+                )
+                .await;
+                assert_eq!(status, StatusCode::OK, "body: {body}");
+                last = body;
+            }
+            last
+        }
+    };
+    const PUBLISH: &[&str] = &["submitted", "approved", "published"];
+
+    // Each subject published on its own tells the guardian about it.
+    let body = move_subject("Mathematics", PUBLISH).await;
+    assert_eq!(body["notifications_queued"], 1);
+    let body = move_subject("English Language", PUBLISH).await;
+    assert_eq!(body["notifications_queued"], 1);
+
+    // Republishing a subject already announced doesn't.
+    move_subject("Mathematics", &["draft"]).await;
+    let body = move_subject("Mathematics", PUBLISH).await;
+    assert_eq!(body["notifications_queued"], 0);
+
+    let bodies: Vec<String> = sqlx::query_scalar(
+        "SELECT body FROM notifications WHERE org_id = $1 ORDER BY created_at",
+    )
+    .bind(school.org_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(bodies.len(), 2, "{bodies:?}");
+    assert!(bodies[0].contains("results for Mathematics have"), "{bodies:?}");
+    assert!(bodies[1].contains("results for English Language have"), "{bodies:?}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_broadsheet_csv_and_xlsx() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "@Bola").await;
+    for (subject, entries) in [
+        (
+            "Mathematics",
+            json!([
+                { "student_id": a, "scores": { "ca": 30, "exam": 45 } },
+                { "student_id": b, "scores": { "ca": 20, "exam": 30 } },
+            ]),
+        ),
+        ("English Language", json!([{ "student_id": a, "scores": { "ca": 35, "exam": 55 } }])),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({ "term": "First Term", "subject": subject, "grade_level": "JSS 1", "entries": entries }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+
+    let download = |format: &'static str| {
+        let request = Request::builder()
+            .method(Method::GET)
+            .uri(format!(
+                "/api/v1/gradebook/broadsheet?term=First%20Term&grade_level=JSS%201&format={format}"
+            ))
+            .header("authorization", format!("Bearer {}", school.token))
+            .body(Body::empty())
+            .unwrap();
+        let app = test_router(state.clone());
+        async move {
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let header = |name: &str| response.headers()[name].to_str().unwrap().to_string();
+            let (content_type, disposition) = (header("content-type"), header("content-disposition"));
+            let bytes = response.into_body().collect().await.unwrap().to_bytes().to_vec();
+            (content_type, disposition, bytes)
+        }
+    };
+
+    let (content_type, disposition, bytes) = download("csv").await;
+    assert!(content_type.starts_with("text/csv"), "got {content_type}");
+    assert!(
+        disposition.contains("broadsheet_JSS_1_First_Term_2025_2026.csv"),
+        "got {disposition}"
+    );
+    let text = String::from_utf8(bytes).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Admission No,Last Name,First Name,Mathematics CA,Mathematics Exam,Mathematics Total,Mathematics Grade,English Language CA,English Language Exam,English Language Total,English Language Grade,Total,Average,Position"
+    );
+    // Roster order; the formula-like name is neutralized and missing subjects are blank.
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].ends_with(",Okafor,'@Bola,20,30,50,C,,,,,50,50,2"), "got {}", lines[1]);
+    assert!(lines[2].ends_with(",Okafor,Amaka,30,45,75,A,35,55,90,A,165,82.5,1"), "got {}", lines[2]);
+
+    let (content_type, disposition, bytes) = download("xlsx").await;
+    assert_eq!(content_type, "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet");
+    assert!(disposition.contains(".xlsx"), "got {disposition}");
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut sheet = String::new();
+    archive
+        .by_name("xl/worksheets/sheet1.xml")
+        .unwrap()
+        .read_to_string(&mut sheet)
+        .unwrap();
+    assert!(sheet.contains(r#"<t xml:space="preserve">Mathematics CA</t>"#));
+    assert!(sheet.contains(r#"<t xml:space="preserve">@Bola</t>"#));
+    assert!(sheet.contains(r#"<c r="L3"><v>165</v></c>"#), "sheet: {sheet}");
+    assert!(archive.by_name("xl/workbook.xml").is_ok());
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/broadsheet?term=First%20Term&grade_level=JSS%201&format=pdf",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let (status, _) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/broadsheet?term=First%20Term&grade_level=JSS%202",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_score_import_csv() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", gradebook_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    let admission = |id: Uuid| {
+        let pool = state.db_pool.clone();
+        async move {
+            sqlx::query_scalar::<_, String>("SELECT admission_number FROM students WHERE id = $1")
+                .bind(id)
+                .fetch_one(&pool)
+                .await
+                .unwrap()
+        }
+    };
+    let (adm_a, adm_b) = (admission(a).await, admission(b).await);
+
+    let csv = format!(
+        "Adm No,Subject,CA 1,Exam\n\
+         {adm_a},mathematics,30,50\n\
+         {adm_b},Mathematics,20,abc\n\
+         NOPE-1,Mathematics,10,10\n\
+         {adm_a},Physics,10,10\n\
+         {adm_b},English Language,45,10\n\
+         {adm_a},Mathematics,31,\n"
+    );
+    let mapping = json!({ "Adm No": "admission_number", "Subject": "subject", "CA 1": "ca", "Exam": "exam" });
+    let import = |skip_invalid: &'static str| {
+        multipart_post(
+            test_router(state.clone()),
+            "/api/v1/gradebook/bulk-import",
+            vec![
+                ("file", Some("scores.csv"), csv.clone().into_bytes()),
+                ("mapping", None, mapping.to_string().into_bytes()),
+                ("term", None, b"First Term".to_vec()),
+                ("skip_invalid", None, skip_invalid.as_bytes().to_vec()),
+            ],
+            &school.token,
+        )
+    };
+
+    // Without skip_invalid nothing is written.
+    let (status, body) = import("false").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY, "body: {body}");
+    assert_eq!(body["imported"], 0);
+    let errors: Vec<(u64, Value)> = body["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| (e["row"].as_u64().unwrap(), e["field"].clone()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (3, json!("exam")),
+            (4, json!("admission_number")),
+            (5, json!("subject")),
+            (6, json!("ca")),
+            (7, Value::Null),
+        ],
+        "body: {body}"
+    );
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM gradebook_scores sc JOIN gradebook_sheets sh ON sh.id = sc.sheet_id WHERE sh.org_id = $1",
+    )
+    .bind(school.org_id)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(count, 0);
+
+    // With skip_invalid the valid row lands on a new JSS 1 Mathematics sheet.
+    let (status, body) = import("true").await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["imported"], 1);
+    assert_eq!(body["skipped"], 5);
+    assert_eq!(body["sheets"][0]["subject"], "Mathematics");
+    assert_eq!(body["sheets"][0]["grade_level"], "JSS 1");
+    assert_eq!(body["sheets"][0]["rows"], 1);
+    let (status, sheet) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets?term=First%20Term&subject=Mathematics&grade_level=JSS%201",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {sheet}");
+    let entry = sheet["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|e| e["student_id"] == json!(a))
+        .unwrap()
+        .clone();
+    assert_eq!(entry["scores"]["ca"], 30.0);
+    assert_eq!(entry["scores"]["exam"], 50.0);
+
+    // The mapping must name the student column.
+    let (status, body) = multipart_post(
+        test_router(state.clone()),
+        "/api/v1/gradebook/bulk-import",
+        vec![
+            ("file", Some("scores.csv"), csv.clone().into_bytes()),
+            ("mapping", None, json!({ "CA 1": "ca" }).to_string().into_bytes()),
+            ("term", None, b"First Term".to_vec()),
+            ("subject", None, b"Mathematics".to_vec()),
+        ],
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+}
@@ -0,0 +1,1027 @@
+use std::io::{Cursor, Read};
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use http_body_util::BodyExt;
+use schoolnify_api::state::AppState;
+use serde_json::{json, Value};
+use serial_test::serial;
+use tower::ServiceExt;
+use uuid::Uuid;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use super::common::fixtures::*;
+use super::common::jwt::*;
+use super::common::state::*;
+
+fn report_card_setup() -> Value {
+    json!({
+        "identity": { "school_type": "secondary", "motto": "Light & Truth" },
+        "branding": { "primary_color": "#0891B2", "secondary_color": "#10B981" },
+        "academic_calendar": {
+            "current_academic_year": "2025/2026",
+            "terms": [
+                { "name": "First Term", "start_date": "2025-09-01", "end_date": "2025-12-15" },
+                { "name": "Second Term", "start_date": "2026-01-10", "end_date": "2026-04-05" }
+            ]
+        },
+        "grade_levels": { "grade_levels": ["JSS 1", "JSS 2"] },
+        "subjects": { "subjects": ["Mathematics", "English Language"] },
+        "grading": {
+            "ca_weight": "40",
+            "exam_weight": "60",
+            "passmark": "50",
+            "grading_scale": [
+                { "grade": "A", "min_score": "70", "max_score": "100", "descriptor": "Excellent" },
+                { "grade": "C", "min_score": "50", "max_score": "69", "descriptor": "Credit" },
+                { "grade": "F", "min_score": "0", "max_score": "49", "descriptor": "Fail" }
+            ]
+        },
+        "report_card": report_card("standard"),
+    })
+}
+
+fn report_card(template: &str) -> Value {
+    json!({
+        "report_template": template,
+        "show_assessment_breakdown": true,
+        "show_class_average": true,
+        "show_highest_lowest": false,
+        "show_grading_legend": true,
+        "show_position": true,
+        "show_psychomotor": true,
+        "psychomotor_traits": ["Handwriting", "Sports"],
+        "show_affective": false,
+        "affective_traits": ["Punctuality"],
+        "show_principal_signature": true,
+        "show_attendance_summary": true,
+        "show_next_term_dates": true
+    })
+}
+
+async fn enter_scores(state: &AppState, token: &str, subject: &str, entries: Value) {
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        json!({
+            "term": "First Term",
+            "subject": subject,
+            "grade_level": "JSS 1",
+            "entries": entries,
+        }),
+        token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+}
+
+/// GET returning (status, content-type, content-disposition, body bytes).
+async fn get_raw(state: &AppState, uri: &str, token: &str) -> (StatusCode, String, String, Vec<u8>) {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_router(state.clone()).oneshot(request).await.unwrap();
+    let status = response.status();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .map(|v| v.to_str().unwrap().to_string())
+            .unwrap_or_default()
+    };
+    let content_type = header("content-type");
+    let disposition = header("content-disposition");
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    (status, content_type, disposition, bytes.to_vec())
+}
+
+// ── Tests ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+#[serial]
+async fn test_student_report_card_json_pdf_and_html() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "<Bola>").await;
+
+    enter_scores(
+        &state,
+        &school.token,
+        "Mathematics",
+        json!([
+            { "student_id": a, "scores": { "ca": 30, "exam": 50 } },
+            { "student_id": b, "scores": { "ca": 20, "exam": 30 } },
+        ]),
+    )
+    .await;
+    enter_scores(
+        &state,
+        &school.token,
+        "English Language",
+        json!([
+            { "student_id": a, "scores": { "ca": 25, "exam": 40 } },
+            { "student_id": b, "scores": { "ca": 30, "exam": 50 } },
+        ]),
+    )
+    .await;
+
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=json");
+    let (status, card) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {card}");
+    assert_eq!(card["school"]["name"], "Test School");
+    assert_eq!(card["school"]["motto"], "Light & Truth");
+    assert_eq!(card["template"], "standard");
+    assert_eq!(card["academic_year"], "2025/2026");
+    assert_eq!(card["provisional"], true);
+    assert_eq!(card["components"], json!(["ca", "exam"]));
+
+    let maths = card["subjects"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|s| s["subject"] == "Mathematics")
+        .unwrap();
+    assert_eq!(maths["scores"], json!({ "ca": 30.0, "exam": 50.0 }));
+    assert_eq!(maths["total"], 80.0);
+    assert_eq!(maths["grade"], "A");
+    assert_eq!(maths["position"], 1);
+    assert_eq!(maths["class_average"], 65.0);
+    assert!(maths.get("highest").is_none());
+
+    // Amaka 72.5, Bola 65.
+    assert_eq!(card["summary"]["average"], 72.5);
+    assert_eq!(card["summary"]["position"], 1);
+    assert_eq!(card["summary"]["class_size"], 2);
+    assert!(card["summary"].get("gpa").is_none());
+
+    let legend: Vec<&str> = card["grading_legend"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["grade"].as_str().unwrap())
+        .collect();
+    assert_eq!(legend, ["A", "C", "F"]);
+    assert_eq!(card["psychomotor"], json!([{ "name": "Handwriting" }, { "name": "Sports" }]));
+    assert!(card.get("affective").is_none());
+    assert!(card["attendance"]["days_opened"].as_i64().unwrap() > 0);
+    assert_eq!(card["next_term"], json!({ "name": "Second Term", "start_date": "2026-01-10" }));
+    assert_eq!(card["signatures"], json!(["Principal"]));
+
+    // PDF is the default format.
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term");
+    let (status, content_type, disposition, bytes) = get_raw(&state, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/pdf");
+    assert!(disposition.starts_with("attachment;"), "{disposition}");
+    assert!(bytes.starts_with(b"%PDF"));
+
+    // HTML is escaped and served inline.
+    let uri = format!("/api/v1/students/{b}/report-card?term=First%20Term&format=html");
+    let (status, content_type, disposition, bytes) = get_raw(&state, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(content_type.starts_with("text/html"), "{content_type}");
+    assert!(disposition.starts_with("inline;"), "{disposition}");
+    let html = String::from_utf8(bytes).unwrap();
+    assert!(html.contains("Test School"));
+    assert!(html.contains("Light &amp; Truth"));
+    assert!(html.contains("&lt;Bola&gt;"));
+    assert!(!html.contains("<Bola>"));
+    assert!(html.contains("Provisional"));
+
+    // Minimal drops the breakdown and legend regardless of toggles.
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "report_card": report_card("minimal") }),
+    )
+    .await;
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=json");
+    let (_, card) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(card["template"], "minimal");
+    assert_eq!(card["components"], json!([]));
+    assert!(card["subjects"][0].get("scores").is_none());
+    assert!(card.get("grading_legend").is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_report_card_pdf_embeds_logo() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    enter_scores(
+        &state,
+        &school.token,
+        "Mathematics",
+        json!([{ "student_id": a, "scores": { "ca": 30, "exam": 50 } }]),
+    )
+    .await;
+
+    // Enough of a JPEG for its frame header to be read: 64x48, RGB.
+    let mut jpeg = vec![0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x11, 8, 0x00, 0x30, 0x00, 0x40, 3];
+    jpeg.extend_from_slice(&[0; 9]);
+    Mock::given(method("GET"))
+        .and(path("/logo.jpg"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(jpeg))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/moved.jpg"))
+        .respond_with(ResponseTemplate::new(302).insert_header("location", "/logo.jpg"))
+        .mount(&mock_server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/logo.gif"))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(b"GIF89a....".to_vec()))
+        .mount(&mock_server)
+        .await;
+
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term");
+    let set_logo = |logo: String| {
+        sqlx::query("UPDATE school_configs SET logo_url = $1 WHERE org_id = $2")
+            .bind(logo)
+            .bind(school.org_id)
+            .execute(&state.db_pool)
+    };
+
+    set_logo(format!("{}/logo.jpg", mock_server.uri())).await.unwrap();
+    let (status, _, _, bytes) = get_raw(&state, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(contains(&bytes, b"/Im1"));
+    assert!(contains(&bytes, b"/DCTDecode"));
+
+    // Unsupported, missing or redirected images fall back to a PDF without
+    // a logo.
+    for logo in ["logo.gif", "missing.png", "moved.jpg"] {
+        set_logo(format!("{}/{logo}", mock_server.uri())).await.unwrap();
+        let (status, _, _, bytes) = get_raw(&state, &uri, &school.token).await;
+        assert_eq!(status, StatusCode::OK, "{logo}");
+        assert!(bytes.starts_with(b"%PDF"));
+        assert!(!contains(&bytes, b"/Im1"), "{logo}");
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|w| w == needle)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_class_report_cards_zip_and_errors() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    enter_scores(
+        &state,
+        &school.token,
+        "Mathematics",
+        json!([
+            { "student_id": a, "scores": { "ca": 30, "exam": 50 } },
+            { "student_id": b, "scores": { "ca": 20, "exam": 30 } },
+        ]),
+    )
+    .await;
+
+    let uri = "/api/v1/report-cards?term=First%20Term&grade_level=JSS%201";
+    let (status, content_type, disposition, bytes) = get_raw(&state, uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/zip");
+    assert!(disposition.contains("report_cards_JSS_1_First_Term_2025_2026.zip"), "{disposition}");
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert_eq!(archive.len(), 2);
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i).unwrap();
+        assert!(file.name().ends_with("_First_Term_2025_2026.pdf"), "{}", file.name());
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents).unwrap();
+        assert!(contents.starts_with(b"%PDF"));
+    }
+
+    let uri = "/api/v1/report-cards?term=First%20Term&grade_level=JSS%201&format=html";
+    let (status, _, _, bytes) = get_raw(&state, uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    let archive = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    assert!(archive.file_names().all(|n| n.ends_with(".html")));
+
+    // JSON isn't a bundle format; unknown formats are rejected.
+    let uri = "/api/v1/report-cards?term=First%20Term&grade_level=JSS%201&format=json";
+    let (status, _) = get_auth(test_router(state.clone()), uri, &school.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=docx");
+    let (status, _) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // No results for the class or the student.
+    let uri = "/api/v1/report-cards?term=First%20Term&grade_level=JSS%202";
+    let (status, _) = get_auth(test_router(state.clone()), uri, &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/api/v1/students/{a}/report-card?term=Second%20Term");
+    let (status, _) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_trait_ratings_bulk_entry_and_report_card() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    let other = create_student(&state, &school.token, "JSS 2", "Chidi").await;
+
+    // Trait names and ratings match case-insensitively; the default scale is 5–1.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/traits",
+        json!({
+            "term": "first term",
+            "grade_level": "JSS 1",
+            "entries": [
+                {
+                    "student_id": a,
+                    "psychomotor": { "handwriting": "4", "Sports": "5" },
+                    "affective": { "PUNCTUALITY": "3" }
+                },
+                { "student_id": b, "psychomotor": { "Handwriting": "2" } },
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["term"], "First Term");
+    assert_eq!(body["psychomotor_traits"], json!(["Handwriting", "Sports"]));
+    assert_eq!(body["affective_traits"], json!(["Punctuality"]));
+    assert_eq!(body["scale"][0], json!({ "rating": "5", "descriptor": "Excellent" }));
+    assert_eq!(body["scale"].as_array().unwrap().len(), 5);
+    let entry = |body: &Value, id: Uuid| {
+        body["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["student_id"] == id.to_string())
+            .cloned()
+            .unwrap()
+    };
+    let amaka = entry(&body, a);
+    assert_eq!(amaka["psychomotor"], json!({ "Handwriting": "4", "Sports": "5" }));
+    assert_eq!(amaka["affective"], json!({ "Punctuality": "3" }));
+    assert_eq!(entry(&body, b)["affective"], json!({}));
+
+    // A second pass updates one trait and clears another.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/traits",
+        json!({
+            "term": "First Term",
+            "grade_level": "JSS 1",
+            "entries": [{ "student_id": a, "psychomotor": { "Sports": null, "Handwriting": "3" } }]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(entry(&body, a)["psychomotor"], json!({ "Handwriting": "3" }));
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/traits?term=First%20Term&grade_level=JSS%201",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["entries"].as_array().unwrap().len(), 2);
+    assert_eq!(entry(&body, b)["psychomotor"], json!({ "Handwriting": "2" }));
+
+    let bad_entries = [
+        json!({ "student_id": a, "psychomotor": { "Dancing": "4" } }),
+        json!({ "student_id": a, "psychomotor": { "Handwriting": "9" } }),
+        json!({ "student_id": other, "psychomotor": { "Handwriting": "4" } }),
+    ];
+    for bad in bad_entries {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/report-cards/traits",
+            json!({ "term": "First Term", "grade_level": "JSS 1", "entries": [bad] }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/traits"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(
+        body["terms"],
+        json!([{
+            "academic_year": "2025/2026",
+            "term": "First Term",
+            "psychomotor": { "Handwriting": "3" },
+            "affective": { "Punctuality": "3" }
+        }])
+    );
+
+    // Report cards show ratings for the tables that are turned on.
+    enter_scores(
+        &state,
+        &school.token,
+        "Mathematics",
+        json!([{ "student_id": a, "scores": { "ca": 30, "exam": 50 } }]),
+    )
+    .await;
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=json");
+    let (status, card) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {card}");
+    assert_eq!(
+        card["psychomotor"],
+        json!([{ "name": "Handwriting", "rating": "3" }, { "name": "Sports" }])
+    );
+    assert!(card.get("affective").is_none());
+    assert_eq!(card["trait_scale"].as_array().unwrap().len(), 5);
+
+    // A custom scale replaces the default; duplicates are rejected.
+    let mut settings = report_card("standard");
+    settings["trait_rating_scale"] = json!(["A", "a"]);
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "report_card": settings }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+
+    let mut settings = report_card("standard");
+    settings["trait_rating_scale"] = json!([{ "rating": "A", "descriptor": "Outstanding" }, "B"]);
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "report_card": settings }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    let save = |rating: &str| {
+        json!({
+            "term": "First Term",
+            "grade_level": "JSS 1",
+            "entries": [{ "student_id": b, "psychomotor": { "Sports": rating } }]
+        })
+    };
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/traits",
+        save("4"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/traits",
+        save("a"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(entry(&body, b)["psychomotor"]["Sports"], "A");
+    assert_eq!(
+        body["scale"],
+        json!([{ "rating": "A", "descriptor": "Outstanding" }, { "rating": "B" }])
+    );
+}
+
+/// A non-admin staff member in the school.
+async fn teacher_token(state: &AppState, org_id: Uuid, mock_server: &MockServer) -> String {
+    let workos_id = unique_workos_id();
+    sqlx::query(
+        r#"
+        INSERT INTO users (workos_user_id, email, email_verified, org_id, role)
+        VALUES ($1, $2, true, $3, 'teacher')
+        "#,
+    )
+    .bind(&workos_id)
+    .bind(unique_email())
+    .bind(org_id)
+    .execute(&state.db_pool)
+    .await
+    .unwrap();
+    sign_test_jwt(&workos_id, None, &mock_server.uri())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_report_card_comments_limits_bank_and_suggestions() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let teacher = teacher_token(&state, school.org_id, &mock_server).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+    let b = create_student(&state, &school.token, "JSS 1", "Bola").await;
+    enter_scores(
+        &state,
+        &school.token,
+        "Mathematics",
+        json!([
+            { "student_id": a, "scores": { "ca": 30, "exam": 50 } },
+            { "student_id": b, "scores": { "ca": 10, "exam": 20 } },
+        ]),
+    )
+    .await;
+
+    let save = |kind: &str, subject: Option<&str>, entries: Value| {
+        json!({
+            "term": "First Term",
+            "grade_level": "JSS 1",
+            "kind": kind,
+            "subject": subject,
+            "entries": entries
+        })
+    };
+
+    // Both teacher comment kinds are off until the report_card section turns them on.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        save("class_teacher", None, json!([{ "student_id": a, "comment": "Good term" }])),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+
+    let mut settings = report_card("standard");
+    settings["comment_char_limit"] = json!("lots");
+    let (status, _) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "report_card": settings }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    settings["comment_char_limit"] = json!("40");
+    settings["show_teacher_comments"] = json!(true);
+    settings["show_class_teacher_comment"] = json!(true);
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "report_card": settings }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    // The comment bank is managed by admins; grades come from the grading scale.
+    for (kind, grade, comment) in [
+        (Some("class_teacher"), Some("a"), "An excellent term's work."),
+        (None, Some("F"), "Needs to work harder."),
+        (None, None, "Keep it up."),
+        (Some("principal"), None, "Promoted on trial."),
+    ] {
+        let (status, body) = post_json_auth(
+            test_router(state.clone()),
+            "/api/v1/report-cards/comment-bank",
+            json!({ "kind": kind, "grade": grade, "comment": comment }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::CREATED, "body: {body}");
+        if grade == Some("a") {
+            assert_eq!(body["grade"], "A");
+        }
+    }
+    let bad_bank = [
+        json!({ "grade": "B", "comment": "No such grade" }),
+        json!({ "kind": "janitor", "comment": "No such kind" }),
+        json!({ "comment": "   " }),
+        json!({ "comment": "This comment is far too long for the school's forty character limit" }),
+    ];
+    for bad in bad_bank {
+        let (status, body) = post_json_auth(
+            test_router(state.clone()),
+            "/api/v1/report-cards/comment-bank",
+            bad,
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+    let (status, _) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comment-bank",
+        json!({ "comment": "From a teacher" }),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comment-bank?kind=class_teacher&grade=A",
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let comments: Vec<&str> =
+        body.as_array().unwrap().iter().map(|e| e["comment"].as_str().unwrap()).collect();
+    assert_eq!(comments, ["An excellent term's work.", "Keep it up."]);
+
+    // Suggestions follow each student's grade: Amaka averages 80 (A), Bola 30 (F).
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments?term=First%20Term&grade_level=JSS%201&kind=class_teacher",
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["char_limit"], 40);
+    let entry = |body: &Value, id: Uuid| {
+        body["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|e| e["student_id"] == id.to_string())
+            .cloned()
+            .unwrap()
+    };
+    let amaka = entry(&body, a);
+    assert_eq!(amaka["grade"], "A");
+    assert_eq!(amaka["suggestions"], json!(["An excellent term's work.", "Keep it up."]));
+    let bola = entry(&body, b);
+    assert_eq!(bola["grade"], "F");
+    assert_eq!(bola["suggestions"], json!(["Needs to work harder.", "Keep it up."]));
+
+    // Comments over the limit are rejected and nothing from the batch is saved.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        save(
+            "class_teacher",
+            None,
+            json!([
+                { "student_id": a, "comment": "Good term" },
+                { "student_id": b, "comment": "Ọ".repeat(41) },
+            ]),
+        ),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        save(
+            "class_teacher",
+            None,
+            json!([
+                { "student_id": a, "comment": "  An excellent term's work. " },
+                { "student_id": b, "comment": "Ọ".repeat(40) },
+            ]),
+        ),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(entry(&body, a)["comment"], "An excellent term's work.");
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        save(
+            "subject_teacher",
+            Some("mathematics"),
+            json!([{ "student_id": a, "comment": "Strong in algebra." }]),
+        ),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["subject"], "Mathematics");
+    assert_eq!(entry(&body, a)["grade"], "A");
+
+    // Subject teacher comments need a subject; other kinds take none.
+    for bad in [
+        save("subject_teacher", None, json!([{ "student_id": a, "comment": "Fine" }])),
+        save("class_teacher", Some("Mathematics"), json!([{ "student_id": a, "comment": "Fine" }])),
+    ] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/report-cards/comments",
+            bad,
+            &teacher,
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    }
+
+    // The principal's remark is for admins.
+    let principal = save("principal", None, json!([{ "student_id": a, "comment": "Well done." }]));
+    let (status, _) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        principal.clone(),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        principal,
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(entry(&body, a)["suggestions"], json!(["Promoted on trial.", "Keep it up."]));
+
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=json");
+    let (status, card) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {card}");
+    assert_eq!(card["subjects"][0]["comment"], "Strong in algebra.");
+    assert_eq!(card["class_teacher_comment"], "An excellent term's work.");
+    assert_eq!(card["principal_comment"], "Well done.");
+
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=html");
+    let (status, _, _, html) = get_raw(&state, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    let html = String::from_utf8(html).unwrap();
+    assert!(html.contains("<h2>Remarks</h2>"));
+    assert!(html.contains("Class Teacher&#39;s Comment:</strong> An excellent term&#39;s work."));
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/comments"),
+        &teacher,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let kinds: Vec<&str> = body["comments"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|c| c["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["subject_teacher", "class_teacher", "principal"]);
+
+    // Turning teacher comments off hides them from the card but keeps them.
+    settings["show_teacher_comments"] = json!(false);
+    settings["show_class_teacher_comment"] = json!(false);
+    let (status, _) = patch_json_auth(
+        test_router(state.clone()),
+        "/api/v1/schools/setup",
+        json!({ "report_card": settings }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let uri = format!("/api/v1/students/{a}/report-card?term=First%20Term&format=json");
+    let (_, card) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert!(card["subjects"][0].get("comment").is_none());
+    assert!(card.get("class_teacher_comment").is_none());
+    assert_eq!(card["principal_comment"], "Well done.");
+
+    // Clearing a comment removes it.
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comments",
+        save("principal", None, json!([{ "student_id": a, "comment": null }])),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(entry(&body, a).get("comment").is_none());
+
+    // Bank entries can be updated and deleted.
+    let (_, bank) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/report-cards/comment-bank",
+        &school.token,
+    )
+    .await;
+    let id = bank[0]["id"].as_str().unwrap().to_string();
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/report-cards/comment-bank/{id}"),
+        json!({ "grade": "", "comment": "Outstanding." }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(body.get("grade").is_none());
+    assert_eq!(body["comment"], "Outstanding.");
+    let (status, _) = delete_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/report-cards/comment-bank/{id}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = delete_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/report-cards/comment-bank/{id}"),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_transcript_spans_years_and_outlives_transfer() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+
+    let sheet = |year: &str, grade: &str, ca: u32, exam: u32| {
+        json!({
+            "academic_year": year,
+            "term": "First Term",
+            "subject": "Mathematics",
+            "grade_level": grade,
+            "entries": [{ "student_id": a, "scores": { "ca": ca, "exam": exam } }],
+        })
+    };
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        sheet("2024/2025", "JSS 1", 30, 50),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    // Those scores were entered during that year, before the promotion.
+    sqlx::query("UPDATE gradebook_scores SET created_at = '2024-10-01' WHERE student_id = $1")
+        .bind(a)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/class"),
+        json!({ "grade_level": "JSS 2", "effective_date": "2025-07-20" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        sheet("2025/2026", "JSS 2", 20, 20),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({
+            "grading": {
+                "ca_weight": "40",
+                "exam_weight": "60",
+                "passmark": "50",
+                "gpa_enabled": true,
+                "grading_scale": [
+                    { "grade": "A", "min_score": "70", "max_score": "100", "gpa_points": "4.0" },
+                    { "grade": "C", "min_score": "50", "max_score": "69", "gpa_points": "2.0" },
+                    { "grade": "F", "min_score": "0", "max_score": "49", "gpa_points": "0" }
+                ]
+            }
+        }),
+    )
+    .await;
+    // Only the first year's results are published.
+    for to in ["submitted", "approved", "published"] {
+        let (status, body) = patch_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/results/status",
+            json!({ "academic_year": "2024/2025", "term": "First Term", "grade_level": "JSS 1", "status": to }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+    let (status, body) = patch_json_auth(
+        test_router(state.clone()),
+        &format!("/api/v1/students/{a}/status"),
+        json!({ "status": "transferred", "effective_date": "2026-01-05" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    let uri = format!("/api/v1/students/{a}/transcript?format=json");
+    let (status, t) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {t}");
+    assert_eq!(t["school"]["name"], "Test School");
+    assert_eq!(t["student"]["status"], "transferred");
+    assert_eq!(t["student"]["left_on"], "2026-01-05");
+    assert_eq!(t["passmark"], 50.0);
+
+    let terms = t["terms"].as_array().unwrap();
+    assert_eq!(terms.len(), 2);
+    assert_eq!(terms[0]["academic_year"], "2024/2025");
+    assert_eq!(terms[0]["grade_level"], "JSS 1");
+    assert_eq!(terms[0]["provisional"], false);
+    assert_eq!(terms[0]["subjects"][0]["total"], 80.0);
+    assert_eq!(terms[0]["subjects"][0]["grade"], "A");
+    assert_eq!(terms[0]["gpa"], 4.0);
+    assert_eq!(terms[1]["academic_year"], "2025/2026");
+    assert_eq!(terms[1]["grade_level"], "JSS 2");
+    assert_eq!(terms[1]["provisional"], true);
+    assert_eq!(terms[1]["subjects"][0]["passed"], false);
+    assert!(terms[1]["gpa"].is_null(), "body: {t}");
+    assert_eq!(terms[1]["cumulative_gpa"], 4.0);
+    assert_eq!(t["cumulative_gpa"], 4.0);
+
+    // PDF is the default format.
+    let uri = format!("/api/v1/students/{a}/transcript");
+    let (status, content_type, disposition, bytes) = get_raw(&state, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(content_type, "application/pdf");
+    assert!(disposition.contains("transcript_"), "{disposition}");
+    assert!(bytes.starts_with(b"%PDF"));
+
+    let uri = format!("/api/v1/students/{a}/transcript?format=html");
+    let (status, _) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let uri = format!("/api/v1/students/{}/transcript", Uuid::new_v4());
+    let (status, _) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_transcript_keeps_class_for_scores_entered_after_promotion() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin", report_card_setup()).await;
+    let a = create_student(&state, &school.token, "JSS 1", "Amaka").await;
+
+    let (status, body) = put_json_auth(
+        test_router(state.clone()),
+        "/api/v1/gradebook/sheets",
+        json!({
+            "academic_year": "2024/2025",
+            "term": "Second Term",
+            "subject": "Mathematics",
+            "grade_level": "JSS 1",
+            "entries": [{ "student_id": a, "scores": { "ca": 30, "exam": 50 } }],
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+
+    let (status, body) = post_json_auth(
+        test_router(state.clone()),
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [{ "student_id": a, "action": "promote", "to_grade": "JSS 2" }],
+            "academic_year": "2024/2025",
+            "effective_date": "2025-07-20",
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    // The term's scores were typed in after the year-end promotion.
+    sqlx::query("UPDATE gradebook_scores SET created_at = '2025-08-01' WHERE student_id = $1")
+        .bind(a)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let uri = format!("/api/v1/students/{a}/transcript?format=json");
+    let (status, t) = get_auth(test_router(state.clone()), &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {t}");
+    assert_eq!(t["terms"][0]["academic_year"], "2024/2025");
+    assert_eq!(t["terms"][0]["grade_level"], "JSS 1");
+}
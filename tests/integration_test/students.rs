@@ -1,6 +1,7 @@
+use std::time::Duration;
+
 use axum::body::Body;
 use axum::http::{Method, Request, StatusCode};
-use axum::Router;
 use chrono::Datelike;
 use http_body_util::BodyExt;
 use schoolnify_api::state::AppState;
@@ -14,8 +15,6 @@ use super::common::fixtures::*;
 use super::common::jwt::*;
 use super::common::state::*;
 
-const TEST_BOUNDARY: &str = "----schoolnifyTestBoundary";
-
 struct TestSchool {
     workos_id: String,
     org_id: Uuid,
@@ -70,51 +69,6 @@ fn min_student(grade: &str) -> serde_json::Value {
     })
 }
 
-async fn multipart_post(
-    app: Router,
-    uri: &str,
-    parts: Vec<(&str, Option<&str>, Vec<u8>)>,
-    token: &str,
-) -> (StatusCode, serde_json::Value) {
-    let mut body: Vec<u8> = Vec::new();
-    for (name, filename, data) in parts {
-        body.extend_from_slice(format!("--{TEST_BOUNDARY}\r\n").as_bytes());
-        let disposition = match filename {
-            Some(f) => format!(
-                "Content-Disposition: form-data; name=\"{name}\"; filename=\"{f}\"\r\n\
-                 Content-Type: text/csv\r\n\r\n"
-            ),
-            None => format!("Content-Disposition: form-data; name=\"{name}\"\r\n\r\n"),
-        };
-        body.extend_from_slice(disposition.as_bytes());
-        body.extend_from_slice(&data);
-        body.extend_from_slice(b"\r\n");
-    }
-    body.extend_from_slice(format!("--{TEST_BOUNDARY}--\r\n").as_bytes());
-
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri(uri)
-        .header(
-            "content-type",
-            format!("multipart/form-data; boundary={TEST_BOUNDARY}"),
-        )
-        .header("authorization", format!("Bearer {token}"))
-        .body(Body::from(body))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-    let status = response.status();
-    let bytes = response.into_body().collect().await.unwrap().to_bytes();
-    let json: serde_json::Value = if bytes.is_empty() {
-        serde_json::Value::Null
-    } else {
-        serde_json::from_slice(&bytes)
-            .unwrap_or_else(|_| json!({ "raw": String::from_utf8_lossy(&bytes).to_string() }))
-    };
-    (status, json)
-}
-
 // ── Tests ───────────────────────────────────────────────────────────
 
 #[tokio::test]
@@ -504,6 +458,506 @@ async fn test_promote_failure_rolls_back_all() {
     assert_eq!(count, 0);
 }
 
+#[tokio::test]
+#[serial]
+async fn test_promote_skip_invalid_commits_the_rest() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let app = test_router(state.clone());
+        let (_, body) =
+            post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    let missing = Uuid::new_v4().to_string();
+    let other = Uuid::new_v4().to_string();
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [
+                { "student_id": ids[0], "action": "promote", "to_grade": "Primary 2" },
+                { "student_id": missing, "action": "promote", "to_grade": "Primary 2" },
+                { "student_id": ids[1], "action": "promote", "to_grade": "Primary 9" },
+                { "student_id": ids[2], "action": "graduate" },
+                { "student_id": other, "action": "expel" }
+            ],
+            "skip_invalid": true
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!((body["promoted"].as_i64(), body["graduated"].as_i64()), (Some(1), Some(1)));
+    let errors = body["errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 3, "errors: {errors:?}");
+    // Validation failures are reported in request order, then the ones
+    // found while applying.
+    assert_eq!(errors[0]["student_id"], ids[1].as_str());
+    assert_eq!(errors[0]["action"], "promote");
+    assert_eq!(errors[1]["student_id"], other.as_str());
+    assert_eq!(errors[2]["student_id"], missing.as_str());
+    assert_eq!(errors[2]["message"], format!("Student {missing} not found"));
+
+    // The applied decisions committed under the returned batch id.
+    let rows: Vec<String> = sqlx::query_scalar(
+        "SELECT change_kind FROM student_class_history WHERE promotion_batch_id = $1 ORDER BY change_kind",
+    )
+    .bind(Uuid::parse_str(body["batch_id"].as_str().unwrap()).unwrap())
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(rows, ["graduate", "promote"]);
+    let grade: String = sqlx::query_scalar("SELECT grade_level FROM students WHERE id = $1")
+        .bind(Uuid::parse_str(&ids[1]).unwrap())
+        .fetch_one(&state.db_pool)
+        .await
+        .unwrap();
+    assert_eq!(grade, "Primary 1");
+
+    // Without the flag the same problems reject the whole batch.
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [
+                { "student_id": ids[1], "action": "retain" },
+                { "student_id": missing, "action": "retain" }
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_promote_dry_run_previews_without_writing() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let mut student = min_student("Primary 1");
+        student["section"] = json!("A");
+        let app = test_router(state.clone());
+        let (_, body) = post_json_auth(app, "/api/v1/students", student, &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+
+    let decisions = json!([
+        { "student_id": ids[0], "action": "promote", "to_grade": "Primary 2", "to_section": "B" },
+        { "student_id": ids[1], "action": "retain" },
+        { "student_id": ids[2], "action": "graduate" }
+    ]);
+    // A preview only reads, so a lock on the students doesn't hold it up.
+    let mut lock = state.db_pool.begin().await.unwrap();
+    sqlx::query("SELECT 1 FROM students WHERE org_id = $1 FOR UPDATE")
+        .bind(school.org_id)
+        .execute(&mut *lock)
+        .await
+        .unwrap();
+    let app = test_router(state.clone());
+    let (status, body) = tokio::time::timeout(
+        Duration::from_secs(5),
+        post_json_auth(
+            app,
+            "/api/v1/students/promote",
+            json!({ "decisions": decisions, "dry_run": true }),
+            &school.token,
+        ),
+    )
+    .await
+    .expect("dry run waited on a row lock");
+    lock.rollback().await.unwrap();
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["dry_run"], true);
+    assert!(body.get("batch_id").is_none(), "body: {body}");
+    assert_eq!((body["promoted"].as_i64(), body["retained"].as_i64()), (Some(1), Some(1)));
+    assert_eq!(body["graduated"], 1);
+    let changes = body["changes"].as_array().unwrap();
+    assert_eq!(changes.len(), 3);
+    assert_eq!(changes[0]["from_grade"], "Primary 1");
+    assert_eq!(changes[0]["from_section"], "A");
+    assert_eq!(changes[0]["to_grade"], "Primary 2");
+    assert_eq!(changes[0]["to_section"], "B");
+    assert_eq!(changes[0]["to_status"], "active");
+    assert_eq!(changes[1]["to_grade"], "Primary 1");
+    assert_eq!(changes[1]["to_section"], "A");
+    assert_eq!(changes[2]["from_status"], "active");
+    assert_eq!(changes[2]["to_status"], "graduated");
+    assert_eq!(changes[2]["to_grade"], "Primary 1");
+
+    // Nothing was saved.
+    let org_rows: (i64, i64, i64) = sqlx::query_as(
+        r#"
+        SELECT (SELECT COUNT(*) FROM student_class_history WHERE org_id = $1),
+               (SELECT COUNT(*) FROM student_status_history WHERE org_id = $1),
+               (SELECT COUNT(*) FROM students
+                WHERE org_id = $1 AND grade_level = 'Primary 1' AND status = 'active')
+        "#,
+    )
+    .bind(school.org_id)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(org_rows, (0, 0, 3));
+
+    // A dry run fails where the real run would.
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [{ "student_id": Uuid::new_v4(), "action": "retain" }],
+            "dry_run": true
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // The real run reports no changes list.
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({ "decisions": decisions }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["dry_run"], false);
+    assert!(body["batch_id"].is_string());
+    assert!(body.get("changes").is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn test_revert_promotion_restores_batch() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let mut student = min_student("Primary 1");
+        student["section"] = json!("A");
+        student["stream"] = json!("Science");
+        let app = test_router(state.clone());
+        let (_, body) = post_json_auth(app, "/api/v1/students", student, &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [
+                { "student_id": ids[0], "action": "promote", "to_grade": "Primary 2", "to_section": "B" },
+                { "student_id": ids[1], "action": "retain" },
+                { "student_id": ids[2], "action": "graduate" }
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+    let revert_uri = format!("/api/v1/students/promotions/{batch_id}/revert");
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        &revert_uri,
+        json!({ "reason": "Wrong class list" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["batch_id"], batch_id.as_str());
+    assert_eq!((body["promoted"].as_i64(), body["retained"].as_i64()), (Some(1), Some(1)));
+    assert_eq!(body["graduated"], 1);
+
+    let restored: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM students
+        WHERE org_id = $1 AND grade_level = 'Primary 1' AND section = 'A'
+          AND stream = 'Science' AND status = 'active' AND graduation_date IS NULL
+        "#,
+    )
+    .bind(school.org_id)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(restored, 3);
+
+    // One revert row per student; the graduate has a status row back.
+    let batch = Uuid::parse_str(&batch_id).unwrap();
+    let reverts: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM student_class_history
+         WHERE promotion_batch_id = $1 AND change_kind = 'revert' AND reason = 'Wrong class list'",
+    )
+    .bind(batch)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(reverts, 3);
+    let statuses: Vec<(String, String)> = sqlx::query_as(
+        "SELECT from_status, to_status FROM student_status_history
+         WHERE promotion_batch_id = $1 ORDER BY changed_at",
+    )
+    .bind(batch)
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        statuses,
+        [("active".into(), "graduated".into()), ("graduated".into(), "active".into())]
+    );
+
+    // A batch can only be reverted once.
+    let app = test_router(state.clone());
+    let (status, _) = post_json_auth(app, &revert_uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/promotions/{}/revert", Uuid::new_v4());
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_revert_promotion_refuses_when_students_changed_since() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut ids = vec![];
+    for _ in 0..2 {
+        let app = test_router(state.clone());
+        let (_, body) =
+            post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    let app = test_router(state.clone());
+    let (_, body) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({
+            "decisions": [
+                { "student_id": ids[0], "action": "promote", "to_grade": "Primary 2" },
+                { "student_id": ids[1], "action": "promote", "to_grade": "Primary 2" }
+            ]
+        }),
+        &school.token,
+    )
+    .await;
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+
+    let app = test_router(state.clone());
+    let (status, _) = patch_json_auth(
+        app,
+        &format!("/api/v1/students/{}/class", ids[1]),
+        json!({ "grade_level": "JSS 1" }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        &format!("/api/v1/students/promotions/{batch_id}/revert"),
+        json!({}),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::CONFLICT, "body: {body}");
+
+    // Nothing was restored, not even the unchanged student.
+    let grades: Vec<String> = sqlx::query_scalar(
+        "SELECT grade_level FROM students WHERE org_id = $1 ORDER BY admission_number",
+    )
+    .bind(school.org_id)
+    .fetch_all(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(grades, ["Primary 2", "JSS 1"]);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_concurrent_reverts_of_retain_batch_apply_once() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut decisions = vec![];
+    for _ in 0..5 {
+        let app = test_router(state.clone());
+        let (_, body) =
+            post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+        decisions.push(json!({ "student_id": body["id"], "action": "retain" }));
+    }
+    // Retaining leaves every student as they were, so only the batch's own
+    // revert rows can tell a second revert that the first happened.
+    let app = test_router(state.clone());
+    let (status, body) = post_json_auth(
+        app,
+        "/api/v1/students/promote",
+        json!({ "decisions": decisions }),
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+    let uri = format!("/api/v1/students/promotions/{batch_id}/revert");
+
+    let ((first, _), (second, _)) = tokio::join!(
+        post_json_auth(test_router(state.clone()), &uri, json!({}), &school.token),
+        post_json_auth(test_router(state.clone()), &uri, json!({}), &school.token),
+    );
+    let mut statuses = [first, second];
+    statuses.sort();
+    assert_eq!(statuses, [StatusCode::OK, StatusCode::CONFLICT]);
+
+    let reverts: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM student_class_history
+         WHERE promotion_batch_id = $1 AND change_kind = 'revert'",
+    )
+    .bind(Uuid::parse_str(&batch_id).unwrap())
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(reverts, 5);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_promotion_batch_history_and_export() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+
+    let mut ids = vec![];
+    for _ in 0..3 {
+        let app = test_router(state.clone());
+        let (_, body) =
+            post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    let mut batches = vec![];
+    for (year, decisions) in [
+        (
+            "2024/2025",
+            json!([
+                { "student_id": ids[0], "action": "promote", "to_grade": "Primary 2", "to_section": "A" },
+                { "student_id": ids[1], "action": "retain", "reason": "=Needs support" }
+            ]),
+        ),
+        (
+            "2025/2026",
+            json!([{ "student_id": ids[2], "action": "graduate" }]),
+        ),
+    ] {
+        let app = test_router(state.clone());
+        let (status, body) = post_json_auth(
+            app,
+            "/api/v1/students/promote",
+            json!({ "decisions": decisions, "academic_year": year, "effective_date": "2025-09-01" }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+        batches.push(body["batch_id"].as_str().unwrap().to_string());
+    }
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/promotions/{}/revert", batches[1]);
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Most recent first; the reversal doesn't count as decisions.
+    let app = test_router(state.clone());
+    let (status, body) = get_auth(app, "/api/v1/students/promotions", &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["pagination"]["total"], 2);
+    let data = body["data"].as_array().unwrap();
+    assert_eq!(data[0]["batch_id"], batches[1].as_str());
+    assert_eq!(data[0]["academic_year"], "2025/2026");
+    assert_eq!(data[0]["graduated"], 1);
+    assert_eq!(data[0]["reverted"], true);
+    assert!(data[0]["reverted_at"].is_string());
+    assert_eq!(data[1]["batch_id"], batches[0].as_str());
+    assert_eq!((data[1]["promoted"].as_i64(), data[1]["retained"].as_i64()), (Some(1), Some(1)));
+    assert_eq!(data[1]["graduated"], 0);
+    assert_eq!(data[1]["reverted"], false);
+    assert_eq!(data[1]["effective_date"], "2025-09-01");
+    assert!(data[1]["run_by"].is_string());
+
+    let app = test_router(state.clone());
+    let (_, body) =
+        get_auth(app, "/api/v1/students/promotions?academic_year=2024/2025", &school.token).await;
+    assert_eq!(body["pagination"]["total"], 1);
+    assert_eq!(body["data"][0]["batch_id"], batches[0].as_str());
+
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/promotions/{}", batches[0]);
+    let (status, body) = get_auth(app, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["batch_id"], batches[0].as_str());
+    assert_eq!(body["promoted"], 1);
+    let decisions = body["decisions"].as_array().unwrap();
+    assert_eq!(decisions.len(), 2);
+    let promoted = decisions.iter().find(|d| d["action"] == "promote").unwrap();
+    assert_eq!(promoted["student_id"], ids[0].as_str());
+    assert_eq!(promoted["from_grade"], "Primary 1");
+    assert_eq!(promoted["to_grade"], "Primary 2");
+    assert_eq!(promoted["to_section"], "A");
+
+    let app = test_router(state.clone());
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/api/v1/students/promotions/{}/export", batches[0]))
+        .header("authorization", format!("Bearer {}", school.token))
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let ct = response.headers().get("content-type").unwrap().to_str().unwrap().to_string();
+    assert!(ct.starts_with("text/csv"), "got {ct}");
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    let text = String::from_utf8(bytes.to_vec()).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Admission No,First Name,Last Name,Action,From Grade,From Section,From Stream,To Grade,To Section,To Stream,Reason"
+    );
+    assert_eq!(lines.len(), 3);
+    // Reasons are sanitized like every other user-controlled cell.
+    assert!(lines.iter().any(|l| l.ends_with(",retain,Primary 1,,,Primary 1,,,'=Needs support")), "{text}");
+
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/promotions/{}", Uuid::new_v4());
+    let (status, _) = get_auth(app, &uri, &school.token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
 #[tokio::test]
 #[serial]
 async fn test_bulk_import_with_skip_invalid_imports_valid_rows() {
@@ -979,3 +1433,338 @@ async fn test_cross_tenant_access_returns_404() {
     // Suppress unused-warning on workos_id field.
     let _ = (&school_a.workos_id, &school_b.workos_id);
 }
+
+#[tokio::test]
+#[serial]
+async fn test_promotion_suggestions_apply_rules() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({
+            "academic_calendar": {
+                "current_academic_year": "2025/2026",
+                "terms": [
+                    { "name": "First Term", "start_date": "2025-09-01", "end_date": "2025-12-15" },
+                    { "name": "Second Term", "start_date": "2026-01-10", "end_date": "2026-04-05" }
+                ]
+            },
+            "subjects": { "subjects": ["Mathematics", "English Language", "Science"] },
+            "grading": {
+                "ca_weight": "40",
+                "exam_weight": "60",
+                "passmark": "50",
+                "grading_scale": [
+                    { "grade": "A", "min_score": "70", "max_score": "100" },
+                    { "grade": "C", "min_score": "50", "max_score": "69" },
+                    { "grade": "F", "min_score": "0", "max_score": "49" }
+                ]
+            },
+            "policies": {
+                "promotion_criteria": "automatic",
+                "promotion_rules": {
+                    "min_subjects_to_pass": "3",
+                    "overall_pass_percentage": "50",
+                    "required_subjects": ["Mathematics"],
+                    "conditional_promotion": true,
+                    "max_subjects_for_supplementary": "1"
+                },
+                "discipline_framework": "merit_demerit"
+            }
+        }),
+    )
+    .await;
+
+    let mut ids = vec![];
+    for grade in ["JSS 1", "JSS 1", "JSS 1", "JSS 1", "JSS 2", "JSS 2"] {
+        let app = test_router(state.clone());
+        let (_, body) = post_json_auth(app, "/api/v1/students", min_student(grade), &school.token).await;
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    // Per-subject (ca, exam) for each student, in both terms. The second
+    // student averages a pass across terms; the third fails one subject and
+    // the fourth fails Mathematics and Science.
+    let scores = [
+        [(30, 50), (30, 50), (30, 50)],
+        [(20, 20), (30, 60), (30, 60)],
+        [(30, 50), (10, 30), (30, 60)],
+        [(10, 20), (35, 60), (10, 20)],
+    ];
+    for term in ["First Term", "Second Term"] {
+        for (i, subject) in ["Mathematics", "English Language", "Science"].into_iter().enumerate() {
+            let entries: Vec<_> = scores
+                .iter()
+                .enumerate()
+                .map(|(s, row)| {
+                    // The second student improves in the second term.
+                    let (ca, exam) = if s == 1 && term == "Second Term" { (35, 60) } else { row[i] };
+                    json!({ "student_id": ids[s], "scores": { "ca": ca, "exam": exam } })
+                })
+                .collect();
+            let (status, body) = put_json_auth(
+                test_router(state.clone()),
+                "/api/v1/gradebook/sheets",
+                json!({ "term": term, "subject": subject, "grade_level": "JSS 1", "entries": entries }),
+                &school.token,
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK, "body: {body}");
+        }
+    }
+
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/promotion-suggestions?grade_level=JSS%201",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["academic_year"], "2025/2026");
+    assert_eq!(body["next_grade"], "JSS 2");
+    assert_eq!(body["criteria"], "automatic");
+    assert_eq!((body["promote"].as_i64(), body["retain"].as_i64()), (Some(3), Some(1)));
+    let decision = |id: &str| {
+        body["decisions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|d| d["student_id"] == id)
+            .unwrap()
+            .clone()
+    };
+
+    let top = decision(&ids[0]);
+    assert_eq!(top["action"], "promote");
+    assert_eq!(top["to_grade"], "JSS 2");
+    assert_eq!(top["conditional"], false);
+    assert_eq!(top["subjects_passed"], 3);
+    assert_eq!(top["average"], 80.0);
+
+    // Mathematics: (40 + 95) / 2 = 67.5.
+    let improved = decision(&ids[1]);
+    assert_eq!(improved["action"], "promote");
+    assert_eq!(improved["subjects_passed"], 3);
+
+    let conditional = decision(&ids[2]);
+    assert_eq!(conditional["action"], "promote");
+    assert_eq!(conditional["conditional"], true);
+    assert_eq!(
+        conditional["reason"],
+        "Passed 2 subjects; 3 required; Conditional: supplementary exams in English Language"
+    );
+
+    let retained = decision(&ids[3]);
+    assert_eq!(retained["action"], "retain");
+    assert!(retained["to_grade"].is_null());
+    assert_eq!(
+        retained["reasons"],
+        json!(["Passed 1 subjects; 3 required", "Did not pass Mathematics"])
+    );
+
+    // The last grade level graduates; no results means retain.
+    for subject in ["Mathematics", "English Language", "Science"] {
+        let (status, body) = put_json_auth(
+            test_router(state.clone()),
+            "/api/v1/gradebook/sheets",
+            json!({
+                "term": "First Term",
+                "subject": subject,
+                "grade_level": "JSS 2",
+                "entries": [{ "student_id": ids[4], "scores": { "ca": 30, "exam": 40 } }]
+            }),
+            &school.token,
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "body: {body}");
+    }
+    let (status, body) = get_auth(
+        test_router(state.clone()),
+        "/api/v1/students/promotion-suggestions?grade_level=JSS%202",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert!(body["next_grade"].is_null());
+    assert_eq!(body["graduate"], 1);
+    let graduating = &body["decisions"].as_array().unwrap();
+    let action = |id: &str| graduating.iter().find(|d| d["student_id"] == id).unwrap().clone();
+    assert_eq!(action(&ids[4])["action"], "graduate");
+    assert!(action(&ids[4])["to_grade"].is_null());
+    assert_eq!(action(&ids[5])["action"], "retain");
+    assert_eq!(action(&ids[5])["reason"], "No results for the year");
+
+    // Nothing was written.
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM student_class_history WHERE org_id = $1",
+    )
+    .bind(school.org_id)
+    .fetch_one(&state.db_pool)
+    .await
+    .unwrap();
+    assert_eq!(count, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rollover_advances_the_academic_year() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({
+            "academic_calendar": {
+                "calendar_type": "trimester",
+                "current_academic_year": "2040/2041",
+                "terms": [
+                    { "name": "First Term", "start_date": "2040-09-10", "end_date": "2040-12-14" },
+                    { "name": "Second Term", "start_date": "2041-01-07", "end_date": "2041-04-05" },
+                ]
+            }
+        }),
+    )
+    .await;
+
+    let mut ids = vec![];
+    for (grade, section, admission_number) in [
+        ("Primary 1", Some("A"), None),
+        ("Primary 2", None, None),
+        ("JSS 2", Some("B"), None),
+        // Already issued for the new year; the sequence must go past it.
+        ("JSS 1", None, Some("INF/2041/007")),
+    ] {
+        let mut student = min_student(grade);
+        student["section"] = json!(section);
+        student["admission_number"] = json!(admission_number);
+        let app = test_router(state.clone());
+        let (status, body) = post_json_auth(app, "/api/v1/students", student, &school.token).await;
+        assert_eq!(status, StatusCode::CREATED, "body: {body}");
+        ids.push(body["id"].as_str().unwrap().to_string());
+    }
+    let request = json!({
+        "decisions": [{ "student_id": ids[1], "action": "retain", "reason": "Repeat year" }],
+        "effective_date": "2041-09-01",
+    });
+
+    let app = test_router(state.clone());
+    let (status, body) =
+        post_json_auth(app, "/api/v1/students/rollover", request, &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["closed_academic_year"], "2040/2041");
+    assert_eq!(body["academic_year"], "2041/2042");
+    assert_eq!((body["promoted"].as_i64(), body["retained"].as_i64()), (Some(2), Some(1)));
+    assert_eq!(body["graduated"], 1);
+    assert_eq!(body["class_roll_size"], 4);
+    assert_eq!(body["admission_number_year"], 2041);
+    assert_eq!(body["terms"][1]["start_date"], "2042-01-07");
+    let batch_id = body["batch_id"].as_str().unwrap().to_string();
+
+    // Next grade by position, sections carried over, last level graduated.
+    for (id, grade, section, status) in [
+        (&ids[0], "Primary 2", Some("A"), "active"),
+        (&ids[1], "Primary 2", None, "active"),
+        (&ids[2], "JSS 2", Some("B"), "graduated"),
+        (&ids[3], "JSS 2", None, "active"),
+    ] {
+        let app = test_router(state.clone());
+        let (_, body) = get_auth(app, &format!("/api/v1/students/{id}"), &school.token).await;
+        assert_eq!(body["grade_level"], grade);
+        assert_eq!(body["section"], json!(section));
+        assert_eq!(body["status"], status);
+    }
+
+    let app = test_router(state.clone());
+    let (_, body) = get_auth(app, "/api/v1/schools/setup", &school.token).await;
+    let calendar = &body["data"]["academic_calendar"];
+    assert_eq!(calendar["current_academic_year"], "2041/2042");
+    assert_eq!(calendar["terms"][0]["start_date"], "2041-09-10");
+
+    // The old year's configuration and class rolls are kept.
+    let app = test_router(state.clone());
+    let (status, body) =
+        get_auth(app, "/api/v1/students/rollover/archives", &school.token).await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body[0]["academic_year"], "2040/2041");
+    assert_eq!(body[0]["promotion_batch_id"], batch_id.as_str());
+    let configuration = &body[0]["configuration"];
+    assert_eq!(configuration["config"]["current_academic_year"], "2040/2041");
+    assert_eq!(configuration["terms"][0]["start_date"], "2040-09-10");
+    assert_eq!(configuration["grade_levels"].as_array().unwrap().len(), 4);
+
+    let app = test_router(state.clone());
+    let (status, body) = get_auth(
+        app,
+        "/api/v1/students/class-rolls?academic_year=2040/2041&grade_level=JSS%202",
+        &school.token,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "body: {body}");
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["student_id"], ids[2].as_str());
+    assert_eq!(body["data"][0]["section"], "B");
+
+    // Admission numbers use the new year and skip numbers already issued.
+    let app = test_router(state.clone());
+    let (_, body) =
+        post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+    assert_eq!(body["admission_number"], "INF/2041/008");
+
+    // The batch can't be reverted on its own, and a year is closed once.
+    let app = test_router(state.clone());
+    let uri = format!("/api/v1/students/promotions/{batch_id}/revert");
+    let (status, _) = post_json_auth(app, &uri, json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "academic_calendar": { "current_academic_year": "2040/2041" } }),
+    )
+    .await;
+    let app = test_router(state.clone());
+    let (status, body) =
+        post_json_auth(app, "/api/v1/students/rollover", json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::CONFLICT, "body: {body}");
+}
+
+#[tokio::test]
+#[serial]
+async fn test_rollover_requires_a_decision_for_unconfigured_grades() {
+    let mock_server = MockServer::start().await;
+    mount_jwks_endpoint(&mock_server).await;
+    let state = test_app_state(&mock_server).await;
+    let school = setup_school(&state, &mock_server, "admin").await;
+    seed_school_setup(
+        &state.db_pool,
+        school.org_id,
+        json!({ "academic_calendar": { "current_academic_year": "2040/2041" } }),
+    )
+    .await;
+    let app = test_router(state.clone());
+    let (_, body) =
+        post_json_auth(app, "/api/v1/students", min_student("Primary 1"), &school.token).await;
+    let admission_number = body["admission_number"].as_str().unwrap().to_string();
+    sqlx::query("UPDATE students SET grade_level = 'Nursery' WHERE org_id = $1")
+        .bind(school.org_id)
+        .execute(&state.db_pool)
+        .await
+        .unwrap();
+
+    let app = test_router(state.clone());
+    let (status, body) =
+        post_json_auth(app, "/api/v1/students/rollover", json!({}), &school.token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "body: {body}");
+    let msg = body["error"]["message"].as_str().unwrap_or("");
+    assert!(msg.contains(&admission_number), "body: {body}");
+    let archives: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM academic_year_archives WHERE org_id = $1")
+            .bind(school.org_id)
+            .fetch_one(&state.db_pool)
+            .await
+            .unwrap();
+    assert_eq!(archives, 0);
+}